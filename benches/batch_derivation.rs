@@ -0,0 +1,56 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use tokio::runtime::Runtime;
+
+use crypto_wallet_api::core::{get_chain_types_by_symbol, ChainType, SecretString};
+use crypto_wallet_api::services::wallet::{DerivationOptions, WalletService};
+
+const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+fn chain_types() -> Vec<ChainType> {
+    ["BTC", "ETH", "SOL", "ATOM", "XLM"]
+        .iter()
+        .flat_map(|symbol| get_chain_types_by_symbol(symbol))
+        .collect()
+}
+
+/// A batch through the shared key tree vs. the same addresses requested one by one,
+/// which re-derives the seed and every hardened level per address
+fn batch_vs_per_address(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let chain_types = chain_types();
+    let options = DerivationOptions::default();
+    let mnemonic = SecretString::from(MNEMONIC);
+    let passphrase = SecretString::default();
+
+    let mut group = c.benchmark_group("derivation");
+    group.sample_size(10);
+
+    for count in [10u32, 100] {
+        group.bench_with_input(BenchmarkId::new("batch", count), &count, |b, &count| {
+            let service = WalletService::new();
+            b.iter(|| {
+                runtime
+                    .block_on(service.batch_generate_wallet_addresses(&mnemonic, &passphrase, &chain_types, 0, count, &options))
+                    .unwrap()
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("per_address", count), &count, |b, &count| {
+            let service = WalletService::new();
+            b.iter(|| {
+                for chain_type in &chain_types {
+                    for index in 0..count {
+                        runtime
+                            .block_on(service.generate_wallet_address(&mnemonic, &passphrase, chain_type, index, &options))
+                            .unwrap();
+                    }
+                }
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, batch_vs_per_address);
+criterion_main!(benches);
@@ -1,4 +1,37 @@
+// Messages carrying mnemonics, passphrases or private keys; their `Debug` impls are
+// written by hand in `src/grpc/secrets.rs` so secrets never reach logs
+const SECRET_MESSAGES: &[&str] = &[
+    ".wallet.v1.GenerateMnemonicResponse",
+    ".wallet.v1.ValidateMnemonicRequest",
+    ".wallet.v1.AssistMnemonicRequest",
+    ".wallet.v1.AssistMnemonicResponse",
+    ".wallet.v1.EntropyToMnemonicRequest",
+    ".wallet.v1.EntropyToMnemonicResponse",
+    ".wallet.v1.MnemonicToEntropyRequest",
+    ".wallet.v1.MnemonicToEntropyResponse",
+    ".wallet.v1.MnemonicToSeedRequest",
+    ".wallet.v1.MnemonicToSeedResponse",
+    ".wallet.v1.SplitSlip39Request",
+    ".wallet.v1.Slip39ShareGroup",
+    ".wallet.v1.CombineSlip39Request",
+    ".wallet.v1.CombineSlip39Response",
+    ".wallet.v1.DeriveBip85Request",
+    ".wallet.v1.DeriveBip85Response",
+    ".wallet.v1.GenerateWalletRequest",
+    ".wallet.v1.BatchGenerateWalletRequest",
+    ".wallet.v1.ExportXpubRequest",
+    ".wallet.v1.DeriveMultisigRequest",
+    ".wallet.v1.WalletAddressResponse",
+    ".wallet.v1.ImportKeyRequest",
+    ".wallet.v1.SignPsbtRequest",
+    ".wallet.v1.BuildPsbtRequest",
+    ".wallet.v1.SignMessageRequest",
+];
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("proto/wallet.proto")?;
+    SECRET_MESSAGES
+        .iter()
+        .fold(tonic_build::configure(), |builder, message| builder.skip_debug(*message))
+        .compile_protos(&["proto/wallet.proto"], &["proto"])?;
     Ok(())
-}
\ No newline at end of file
+}
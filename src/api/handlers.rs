@@ -1,12 +1,20 @@
 use actix_web::{get, post, web, HttpResponse};
+use bitcoin::absolute::LockTime;
+use bitcoin::{consensus, Amount};
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use tracing::info;
 
 use crate::api::models::*;
-use crate::core::{get_chain_info, get_chain_types_by_symbol, get_token_registry};
+use crate::chains::bitcoin::XpubFormat;
+use crate::chains::message::MessageRequest;
+use crate::chains::multisig::{Cosigner, MultisigWallet};
+use crate::chains::psbt::{self, BuildOptions, BuildRequest, Finality, Payout, SigningRequest, Unsigned, Utxo};
+use crate::core::bip85::Bip85Application;
+use crate::core::mnemonic::{language_code, parse_language};
+use crate::core::slip39::Slip39Scheme;
+use crate::core::{get_chain_info, get_chain_types_by_symbol, get_token_registry, ChainType, SecretString};
 use crate::errors::{ApiError, ApiResult};
-use crate::services::wallet::WalletService;
+use crate::services::wallet::{DerivationOptions, WalletService};
 
 #[get("/health")]
 pub async fn health_check() -> ApiResult<HttpResponse> {
@@ -21,12 +29,12 @@ pub async fn health_check() -> ApiResult<HttpResponse> {
 
 #[post("/mnemonic/generate")]
 pub async fn generate_mnemonic(
-    wallet_service: web::Data<Arc<Mutex<WalletService>>>,
+    wallet_service: web::Data<Arc<WalletService>>,
     req: web::Json<GenerateMnemonicRequest>,
 ) -> ApiResult<HttpResponse> {
     // Validate request
     req.validate()
-        .map_err(|e| ApiError::BadRequest(e))?;
+        .map_err(ApiError::BadRequest)?;
 
     info!(
         "Generating {} word mnemonic in {}",
@@ -34,8 +42,7 @@ pub async fn generate_mnemonic(
     );
 
     // Generate mnemonic
-    let service = wallet_service.lock().await;
-    let mnemonic = service
+    let mnemonic = wallet_service
         .generate_mnemonic(&req.language, req.word_count)
         .await?;
 
@@ -51,13 +58,12 @@ pub async fn generate_mnemonic(
 
 #[post("/mnemonic/validate")]
 pub async fn validate_mnemonic(
-    wallet_service: web::Data<Arc<Mutex<WalletService>>>,
+    wallet_service: web::Data<Arc<WalletService>>,
     req: web::Json<ValidateMnemonicRequest>,
 ) -> ApiResult<HttpResponse> {
     info!("Validating mnemonic in {}", req.language);
 
-    let service = wallet_service.lock().await;
-    let (valid, word_count) = service
+    let (valid, word_count) = wallet_service
         .validate_mnemonic(&req.mnemonic, &req.language)
         .await;
 
@@ -74,6 +80,170 @@ pub async fn validate_mnemonic(
     Ok(HttpResponse::Ok().json(response))
 }
 
+#[post("/mnemonic/assist")]
+pub async fn assist_mnemonic(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<AssistMnemonicRequest>,
+) -> ApiResult<HttpResponse> {
+    info!("Checking mnemonic words in {}", req.language.as_deref().unwrap_or("any language"));
+
+    let report = wallet_service
+        .assist_mnemonic(&req.mnemonic, req.language.as_deref())
+        .await?;
+
+    let response = AssistMnemonicResponse {
+        language: language_code(report.language).to_string(),
+        word_count: report.word_count as u32,
+        valid: report.valid,
+        invalid_words: report
+            .invalid_words
+            .into_iter()
+            .map(|word| InvalidWordResponse {
+                position: word.position as u32,
+                suggestions: word.suggestions.into_iter().map(str::to_string).collect(),
+            })
+            .collect(),
+        final_words: report.final_words.into_iter().map(str::to_string).collect(),
+        repairs: report.repairs,
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[post("/mnemonic/from-entropy")]
+pub async fn entropy_to_mnemonic(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<EntropyToMnemonicRequest>,
+) -> ApiResult<HttpResponse> {
+    info!("Encoding {:?} entropy as a mnemonic in {}", req.source, req.language);
+
+    let mnemonic = wallet_service
+        .entropy_to_mnemonic(&req.entropy, req.source, req.word_count, &req.language)
+        .await?;
+
+    let response = EntropyToMnemonicResponse {
+        word_count: mnemonic.expose_secret().split_whitespace().count() as u32,
+        mnemonic,
+        language: req.language.clone(),
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[post("/mnemonic/to-entropy")]
+pub async fn mnemonic_to_entropy(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<MnemonicToEntropyRequest>,
+) -> ApiResult<HttpResponse> {
+    info!("Decoding mnemonic entropy in {}", req.language);
+
+    let entropy = wallet_service
+        .mnemonic_to_entropy(&req.mnemonic, &req.language)
+        .await?;
+
+    let response = MnemonicToEntropyResponse {
+        // Every word carries 11 bits, one of each 33 being checksum
+        word_count: (entropy.expose_secret().len() * 3 / 8) as u32,
+        entropy,
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[post("/mnemonic/to-seed")]
+pub async fn mnemonic_to_seed(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<MnemonicToSeedRequest>,
+) -> ApiResult<HttpResponse> {
+    info!("Deriving BIP39 seed in {}", req.language);
+
+    let seed = wallet_service
+        .mnemonic_to_seed(&req.mnemonic, &req.passphrase, &req.language)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(MnemonicToSeedResponse { seed }))
+}
+
+#[post("/mnemonic/slip39/split")]
+pub async fn split_slip39(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<SplitSlip39Request>,
+) -> ApiResult<HttpResponse> {
+    let defaults = Slip39Scheme::default();
+    let scheme = Slip39Scheme {
+        group_threshold: req.group_threshold.unwrap_or(defaults.group_threshold),
+        groups: req.groups.clone(),
+        iteration_exponent: req.iteration_exponent.unwrap_or(defaults.iteration_exponent),
+        extendable: req.extendable.unwrap_or(defaults.extendable),
+    };
+
+    info!(
+        "Splitting a master secret into {}-of-{} SLIP-39 groups",
+        scheme.group_threshold,
+        scheme.groups.len()
+    );
+
+    let backup = wallet_service
+        .split_slip39(req.master_secret.as_ref(), req.strength.unwrap_or(128), &req.passphrase, &scheme)
+        .await?;
+
+    let response = SplitSlip39Response {
+        identifier: backup.identifier,
+        extendable: backup.extendable,
+        iteration_exponent: backup.iteration_exponent,
+        group_threshold: backup.group_threshold,
+        groups: backup
+            .groups
+            .into_iter()
+            .map(|group| Slip39GroupResponse {
+                member_threshold: group.member_threshold,
+                shares: group.shares,
+            })
+            .collect(),
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[post("/mnemonic/slip39/combine")]
+pub async fn combine_slip39(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<CombineSlip39Request>,
+) -> ApiResult<HttpResponse> {
+    info!("Recovering a master secret from {} SLIP-39 shares", req.shares.len());
+
+    let master_secret = wallet_service
+        .combine_slip39(&req.shares, &req.passphrase)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(CombineSlip39Response {
+        master_secret: SecretString::hex_encode(master_secret.expose_secret().to_vec()),
+    }))
+}
+
+#[post("/mnemonic/bip85")]
+pub async fn derive_bip85(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<Bip85Request>,
+) -> ApiResult<HttpResponse> {
+    let child_language = req.child_language.as_deref().map(parse_language).transpose()?;
+    let application = Bip85Application::new(req.application, child_language, req.word_count, req.length)?;
+    let language = wallet_service.mnemonic_language(&req.mnemonic, req.language.as_deref()).await?;
+
+    info!("Deriving BIP85 application {} child {}", req.application, req.index);
+
+    let child = wallet_service
+        .derive_bip85(&req.mnemonic, &req.passphrase, Some(language), application, req.index)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(Bip85Response {
+        application: req.application,
+        index: req.index,
+        derivation_path: application.path(req.index).to_string(),
+        child,
+    }))
+}
+
 #[get("/languages")]
 pub async fn get_supported_languages() -> ApiResult<HttpResponse> {
     let languages = vec![
@@ -136,7 +306,7 @@ pub async fn get_supported_languages() -> ApiResult<HttpResponse> {
 
 #[post("/wallet/generate")]
 pub async fn generate_wallet(
-    wallet_service: web::Data<Arc<Mutex<WalletService>>>,
+    wallet_service: web::Data<Arc<WalletService>>,
     req: web::Json<GenerateWalletRequest>,
 ) -> ApiResult<HttpResponse> {
     // Get chain types for the symbol
@@ -148,7 +318,22 @@ pub async fn generate_wallet(
         ));
     }
     
-    let mut service = wallet_service.lock().await;
+    let master_secret = wallet_service.master_secret_seed(&req.mnemonic, &req.passphrase, req.master_secret.as_ref())?;
+    let language = match master_secret {
+        Some(_) => None,
+        None => Some(wallet_service.mnemonic_language(&req.mnemonic, req.language.as_deref()).await?),
+    };
+    
+    let options = DerivationOptions {
+        account: req.account,
+        derivation_path: req.derivation_path.clone(),
+        legacy_ed25519_derivation: req.legacy_ed25519_derivation,
+        include_private_key: req.include_private_key,
+        key_format: req.key_format,
+        language,
+        network: req.network,
+    };
+    
     let mut wallets = Vec::new();
     
     // Generate wallets for all chain types associated with the symbol
@@ -156,18 +341,28 @@ pub async fn generate_wallet(
         let chain_info = get_chain_info(&chain_type);
         
         info!(
-            "Generating {} ({}) wallet at index {}",
-            chain_info.name, chain_info.symbol, req.index
+            "Generating {} ({}) wallet at account {} index {}",
+            chain_info.name, chain_info.symbol, req.account, req.index
         );
         
-        let wallet = service
-            .generate_wallet_address(
-                &req.mnemonic,
-                &req.passphrase,
-                &chain_type,
-                req.index,
-            )
-            .await?;
+        let wallet = match &master_secret {
+            Some(seed) => {
+                wallet_service
+                    .generate_wallet_address_from_seed(seed, &chain_type, req.index, &options)
+                    .await?
+            }
+            None => {
+                wallet_service
+                    .generate_wallet_address(
+                        &req.mnemonic,
+                        &req.passphrase,
+                        &chain_type,
+                        req.index,
+                        &options,
+                    )
+                    .await?
+            }
+        };
             
         wallets.push(wallet);
     }
@@ -185,6 +380,7 @@ pub async fn generate_wallet(
             crate::core::ChainType::Avalanche => "Avalanche",
             crate::core::ChainType::BitcoinSegwit => "Bitcoin",
             crate::core::ChainType::BitcoinLegacy => "Bitcoin",
+            crate::core::ChainType::BitcoinNestedSegwit => "Bitcoin",
             crate::core::ChainType::BitcoinTaproot => "Bitcoin",
             crate::core::ChainType::Solana => "Solana",
             crate::core::ChainType::Tron => "Tron",
@@ -202,6 +398,7 @@ pub async fn generate_wallet(
             crate::core::ChainType::Injective => "Injective",
             crate::core::ChainType::Tezos => "Tezos",
             crate::core::ChainType::Filecoin => "Filecoin",
+            crate::core::ChainType::Stellar => "Stellar",
         }
     };
     
@@ -237,10 +434,13 @@ pub async fn generate_wallet(
             chain_symbol: wallet.chain_info.symbol,
             address_type: format!("{:?}", wallet.chain_type),
             derivation_path: wallet.derivation_path,
+            account: wallet.account,
             index: wallet.index,
             public_key: wallet.public_key,
+            public_key_formats: wallet.public_key_formats,
             private_key: wallet.private_key,
             supported_tokens: get_supported_tokens(&wallet.chain_type),
+            mnemonic_language: language.map(|language| language_code(language).to_string()),
         };
         Ok(HttpResponse::Ok().json(response))
     } else {
@@ -254,10 +454,13 @@ pub async fn generate_wallet(
                     chain_symbol: wallet.chain_info.symbol,
                     address_type: format!("{:?}", wallet.chain_type),
                     derivation_path: wallet.derivation_path,
+                    account: wallet.account,
                     index: wallet.index,
                     public_key: wallet.public_key,
+                    public_key_formats: wallet.public_key_formats,
                     private_key: wallet.private_key,
                     supported_tokens: get_supported_tokens(&wallet.chain_type),
+                    mnemonic_language: language.map(|language| language_code(language).to_string()),
                 }
             })
             .collect();
@@ -267,7 +470,7 @@ pub async fn generate_wallet(
 
 #[post("/wallet/batch")]
 pub async fn batch_generate_wallets(
-    wallet_service: web::Data<Arc<Mutex<WalletService>>>,
+    wallet_service: web::Data<Arc<WalletService>>,
     req: web::Json<BatchGenerateWalletRequest>,
 ) -> ApiResult<HttpResponse> {
     // Validate count
@@ -302,16 +505,41 @@ pub async fn batch_generate_wallets(
         req.start_index
     );
 
-    let mut service = wallet_service.lock().await;
-    let addresses = service
-        .batch_generate_wallet_addresses(
-            &req.mnemonic,
-            &req.passphrase,
-            &all_chain_types,
-            req.start_index,
-            req.count,
-        )
-        .await?;
+    let master_secret = wallet_service.master_secret_seed(&req.mnemonic, &req.passphrase, req.master_secret.as_ref())?;
+    let language = match master_secret {
+        Some(_) => None,
+        None => Some(wallet_service.mnemonic_language(&req.mnemonic, req.language.as_deref()).await?),
+    };
+    
+    let options = DerivationOptions {
+        account: req.account,
+        derivation_path: req.derivation_path.clone(),
+        legacy_ed25519_derivation: req.legacy_ed25519_derivation,
+        include_private_key: req.include_private_key,
+        key_format: req.key_format,
+        language,
+        network: req.network,
+    };
+
+    let addresses = match &master_secret {
+        Some(seed) => {
+            wallet_service
+                .batch_generate_wallet_addresses_from_seed(seed, &all_chain_types, req.start_index, req.count, &options)
+                .await?
+        }
+        None => {
+            wallet_service
+                .batch_generate_wallet_addresses(
+                    &req.mnemonic,
+                    &req.passphrase,
+                    &all_chain_types,
+                    req.start_index,
+                    req.count,
+                    &options,
+                )
+                .await?
+        }
+    };
 
     let registry = get_token_registry();
     
@@ -328,6 +556,7 @@ pub async fn batch_generate_wallets(
                 crate::core::ChainType::Avalanche => "Avalanche",
                 crate::core::ChainType::BitcoinSegwit => "Bitcoin",
                 crate::core::ChainType::BitcoinLegacy => "Bitcoin",
+                crate::core::ChainType::BitcoinNestedSegwit => "Bitcoin",
                 crate::core::ChainType::BitcoinTaproot => "Bitcoin",
                 crate::core::ChainType::Solana => "Solana",
                 crate::core::ChainType::Tron => "Tron",
@@ -345,6 +574,7 @@ pub async fn batch_generate_wallets(
                 crate::core::ChainType::Injective => "Injective",
                 crate::core::ChainType::Tezos => "Tezos",
                 crate::core::ChainType::Filecoin => "Filecoin",
+                crate::core::ChainType::Stellar => "Stellar",
             };
             
             // Get all tokens supported on this chain
@@ -372,10 +602,13 @@ pub async fn batch_generate_wallets(
                 chain_symbol: wallet.chain_info.symbol,
                 address_type: format!("{:?}", wallet.chain_type),
                 derivation_path: wallet.derivation_path,
+                account: wallet.account,
                 index: wallet.index,
                 public_key: wallet.public_key,
+                public_key_formats: wallet.public_key_formats,
                 private_key: wallet.private_key,
                 supported_tokens: if supported_tokens.is_empty() { None } else { Some(supported_tokens) },
+                mnemonic_language: language.map(|language| language_code(language).to_string()),
             }
         })
         .collect();
@@ -385,4 +618,1030 @@ pub async fn batch_generate_wallets(
     };
 
     Ok(HttpResponse::Ok().json(response))
-}
\ No newline at end of file
+}
+
+#[post("/wallet/xpub")]
+pub async fn export_account_xpub(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<ExportXpubRequest>,
+) -> ApiResult<HttpResponse> {
+    let chain_types = get_chain_types_by_symbol(&req.symbol);
+    
+    if chain_types.is_empty() {
+        return Err(ApiError::BadRequest(
+            format!("Unsupported symbol: {}", req.symbol)
+        ));
+    }
+    
+    info!("Exporting {} account {} extended public keys", req.symbol, req.account);
+    
+    let language = wallet_service
+        .mnemonic_language(&req.mnemonic, req.language.as_deref())
+        .await?;
+    
+    let mut extended_keys = Vec::new();
+    
+    for chain_type in chain_types {
+        let xpub = wallet_service
+            .export_account_xpub(&req.mnemonic, &req.passphrase, &chain_type, req.account, Some(language), req.network)
+            .await?;
+        
+        extended_keys.push(ExtendedPublicKeyResponse {
+            chain_name: xpub.chain_info.name,
+            chain_symbol: xpub.chain_info.symbol,
+            address_type: format!("{:?}", xpub.chain_type),
+            extended_public_key: xpub.extended_public_key,
+            format: xpub.format,
+            master_fingerprint: xpub.master_fingerprint,
+            derivation_path: xpub.derivation_path,
+            key_origin: xpub.key_origin,
+            account: xpub.account,
+            receive_descriptor: xpub.receive_descriptor,
+            change_descriptor: xpub.change_descriptor,
+            mnemonic_language: language_code(language).to_string(),
+        });
+    }
+    
+    Ok(HttpResponse::Ok().json(ExportXpubResponse { extended_keys }))
+}
+
+#[post("/wallet/derive-public")]
+pub async fn derive_public_addresses(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<DerivePublicRequest>,
+) -> ApiResult<HttpResponse> {
+    if req.count == 0 || req.count > 100 {
+        return Err(ApiError::BadRequest(
+            "Count must be between 1 and 100".to_string(),
+        ));
+    }
+    
+    // Without an address type, a ypub or zpub derives only the type it was made for
+    let (format, _) = XpubFormat::decode(&req.extended_public_key)?;
+    let chain_types: Vec<_> = get_chain_types_by_symbol(&req.symbol)
+        .into_iter()
+        .filter(|ct| match &req.address_type {
+            Some(address_type) => format!("{:?}", ct).eq_ignore_ascii_case(address_type),
+            None => format.fits(ct),
+        })
+        .collect();
+    
+    if chain_types.is_empty() {
+        return Err(ApiError::BadRequest(
+            format!("Unsupported symbol or address type: {}", req.symbol)
+        ));
+    }
+    
+    info!(
+        "Deriving {} watch-only {} addresses on branch {} starting at index {}",
+        req.count, req.symbol, req.change, req.start_index
+    );
+    
+    let mut addresses = Vec::new();
+    
+    for chain_type in chain_types {
+        let derived = wallet_service
+            .derive_public_addresses(
+                &req.extended_public_key,
+                &chain_type,
+                req.network,
+                req.change,
+                req.start_index,
+                req.count,
+            )
+            .await?;
+        
+        addresses.extend(derived.into_iter().map(|wallet| WatchOnlyAddressResponse {
+            chain_name: wallet.chain_info.name,
+            chain_symbol: wallet.chain_info.symbol,
+            address_type: format!("{:?}", wallet.chain_type),
+            address: wallet.address,
+            relative_path: wallet.relative_path,
+            change: wallet.change,
+            index: wallet.index,
+            public_key: wallet.public_key,
+            public_key_formats: wallet.public_key_formats,
+        }));
+    }
+    
+    Ok(HttpResponse::Ok().json(DerivePublicResponse { addresses }))
+}
+
+#[post("/wallet/validate-address")]
+pub async fn validate_address(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<ValidateAddressRequest>,
+) -> ApiResult<HttpResponse> {
+    let chain_types: Vec<_> = get_chain_types_by_symbol(&req.symbol)
+        .into_iter()
+        .filter(|ct| match &req.address_type {
+            Some(address_type) => format!("{:?}", ct).eq_ignore_ascii_case(address_type),
+            None => true,
+        })
+        .collect();
+    
+    if chain_types.is_empty() {
+        return Err(ApiError::BadRequest(
+            format!("Unsupported symbol or address type: {}", req.symbol)
+        ));
+    }
+    
+    // Only a redeem script or public key tells nested SegWit apart from any other P2SH
+    let nested_segwit = if req.redeem_script.is_some() || req.public_key.is_some() {
+        if !chain_types.contains(&ChainType::BitcoinNestedSegwit) {
+            return Err(ApiError::BadRequest(
+                "redeem_script and public_key only apply to BitcoinNestedSegwit addresses".to_string(),
+            ));
+        }
+        
+        Some(
+            wallet_service
+                .is_nested_witness_address(req.network, &req.address, req.redeem_script.as_deref(), req.public_key.as_deref())
+                .await?,
+        )
+    } else {
+        None
+    };
+    
+    let mut address_types = Vec::new();
+    
+    for chain_type in chain_types {
+        let valid = match chain_type {
+            ChainType::BitcoinNestedSegwit if nested_segwit.is_some() => nested_segwit == Some(true),
+            _ => wallet_service.validate_address(&chain_type, req.network, &req.address).await,
+        };
+        if valid {
+            address_types.push(format!("{:?}", chain_type));
+        }
+    }
+    
+    Ok(HttpResponse::Ok().json(ValidateAddressResponse {
+        valid: !address_types.is_empty(),
+        address_types,
+        nested_segwit,
+    }))
+}
+
+#[post("/wallet/multisig")]
+pub async fn derive_multisig(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<MultisigRequest>,
+) -> ApiResult<HttpResponse> {
+    if req.count == 0 || req.count > 100 {
+        return Err(ApiError::BadRequest(
+            "Count must be between 1 and 100".to_string(),
+        ));
+    }
+    
+    let mut cosigners = req
+        .cosigners
+        .iter()
+        .map(|cosigner| Cosigner::parse(cosigner))
+        .collect::<ApiResult<Vec<_>>>()?;
+    
+    // The mnemonic joins as one more cosigner with its BIP48 key
+    let mut own_key = None;
+    let mut mnemonic_language = None;
+    if let Some(mnemonic) = &req.mnemonic {
+        let language = wallet_service
+            .mnemonic_language(mnemonic, req.language.as_deref())
+            .await?;
+        let cosigner = wallet_service
+            .multisig_cosigner(mnemonic, &req.passphrase, Some(language), req.network, req.account, req.script_type)
+            .await?;
+        
+        own_key = Some(cosigner.to_string());
+        mnemonic_language = Some(language_code(language).to_string());
+        cosigners.push(cosigner);
+    }
+    
+    let wallet = MultisigWallet::new(req.threshold, req.script_type, req.network.bitcoin_network(), cosigners)?;
+    
+    info!(
+        "Deriving {} {}-of-{} {} multisig addresses on branch {} starting at index {}",
+        req.count,
+        req.threshold,
+        wallet.cosigners().len(),
+        req.script_type,
+        req.change,
+        req.start_index
+    );
+    
+    let script_type = wallet.script_type();
+    let threshold = wallet.threshold();
+    let total_keys = wallet.cosigners().len() as u32;
+    let receive_descriptor = wallet.descriptor(0)?;
+    let change_descriptor = wallet.descriptor(1)?;
+    
+    let addresses = wallet_service
+        .derive_multisig_addresses(wallet, req.change, req.start_index, req.count)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(MultisigResponse {
+        script_type,
+        threshold,
+        total_keys,
+        receive_descriptor,
+        change_descriptor,
+        own_key,
+        mnemonic_language,
+        addresses: addresses
+            .into_iter()
+            .map(|address| MultisigAddressResponse {
+                address: address.address,
+                relative_path: address.relative_path,
+                change: address.change,
+                index: address.index,
+                witness_script: address.witness_script,
+            })
+            .collect(),
+    }))
+}
+
+#[post("/wallet/policy")]
+pub async fn compile_policy(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<PolicyRequest>,
+) -> ApiResult<HttpResponse> {
+    info!("Compiling a spending policy to {} on {}", req.script_type, req.network);
+    
+    let compiled = wallet_service
+        .compile_policy(&req.policy, &req.keys, req.script_type, req.network)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(PolicyResponse {
+        script_type: compiled.script_type,
+        descriptor: compiled.descriptor,
+        address: compiled.address,
+        miniscript: compiled.miniscript,
+        witness_script: compiled.witness_script,
+        internal_key: compiled.internal_key,
+        leaves: compiled
+            .leaves
+            .into_iter()
+            .map(|leaf| TapLeafResponse {
+                miniscript: leaf.miniscript,
+                script: leaf.script,
+                leaf_version: leaf.leaf_version,
+                control_block: leaf.control_block,
+            })
+            .collect(),
+    }))
+}
+
+#[post("/wallet/import-key")]
+pub async fn import_private_key(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<ImportKeyRequest>,
+) -> ApiResult<HttpResponse> {
+    let chain_types: Vec<_> = get_chain_types_by_symbol(&req.symbol)
+        .into_iter()
+        .filter(|ct| match &req.address_type {
+            Some(address_type) => format!("{:?}", ct).eq_ignore_ascii_case(address_type),
+            None => true,
+        })
+        .collect();
+    
+    if chain_types.is_empty() {
+        return Err(ApiError::BadRequest(
+            format!("Unsupported symbol or address type: {}", req.symbol)
+        ));
+    }
+    
+    info!("Importing private key for {}", req.symbol);
+    
+    let mut keys = Vec::new();
+    let mut first_error = None;
+    
+    for chain_type in chain_types {
+        // A key may fit only some of a symbol's address types: an uncompressed WIF
+        // has no SegWit address
+        let imported = match wallet_service.import_private_key(&req.private_key, &chain_type, req.network).await {
+            Ok(imported) => imported,
+            Err(e) => {
+                first_error.get_or_insert(e);
+                continue;
+            }
+        };
+        
+        keys.push(ImportedKeyResponse {
+            chain_name: imported.chain_info.name,
+            chain_symbol: imported.chain_info.symbol,
+            address_type: format!("{:?}", chain_type),
+            address: imported.address,
+            public_key: imported.public_key,
+            public_key_formats: imported.public_key_formats,
+            curve: imported.curve,
+            compressed: imported.compressed,
+            key_format: imported.key_format,
+        });
+    }
+    
+    if let Some(e) = first_error.filter(|_| keys.is_empty()) {
+        return Err(e);
+    }
+    
+    Ok(HttpResponse::Ok().json(ImportKeyResponse { keys }))
+}
+
+fn utxos(chain_types: &[ChainType], utxos: &[UtxoRequest]) -> ApiResult<Vec<Utxo>> {
+    utxos
+        .iter()
+        .map(|utxo| {
+            Ok(Utxo {
+                outpoint: psbt::parse_outpoint(&utxo.txid, utxo.vout)?,
+                value: psbt::parse_amount(chain_types, utxo.value)?,
+                chain_type: psbt::address_type(chain_types, utxo.address_type.as_deref().unwrap_or_default())?,
+                change: utxo.change,
+                index: utxo.index,
+                previous_transaction: utxo
+                    .previous_transaction
+                    .as_deref()
+                    .map(psbt::parse_transaction)
+                    .transpose()?,
+            })
+        })
+        .collect()
+}
+
+fn payouts(chain_types: &[ChainType], outputs: &[PayoutRequest]) -> ApiResult<Vec<Payout>> {
+    outputs
+        .iter()
+        .map(|output| {
+            Ok(Payout {
+                address: output.address.clone(),
+                value: psbt::parse_amount(chain_types, output.value)?,
+            })
+        })
+        .collect()
+}
+
+#[post("/psbt/sign")]
+pub async fn sign_psbt(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<SignPsbtRequest>,
+) -> ApiResult<HttpResponse> {
+    let chain_types = psbt::chain_types(&req.symbol)?;
+    
+    let transaction = match &req.psbt {
+        Some(encoded) => {
+            if !req.utxos.is_empty() || !req.outputs.is_empty() {
+                return Err(ApiError::BadRequest(
+                    "Send either a psbt or utxos and outputs, not both".to_string()
+                ));
+            }
+            Unsigned::Psbt(psbt::decode(encoded)?)
+        }
+        None => Unsigned::Spend {
+            utxos: utxos(&chain_types, &req.utxos)?,
+            payouts: payouts(&chain_types, &req.outputs)?,
+        },
+    };
+    
+    info!("Signing a {} PSBT on {}", req.symbol, req.network);
+    
+    let language = wallet_service
+        .mnemonic_language(&req.mnemonic, req.language.as_deref())
+        .await?;
+    
+    let request = SigningRequest {
+        chain_types,
+        network: req.network.bitcoin_network(),
+        account: req.account,
+        transaction,
+        sign_only: req.sign_only,
+        allow_missing_previous_transactions: req.allow_missing_previous_transactions,
+        allow_any_sighash: req.allow_any_sighash,
+    };
+    let signed = wallet_service
+        .sign_psbt(&req.mnemonic, &req.passphrase, Some(language), request)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(SignPsbtResponse {
+        psbt: psbt::encode(&signed.psbt),
+        signed_inputs: signed.signed_inputs.into_iter().map(|i| i as u32).collect(),
+        complete: signed.complete,
+        txid: signed.transaction.as_ref().map(|tx| tx.txid().to_string()),
+        transaction: signed.transaction.as_ref().map(consensus::encode::serialize_hex),
+        fee: signed.fee.map(Amount::to_sat),
+        mnemonic_language: language_code(language).to_string(),
+    }))
+}
+
+#[post("/psbt/build")]
+pub async fn build_psbt(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<BuildPsbtRequest>,
+) -> ApiResult<HttpResponse> {
+    let chain_types = psbt::chain_types(&req.symbol)?;
+    let change_type = req
+        .change_address_type
+        .as_deref()
+        .map(|name| psbt::address_type(&chain_types, name))
+        .transpose()?;
+    let options = BuildOptions {
+        fee_rate: psbt::fee_rate(req.fee_rate)?,
+        change_type,
+        change_index: req.change_index,
+        finality: Finality {
+            rbf: req.rbf.unwrap_or(true),
+            lock_time: LockTime::from_consensus(req.locktime),
+        },
+    };
+    let utxos = utxos(&chain_types, &req.utxos)?;
+    let payouts = payouts(&chain_types, &req.outputs)?;
+    
+    info!(
+        "Building a {} PSBT from {} UTXOs at {} sat/vB on {}",
+        req.symbol,
+        utxos.len(),
+        req.fee_rate,
+        req.network
+    );
+    
+    let language = wallet_service
+        .mnemonic_language(&req.mnemonic, req.language.as_deref())
+        .await?;
+    
+    let request = BuildRequest {
+        chain_types,
+        network: req.network.bitcoin_network(),
+        account: req.account,
+        utxos,
+        payouts,
+        options,
+        allow_missing_previous_transactions: req.allow_missing_previous_transactions,
+    };
+    let built = wallet_service
+        .build_psbt(&req.mnemonic, &req.passphrase, Some(language), request)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(BuildPsbtResponse {
+        psbt: psbt::encode(&built.psbt),
+        algorithm: built.algorithm.to_string(),
+        selected_utxos: built
+            .psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .map(|input| input.previous_output.to_string())
+            .collect(),
+        fee: built.fee.to_sat(),
+        vsize: built.vsize,
+        change: built.change.map(|change| ChangeOutputResponse {
+            output_index: change.output as u32,
+            address: change.address,
+            derivation_path: change.path.to_string(),
+            value: change.value.to_sat(),
+        }),
+        mnemonic_language: language_code(language).to_string(),
+    }))
+}
+
+#[post("/sign/message")]
+pub async fn sign_message(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<SignMessageRequest>,
+) -> ApiResult<HttpResponse> {
+    let chain_types = psbt::chain_types(&req.symbol)?;
+    let chain_type = psbt::address_type(&chain_types, req.address_type.as_deref().unwrap_or(""))?;
+    
+    info!("Signing a message with {:?} index {} on {}", chain_type, req.index, req.network);
+    
+    let language = wallet_service
+        .mnemonic_language(&req.mnemonic, req.language.as_deref())
+        .await?;
+    
+    let request = MessageRequest {
+        chain_type,
+        network: req.network,
+        account: req.account,
+        index: req.index,
+        message: req.message.clone(),
+        format: req.format,
+    };
+    let signed = wallet_service
+        .sign_message(&req.mnemonic, &req.passphrase, Some(language), request)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(SignMessageResponse {
+        address: signed.address,
+        derivation_path: signed.derivation_path,
+        format: signed.format,
+        signature: signed.signature,
+        mnemonic_language: language_code(language).to_string(),
+    }))
+}
+
+#[post("/verify/message")]
+pub async fn verify_message(
+    wallet_service: web::Data<Arc<WalletService>>,
+    req: web::Json<VerifyMessageRequest>,
+) -> ApiResult<HttpResponse> {
+    let chain_types = psbt::chain_types(&req.symbol)?;
+    
+    info!("Verifying a {} message signature on {}", req.symbol, req.network);
+    
+    let verification = wallet_service
+        .verify_message(&chain_types, req.network, &req.address, &req.message, &req.signature)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(VerifyMessageResponse {
+        valid: verification.valid,
+        format: verification.format,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::core::{MessageFormat, SecretBytes};
+    use std::io;
+    use std::sync::Mutex;
+    use tracing_subscriber::fmt::MakeWriter;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const PASSPHRASE: &str = "correct horse battery staple";
+
+    /// Collects everything the fmt subscriber writes
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for LogBuffer {
+        type Writer = LogBuffer;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_secrets_never_reach_logs_or_debug_output() {
+        let logs = LogBuffer::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_writer(logs.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(WalletService::new())))
+                .service(generate_wallet)
+                .service(batch_generate_wallets),
+        )
+        .await;
+
+        let body = serde_json::json!({ "mnemonic": MNEMONIC, "passphrase": PASSPHRASE, "symbol": "BTC" });
+        let request: GenerateWalletRequest = serde_json::from_value(body.clone()).unwrap();
+        let response: serde_json::Value = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/wallet/generate").set_json(&body).to_request(),
+        )
+        .await;
+
+        let batch_body = serde_json::json!({ "mnemonic": MNEMONIC, "passphrase": PASSPHRASE, "symbols": ["ETH", "SOL", "XLM"], "count": 2 });
+        let batch: BatchGenerateWalletResponse = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/wallet/batch").set_json(&batch_body).to_request(),
+        )
+        .await;
+
+        // BTC returns one wallet per address type
+        let mut private_keys: Vec<String> = response
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|wallet| wallet["private_key"].as_str().unwrap().to_string())
+            .collect();
+        private_keys.extend(batch.addresses.iter().map(|wallet| wallet.private_key.as_ref().unwrap().expose_secret().to_string()));
+        assert!(private_keys.len() > 3 && private_keys.iter().all(|key| !key.is_empty()));
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains("Batch generating"), "handlers logged nothing: {}", logs);
+
+        let debug = format!("{:?} {:?}", request, batch);
+        for output in [&logs, &debug] {
+            assert!(!output.contains(MNEMONIC));
+            assert!(!output.contains(PASSPHRASE));
+            for private_key in &private_keys {
+                assert!(!output.contains(private_key.as_str()), "private key leaked: {}", output);
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_recovered_slip39_secret_derives_wallets() {
+        let wallet_service = Arc::new(WalletService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(wallet_service.clone()))
+                .service(split_slip39)
+                .service(combine_slip39)
+                .service(generate_wallet)
+                .service(batch_generate_wallets),
+        )
+        .await;
+
+        let body = serde_json::json!({
+            "strength": 256,
+            "passphrase": PASSPHRASE,
+            "groups": [{ "member_threshold": 2, "member_count": 3 }],
+            "iteration_exponent": 0,
+        });
+        let backup: SplitSlip39Response = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/mnemonic/slip39/split").set_json(&body).to_request(),
+        )
+        .await;
+
+        let shares = &backup.groups[0].shares;
+        let body = serde_json::json!({ "shares": [shares[2], shares[0]], "passphrase": PASSPHRASE });
+        let recovered: CombineSlip39Response = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/mnemonic/slip39/combine").set_json(&body).to_request(),
+        )
+        .await;
+
+        let body = serde_json::json!({ "master_secret": recovered.master_secret, "symbol": "SOL", "index": 1 });
+        let wallet: GenerateWalletResponse = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/wallet/generate").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(wallet.mnemonic_language, None);
+
+        // The secret is the BIP32 seed the wallet's keys come from
+        let seed = SecretBytes::new(hex::decode(recovered.master_secret.expose_secret()).unwrap());
+        let expected = wallet_service
+            .generate_wallet_address_from_seed(&seed, &ChainType::Solana, 1, &DerivationOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(wallet.address, expected.address);
+
+        let body = serde_json::json!({ "master_secret": recovered.master_secret, "symbols": ["SOL"], "count": 2 });
+        let batch: BatchGenerateWalletResponse = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/wallet/batch").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(batch.addresses[1].address, wallet.address);
+
+        // The SLIP-39 passphrase was applied when combining, and the seed replaces the mnemonic
+        for body in [
+            serde_json::json!({ "master_secret": recovered.master_secret, "passphrase": PASSPHRASE, "symbol": "SOL" }),
+            serde_json::json!({ "master_secret": recovered.master_secret, "mnemonic": MNEMONIC, "symbol": "SOL" }),
+            serde_json::json!({ "master_secret": "abcd", "symbol": "SOL" }),
+        ] {
+            let response = test::call_service(
+                &app,
+                test::TestRequest::post().uri("/wallet/generate").set_json(&body).to_request(),
+            )
+            .await;
+            assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_validate_address_tells_nested_segwit_from_other_p2sh() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(WalletService::new())))
+                .service(validate_address),
+        )
+        .await;
+
+        // BIP49 test vector, first receive address
+        let address = "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf";
+        let public_key = "039b3b694b8fc5b5e07fb069c783cac754f5d38c3e08bed1960e31fdb1dda35c24";
+
+        let body = serde_json::json!({ "address": address, "symbol": "BTC" });
+        let response: ValidateAddressResponse = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/wallet/validate-address").set_json(&body).to_request(),
+        )
+        .await;
+        assert!(response.valid);
+        assert_eq!(response.address_types, ["BitcoinNestedSegwit"]);
+        assert_eq!(response.nested_segwit, None);
+
+        let body = serde_json::json!({ "address": address, "symbol": "BTC", "public_key": public_key });
+        let response: ValidateAddressResponse = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/wallet/validate-address").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(response.nested_segwit, Some(true));
+        assert_eq!(response.address_types, ["BitcoinNestedSegwit"]);
+
+        // 1-of-1 bare multisig: a P2SH address, but not a wrapped witness program
+        let multisig = format!("5121{}51ae", public_key);
+        let script = hex::decode(&multisig).unwrap();
+        let other = bitcoin::Address::p2sh(bitcoin::Script::from_bytes(&script), bitcoin::Network::Bitcoin).unwrap();
+        for (field, value) in [("redeem_script", multisig.as_str()), ("public_key", public_key)] {
+            let body = serde_json::json!({ "address": other.to_string(), "symbol": "BTC", field: value });
+            let response: ValidateAddressResponse = test::call_and_read_body_json(
+                &app,
+                test::TestRequest::post().uri("/wallet/validate-address").set_json(&body).to_request(),
+            )
+            .await;
+            assert!(!response.valid);
+            assert_eq!(response.nested_segwit, Some(false));
+        }
+
+        let body = serde_json::json!({ "address": address, "symbol": "ETH", "public_key": public_key });
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post().uri("/wallet/validate-address").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_import_key_skips_address_types_the_key_cannot_use() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(WalletService::new())))
+                .service(import_private_key),
+        )
+        .await;
+        
+        // Uncompressed WIF: valid for P2PKH only, no wallet builds SegWit or Taproot outputs from it
+        let wif = "5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTJ";
+        let body = serde_json::json!({ "private_key": wif, "symbol": "BTC" });
+        let response: ImportKeyResponse = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/wallet/import-key").set_json(&body).to_request(),
+        )
+        .await;
+        
+        let address_types: Vec<_> = response.keys.iter().map(|key| key.address_type.as_str()).collect();
+        assert_eq!(address_types, ["BitcoinLegacy"]);
+        assert_eq!(response.keys[0].address, "1GAehh7TsJAHuUAeKZcXf5CnwuGuGgyX2S");
+        assert_eq!(response.keys[0].compressed, Some(false));
+        
+        let body = serde_json::json!({ "private_key": wif, "symbol": "BTC", "address_type": "BitcoinSegwit" });
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post().uri("/wallet/import-key").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+    
+    #[actix_web::test]
+    async fn test_mnemonic_language_is_detected_and_normalized() {
+        use unicode_normalization::UnicodeNormalization;
+        
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(WalletService::new())))
+                .service(generate_wallet),
+        )
+        .await;
+        
+        // Composed (NFC) kana as most keyboards produce them; BIP39 hashes the NFKD form
+        let composed = "そつう　れきだい　ほんやく　わかす　りくつ　ばいか　ろせん　やちん　そつう　れきだい　ほんやく　わかめ";
+        let decomposed: String = composed.nfd().collect();
+        
+        let body = serde_json::json!({ "mnemonic": composed, "symbol": "ETH" });
+        let detected: Vec<GenerateWalletResponse> = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/wallet/generate").set_json(&body).to_request(),
+        )
+        .await;
+        assert!(detected.iter().all(|wallet| wallet.mnemonic_language.as_deref() == Some("japanese")));
+        
+        let body = serde_json::json!({ "mnemonic": decomposed, "symbol": "ETH", "language": "japanese" });
+        let named: Vec<GenerateWalletResponse> = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/wallet/generate").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(named[0].address, detected[0].address);
+        
+        // Every word is in both Chinese wordlists
+        let body = serde_json::json!({ "mnemonic": "的 的 的 的 的 的 的 的 的 的 的 在", "symbol": "ETH" });
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post().uri("/wallet/generate").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let error: serde_json::Value = test::read_body_json(response).await;
+        assert!(error.to_string().contains("AMBIGUOUS_MNEMONIC_LANGUAGE"), "{}", error);
+    }
+    
+    #[actix_web::test]
+    async fn test_mnemonic_assistant_reports_structured_results() {
+        use unicode_normalization::UnicodeNormalization;
+        
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(WalletService::new())))
+                .service(assist_mnemonic),
+        )
+        .await;
+        
+        let typed = MNEMONIC.replacen("abandon", "abandonn", 1);
+        let body = serde_json::json!({ "mnemonic": typed, "language": "english" });
+        let report: AssistMnemonicResponse = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/mnemonic/assist").set_json(&body).to_request(),
+        )
+        .await;
+        assert!(!report.valid);
+        assert_eq!(report.invalid_words.len(), 1);
+        assert_eq!(report.invalid_words[0].position, 1);
+        assert_eq!(report.invalid_words[0].suggestions, vec!["abandon"]);
+        assert_eq!(report.repairs.len(), 1);
+        assert_eq!(report.repairs[0].expose_secret(), MNEMONIC);
+        
+        // A composed (NFC) Japanese phrase missing its last word
+        let prefix = "そつう　れきだい　ほんやく　わかす　りくつ　ばいか　ろせん　やちん　そつう　れきだい　ほんやく";
+        let body = serde_json::json!({ "mnemonic": prefix });
+        let report: AssistMnemonicResponse = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/mnemonic/assist").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(report.language, "japanese");
+        assert_eq!(report.word_count, 11);
+        assert!(report.invalid_words.is_empty());
+        let last_word = "わかめ".nfkd().collect::<String>();
+        assert!(report.final_words.contains(&last_word));
+    }
+    
+    #[actix_web::test]
+    async fn test_multisig_with_own_mnemonic_matches_shared_keys() {
+        let service = Arc::new(WalletService::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(service.clone()))
+                .service(derive_multisig),
+        )
+        .await;
+        
+        let mut cosigners = Vec::new();
+        for mnemonic in [
+            "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+        ] {
+            let cosigner = service
+                .multisig_cosigner(&mnemonic.into(), &SecretString::default(), None, Default::default(), 0, Default::default())
+                .await
+                .unwrap();
+            cosigners.push(cosigner.to_string());
+        }
+        
+        let body = serde_json::json!({ "cosigners": cosigners, "threshold": 2, "mnemonic": MNEMONIC, "count": 3 });
+        let with_mnemonic: MultisigResponse = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/wallet/multisig").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!((with_mnemonic.threshold, with_mnemonic.total_keys), (2, 3));
+        assert!(with_mnemonic.receive_descriptor.starts_with("wsh(sortedmulti(2,["));
+        let own_key = with_mnemonic.own_key.unwrap();
+        assert!(own_key.starts_with("[73c5da0a/48'/0'/0'/2']xpub"));
+        assert_eq!(with_mnemonic.addresses.len(), 3);
+        
+        // A watch-only coordinator holding the same three keys sees the same wallet
+        cosigners.push(own_key);
+        let body = serde_json::json!({ "cosigners": cosigners, "threshold": 2, "count": 3 });
+        let watch_only: MultisigResponse = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/wallet/multisig").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(watch_only.receive_descriptor, with_mnemonic.receive_descriptor);
+        for (a, b) in watch_only.addresses.iter().zip(&with_mnemonic.addresses) {
+            assert_eq!((&a.address, &a.witness_script), (&b.address, &b.witness_script));
+        }
+        
+        let body = serde_json::json!({ "cosigners": cosigners, "threshold": 4, "count": 1 });
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post().uri("/wallet/multisig").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+    
+    #[actix_web::test]
+    async fn test_psbt_signed_then_finalized() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(WalletService::new())))
+                .service(sign_psbt),
+        )
+        .await;
+        
+        let utxo = serde_json::json!({
+            "txid": "0707070707070707070707070707070707070707070707070707070707070707",
+            "vout": 1,
+            "value": 100_000,
+            "address_type": "BitcoinTaproot",
+            "index": 0,
+        });
+        let body = serde_json::json!({
+            "mnemonic": MNEMONIC,
+            "symbol": "BTC",
+            "utxos": [utxo],
+            "outputs": [{ "address": "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq", "value": 99_000 }],
+            "sign_only": true,
+        });
+        let signed: SignPsbtResponse = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/psbt/sign").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(signed.signed_inputs, [0]);
+        assert!(!signed.complete && signed.transaction.is_none());
+        assert_eq!(signed.fee, Some(1_000));
+        
+        // Signing again finalizes; a Taproot key-path witness is the lone signature
+        let body = serde_json::json!({ "mnemonic": MNEMONIC, "symbol": "BTC", "psbt": signed.psbt });
+        let finalized: SignPsbtResponse = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/psbt/sign").set_json(&body).to_request(),
+        )
+        .await;
+        assert!(finalized.complete);
+        let tx = psbt::parse_transaction(&finalized.transaction.unwrap()).unwrap();
+        assert_eq!(tx.txid().to_string(), finalized.txid.unwrap());
+        assert_eq!(tx.input[0].witness.len(), 1);
+        
+        let body = serde_json::json!({ "mnemonic": MNEMONIC, "symbol": "ETH", "psbt": signed.psbt });
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post().uri("/psbt/sign").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+    
+    #[actix_web::test]
+    async fn test_message_signed_then_verified() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(WalletService::new())))
+                .service(sign_message)
+                .service(verify_message),
+        )
+        .await;
+        
+        let body = serde_json::json!({
+            "mnemonic": MNEMONIC,
+            "symbol": "BTC",
+            "address_type": "BitcoinSegwit",
+            "message": "Hello World",
+        });
+        let signed: SignMessageResponse = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post().uri("/sign/message").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(signed.address, "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu");
+        assert_eq!(signed.derivation_path, "m/84'/0'/0'/0/0");
+        assert_eq!(signed.format, MessageFormat::Bip322Simple);
+        
+        for (message, valid) in [("Hello World", true), ("Hello", false)] {
+            let body = serde_json::json!({
+                "symbol": "BTC",
+                "address": signed.address,
+                "message": message,
+                "signature": signed.signature,
+            });
+            let verified: VerifyMessageResponse = test::call_and_read_body_json(
+                &app,
+                test::TestRequest::post().uri("/verify/message").set_json(&body).to_request(),
+            )
+            .await;
+            assert_eq!(verified.valid, valid);
+            assert_eq!(verified.format, Some(MessageFormat::Bip322Simple));
+        }
+        
+        // BTC has several address types to sign with
+        let body = serde_json::json!({ "mnemonic": MNEMONIC, "symbol": "BTC", "message": "Hello World" });
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post().uri("/sign/message").set_json(&body).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,4 +1,8 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::core::slip39::GroupSpec;
+use crate::core::{Curve, EntropySource, KeyFormat, MessageFormat, MultisigScriptType, NetworkType, PolicyScriptType, SecretString};
 
 // Health check
 #[derive(Debug, Serialize, Deserialize)]
@@ -38,7 +42,7 @@ impl GenerateMnemonicRequest {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GenerateMnemonicResponse {
-    pub mnemonic: String,
+    pub mnemonic: SecretString,
     pub language: String,
     pub word_count: u32,
     pub generated_at: i64,
@@ -47,7 +51,7 @@ pub struct GenerateMnemonicResponse {
 // Mnemonic validation
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ValidateMnemonicRequest {
-    pub mnemonic: String,
+    pub mnemonic: SecretString,
     pub language: String,
 }
 
@@ -58,6 +62,154 @@ pub struct ValidateMnemonicResponse {
     pub message: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssistMnemonicRequest {
+    pub mnemonic: SecretString,
+    /// Omitted uses the wordlist most of the words are from
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvalidWordResponse {
+    /// 1-based
+    pub position: u32,
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssistMnemonicResponse {
+    pub language: String,
+    pub word_count: u32,
+    pub valid: bool,
+    pub invalid_words: Vec<InvalidWordResponse>,
+    /// Last words completing a phrase one word short of a valid length
+    pub final_words: Vec<String>,
+    /// Checksum-valid phrases with the invalid words corrected
+    pub repairs: Vec<SecretString>,
+}
+
+// Entropy-level mnemonic operations
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntropyToMnemonicRequest {
+    /// Hex entropy, dice rolls (1-6) or coin flips (H/T), depending on `source`
+    pub entropy: SecretString,
+    #[serde(default)]
+    pub source: EntropySource,
+    /// Required for dice (default 24); otherwise checked against the entropy length
+    pub word_count: Option<u32>,
+    pub language: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntropyToMnemonicResponse {
+    pub mnemonic: SecretString,
+    pub language: String,
+    pub word_count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MnemonicToEntropyRequest {
+    pub mnemonic: SecretString,
+    pub language: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MnemonicToEntropyResponse {
+    /// Hex entropy
+    pub entropy: SecretString,
+    pub word_count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MnemonicToSeedRequest {
+    pub mnemonic: SecretString,
+    #[serde(default)]
+    pub passphrase: SecretString,
+    pub language: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MnemonicToSeedResponse {
+    /// Hex 64-byte BIP39 seed
+    pub seed: SecretString,
+}
+
+// SLIP-39 Shamir shares
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SplitSlip39Request {
+    /// Hex master secret; omitted generates a random one of `strength` bits
+    #[serde(default)]
+    pub master_secret: Option<SecretString>,
+    /// Bits of a generated master secret (default 128)
+    pub strength: Option<u32>,
+    #[serde(default)]
+    pub passphrase: SecretString,
+    /// Groups needed to recover the secret (default 1)
+    pub group_threshold: Option<u8>,
+    pub groups: Vec<GroupSpec>,
+    /// PBKDF2 cost, `10000 << iteration_exponent` iterations (default 1)
+    pub iteration_exponent: Option<u8>,
+    /// Default true
+    pub extendable: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Slip39GroupResponse {
+    pub member_threshold: u8,
+    pub shares: Vec<SecretString>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SplitSlip39Response {
+    pub identifier: u16,
+    pub extendable: bool,
+    pub iteration_exponent: u8,
+    pub group_threshold: u8,
+    pub groups: Vec<Slip39GroupResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CombineSlip39Request {
+    pub shares: Vec<SecretString>,
+    #[serde(default)]
+    pub passphrase: SecretString,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CombineSlip39Response {
+    /// Hex master secret, which is the wallet's BIP32 seed
+    pub master_secret: SecretString,
+}
+
+// BIP85 deterministic children
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bip85Request {
+    pub mnemonic: SecretString,
+    #[serde(default)]
+    pub passphrase: SecretString,
+    /// Wordlist of `mnemonic`; omitted detects it
+    pub language: Option<String>,
+    /// 39 (mnemonic), 2 (WIF), 32 (xprv), 128169 (hex), 707764 (base64 password)
+    /// or 707785 (base85 password)
+    pub application: u32,
+    #[serde(default)]
+    pub index: u32,
+    /// Application 39: wordlist of the child mnemonic (default english)
+    pub child_language: Option<String>,
+    /// Application 39: 12, 18 or 24 (default 24)
+    pub word_count: Option<u32>,
+    /// Bytes of hex entropy, or characters of a password
+    pub length: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bip85Response {
+    pub application: u32,
+    pub index: u32,
+    pub derivation_path: String,
+    pub child: SecretString,
+}
+
 // Language support
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LanguageInfo {
@@ -74,12 +226,39 @@ pub struct SupportedLanguagesResponse {
 // Wallet generation
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GenerateWalletRequest {
-    pub mnemonic: String,
     #[serde(default)]
-    pub passphrase: String,
+    pub mnemonic: SecretString,
+    #[serde(default)]
+    pub passphrase: SecretString,
+    /// Hex BIP32 seed in place of `mnemonic`, such as a master secret recovered from
+    /// SLIP-39 shares
+    #[serde(default)]
+    pub master_secret: Option<SecretString>,
     pub symbol: String,
+    /// BIP44 account level; on Solana, NEAR and Stellar `index` counts accounts from it
+    #[serde(default)]
+    pub account: u32,
     #[serde(default)]
     pub index: u32,
+    /// Overrides the chain's default path, e.g. `m/44'/60'/3'/0/7`
+    #[serde(default)]
+    pub derivation_path: Option<String>,
+    /// Re-derive Tezos addresses issued before the switch to SLIP-0010
+    #[serde(default)]
+    pub legacy_ed25519_derivation: bool,
+    /// Set to false to get addresses and public keys only; omitted defers to the server policy
+    #[serde(default)]
+    pub include_private_key: Option<bool>,
+    /// "hex" or "native" (WIF, `edsk...`, `suiprivkey1...`, ...); omitted uses the chain's default
+    #[serde(default)]
+    pub key_format: Option<KeyFormat>,
+    /// Mnemonic wordlist, e.g. "japanese"; omitted detects it from the words
+    #[serde(default)]
+    pub language: Option<String>,
+    /// "mainnet" (default), "testnet", "signet" or "regtest"; only Bitcoin and Dogecoin
+    /// addresses and keys differ between networks
+    #[serde(default)]
+    pub network: NetworkType,
 }
 
 
@@ -90,22 +269,62 @@ pub struct GenerateWalletResponse {
     pub chain_symbol: String,
     pub address_type: String,
     pub derivation_path: String,
+    pub account: u32,
     pub index: u32,
     pub public_key: String,
-    pub private_key: String,
+    /// Chain-native encodings of `public_key`, e.g. `bech32` for Cosmos SDK chains or
+    /// `x_only` for Taproot; empty when the chain only uses hex
+    #[serde(default)]
+    pub public_key_formats: BTreeMap<String, String>,
+    /// Absent when the request or the server policy excludes private keys
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<SecretString>,
     pub supported_tokens: Option<Vec<TokenInfo>>,
+    /// Wordlist the mnemonic was read in, detected unless the request named one; absent
+    /// for a `master_secret`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mnemonic_language: Option<String>,
 }
 
 // Batch wallet generation
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BatchGenerateWalletRequest {
-    pub mnemonic: String,
     #[serde(default)]
-    pub passphrase: String,
+    pub mnemonic: SecretString,
+    #[serde(default)]
+    pub passphrase: SecretString,
+    /// Hex BIP32 seed in place of `mnemonic`, such as a master secret recovered from
+    /// SLIP-39 shares
+    #[serde(default)]
+    pub master_secret: Option<SecretString>,
     pub symbols: Vec<String>,
+    /// BIP44 account level shared by every address in the batch; the first of the
+    /// batch's accounts on Solana, NEAR and Stellar
+    #[serde(default)]
+    pub account: u32,
     #[serde(default)]
     pub start_index: u32,
     pub count: u32,
+    /// Path template applied to every symbol; `{account}` and `{index}` are replaced
+    /// per address, e.g. `m/44'/60'/{account}'/0/{index}`
+    #[serde(default)]
+    pub derivation_path: Option<String>,
+    /// Re-derive Tezos addresses issued before the switch to SLIP-0010
+    #[serde(default)]
+    pub legacy_ed25519_derivation: bool,
+    /// Set to false to get addresses and public keys only; omitted defers to the server policy
+    #[serde(default)]
+    pub include_private_key: Option<bool>,
+    /// "hex" or "native" (WIF, `edsk...`, `suiprivkey1...`, ...); omitted uses the chain's default
+    #[serde(default)]
+    pub key_format: Option<KeyFormat>,
+    /// Mnemonic wordlist, e.g. "japanese"; omitted detects it from the words
+    #[serde(default)]
+    pub language: Option<String>,
+    /// "mainnet" (default), "testnet", "signet" or "regtest"; only Bitcoin and Dogecoin
+    /// addresses and keys differ between networks
+    #[serde(default)]
+    pub network: NetworkType,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -115,10 +334,21 @@ pub struct WalletAddressResponse {
     pub chain_symbol: String,
     pub address_type: String,
     pub derivation_path: String,
+    pub account: u32,
     pub index: u32,
     pub public_key: String,
-    pub private_key: String,
+    /// Chain-native encodings of `public_key`, e.g. `bech32` for Cosmos SDK chains or
+    /// `x_only` for Taproot; empty when the chain only uses hex
+    #[serde(default)]
+    pub public_key_formats: BTreeMap<String, String>,
+    /// Absent when the request or the server policy excludes private keys
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<SecretString>,
     pub supported_tokens: Option<Vec<TokenInfo>>,
+    /// Wordlist the mnemonic was read in, detected unless the request named one; absent
+    /// for a `master_secret`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mnemonic_language: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -126,6 +356,448 @@ pub struct BatchGenerateWalletResponse {
     pub addresses: Vec<WalletAddressResponse>,
 }
 
+// Extended public key export
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportXpubRequest {
+    pub mnemonic: SecretString,
+    #[serde(default)]
+    pub passphrase: SecretString,
+    pub symbol: String,
+    #[serde(default)]
+    pub account: u32,
+    /// Mnemonic wordlist, e.g. "japanese"; omitted detects it from the words
+    #[serde(default)]
+    pub language: Option<String>,
+    /// "mainnet" (default), "testnet", "signet" or "regtest"; only Bitcoin and Dogecoin
+    /// addresses and keys differ between networks
+    #[serde(default)]
+    pub network: NetworkType,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtendedPublicKeyResponse {
+    pub chain_name: String,
+    pub chain_symbol: String,
+    pub address_type: String,
+    pub extended_public_key: String,
+    pub format: String,
+    pub master_fingerprint: String,
+    pub derivation_path: String,
+    pub key_origin: String,
+    pub account: u32,
+    /// BIP380 descriptors for Bitcoin Core `importdescriptors` and Sparrow; Bitcoin only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receive_descriptor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change_descriptor: Option<String>,
+    /// Wordlist the mnemonic was read in, detected unless the request named one
+    pub mnemonic_language: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportXpubResponse {
+    pub extended_keys: Vec<ExtendedPublicKeyResponse>,
+}
+
+// Watch-only derivation from an extended public key
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DerivePublicRequest {
+    pub extended_public_key: String,
+    pub symbol: String,
+    /// Narrows symbols with several address types, e.g. "BitcoinSegwit" for BTC
+    pub address_type: Option<String>,
+    /// 0 for receive addresses, 1 for change
+    #[serde(default)]
+    pub change: u32,
+    #[serde(default)]
+    pub start_index: u32,
+    pub count: u32,
+    /// "mainnet" (default), "testnet", "signet" or "regtest"; only Bitcoin and Dogecoin
+    /// addresses and keys differ between networks
+    #[serde(default)]
+    pub network: NetworkType,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchOnlyAddressResponse {
+    pub chain_name: String,
+    pub chain_symbol: String,
+    pub address_type: String,
+    pub address: String,
+    pub relative_path: String,
+    pub change: u32,
+    pub index: u32,
+    pub public_key: String,
+    #[serde(default)]
+    pub public_key_formats: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DerivePublicResponse {
+    pub addresses: Vec<WatchOnlyAddressResponse>,
+}
+
+// Address validation
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidateAddressRequest {
+    pub address: String,
+    pub symbol: String,
+    /// Narrows symbols with several address types, e.g. "BitcoinNestedSegwit" for BTC
+    pub address_type: Option<String>,
+    /// Hex redeem script the `3...` address should commit to; only BTC
+    pub redeem_script: Option<String>,
+    /// Hex public key whose P2SH-P2WPKH address it should be, in place of `redeem_script`
+    pub public_key: Option<String>,
+    #[serde(default)]
+    pub network: NetworkType,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidateAddressResponse {
+    pub valid: bool,
+    /// Every address type of the symbol the address parses as
+    pub address_types: Vec<String>,
+    /// Whether the address wraps a witness program; only set when a redeem script or
+    /// public key was given, as no address alone tells nested SegWit from other P2SH
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nested_segwit: Option<bool>,
+}
+
+// Multisig wallets from cosigner keys
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultisigRequest {
+    /// Cosigners' account keys, `[fingerprint/path]xpub...` or bare keys (any SLIP-0132 prefix)
+    #[serde(default)]
+    pub cosigners: Vec<String>,
+    /// Signatures required to spend
+    pub threshold: u32,
+    /// "p2wsh" (default) or "p2sh_p2wsh"
+    #[serde(default)]
+    pub script_type: MultisigScriptType,
+    /// Adds this mnemonic's BIP48 key as a cosigner
+    pub mnemonic: Option<SecretString>,
+    #[serde(default)]
+    pub passphrase: SecretString,
+    /// Mnemonic wordlist, e.g. "japanese"; omitted detects it from the words
+    #[serde(default)]
+    pub language: Option<String>,
+    /// BIP48 account of the mnemonic's key
+    #[serde(default)]
+    pub account: u32,
+    /// 0 for receive addresses, 1 for change
+    #[serde(default)]
+    pub change: u32,
+    #[serde(default)]
+    pub start_index: u32,
+    pub count: u32,
+    /// "mainnet" (default), "testnet", "signet" or "regtest"
+    #[serde(default)]
+    pub network: NetworkType,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultisigAddressResponse {
+    pub address: String,
+    pub relative_path: String,
+    pub change: u32,
+    pub index: u32,
+    /// Hex; spending needs it along with `threshold` signatures
+    pub witness_script: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultisigResponse {
+    pub script_type: MultisigScriptType,
+    pub threshold: u32,
+    pub total_keys: u32,
+    /// BIP380 descriptors for Bitcoin Core `importdescriptors` and Sparrow
+    pub receive_descriptor: String,
+    pub change_descriptor: String,
+    /// The mnemonic's cosigner key, `[fingerprint/48'/0'/account'/2']xpub...`, to share
+    /// with the other cosigners
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub own_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mnemonic_language: Option<String>,
+    pub addresses: Vec<MultisigAddressResponse>,
+}
+
+// Miniscript spending policies
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PolicyRequest {
+    /// e.g. "or(99@pk(owner),and(pk(recovery),older(52560)))"
+    pub policy: String,
+    /// Hex public keys by the names the policy uses; the policy may also use hex keys directly
+    #[serde(default)]
+    pub keys: BTreeMap<String, String>,
+    /// "p2wsh" (default) or "taproot"
+    #[serde(default)]
+    pub script_type: PolicyScriptType,
+    /// "mainnet" (default), "testnet", "signet" or "regtest"
+    #[serde(default)]
+    pub network: NetworkType,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TapLeafResponse {
+    pub miniscript: String,
+    /// Hex
+    pub script: String,
+    pub leaf_version: u8,
+    /// Hex; follows the script in a script path spend's witness
+    pub control_block: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PolicyResponse {
+    pub script_type: PolicyScriptType,
+    /// BIP380 descriptor for Bitcoin Core `importdescriptors`
+    pub descriptor: String,
+    pub address: String,
+    /// The whole policy as one miniscript and its hex witness script (P2WSH)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub miniscript: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub witness_script: Option<String>,
+    /// X-only key path key (Taproot); BIP341's unspendable key when no key spends alone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub internal_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub leaves: Vec<TapLeafResponse>,
+}
+
+// PSBT signing
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UtxoRequest {
+    pub txid: String,
+    pub vout: u32,
+    /// Satoshis (koinu for DOGE)
+    pub value: u64,
+    /// e.g. "BitcoinSegwit"; may be omitted for DOGE
+    #[serde(default)]
+    pub address_type: Option<String>,
+    /// 0 for receive addresses, 1 for change
+    #[serde(default)]
+    pub change: u32,
+    pub index: u32,
+    /// Hex of the transaction that created the UTXO; required for legacy and DOGE inputs
+    #[serde(default)]
+    pub previous_transaction: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PayoutRequest {
+    pub address: String,
+    pub value: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignPsbtRequest {
+    pub mnemonic: SecretString,
+    #[serde(default)]
+    pub passphrase: SecretString,
+    /// Mnemonic wordlist, e.g. "japanese"; omitted detects it from the words
+    #[serde(default)]
+    pub language: Option<String>,
+    /// "BTC" or "DOGE"
+    pub symbol: String,
+    /// Base64 PSBT to sign; or else `utxos` and `outputs` to spend
+    #[serde(default)]
+    pub psbt: Option<String>,
+    #[serde(default)]
+    pub utxos: Vec<UtxoRequest>,
+    #[serde(default)]
+    pub outputs: Vec<PayoutRequest>,
+    #[serde(default)]
+    pub account: u32,
+    /// Return the signed PSBT without finalizing its inputs
+    #[serde(default)]
+    pub sign_only: bool,
+    /// Spend legacy and DOGE UTXOs without their previous transaction, trusting the
+    /// given amounts
+    #[serde(default)]
+    pub allow_missing_previous_transactions: bool,
+    /// Sign inputs asking for a sighash type other than ALL (or DEFAULT for Taproot)
+    #[serde(default)]
+    pub allow_any_sighash: bool,
+    /// "mainnet" (default), "testnet", "signet" or "regtest"
+    #[serde(default)]
+    pub network: NetworkType,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignPsbtResponse {
+    /// Base64, with this wallet's signatures and key origins
+    pub psbt: String,
+    pub signed_inputs: Vec<u32>,
+    /// Every input is final and `transaction` is ready to broadcast
+    pub complete: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub txid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u64>,
+    pub mnemonic_language: String,
+}
+
+// PSBT building
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BuildPsbtRequest {
+    pub mnemonic: SecretString,
+    #[serde(default)]
+    pub passphrase: SecretString,
+    /// Mnemonic wordlist, e.g. "japanese"; omitted detects it from the words
+    #[serde(default)]
+    pub language: Option<String>,
+    /// "BTC" or "DOGE"
+    pub symbol: String,
+    /// Candidates for coin selection
+    pub utxos: Vec<UtxoRequest>,
+    pub outputs: Vec<PayoutRequest>,
+    /// Satoshis (koinu for DOGE) per virtual byte; fractions allowed
+    pub fee_rate: f64,
+    /// Address type of the change output; omitted uses the first UTXO's
+    #[serde(default)]
+    pub change_address_type: Option<String>,
+    /// Index of the next unused change address
+    #[serde(default)]
+    pub change_index: u32,
+    /// Signal BIP125 replace-by-fee; defaults to true
+    #[serde(default)]
+    pub rbf: Option<bool>,
+    /// nLockTime: a block height, or a Unix time from 500000000
+    #[serde(default)]
+    pub locktime: u32,
+    #[serde(default)]
+    pub account: u32,
+    /// Spend legacy and DOGE UTXOs without their previous transaction, trusting the
+    /// given amounts
+    #[serde(default)]
+    pub allow_missing_previous_transactions: bool,
+    /// "mainnet" (default), "testnet", "signet" or "regtest"
+    #[serde(default)]
+    pub network: NetworkType,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangeOutputResponse {
+    /// Position among the transaction's outputs
+    pub output_index: u32,
+    pub address: String,
+    pub derivation_path: String,
+    pub value: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BuildPsbtResponse {
+    /// Base64, unsigned, with key origins for every input and the change output
+    pub psbt: String,
+    /// "branch_and_bound" or "knapsack"
+    pub algorithm: String,
+    /// Spent UTXOs as txid:vout
+    pub selected_utxos: Vec<String>,
+    pub fee: u64,
+    /// Expected virtual size once signed
+    pub vsize: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change: Option<ChangeOutputResponse>,
+    pub mnemonic_language: String,
+}
+
+// Message signing
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignMessageRequest {
+    pub mnemonic: SecretString,
+    #[serde(default)]
+    pub passphrase: SecretString,
+    /// Mnemonic wordlist, e.g. "japanese"; omitted detects it from the words
+    #[serde(default)]
+    pub language: Option<String>,
+    /// "BTC" or "DOGE"
+    pub symbol: String,
+    /// e.g. "BitcoinTaproot"; needed for BTC
+    #[serde(default)]
+    pub address_type: Option<String>,
+    #[serde(default)]
+    pub account: u32,
+    #[serde(default)]
+    pub index: u32,
+    pub message: String,
+    /// "bip137", "bip322_simple" or "bip322_full"; omitted picks the address type's usual one
+    #[serde(default)]
+    pub format: Option<MessageFormat>,
+    /// "mainnet" (default), "testnet", "signet" or "regtest"
+    #[serde(default)]
+    pub network: NetworkType,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignMessageResponse {
+    pub address: String,
+    pub derivation_path: String,
+    pub format: MessageFormat,
+    /// Base64
+    pub signature: String,
+    pub mnemonic_language: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyMessageRequest {
+    /// "BTC" or "DOGE"
+    pub symbol: String,
+    /// Any address of the coin, not only this wallet's
+    pub address: String,
+    pub message: String,
+    /// Base64 BIP137 or BIP322 signature
+    pub signature: String,
+    #[serde(default)]
+    pub network: NetworkType,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyMessageResponse {
+    pub valid: bool,
+    /// The format the signature was read as; absent when it could not be decoded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<MessageFormat>,
+}
+
+// Private key import
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportKeyRequest {
+    /// Hex, or the chain's native format (WIF, Solana keypair, edsk, ...)
+    pub private_key: SecretString,
+    pub symbol: String,
+    /// Narrows symbols with several address types, e.g. "BitcoinSegwit" for BTC
+    pub address_type: Option<String>,
+    /// "mainnet" (default), "testnet", "signet" or "regtest"; only Bitcoin and Dogecoin
+    /// addresses and keys differ between networks
+    #[serde(default)]
+    pub network: NetworkType,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportedKeyResponse {
+    pub chain_name: String,
+    pub chain_symbol: String,
+    pub address_type: String,
+    pub address: String,
+    pub public_key: String,
+    #[serde(default)]
+    pub public_key_formats: BTreeMap<String, String>,
+    pub curve: Curve,
+    /// Only set for secp256k1 keys
+    pub compressed: Option<bool>,
+    /// Format the private key was recognised in
+    pub key_format: KeyFormat,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportKeyResponse {
+    pub keys: Vec<ImportedKeyResponse>,
+}
+
 // Token information
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TokenInfo {
@@ -1,14 +1,286 @@
 use async_trait::async_trait;
-use bitcoin::{Address, PublicKey, XOnlyPublicKey};
-use bitcoin::bip32::{Xpriv, DerivationPath as BtcDerivationPath, ChildNumber};
-use bitcoin::secp256k1::Secp256k1;
+use bitcoin::{Address, AddressType, PublicKey, Script, XOnlyPublicKey};
+use bitcoin::key::TapTweak;
+use bitcoin::bip32::{ChildNumber, Xpub};
+use bitcoin::secp256k1::{self, Secp256k1};
+use std::collections::BTreeMap;
+use std::str::FromStr;
 
-use crate::core::{Chain, ChainInfo, ChainType, WalletAddress, DerivationPath, get_chain_info};
+use zeroize::Zeroizing;
+
+use crate::core::{Chain, KeyTree, ChainInfo, ChainType, AddressFormat, WalletAddress, SecretString, KeyFormat, DerivationPath, ExtendedPublicKey, ImportedKey, get_chain_info, decode_hex_key, import_secp256k1_key};
+use crate::chains::descriptor;
 use crate::errors::{ApiError, ApiResult};
 
 // Re-export Network so it can be used in mod.rs
 pub use bitcoin::Network;
 
+/// SLIP-0132 version bytes that tell wallets which script type an extended key is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XpubFormat {
+    /// BIP44 P2PKH, BIP86 Taproot, and every non-Bitcoin BIP32 chain
+    Xpub,
+    /// BIP49 P2SH-P2WPKH
+    Ypub,
+    /// BIP84 P2WPKH
+    Zpub,
+    /// Dogecoin BIP44
+    Dgub,
+    /// BIP48 P2SH-P2WSH multisig cosigner
+    MultisigYpub,
+    /// BIP48 P2WSH multisig cosigner
+    MultisigZpub,
+}
+
+impl XpubFormat {
+    const ALL: [XpubFormat; 6] = [
+        XpubFormat::Xpub,
+        XpubFormat::Ypub,
+        XpubFormat::Zpub,
+        XpubFormat::Dgub,
+        XpubFormat::MultisigYpub,
+        XpubFormat::MultisigZpub,
+    ];
+
+    /// The format `chain_type`'s account keys are exported in
+    pub fn for_chain(chain_type: &ChainType) -> XpubFormat {
+        match chain_type {
+            ChainType::BitcoinNestedSegwit => XpubFormat::Ypub,
+            ChainType::BitcoinSegwit => XpubFormat::Zpub,
+            ChainType::Dogecoin => XpubFormat::Dgub,
+            _ => XpubFormat::Xpub,
+        }
+    }
+
+    /// Whether keys in this format can derive `chain_type`'s addresses; xpub and tpub
+    /// name no script type and fit any
+    pub fn fits(&self, chain_type: &ChainType) -> bool {
+        *self == XpubFormat::Xpub || *self == XpubFormat::for_chain(chain_type)
+    }
+
+    fn version(&self, network: Network) -> [u8; 4] {
+        match (self, network == Network::Bitcoin) {
+            (XpubFormat::Xpub, true) => [0x04, 0x88, 0xB2, 0x1E],
+            (XpubFormat::Xpub, false) => [0x04, 0x35, 0x87, 0xCF],
+            (XpubFormat::Ypub, true) => [0x04, 0x9D, 0x7C, 0xB2],
+            (XpubFormat::Ypub, false) => [0x04, 0x4A, 0x52, 0x62],
+            (XpubFormat::Zpub, true) => [0x04, 0xB2, 0x47, 0x46],
+            (XpubFormat::Zpub, false) => [0x04, 0x5F, 0x1C, 0xF6],
+            (XpubFormat::Dgub, true) => [0x02, 0xFA, 0xCA, 0xFD],
+            (XpubFormat::Dgub, false) => [0x04, 0x32, 0xA9, 0xA8],
+            (XpubFormat::MultisigYpub, true) => [0x02, 0x95, 0xB4, 0x3F],
+            (XpubFormat::MultisigYpub, false) => [0x02, 0x42, 0x89, 0xEF],
+            (XpubFormat::MultisigZpub, true) => [0x02, 0xAA, 0x7E, 0xD3],
+            (XpubFormat::MultisigZpub, false) => [0x02, 0x57, 0x54, 0x83],
+        }
+    }
+
+    pub fn prefix(&self, network: Network) -> &'static str {
+        match (self, network == Network::Bitcoin) {
+            (XpubFormat::Xpub, true) => "xpub",
+            (XpubFormat::Xpub, false) => "tpub",
+            (XpubFormat::Ypub, true) => "ypub",
+            (XpubFormat::Ypub, false) => "upub",
+            (XpubFormat::Zpub, true) => "zpub",
+            (XpubFormat::Zpub, false) => "vpub",
+            (XpubFormat::Dgub, true) => "dgub",
+            (XpubFormat::Dgub, false) => "tgub",
+            (XpubFormat::MultisigYpub, true) => "Ypub",
+            (XpubFormat::MultisigYpub, false) => "Upub",
+            (XpubFormat::MultisigZpub, true) => "Zpub",
+            (XpubFormat::MultisigZpub, false) => "Vpub",
+        }
+    }
+
+    /// Base58check-encode `xpub` with this format's version bytes
+    pub fn encode(&self, xpub: &Xpub) -> String {
+        let mut data = xpub.encode();
+        data[..4].copy_from_slice(&self.version(xpub.network));
+        bitcoin::base58::encode_check(&data)
+    }
+
+    /// Parse an extended public key with any supported SLIP-0132 prefix
+    pub fn decode(s: &str) -> ApiResult<(XpubFormat, Xpub)> {
+        let invalid = |reason: String| ApiError::BadRequest(format!("Invalid extended public key: {}", reason));
+
+        let mut data = bitcoin::base58::decode_check(s.trim()).map_err(|e| invalid(e.to_string()))?;
+        if data.len() != 78 {
+            return Err(invalid(format!("expected 78 bytes, got {}", data.len())));
+        }
+
+        for network in [Network::Bitcoin, Network::Testnet] {
+            for format in Self::ALL {
+                if data[..4] == format.version(network) {
+                    // Swap in the plain xpub/tpub version the bitcoin crate understands
+                    data[..4].copy_from_slice(&XpubFormat::Xpub.version(network));
+                    let xpub = Xpub::decode(&data).map_err(|e| invalid(e.to_string()))?;
+                    return Ok((format, xpub));
+                }
+            }
+        }
+
+        Err(invalid(format!("unknown version bytes {}", hex::encode(&data[..4]))))
+    }
+}
+
+/// Parse a compressed or uncompressed SEC1 secp256k1 public key
+pub fn secp256k1_public_key(public_key: &[u8]) -> ApiResult<secp256k1::PublicKey> {
+    secp256k1::PublicKey::from_slice(public_key)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid secp256k1 public key: {}", e)))
+}
+
+/// Non-hardened `change/index` child of an account-level extended public key
+pub fn derive_public_child(xpub: &Xpub, change: u32, index: u32) -> ApiResult<secp256k1::PublicKey> {
+    let path = [change, index]
+        .into_iter()
+        .map(ChildNumber::from_normal_idx)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ApiError::InvalidDerivationPath(format!("{}/{}: {}", change, index, e)))?;
+
+    let secp = Secp256k1::verification_only();
+    let child = xpub.derive_pub(&secp, &path)
+        .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+
+    Ok(child.public_key)
+}
+
+/// Derive the extended public key of the account node at `account_path`,
+/// the watch-only parent of every address `generate_address` derives for `account`
+pub fn derive_account_xpub(
+    chain_type: ChainType,
+    network: Network,
+    keys: &KeyTree,
+    account_path: &DerivationPath,
+    account: u32,
+    format: XpubFormat,
+) -> ApiResult<ExtendedPublicKey> {
+    let master = keys.secp256k1_key(&DerivationPath::hardened(&[]))?;
+    let master_fingerprint = master.fingerprint(keys.secp());
+
+    let mut account_key = keys.secp256k1_key(account_path)?;
+    account_key.network = network;
+    let xpub = Xpub::from_priv(keys.secp(), &account_key);
+
+    // Key origin drops the leading "m": [fingerprint/84'/0'/0']
+    let path = account_path.to_string();
+    let key_origin = format!("[{}{}]", master_fingerprint, path.trim_start_matches('m'));
+    let receive_descriptor = descriptor::single_key(&chain_type, &key_origin, &xpub, 0)?;
+    let change_descriptor = descriptor::single_key(&chain_type, &key_origin, &xpub, 1)?;
+
+    Ok(ExtendedPublicKey {
+        chain_info: get_chain_info(&chain_type),
+        chain_type,
+        extended_public_key: format.encode(&xpub),
+        format: format.prefix(network).to_string(),
+        master_fingerprint: master_fingerprint.to_string(),
+        derivation_path: path,
+        key_origin,
+        account,
+        receive_descriptor,
+        change_descriptor,
+    })
+}
+
+/// BIP44 coin type of Bitcoin on `network`; every test network uses 1
+pub fn coin_type(network: Network) -> u32 {
+    match network {
+        Network::Bitcoin => 0,
+        _ => 1,
+    }
+}
+
+/// Chain info with the coin type and address prefixes of `network`
+fn network_chain_info(chain_type: &ChainType, network: Network) -> ChainInfo {
+    let mut info = get_chain_info(chain_type);
+    info.coin_type = coin_type(network);
+    info.address_format = match (info.address_format, network) {
+        (format, Network::Bitcoin) => format,
+        (AddressFormat::Bech32 { .. }, Network::Regtest) => AddressFormat::Bech32 { hrp: "bcrt".to_string() },
+        (AddressFormat::Bech32 { .. }, _) => AddressFormat::Bech32 { hrp: "tb".to_string() },
+        // Test network P2SH addresses start with 2, P2PKH ones with m or n
+        (AddressFormat::Bitcoin { prefix }, _) if prefix == "3" => AddressFormat::Bitcoin { prefix: "2".to_string() },
+        (AddressFormat::Bitcoin { .. }, _) => AddressFormat::Bitcoin { prefix: "m".to_string() },
+        (format, _) => format,
+    };
+    info
+}
+
+/// Whether `address` is an `address_type` address on `network`. Base58 addresses are
+/// shared by every test network; bech32 ones use `tb` on testnet and signet and
+/// `bcrt` on regtest.
+fn is_address_of_type(address: &str, network: Network, address_type: AddressType) -> bool {
+    Address::from_str(address)
+        .ok()
+        .and_then(|address| address.require_network(network).ok())
+        .is_some_and(|address| address.address_type() == Some(address_type))
+}
+
+/// Whether `address` is a P2SH address on `network` committing to `redeem_script`, and
+/// that script is a witness program (P2SH-P2WPKH or P2SH-P2WSH). The address alone only
+/// shows a script hash; it takes the redeem script to tell nested SegWit from any other P2SH.
+pub fn is_nested_witness_address(address: &str, redeem_script: &[u8], network: Network) -> bool {
+    let redeem_script = Script::from_bytes(redeem_script);
+    if !redeem_script.is_witness_program() {
+        return false;
+    }
+    
+    let Ok(expected) = Address::p2sh(redeem_script, network) else {
+        return false;
+    };
+    Address::from_str(address)
+        .ok()
+        .and_then(|address| address.require_network(network).ok())
+        .is_some_and(|address| address == expected)
+}
+
+/// A secp256k1 private key in `format`; Bitcoin's native format is compressed WIF
+pub fn encode_private_key(private_key: &secp256k1::SecretKey, network: Network, format: KeyFormat) -> SecretString {
+    match format {
+        KeyFormat::Hex => SecretString::hex_encode(private_key.secret_bytes()),
+        KeyFormat::Native => SecretString::new(bitcoin::PrivateKey::new(*private_key, network).to_wif()),
+    }
+}
+
+/// WIF version byte of Bitcoin private keys on `network`
+pub fn wif_version(network: Network) -> u8 {
+    match network {
+        Network::Bitcoin => 0x80,
+        _ => 0xef,
+    }
+}
+
+/// Parse a secp256k1 private key given as hex or as WIF with one of `wif_versions`.
+/// Returns the key, whether its public key is compressed (hex keys always are), and
+/// the format it was given in.
+pub fn decode_private_key(
+    private_key: &SecretString,
+    wif_versions: &[u8],
+    chain_name: &str,
+) -> ApiResult<(secp256k1::SecretKey, bool, KeyFormat)> {
+    let invalid = || ApiError::InvalidPrivateKey(chain_name.to_string());
+    
+    if let Some(key) = decode_hex_key(private_key.expose_secret()) {
+        let secret_key = secp256k1::SecretKey::from_slice(&key[..]).map_err(|_| invalid())?;
+        return Ok((secret_key, true, KeyFormat::Hex));
+    }
+    
+    // version || key, followed by 0x01 when the public key is compressed
+    let data = Zeroizing::new(
+        bitcoin::base58::decode_check(private_key.expose_secret().trim()).map_err(|_| invalid())?,
+    );
+    let compressed = match data.len() {
+        33 => false,
+        34 if data[33] == 0x01 => true,
+        _ => return Err(invalid()),
+    };
+    if !wif_versions.contains(&data[0]) {
+        return Err(invalid());
+    }
+    
+    let secret_key = secp256k1::SecretKey::from_slice(&data[1..33]).map_err(|_| invalid())?;
+    Ok((secret_key, compressed, KeyFormat::Native))
+}
+
 // Bitcoin Legacy implementation
 pub struct BitcoinLegacy {
     network: Network,
@@ -23,72 +295,181 @@ impl BitcoinLegacy {
 #[async_trait]
 impl Chain for BitcoinLegacy {
     fn info(&self) -> ChainInfo {
-        get_chain_info(&ChainType::BitcoinLegacy)
+        network_chain_info(&ChainType::BitcoinLegacy, self.network)
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
-        let secp = Secp256k1::new();
-        let master = Xpriv::new_master(self.network, seed)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-
-        let path = self.derivation_path(index);
-        let btc_path = BtcDerivationPath::from(vec![
-            ChildNumber::from_hardened_idx(path.purpose).unwrap(),
-            ChildNumber::from_hardened_idx(path.coin_type).unwrap(),
-            ChildNumber::from_hardened_idx(path.account).unwrap(),
-            ChildNumber::from_normal_idx(path.change).unwrap(),
-            ChildNumber::from_normal_idx(path.index).unwrap(),
-        ]);
-        
-        let child = master.derive_priv(&secp, &btc_path)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
+        let child = keys.secp256k1_key(&path)?;
         
         let private_key = child.private_key;
-        let secp_pubkey = private_key.public_key(&secp);
+        let secp_pubkey = private_key.public_key(keys.secp());
         
+        let address = self.address_from_public_key(&secp_pubkey.serialize())?;
+        
+        Ok(WalletAddress {
+            address,
+            chain_type: ChainType::BitcoinLegacy,
+            chain_info: self.info(),
+            derivation_path: path.to_string(),
+            account,
+            index,
+            public_key: hex::encode(secp_pubkey.serialize()),
+            public_key_formats: self.public_key_formats(&secp_pubkey.serialize())?,
+            private_key: key_format.map(|format| encode_private_key(&private_key, self.network, format)),
+        })
+    }
+
+    fn derivation_path(&self, account: u32, index: u32) -> DerivationPath {
+        DerivationPath::new(44, coin_type(self.network), account, 0, index)
+    }
+
+    fn account_xpub(&self, keys: &KeyTree, account: u32) -> ApiResult<ExtendedPublicKey> {
+        let account_path = self.derivation_path(account, 0).truncated(3);
+        derive_account_xpub(ChainType::BitcoinLegacy, self.network, keys, &account_path, account, XpubFormat::Xpub)
+    }
+
+    fn address_from_private_key(&self, private_key: &SecretString) -> ApiResult<ImportedKey> {
+        let (secret_key, compressed, key_format) =
+            decode_private_key(private_key, &[wif_version(self.network)], &self.info().name)?;
+        import_secp256k1_key(self, &secret_key, compressed, key_format)
+    }
+
+    fn address_from_public_key(&self, public_key: &[u8]) -> ApiResult<String> {
+        let secp_pubkey = secp256k1_public_key(public_key)?;
+        
+        // Uncompressed keys (65 bytes) hash to a different address
         let bitcoin_pubkey = PublicKey {
-            compressed: true,
+            compressed: public_key.len() != 65,
             inner: secp_pubkey,
         };
         
         let address = Address::p2pkh(&bitcoin_pubkey, self.network);
         
+        Ok(address.to_string())
+    }
+
+    async fn validate_address(&self, address: &str) -> bool {
+        is_address_of_type(address, self.network, AddressType::P2pkh)
+    }
+
+    fn example_address(&self) -> &str {
+        "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"
+    }
+}
+
+// Bitcoin nested SegWit (BIP49 P2SH-P2WPKH) implementation
+pub struct BitcoinNestedSegwit {
+    network: Network,
+}
+
+impl BitcoinNestedSegwit {
+    pub fn new(network: Network) -> Self {
+        Self { network }
+    }
+}
+
+/// P2WPKH witness program wrapped by a nested SegWit address
+pub fn p2wpkh_redeem_script(public_key: &[u8]) -> ApiResult<bitcoin::ScriptBuf> {
+    let secp_pubkey = secp256k1_public_key(public_key)?;
+    if public_key.len() == 65 {
+        return Err(ApiError::BadRequest("SegWit addresses require a compressed public key".to_string()));
+    }
+    
+    let bitcoin_pubkey = PublicKey {
+        compressed: true,
+        inner: secp_pubkey,
+    };
+    let wpubkey_hash = bitcoin_pubkey
+        .wpubkey_hash()
+        .ok_or_else(|| ApiError::CryptoError("Public key is not compressed".to_string()))?;
+    
+    Ok(bitcoin::ScriptBuf::new_p2wpkh(&wpubkey_hash))
+}
+
+#[async_trait]
+impl Chain for BitcoinNestedSegwit {
+    fn info(&self) -> ChainInfo {
+        network_chain_info(&ChainType::BitcoinNestedSegwit, self.network)
+    }
+
+    fn generate_address(
+        &self,
+        keys: &KeyTree,
+        _passphrase: &str,
+        account: u32,
+        index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
+    ) -> ApiResult<WalletAddress> {
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
+        let child = keys.secp256k1_key(&path)?;
+        
+        let private_key = child.private_key;
+        let secp_pubkey = private_key.public_key(keys.secp());
+        
+        let address = self.address_from_public_key(&secp_pubkey.serialize())?;
+        
         Ok(WalletAddress {
-            address: address.to_string(),
-            chain_type: ChainType::BitcoinLegacy,
+            address,
+            chain_type: ChainType::BitcoinNestedSegwit,
             chain_info: self.info(),
             derivation_path: path.to_string(),
+            account,
             index,
             public_key: hex::encode(secp_pubkey.serialize()),
-            private_key: hex::encode(private_key.secret_bytes()),
+            public_key_formats: self.public_key_formats(&secp_pubkey.serialize())?,
+            private_key: key_format.map(|format| encode_private_key(&private_key, self.network, format)),
         })
     }
 
-    fn derivation_path(&self, index: u32) -> DerivationPath {
-        DerivationPath::new(44, 0, 0, 0, index)
+    fn derivation_path(&self, account: u32, index: u32) -> DerivationPath {
+        DerivationPath::new(49, coin_type(self.network), account, 0, index)
+    }
+
+    fn account_xpub(&self, keys: &KeyTree, account: u32) -> ApiResult<ExtendedPublicKey> {
+        let account_path = self.derivation_path(account, 0).truncated(3);
+        derive_account_xpub(ChainType::BitcoinNestedSegwit, self.network, keys, &account_path, account, XpubFormat::Ypub)
+    }
+
+    fn address_from_private_key(&self, private_key: &SecretString) -> ApiResult<ImportedKey> {
+        let (secret_key, compressed, key_format) =
+            decode_private_key(private_key, &[wif_version(self.network)], &self.info().name)?;
+        import_secp256k1_key(self, &secret_key, compressed, key_format)
+    }
+
+    fn address_from_public_key(&self, public_key: &[u8]) -> ApiResult<String> {
+        let redeem_script = p2wpkh_redeem_script(public_key)?;
+        
+        let address = Address::p2sh(&redeem_script, self.network)
+            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        
+        Ok(address.to_string())
+    }
+
+    fn public_key_formats(&self, public_key: &[u8]) -> ApiResult<BTreeMap<String, String>> {
+        // Spending needs the redeem script, and it proves the address wraps this key
+        let redeem_script = p2wpkh_redeem_script(public_key)?;
+        
+        Ok(BTreeMap::from([("redeem_script".to_string(), hex::encode(redeem_script.as_bytes()))]))
     }
 
     async fn validate_address(&self, address: &str) -> bool {
-        use std::str::FromStr;
-        match Address::from_str(address) {
-            Ok(addr) => {
-                // Check if it's valid for the network and starts with 1
-                match addr.require_network(self.network) {
-                    Ok(_) => address.starts_with('1'),
-                    Err(_) => false,
-                }
-            }
-            Err(_) => false,
-        }
+        // Any P2SH address could be nested SegWit; `is_nested_witness_address` needs the
+        // redeem script to tell them apart
+        is_address_of_type(address, self.network, AddressType::P2sh)
     }
 
     fn example_address(&self) -> &str {
-        "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"
+        "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf"
     }
 }
 
@@ -106,69 +487,73 @@ impl BitcoinSegwit {
 #[async_trait]
 impl Chain for BitcoinSegwit {
     fn info(&self) -> ChainInfo {
-        get_chain_info(&ChainType::BitcoinSegwit)
+        network_chain_info(&ChainType::BitcoinSegwit, self.network)
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
-        let secp = Secp256k1::new();
-        let master = Xpriv::new_master(self.network, seed)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-
-        let path = self.derivation_path(index);
-        let btc_path = BtcDerivationPath::from(vec![
-            ChildNumber::from_hardened_idx(path.purpose).unwrap(),
-            ChildNumber::from_hardened_idx(path.coin_type).unwrap(),
-            ChildNumber::from_hardened_idx(path.account).unwrap(),
-            ChildNumber::from_normal_idx(path.change).unwrap(),
-            ChildNumber::from_normal_idx(path.index).unwrap(),
-        ]);
-        
-        let child = master.derive_priv(&secp, &btc_path)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
+        let child = keys.secp256k1_key(&path)?;
         
         let private_key = child.private_key;
-        let secp_pubkey = private_key.public_key(&secp);
-        
-        let bitcoin_pubkey = PublicKey {
-            compressed: true,
-            inner: secp_pubkey,
-        };
+        let secp_pubkey = private_key.public_key(keys.secp());
         
-        let address = Address::p2wpkh(&bitcoin_pubkey, self.network)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        let address = self.address_from_public_key(&secp_pubkey.serialize())?;
         
         Ok(WalletAddress {
-            address: address.to_string(),
+            address,
             chain_type: ChainType::BitcoinSegwit,
             chain_info: self.info(),
             derivation_path: path.to_string(),
+            account,
             index,
             public_key: hex::encode(secp_pubkey.serialize()),
-            private_key: hex::encode(private_key.secret_bytes()),
+            public_key_formats: self.public_key_formats(&secp_pubkey.serialize())?,
+            private_key: key_format.map(|format| encode_private_key(&private_key, self.network, format)),
         })
     }
 
-    fn derivation_path(&self, index: u32) -> DerivationPath {
-        DerivationPath::new(84, 0, 0, 0, index)
+    fn derivation_path(&self, account: u32, index: u32) -> DerivationPath {
+        DerivationPath::new(84, coin_type(self.network), account, 0, index)
     }
 
-    async fn validate_address(&self, address: &str) -> bool {
-        use std::str::FromStr;
-        match Address::from_str(address) {
-            Ok(addr) => {
-                // Check if it's valid for the network and starts with bc1q
-                match addr.require_network(self.network) {
-                    Ok(_) => address.starts_with("bc1q"),
-                    Err(_) => false,
-                }
-            }
-            Err(_) => false,
+    fn account_xpub(&self, keys: &KeyTree, account: u32) -> ApiResult<ExtendedPublicKey> {
+        let account_path = self.derivation_path(account, 0).truncated(3);
+        derive_account_xpub(ChainType::BitcoinSegwit, self.network, keys, &account_path, account, XpubFormat::Zpub)
+    }
+
+    fn address_from_private_key(&self, private_key: &SecretString) -> ApiResult<ImportedKey> {
+        let (secret_key, compressed, key_format) =
+            decode_private_key(private_key, &[wif_version(self.network)], &self.info().name)?;
+        import_secp256k1_key(self, &secret_key, compressed, key_format)
+    }
+
+    fn address_from_public_key(&self, public_key: &[u8]) -> ApiResult<String> {
+        let secp_pubkey = secp256k1_public_key(public_key)?;
+        if public_key.len() == 65 {
+            return Err(ApiError::BadRequest("SegWit addresses require a compressed public key".to_string()));
         }
+        
+        let bitcoin_pubkey = PublicKey {
+            compressed: true,
+            inner: secp_pubkey,
+        };
+        
+        let address = Address::p2wpkh(&bitcoin_pubkey, self.network)
+            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        
+        Ok(address.to_string())
+    }
+
+    async fn validate_address(&self, address: &str) -> bool {
+        is_address_of_type(address, self.network, AddressType::P2wpkh)
     }
 
     fn example_address(&self) -> &str {
@@ -190,67 +575,337 @@ impl BitcoinTaproot {
 #[async_trait]
 impl Chain for BitcoinTaproot {
     fn info(&self) -> ChainInfo {
-        get_chain_info(&ChainType::BitcoinTaproot)
+        network_chain_info(&ChainType::BitcoinTaproot, self.network)
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
-        let secp = Secp256k1::new();
-        let master = Xpriv::new_master(self.network, seed)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-
-        let path = self.derivation_path(index);
-        let btc_path = BtcDerivationPath::from(vec![
-            ChildNumber::from_hardened_idx(path.purpose).unwrap(),
-            ChildNumber::from_hardened_idx(path.coin_type).unwrap(),
-            ChildNumber::from_hardened_idx(path.account).unwrap(),
-            ChildNumber::from_normal_idx(path.change).unwrap(),
-            ChildNumber::from_normal_idx(path.index).unwrap(),
-        ]);
-        
-        let child = master.derive_priv(&secp, &btc_path)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
+        let child = keys.secp256k1_key(&path)?;
         
         let private_key = child.private_key;
-        let secp_pubkey = private_key.public_key(&secp);
-        let x_only_pubkey = XOnlyPublicKey::from(secp_pubkey);
+        let secp_pubkey = private_key.public_key(keys.secp());
         
-        let address = Address::p2tr(&secp, x_only_pubkey, None, self.network);
+        let address = self.address_from_public_key(&secp_pubkey.serialize())?;
         
         Ok(WalletAddress {
-            address: address.to_string(),
+            address,
             chain_type: ChainType::BitcoinTaproot,
             chain_info: self.info(),
             derivation_path: path.to_string(),
+            account,
             index,
             public_key: hex::encode(secp_pubkey.serialize()),
-            private_key: hex::encode(private_key.secret_bytes()),
+            public_key_formats: self.public_key_formats(&secp_pubkey.serialize())?,
+            private_key: key_format.map(|format| encode_private_key(&private_key, self.network, format)),
         })
     }
 
-    fn derivation_path(&self, index: u32) -> DerivationPath {
-        DerivationPath::new(86, 0, 0, 0, index)
+    fn derivation_path(&self, account: u32, index: u32) -> DerivationPath {
+        DerivationPath::new(86, coin_type(self.network), account, 0, index)
     }
 
-    async fn validate_address(&self, address: &str) -> bool {
-        use std::str::FromStr;
-        match Address::from_str(address) {
-            Ok(addr) => {
-                // Check if it's valid for the network and starts with bc1p
-                match addr.require_network(self.network) {
-                    Ok(_) => address.starts_with("bc1p"),
-                    Err(_) => false,
-                }
-            }
-            Err(_) => false,
+    fn account_xpub(&self, keys: &KeyTree, account: u32) -> ApiResult<ExtendedPublicKey> {
+        let account_path = self.derivation_path(account, 0).truncated(3);
+        derive_account_xpub(ChainType::BitcoinTaproot, self.network, keys, &account_path, account, XpubFormat::Xpub)
+    }
+
+    fn address_from_private_key(&self, private_key: &SecretString) -> ApiResult<ImportedKey> {
+        let (secret_key, compressed, key_format) =
+            decode_private_key(private_key, &[wif_version(self.network)], &self.info().name)?;
+        import_secp256k1_key(self, &secret_key, compressed, key_format)
+    }
+
+    fn address_from_public_key(&self, public_key: &[u8]) -> ApiResult<String> {
+        let secp = Secp256k1::verification_only();
+        let secp_pubkey = secp256k1_public_key(public_key)?;
+        if public_key.len() == 65 {
+            return Err(ApiError::BadRequest("Taproot addresses require a compressed public key".to_string()));
         }
+        
+        let x_only_pubkey = XOnlyPublicKey::from(secp_pubkey);
+        
+        let address = Address::p2tr(&secp, x_only_pubkey, None, self.network);
+        
+        Ok(address.to_string())
+    }
+
+    fn public_key_formats(&self, public_key: &[u8]) -> ApiResult<BTreeMap<String, String>> {
+        let secp = Secp256k1::verification_only();
+        let internal_key = XOnlyPublicKey::from(secp256k1_public_key(public_key)?);
+        
+        // BIP86 commits to no script tree, so the output key is the internal key tweaked by itself
+        let (output_key, _) = internal_key.tap_tweak(&secp, None);
+        
+        Ok(BTreeMap::from([
+            ("x_only".to_string(), hex::encode(internal_key.serialize())),
+            ("output_key".to_string(), hex::encode(output_key.to_inner().serialize())),
+        ]))
+    }
+
+    async fn validate_address(&self, address: &str) -> bool {
+        is_address_of_type(address, self.network, AddressType::P2tr)
     }
 
     fn example_address(&self) -> &str {
         "bc1p5d7rjq7g6rdk2yhzks9smlaqtedr4dekq08ge8ztwac72sfr9rusxg3297"
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bip39::Mnemonic;
+    
+    const TEST_MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    
+    #[test]
+    fn test_account_xpub_vectors() {
+        let seed = Mnemonic::parse(TEST_MNEMONIC).unwrap().to_seed("");
+        let keys = KeyTree::new(&seed);
+        
+        // BIP84 test vector
+        let zpub = BitcoinSegwit::new(Network::Bitcoin).account_xpub(&keys, 0).unwrap();
+        assert_eq!(
+            zpub.extended_public_key,
+            "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs"
+        );
+        assert_eq!(zpub.format, "zpub");
+        assert_eq!(zpub.master_fingerprint, "73c5da0a");
+        assert_eq!(zpub.derivation_path, "m/84'/0'/0'");
+        assert_eq!(zpub.key_origin, "[73c5da0a/84'/0'/0']");
+        
+        // Descriptors carry the plain xpub version, which Core and Sparrow require
+        assert_eq!(
+            zpub.receive_descriptor.unwrap(),
+            "wpkh([73c5da0a/84'/0'/0']xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V/0/*)#wc3n3van"
+        );
+        assert!(zpub.change_descriptor.unwrap().starts_with("wpkh([73c5da0a/84'/0'/0']xpub6CatWdiZ"));
+        
+        let xpub = BitcoinLegacy::new(Network::Bitcoin).account_xpub(&keys, 0).unwrap();
+        assert_eq!(
+            xpub.extended_public_key,
+            "xpub6BosfCnifzxcFwrSzQiqu2DBVTshkCXacvNsWGYJVVhhawA7d4R5WSWGFNbi8Aw6ZRc1brxMyWMzG3DSSSSoekkudhUd9yLb6qx39T9nMdj"
+        );
+        
+        let xpub = BitcoinLegacy::new(Network::Bitcoin).account_xpub(&keys, 1).unwrap();
+        assert_eq!(xpub.key_origin, "[73c5da0a/44'/0'/1']");
+    }
+    
+    #[tokio::test]
+    async fn test_watch_only_derivation_matches_seed() {
+        let seed = Mnemonic::parse(TEST_MNEMONIC).unwrap().to_seed("");
+        let keys = KeyTree::new(&seed);
+        let segwit = BitcoinSegwit::new(Network::Bitcoin);
+        
+        let zpub = segwit.account_xpub(&keys, 0).unwrap();
+        let (format, xpub) = XpubFormat::decode(&zpub.extended_public_key).unwrap();
+        assert_eq!(format, XpubFormat::Zpub);
+        
+        // BIP84 test vectors for the first receive and change addresses
+        let receive = derive_public_child(&xpub, 0, 0).unwrap();
+        assert_eq!(
+            segwit.address_from_public_key(&receive.serialize()).unwrap(),
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        );
+        let change = derive_public_child(&xpub, 1, 0).unwrap();
+        assert_eq!(
+            segwit.address_from_public_key(&change.serialize()).unwrap(),
+            "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el"
+        );
+        
+        let wallet = segwit.generate_address(&keys, "", 0, 7, None, Some(KeyFormat::Hex)).unwrap();
+        let derived = derive_public_child(&xpub, 0, 7).unwrap();
+        assert_eq!(segwit.address_from_public_key(&derived.serialize()).unwrap(), wallet.address);
+        
+        assert!(derive_public_child(&xpub, 0, 0x8000_0000).is_err());
+    }
+    
+    #[test]
+    fn test_private_key_wif() {
+        let private_key = secp256k1::SecretKey::from_slice(
+            &hex::decode("0c28fca386c7a227600b2fe50b7cae11ec86d3bf1fbe471be89827e19d72aa1d").unwrap(),
+        )
+        .unwrap();
+        
+        let wif = encode_private_key(&private_key, Network::Bitcoin, KeyFormat::Native);
+        assert_eq!(wif.expose_secret(), "KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98617");
+        let hex = encode_private_key(&private_key, Network::Bitcoin, KeyFormat::Hex);
+        assert_eq!(hex.expose_secret(), "0c28fca386c7a227600b2fe50b7cae11ec86d3bf1fbe471be89827e19d72aa1d");
+    }
+    
+    #[test]
+    fn test_taproot_x_only_keys() {
+        // BIP86 test vector, first receive address
+        let seed = Mnemonic::parse(TEST_MNEMONIC).unwrap().to_seed("");
+        let keys = KeyTree::new(&seed);
+        let wallet = BitcoinTaproot::new(Network::Bitcoin).generate_address(&keys, "", 0, 0, None, None).unwrap();
+        
+        assert_eq!(wallet.address, "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr");
+        assert_eq!(wallet.public_key_formats["x_only"], "cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115");
+        assert_eq!(wallet.public_key_formats["output_key"], "a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c");
+        assert!(wallet.private_key.is_none());
+    }
+    
+    #[test]
+    fn test_import_wif() {
+        let legacy = BitcoinLegacy::new(Network::Bitcoin);
+        
+        // Same key as test_private_key_wif, uncompressed and compressed
+        let imported = legacy
+            .address_from_private_key(&"5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTJ".into())
+            .unwrap();
+        assert_eq!(imported.address, "1GAehh7TsJAHuUAeKZcXf5CnwuGuGgyX2S");
+        assert_eq!(imported.compressed, Some(false));
+        assert_eq!(imported.key_format, KeyFormat::Native);
+        assert_eq!(imported.public_key.len(), 130);
+        
+        let imported = legacy
+            .address_from_private_key(&"KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98617".into())
+            .unwrap();
+        assert_eq!(imported.address, "1LoVGDgRs9hTfTNJNuXKSpywcbdvwRXpmK");
+        assert_eq!(imported.compressed, Some(true));
+        
+        let imported = legacy
+            .address_from_private_key(&"0c28fca386c7a227600b2fe50b7cae11ec86d3bf1fbe471be89827e19d72aa1d".into())
+            .unwrap();
+        assert_eq!(imported.address, "1LoVGDgRs9hTfTNJNuXKSpywcbdvwRXpmK");
+        assert_eq!(imported.key_format, KeyFormat::Hex);
+        
+        // Uncompressed keys have no SegWit or Taproot address; testnet keys are rejected on mainnet
+        let segwit = BitcoinSegwit::new(Network::Bitcoin);
+        assert!(segwit.address_from_private_key(&"5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTJ".into()).is_err());
+        let taproot = BitcoinTaproot::new(Network::Bitcoin);
+        assert!(taproot.address_from_private_key(&"5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTJ".into()).is_err());
+        let private_key = secp256k1::SecretKey::from_slice(&[1u8; 32]).unwrap();
+        let testnet_wif = encode_private_key(&private_key, Network::Testnet, KeyFormat::Native);
+        assert!(segwit.address_from_private_key(&testnet_wif).is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_nested_segwit_bip49_vectors() {
+        let seed = Mnemonic::parse(TEST_MNEMONIC).unwrap().to_seed("");
+        let keys = KeyTree::new(&seed);
+        
+        let nested = BitcoinNestedSegwit::new(Network::Bitcoin);
+        let wallet = nested.generate_address(&keys, "", 0, 0, None, None).unwrap();
+        assert_eq!(wallet.derivation_path, "m/49'/0'/0'/0/0");
+        assert_eq!(wallet.address, "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf");
+        
+        let ypub = nested.account_xpub(&keys, 0).unwrap();
+        assert_eq!(
+            ypub.extended_public_key,
+            "ypub6Ww3ibxVfGzLrAH1PNcjyAWenMTbbAosGNB6VvmSEgytSER9azLDWCxoJwW7Ke7icmizBMXrzBx9979FfaHxHcrArf3zbeJJJUZPf663zsP"
+        );
+        assert_eq!(ypub.key_origin, "[73c5da0a/49'/0'/0']");
+        assert!(ypub.receive_descriptor.unwrap().starts_with("sh(wpkh([73c5da0a/49'/0'/0']xpub"));
+        
+        // BIP49 test vector, which is given on testnet
+        let testnet = BitcoinNestedSegwit::new(Network::Testnet);
+        let wallet = testnet.generate_address(&keys, "", 0, 0, None, None).unwrap();
+        assert_eq!(wallet.derivation_path, "m/49'/1'/0'/0/0");
+        assert_eq!(wallet.address, "2Mww8dCYPUpKHofjgcXcBCEGmniw9CoaiD2");
+        assert_eq!(testnet.account_xpub(&keys, 0).unwrap().format, "upub");
+        
+        let (format, xpub) = XpubFormat::decode(&ypub.extended_public_key).unwrap();
+        assert_eq!(format, XpubFormat::Ypub);
+        let receive = derive_public_child(&xpub, 0, 0).unwrap();
+        assert_eq!(nested.address_from_public_key(&receive.serialize()).unwrap(), "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf");
+    }
+    
+    #[tokio::test]
+    async fn test_nested_segwit_validation() {
+        let seed = Mnemonic::parse(TEST_MNEMONIC).unwrap().to_seed("");
+        let nested = BitcoinNestedSegwit::new(Network::Bitcoin);
+        let wallet = nested.generate_address(&KeyTree::new(&seed), "", 0, 0, None, None).unwrap();
+        let redeem_script = hex::decode(&wallet.public_key_formats["redeem_script"]).unwrap();
+        assert_eq!(redeem_script.len(), 22);
+        assert_eq!(redeem_script[..2], [0x00, 0x14]);
+        
+        assert!(nested.validate_address(&wallet.address).await);
+        assert!(!nested.validate_address("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa").await);
+        assert!(!nested.validate_address("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu").await);
+        assert!(!nested.validate_address("2Mww8dCYPUpKHofjgcXcBCEGmniw9CoaiD2").await);
+        
+        // Only the redeem script tells a wrapped witness program from any other P2SH script
+        assert!(is_nested_witness_address(&wallet.address, &redeem_script, Network::Bitcoin));
+        assert!(!is_nested_witness_address(&wallet.address, &redeem_script, Network::Testnet));
+        let mut other_key = redeem_script.clone();
+        other_key[2] ^= 1;
+        assert!(!is_nested_witness_address(&wallet.address, &other_key, Network::Bitcoin));
+        
+        // 1-of-1 bare multisig: a valid P2SH redeem script, but not a witness program
+        let public_key = hex::decode(&wallet.public_key).unwrap();
+        let multisig = [&[0x51, 0x21][..], &public_key, &[0x51, 0xae]].concat();
+        let address = Address::p2sh(Script::from_bytes(&multisig), Network::Bitcoin).unwrap().to_string();
+        assert!(nested.validate_address(&address).await);
+        assert!(!is_nested_witness_address(&address, &multisig, Network::Bitcoin));
+        
+        let uncompressed = secp256k1_public_key(&public_key).unwrap().serialize_uncompressed();
+        assert!(nested.address_from_public_key(&uncompressed).is_err());
+    }
+    
+    #[test]
+    fn test_test_network_addresses() {
+        let seed = Mnemonic::parse(TEST_MNEMONIC).unwrap().to_seed("");
+        let keys = KeyTree::new(&seed);
+        
+        // Coin type 1 on every test network
+        let segwit = BitcoinSegwit::new(Network::Testnet);
+        let wallet = segwit.generate_address(&keys, "", 0, 0, None, Some(KeyFormat::Native)).unwrap();
+        assert_eq!(wallet.derivation_path, "m/84'/1'/0'/0/0");
+        assert_eq!(wallet.address, "tb1q6rz28mcfaxtmd6v789l9rrlrusdprr9pqcpvkl");
+        assert_eq!(wallet.chain_info.coin_type, 1);
+        assert!(wallet.private_key.unwrap().expose_secret().starts_with('c'));
+        
+        let legacy = BitcoinLegacy::new(Network::Testnet).generate_address(&keys, "", 0, 0, None, None).unwrap();
+        assert_eq!(legacy.address, "mkpZhYtJu2r87Js3pDiWJDmPte2NRZ8bJV");
+        
+        let regtest = BitcoinSegwit::new(Network::Regtest).generate_address(&keys, "", 0, 0, None, None).unwrap();
+        assert!(regtest.address.starts_with("bcrt1q"));
+        let taproot = BitcoinTaproot::new(Network::Signet).generate_address(&keys, "", 0, 0, None, None).unwrap();
+        assert!(taproot.address.starts_with("tb1p"));
+        
+        let vpub = segwit.account_xpub(&keys, 0).unwrap();
+        assert_eq!(vpub.format, "vpub");
+        assert_eq!(vpub.derivation_path, "m/84'/1'/0'");
+    }
+    
+    #[tokio::test]
+    async fn test_validation_follows_network() {
+        let testnet = BitcoinSegwit::new(Network::Testnet);
+        let signet = BitcoinSegwit::new(Network::Signet);
+        let regtest = BitcoinSegwit::new(Network::Regtest);
+        let mainnet = BitcoinSegwit::new(Network::Bitcoin);
+        
+        let tb = "tb1q6rz28mcfaxtmd6v789l9rrlrusdprr9pqcpvkl";
+        assert!(testnet.validate_address(tb).await);
+        assert!(signet.validate_address(tb).await);
+        assert!(!regtest.validate_address(tb).await);
+        assert!(!mainnet.validate_address(tb).await);
+        assert!(!testnet.validate_address("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu").await);
+        
+        let seed = Mnemonic::parse(TEST_MNEMONIC).unwrap().to_seed("");
+        let bcrt = regtest.generate_address(&KeyTree::new(&seed), "", 0, 0, None, None).unwrap().address;
+        assert!(regtest.validate_address(&bcrt).await);
+        assert!(!testnet.validate_address(&bcrt).await);
+        
+        // Base58 test addresses are shared by testnet, signet and regtest
+        let legacy = "mkpZhYtJu2r87Js3pDiWJDmPte2NRZ8bJV";
+        assert!(BitcoinLegacy::new(Network::Testnet).validate_address(legacy).await);
+        assert!(BitcoinLegacy::new(Network::Regtest).validate_address(legacy).await);
+        assert!(!BitcoinLegacy::new(Network::Bitcoin).validate_address(legacy).await);
+        
+        // The address type is checked, not only the network
+        let taproot = "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr";
+        assert!(BitcoinTaproot::new(Network::Bitcoin).validate_address(taproot).await);
+        assert!(!mainnet.validate_address(taproot).await);
+        assert!(!BitcoinLegacy::new(Network::Bitcoin).validate_address("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy").await);
+    }
+}
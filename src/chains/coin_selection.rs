@@ -0,0 +1,381 @@
+//! Coin selection and size estimates for fee-rate aware transactions.
+//!
+//! Candidates are compared by effective value, their amount less the fee their input
+//! costs at the target fee rate, so a selection always pays for itself. Branch and bound
+//! (Bitcoin Core's `SelectCoinsBnB`) first searches for a set that covers the payment
+//! without leaving change, where any excess up to the cost of creating and later
+//! spending a change output goes to the fee instead. When no such set exists within its
+//! budget of tries, the knapsack solver (Core's `KnapsackSolver`) looks for the smallest
+//! set leaving at least a minimal change output.
+
+use bitcoin::{Amount, FeeRate, Script, TxOut, VarInt};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::core::ChainType;
+
+/// Branch-and-bound states explored before giving up, as in Bitcoin Core
+pub const BNB_MAX_TRIES: usize = 100_000;
+
+/// Random subsets the knapsack solver tries per target
+pub const KNAPSACK_ITERATIONS: usize = 1_000;
+
+/// SegWit marker and flag bytes, in weight units
+pub const SEGWIT_MARKER_WEIGHT: u64 = 2;
+
+/// Dogecoin Core's default dust limit, 0.01 DOGE
+pub const DOGECOIN_DUST_LIMIT: Amount = Amount::from_sat(1_000_000);
+
+/// Dogecoin Core's money range, 10 billion DOGE
+pub const DOGECOIN_MAX_MONEY: Amount = Amount::from_sat(1_000_000_000_000_000_000);
+
+/// Highest Dogecoin fee rate accepted, 1 DOGE per kvB: a hundred times Dogecoin Core's
+/// recommended 0.01 DOGE/kB
+pub const DOGECOIN_MAX_FEE_RATE: FeeRate = FeeRate::from_sat_per_kwu(25_000_000);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    BranchAndBound,
+    Knapsack,
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Algorithm::BranchAndBound => write!(f, "branch_and_bound"),
+            Algorithm::Knapsack => write!(f, "knapsack"),
+        }
+    }
+}
+
+/// Weight of a signed single-key input, with 72-byte low-R ECDSA signatures and
+/// 64-byte default-sighash Schnorr signatures
+pub fn input_weight(chain_type: &ChainType) -> u64 {
+    let outpoint_and_sequence = 32 + 4 + 4;
+    // Each adds the scriptSig length, then the scriptSig or witness: <sig> <pubkey>,
+    // behind a redeem script for P2SH-P2WPKH, or the lone Schnorr signature
+    match chain_type {
+        ChainType::BitcoinNestedSegwit => 4 * (outpoint_and_sequence + 1 + 23) + (1 + 1 + 72 + 1 + 33),
+        ChainType::BitcoinSegwit => 4 * (outpoint_and_sequence + 1) + (1 + 1 + 72 + 1 + 33),
+        ChainType::BitcoinTaproot => 4 * (outpoint_and_sequence + 1) + (1 + 1 + 64),
+        _ => 4 * (outpoint_and_sequence + 1 + 1 + 72 + 1 + 33),
+    }
+}
+
+pub fn is_segwit(chain_type: &ChainType) -> bool {
+    matches!(
+        chain_type,
+        ChainType::BitcoinNestedSegwit | ChainType::BitcoinSegwit | ChainType::BitcoinTaproot
+    )
+}
+
+pub fn output_weight(script_pubkey: &Script) -> u64 {
+    let len = script_pubkey.len() as u64;
+    4 * (8 + VarInt(len).size() as u64 + len)
+}
+
+/// Weight of a signed transaction spending inputs of `input_types` to `outputs`
+pub fn transaction_weight(input_types: &[ChainType], outputs: &[TxOut]) -> u64 {
+    let counts = VarInt(input_types.len() as u64).size() + VarInt(outputs.len() as u64).size();
+    let marker = if input_types.iter().any(is_segwit) { SEGWIT_MARKER_WEIGHT } else { 0 };
+    4 * (4 + 4 + counts as u64)
+        + marker
+        + input_types.iter().map(input_weight).sum::<u64>()
+        + outputs.iter().map(|output| output_weight(&output.script_pubkey)).sum::<u64>()
+}
+
+/// Virtual size of a weight, rounded up as nodes do
+pub fn vsize(weight: u64) -> u64 {
+    weight.div_ceil(4)
+}
+
+/// Fee for a transaction, or part of one, of `weight` at `fee_rate` per virtual byte
+pub fn fee(fee_rate: FeeRate, weight: u64) -> u64 {
+    fee_rate.fee_vb(vsize(weight)).map_or(u64::MAX, Amount::to_sat)
+}
+
+/// Smallest output nodes relay: Bitcoin Core's threshold at 3 sat/vB, or Dogecoin's
+/// flat limit
+pub fn dust_limit(script_pubkey: &Script, dogecoin: bool) -> Amount {
+    if dogecoin {
+        DOGECOIN_DUST_LIMIT
+    } else {
+        script_pubkey.dust_value()
+    }
+}
+
+/// A spendable output as coin selection sees it
+#[derive(Debug, Clone, Copy)]
+pub struct Candidate {
+    /// Amount less the fee for spending it
+    pub effective_value: u64,
+}
+
+/// Indices of the chosen candidates, and how they were found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    pub indices: Vec<usize>,
+    pub algorithm: Algorithm,
+}
+
+/// Pick candidates whose effective values cover `target`, the payouts plus the fee for
+/// everything but the inputs. A changeless set may exceed it by up to `cost_of_change`;
+/// otherwise the set must leave `min_change` on top of the change output's fee.
+pub fn select<R: Rng + ?Sized>(
+    candidates: &[Candidate],
+    target: u64,
+    cost_of_change: u64,
+    change_fee: u64,
+    min_change: u64,
+    rng: &mut R,
+) -> Option<Selection> {
+    if let Some(indices) = branch_and_bound(candidates, target, cost_of_change) {
+        return Some(Selection { indices, algorithm: Algorithm::BranchAndBound });
+    }
+    knapsack(candidates, target, change_fee.saturating_add(min_change), rng)
+        .map(|indices| Selection { indices, algorithm: Algorithm::Knapsack })
+}
+
+/// Depth-first search for the changeless set with the least excess over `target`
+pub fn branch_and_bound(candidates: &[Candidate], target: u64, cost_of_change: u64) -> Option<Vec<usize>> {
+    let mut pool: Vec<usize> = (0..candidates.len())
+        .filter(|&i| candidates[i].effective_value > 0)
+        .collect();
+    pool.sort_by(|&a, &b| candidates[b].effective_value.cmp(&candidates[a].effective_value));
+    let value = |position: usize| candidates[pool[position]].effective_value;
+
+    let mut available: u64 = (0..pool.len()).map(value).sum();
+    if available < target {
+        return None;
+    }
+
+    let mut selected: Vec<usize> = Vec::new();
+    let mut selected_value = 0;
+    let mut best: Option<(Vec<usize>, u64)> = None;
+    let mut position = 0;
+
+    for _ in 0..BNB_MAX_TRIES {
+        let backtrack = if selected_value + available < target || selected_value > target.saturating_add(cost_of_change) {
+            true
+        } else if selected_value >= target {
+            let excess = selected_value - target;
+            if best.as_ref().is_none_or(|(_, best_excess)| excess <= *best_excess) {
+                best = Some((selected.clone(), excess));
+            }
+            true
+        } else {
+            false
+        };
+
+        if backtrack {
+            let Some(&last) = selected.last() else { break };
+            // Give back the candidates skipped since the last one taken, then try
+            // the branch without it
+            position -= 1;
+            while position > last {
+                available += value(position);
+                position -= 1;
+            }
+            selected.pop();
+            selected_value -= value(position);
+        } else {
+            available -= value(position);
+            // Taking an equal candidate right after skipping its twin repeats a
+            // branch already searched
+            let repeats = !selected.is_empty()
+                && selected.last() != Some(&(position - 1))
+                && value(position) == value(position - 1);
+            if !repeats {
+                selected.push(position);
+                selected_value += value(position);
+            }
+        }
+        position += 1;
+    }
+
+    best.map(|(positions, _)| positions.into_iter().map(|position| pool[position]).collect())
+}
+
+/// A single exact match, every smaller candidate when exactly enough, the best random
+/// subset reaching `target + min_change`, or the smallest candidate that does alone
+pub fn knapsack<R: Rng + ?Sized>(
+    candidates: &[Candidate],
+    target: u64,
+    min_change: u64,
+    rng: &mut R,
+) -> Option<Vec<usize>> {
+    let target_with_change = target.saturating_add(min_change);
+    let mut order: Vec<usize> = (0..candidates.len())
+        .filter(|&i| candidates[i].effective_value > 0)
+        .collect();
+    order.shuffle(rng);
+
+    let mut smaller = Vec::new();
+    let mut smaller_total = 0;
+    let mut lowest_larger: Option<usize> = None;
+    for i in order {
+        let value = candidates[i].effective_value;
+        if value == target {
+            return Some(vec![i]);
+        } else if value < target_with_change {
+            smaller.push(i);
+            smaller_total += value;
+        } else if lowest_larger.is_none_or(|larger| value < candidates[larger].effective_value) {
+            lowest_larger = Some(i);
+        }
+    }
+
+    if smaller_total == target {
+        return Some(smaller);
+    }
+    if smaller_total < target {
+        return lowest_larger.map(|i| vec![i]);
+    }
+
+    smaller.sort_by(|&a, &b| candidates[b].effective_value.cmp(&candidates[a].effective_value));
+    let values: Vec<u64> = smaller.iter().map(|&i| candidates[i].effective_value).collect();
+    let (mut best, mut best_value) = approximate_best_subset(&values, smaller_total, target, rng);
+    if best_value != target && smaller_total >= target_with_change {
+        (best, best_value) = approximate_best_subset(&values, smaller_total, target_with_change, rng);
+    }
+
+    // A single larger candidate beats a subset that leaves too little change or
+    // spends more
+    if let Some(larger) = lowest_larger {
+        let larger_value = candidates[larger].effective_value;
+        if (best_value != target && best_value < target_with_change) || larger_value <= best_value {
+            return Some(vec![larger]);
+        }
+    }
+
+    Some(
+        smaller
+            .into_iter()
+            .zip(best)
+            .filter_map(|(i, included)| included.then_some(i))
+            .collect(),
+    )
+}
+
+/// Random subsets of `values`, largest first, keeping the smallest total that reaches
+/// `target`
+fn approximate_best_subset<R: Rng + ?Sized>(values: &[u64], total: u64, target: u64, rng: &mut R) -> (Vec<bool>, u64) {
+    let mut best = vec![true; values.len()];
+    let mut best_value = total;
+
+    for _ in 0..KNAPSACK_ITERATIONS {
+        if best_value == target {
+            break;
+        }
+        let mut included = vec![false; values.len()];
+        let mut value = 0;
+        let mut reached = false;
+        // The first pass takes each value at random, the second the rest in order
+        for pass in 0..2 {
+            if reached {
+                break;
+            }
+            for i in 0..values.len() {
+                let take = if pass == 0 { rng.gen_bool(0.5) } else { !included[i] };
+                if !take {
+                    continue;
+                }
+                value += values[i];
+                included[i] = true;
+                if value >= target {
+                    reached = true;
+                    if value < best_value {
+                        best_value = value;
+                        best = included.clone();
+                    }
+                    value -= values[i];
+                    included[i] = false;
+                }
+            }
+        }
+    }
+    (best, best_value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::ScriptBuf;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn candidates(values: &[u64]) -> Vec<Candidate> {
+        values.iter().map(|&effective_value| Candidate { effective_value }).collect()
+    }
+
+    fn total(candidates: &[Candidate], indices: &[usize]) -> u64 {
+        indices.iter().map(|&i| candidates[i].effective_value).sum()
+    }
+
+    #[test]
+    fn test_input_sizes() {
+        // 148, 91, 68 and 57.5 vbytes
+        assert_eq!(input_weight(&ChainType::BitcoinLegacy), 592);
+        assert_eq!(input_weight(&ChainType::BitcoinNestedSegwit), 364);
+        assert_eq!(input_weight(&ChainType::BitcoinSegwit), 272);
+        assert_eq!(input_weight(&ChainType::BitcoinTaproot), 230);
+        assert_eq!(input_weight(&ChainType::Dogecoin), 592);
+
+        // One P2WPKH input paying P2WPKH and P2TR outputs: 11 + 68 + 31 + 43 vbytes
+        let outputs = [22, 34].map(|len| TxOut { value: Amount::ZERO, script_pubkey: ScriptBuf::from(vec![0; len]) });
+        let weight = transaction_weight(&[ChainType::BitcoinSegwit], &outputs);
+        assert_eq!(weight, 610);
+        assert_eq!(vsize(weight), 153);
+        assert_eq!(fee(FeeRate::from_sat_per_kwu(500), weight), 306);
+    }
+
+    #[test]
+    fn test_branch_and_bound_finds_changeless_set() {
+        let pool = candidates(&[1_000, 2_000, 3_000, 4_000, 5_000]);
+        let indices = branch_and_bound(&pool, 7_000, 0).unwrap();
+        assert_eq!(total(&pool, &indices), 7_000);
+
+        // The least excess within the cost of change
+        let indices = branch_and_bound(&pool, 9_600, 500).unwrap();
+        assert_eq!(total(&pool, &indices), 10_000);
+
+        assert_eq!(branch_and_bound(&pool, 9_600, 300), None);
+        assert_eq!(branch_and_bound(&pool, 16_000, 1_000), None);
+    }
+
+    #[test]
+    fn test_knapsack_leaves_change() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let pool = candidates(&[1_000, 2_000, 3_000, 40_000]);
+
+        // Exactly enough smaller candidates
+        let mut indices = knapsack(&pool, 6_000, 500, &mut rng).unwrap();
+        indices.sort();
+        assert_eq!(indices, [0, 1, 2]);
+
+        // Subsets leaving at least the minimum change beat the large candidate
+        let indices = knapsack(&pool, 4_500, 500, &mut rng).unwrap();
+        assert_eq!(total(&pool, &indices), 5_000);
+
+        // Only the large candidate covers it
+        assert_eq!(knapsack(&pool, 6_200, 500, &mut rng).unwrap(), [3]);
+        assert_eq!(knapsack(&pool, 47_000, 500, &mut rng), None);
+    }
+
+    #[test]
+    fn test_select_prefers_changeless() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let pool = candidates(&[10_000, 25_000, 60_000]);
+
+        let selection = select(&pool, 34_900, 200, 100, 300, &mut rng).unwrap();
+        assert_eq!(selection.algorithm, Algorithm::BranchAndBound);
+        assert_eq!(total(&pool, &selection.indices), 35_000);
+
+        let selection = select(&pool, 40_000, 200, 100, 300, &mut rng).unwrap();
+        assert_eq!(selection.algorithm, Algorithm::Knapsack);
+        assert!(total(&pool, &selection.indices) >= 40_400);
+    }
+}
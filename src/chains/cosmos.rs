@@ -1,14 +1,18 @@
 use async_trait::async_trait;
-use bitcoin::bip32::{Xpriv, DerivationPath as BtcDerivationPath, ChildNumber};
-use bitcoin::secp256k1::Secp256k1;
 use bitcoin::Network;
 use sha2::{Sha256, Digest};
 use ripemd::Ripemd160;
 use bech32::ToBase32;
+use base64::Engine;
+use std::collections::BTreeMap;
 
-use crate::core::{Chain, ChainInfo, ChainType, WalletAddress, DerivationPath, get_chain_info};
+use crate::chains::bitcoin::{derive_account_xpub, secp256k1_public_key, XpubFormat};
+use crate::core::{Chain, KeyTree, ChainInfo, ChainType, WalletAddress, SecretString, KeyFormat, DerivationPath, ExtendedPublicKey, get_chain_info};
 use crate::errors::{ApiError, ApiResult};
 
+/// Amino prefix of a legacy bech32 `tendermint/PubKeySecp256k1`: type prefix, then the key length
+const AMINO_SECP256K1_PREFIX: [u8; 5] = [0xeb, 0x5a, 0xe9, 0x87, 0x21];
+
 pub struct CosmosChain {
     chain_type: ChainType,
 }
@@ -17,6 +21,13 @@ impl CosmosChain {
     pub fn new(chain_type: ChainType) -> Self {
         Self { chain_type }
     }
+
+    fn hrp(&self) -> ApiResult<String> {
+        match self.info().address_format {
+            crate::core::AddressFormat::Bech32 { hrp } => Ok(hrp),
+            _ => Err(ApiError::CryptoError("Invalid address format".to_string())),
+        }
+    }
 }
 
 #[async_trait]
@@ -25,62 +36,82 @@ impl Chain for CosmosChain {
         get_chain_info(&self.chain_type)
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
-        let secp = Secp256k1::new();
-        let master = Xpriv::new_master(Network::Bitcoin, seed)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-
-        let path = self.derivation_path(index);
-        let btc_path = BtcDerivationPath::from(vec![
-            ChildNumber::from_hardened_idx(path.purpose).unwrap(),
-            ChildNumber::from_hardened_idx(path.coin_type).unwrap(),
-            ChildNumber::from_hardened_idx(path.account).unwrap(),
-            ChildNumber::from_normal_idx(path.change).unwrap(),
-            ChildNumber::from_normal_idx(path.index).unwrap(),
-        ]);
-        
-        let child = master.derive_priv(&secp, &btc_path)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
+        let child = keys.secp256k1_key(&path)?;
         
         let private_key = child.private_key;
-        let secp_pubkey = private_key.public_key(&secp);
+        let secp_pubkey = private_key.public_key(keys.secp());
         
-        // Cosmos uses compressed public key
-        let pubkey_bytes = secp_pubkey.serialize();
-        
-        // Hash public key with SHA256 then RIPEMD160
-        let sha256_hash = Sha256::digest(&pubkey_bytes);
-        let ripemd_hash = Ripemd160::digest(&sha256_hash);
-        
-        // Get the HRP from chain info
-        let hrp = match &self.info().address_format {
-            crate::core::AddressFormat::Bech32 { hrp } => hrp.clone(),
-            _ => return Err(ApiError::CryptoError("Invalid address format".to_string())),
-        };
-        
-        // Bech32 encode
-        let address = bech32::encode(&hrp, ripemd_hash.to_base32(), bech32::Variant::Bech32)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        let address = self.address_from_public_key(&secp_pubkey.serialize())?;
         
         Ok(WalletAddress {
             address,
             chain_type: self.chain_type.clone(),
             chain_info: self.info(),
             derivation_path: path.to_string(),
+            account,
             index,
             public_key: hex::encode(secp_pubkey.serialize()),
-            private_key: hex::encode(private_key.secret_bytes()),
+            public_key_formats: self.public_key_formats(&secp_pubkey.serialize())?,
+            private_key: key_format.map(|_| SecretString::hex_encode(private_key.secret_bytes())),
         })
     }
 
-    fn derivation_path(&self, index: u32) -> DerivationPath {
+    fn derivation_path(&self, account: u32, index: u32) -> DerivationPath {
         let coin_type = self.info().coin_type;
-        DerivationPath::new(44, coin_type, 0, 0, index)
+        DerivationPath::new(44, coin_type, account, 0, index)
+    }
+
+    fn account_xpub(&self, keys: &KeyTree, account: u32) -> ApiResult<ExtendedPublicKey> {
+        let account_path = self.derivation_path(account, 0).truncated(3);
+        derive_account_xpub(self.chain_type.clone(), Network::Bitcoin, keys, &account_path, account, XpubFormat::Xpub)
+    }
+
+    fn address_from_public_key(&self, public_key: &[u8]) -> ApiResult<String> {
+        let secp_pubkey = secp256k1_public_key(public_key)?;
+        
+        // Cosmos uses compressed public key
+        let pubkey_bytes = secp_pubkey.serialize();
+        
+        // Hash public key with SHA256 then RIPEMD160
+        let sha256_hash = Sha256::digest(pubkey_bytes);
+        let ripemd_hash = Ripemd160::digest(sha256_hash);
+        
+        // Bech32 encode with the HRP from chain info
+        let address = bech32::encode(&self.hrp()?, ripemd_hash.to_base32(), bech32::Variant::Bech32)
+            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        
+        Ok(address)
+    }
+
+    fn public_key_formats(&self, public_key: &[u8]) -> ApiResult<BTreeMap<String, String>> {
+        let pubkey_bytes = secp256k1_public_key(public_key)?.serialize();
+        
+        // Legacy amino bech32 (`cosmospub1addwnpep...`), still used by genesis files and older tooling
+        let mut amino = AMINO_SECP256K1_PREFIX.to_vec();
+        amino.extend_from_slice(&pubkey_bytes);
+        let bech32 = bech32::encode(&format!("{}pub", self.hrp()?), amino.to_base32(), bech32::Variant::Bech32)
+            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        
+        // The protobuf `Any` as the SDK's JSON encoding shows it in transactions and `keys show`
+        let any = serde_json::json!({
+            "@type": "/cosmos.crypto.secp256k1.PubKey",
+            "key": base64::engine::general_purpose::STANDARD.encode(pubkey_bytes),
+        });
+        
+        Ok(BTreeMap::from([
+            ("bech32".to_string(), bech32),
+            ("any".to_string(), any.to_string()),
+        ]))
     }
 
     async fn validate_address(&self, address: &str) -> bool {
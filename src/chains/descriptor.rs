@@ -0,0 +1,104 @@
+//! BIP380 output descriptors for the Bitcoin wallets this service derives.
+//!
+//! A descriptor names the script type, the keys with their origins and the derivation
+//! below them, e.g. `wpkh([73c5da0a/84'/0'/0']xpub.../0/*)#checksum`, so watch-only
+//! wallets such as Bitcoin Core (`importdescriptors`) and Sparrow reproduce every address.
+//! Keys are written as plain xpub/tpub: SLIP-0132 prefixes are not valid in descriptors.
+
+use bitcoin::bip32::Xpub;
+
+use crate::core::ChainType;
+use crate::errors::{ApiError, ApiResult};
+
+/// Characters a descriptor may contain, ordered so that common typos change the checksum
+const INPUT_CHARSET: &str =
+    "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn polymod(c: u64, value: u64) -> u64 {
+    let top = c >> 35;
+    let mut c = ((c & 0x7_ffff_ffff) << 5) ^ value;
+    for (bit, generator) in [0xf5dee51989, 0xa9fdca3312, 0x1bab10e32d, 0x3706b1677a, 0x644d626ffd]
+        .into_iter()
+        .enumerate()
+    {
+        if top >> bit & 1 == 1 {
+            c ^= generator;
+        }
+    }
+    c
+}
+
+/// The 8-character checksum of `descriptor`, which must not already carry one
+pub fn checksum(descriptor: &str) -> ApiResult<String> {
+    let mut c = 1u64;
+    let mut groups = Vec::with_capacity(3);
+    for ch in descriptor.chars() {
+        let position = INPUT_CHARSET
+            .find(ch)
+            .ok_or_else(|| ApiError::BadRequest(format!("Invalid character in descriptor: {:?}", ch)))?
+            as u64;
+
+        // Symbol within its group of 32, then every 3 group numbers as one more symbol
+        c = polymod(c, position & 31);
+        groups.push(position >> 5);
+        if groups.len() == 3 {
+            c = polymod(c, groups[0] * 9 + groups[1] * 3 + groups[2]);
+            groups.clear();
+        }
+    }
+    if !groups.is_empty() {
+        c = polymod(c, groups.iter().fold(0, |class, group| class * 3 + group));
+    }
+    for _ in 0..8 {
+        c = polymod(c, 0);
+    }
+    c ^= 1;
+
+    Ok((0..8)
+        .map(|i| CHECKSUM_CHARSET[(c >> (5 * (7 - i)) & 31) as usize] as char)
+        .collect())
+}
+
+/// `descriptor#checksum`
+pub fn with_checksum(descriptor: &str) -> ApiResult<String> {
+    Ok(format!("{}#{}", descriptor, checksum(descriptor)?))
+}
+
+/// Key expression for the addresses on `branch` (0 receive, 1 change) below an account
+/// key: `[fingerprint/path]xpub/branch/*`. `key_origin` may be empty when unknown.
+pub fn key_expression(key_origin: &str, xpub: &Xpub, branch: u32) -> String {
+    format!("{}{}/{}/*", key_origin, xpub, branch)
+}
+
+/// Descriptor, with checksum, of a single-key Bitcoin address type's addresses on
+/// `branch`; `None` for every other chain
+pub fn single_key(chain_type: &ChainType, key_origin: &str, xpub: &Xpub, branch: u32) -> ApiResult<Option<String>> {
+    let key = key_expression(key_origin, xpub, branch);
+    let descriptor = match chain_type {
+        ChainType::BitcoinLegacy => format!("pkh({})", key),
+        ChainType::BitcoinNestedSegwit => format!("sh(wpkh({}))", key),
+        ChainType::BitcoinSegwit => format!("wpkh({})", key),
+        ChainType::BitcoinTaproot => format!("tr({})", key),
+        _ => return Ok(None),
+    };
+
+    with_checksum(&descriptor).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_vectors() {
+        // BIP380 and Bitcoin Core's descriptor documentation
+        assert_eq!(checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+        assert_eq!(checksum("addr(mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j)").unwrap(), "02wpgw69");
+        assert_eq!(
+            with_checksum("pkh([d34db33f/44'/0'/0']xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL/1/*)").unwrap(),
+            "pkh([d34db33f/44'/0'/0']xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL/1/*)#ml40v0wf"
+        );
+        assert!(checksum("raw(deadbeef)\u{e9}").is_err());
+    }
+}
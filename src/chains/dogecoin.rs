@@ -1,17 +1,33 @@
 // src/chains/dogecoin.rs
 use async_trait::async_trait;
-use bitcoin::PublicKey;
-use bitcoin::bip32::{Xpriv, DerivationPath as BtcDerivationPath, ChildNumber};
-use bitcoin::secp256k1::Secp256k1;
+use bitcoin::hashes::Hash;
+use bitcoin::secp256k1;
+use bitcoin::{PubkeyHash, ScriptBuf, ScriptHash};
 use sha2::{Sha256, Digest};
 use ripemd::Ripemd160;
+use zeroize::Zeroizing;
 
-use crate::core::{Chain, ChainInfo, ChainType, WalletAddress, DerivationPath, get_chain_info};
+use crate::chains::bitcoin::{decode_private_key, derive_account_xpub, secp256k1_public_key, XpubFormat};
+use crate::core::{Chain, KeyTree, ChainInfo, ChainType, AddressFormat, WalletAddress, SecretString, KeyFormat, DerivationPath, ExtendedPublicKey, ImportedKey, get_chain_info, import_secp256k1_key};
 use crate::errors::{ApiError, ApiResult};
 
 // Re-export Network so it can be used in mod.rs
 pub use bitcoin::Network;
 
+/// WIF version bytes; Dogecoin keeps Bitcoin's WIF layout with its own prefixes
+const WIF_VERSION_MAINNET: u8 = 0x9e;
+const WIF_VERSION_TESTNET: u8 = 0xf1;
+const WIF_VERSION_REGTEST: u8 = 0xef;
+
+/// P2PKH version bytes: `D...` on mainnet, `n...` on testnet, `m...`/`n...` on regtest
+const P2PKH_VERSION_MAINNET: u8 = 0x1e;
+const P2PKH_VERSION_TESTNET: u8 = 0x71;
+const P2PKH_VERSION_REGTEST: u8 = 0x6f;
+
+/// P2SH version bytes: `A...`/`9...` on mainnet, `2...` on test networks
+const P2SH_VERSION_MAINNET: u8 = 0x16;
+const P2SH_VERSION_TESTNET: u8 = 0xc4;
+
 // Dogecoin implementation
 pub struct Dogecoin {
     network: Network,
@@ -21,93 +37,156 @@ impl Dogecoin {
     pub fn new(network: Network) -> Self {
         Self { network }
     }
+
+    /// Dogecoin has no signet; `chains::create_chain` never builds one for it
+    fn wif_version(&self) -> u8 {
+        match self.network {
+            Network::Bitcoin => WIF_VERSION_MAINNET,
+            Network::Regtest => WIF_VERSION_REGTEST,
+            _ => WIF_VERSION_TESTNET,
+        }
+    }
+
+    fn p2pkh_version(&self) -> u8 {
+        match self.network {
+            Network::Bitcoin => P2PKH_VERSION_MAINNET,
+            Network::Regtest => P2PKH_VERSION_REGTEST,
+            _ => P2PKH_VERSION_TESTNET,
+        }
+    }
+
+    fn p2sh_version(&self) -> u8 {
+        match self.network {
+            Network::Bitcoin => P2SH_VERSION_MAINNET,
+            _ => P2SH_VERSION_TESTNET,
+        }
+    }
+
+    /// Output script paying a P2PKH or P2SH address on this network
+    pub fn script_pubkey(&self, address: &str) -> ApiResult<ScriptBuf> {
+        let invalid = || ApiError::BadRequest(format!("Invalid {} address: {}", self.info().name, address));
+        let data = bitcoin::base58::decode_check(address).map_err(|_| invalid())?;
+        if data.len() != 21 {
+            return Err(invalid());
+        }
+
+        let hash: [u8; 20] = data[1..].try_into().map_err(|_| invalid())?;
+        match data[0] {
+            version if version == self.p2pkh_version() => Ok(ScriptBuf::new_p2pkh(&PubkeyHash::from_byte_array(hash))),
+            version if version == self.p2sh_version() => Ok(ScriptBuf::new_p2sh(&ScriptHash::from_byte_array(hash))),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Compressed WIF (`Q...` on mainnet), as Dogecoin Core's `importprivkey` expects
+    fn private_key_wif(&self, private_key: &secp256k1::SecretKey) -> SecretString {
+        let mut payload = Zeroizing::new(Vec::with_capacity(34));
+        payload.push(self.wif_version());
+        payload.extend_from_slice(&private_key.secret_bytes());
+        payload.push(0x01); // compressed public key
+        SecretString::new(bitcoin::base58::encode_check(&payload))
+    }
 }
 
 #[async_trait]
 impl Chain for Dogecoin {
     fn info(&self) -> ChainInfo {
-        get_chain_info(&ChainType::Dogecoin)
+        let mut info = get_chain_info(&ChainType::Dogecoin);
+        if self.network != Network::Bitcoin {
+            info.coin_type = 1;
+            info.address_format = AddressFormat::Bitcoin { prefix: "n".to_string() };
+        }
+        info
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
-        let secp = Secp256k1::new();
-        let master = Xpriv::new_master(self.network, seed)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-
-        let path = self.derivation_path(index);
-        let btc_path = BtcDerivationPath::from(vec![
-            ChildNumber::from_hardened_idx(path.purpose).unwrap(),
-            ChildNumber::from_hardened_idx(path.coin_type).unwrap(),
-            ChildNumber::from_hardened_idx(path.account).unwrap(),
-            ChildNumber::from_normal_idx(path.change).unwrap(),
-            ChildNumber::from_normal_idx(path.index).unwrap(),
-        ]);
-        
-        let child = master.derive_priv(&secp, &btc_path)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
+        let child = keys.secp256k1_key(&path)?;
         
         let private_key = child.private_key;
-        let secp_pubkey = private_key.public_key(&secp);
+        let secp_pubkey = private_key.public_key(keys.secp());
         
-        // Get compressed public key
-        let pubkey_bytes = secp_pubkey.serialize();
+        let address = self.address_from_public_key(&secp_pubkey.serialize())?;
+        
+        Ok(WalletAddress {
+            address,
+            chain_type: ChainType::Dogecoin,
+            chain_info: self.info(),
+            derivation_path: path.to_string(),
+            account,
+            index,
+            public_key: hex::encode(secp_pubkey.serialize()),
+            public_key_formats: self.public_key_formats(&secp_pubkey.serialize())?,
+            private_key: key_format.map(|format| match format {
+                KeyFormat::Hex => SecretString::hex_encode(private_key.secret_bytes()),
+                KeyFormat::Native => self.private_key_wif(&private_key),
+            }),
+        })
+    }
+
+    fn derivation_path(&self, account: u32, index: u32) -> DerivationPath {
+        // Dogecoin uses m/44'/3'/{account}'/0/{index}, and m/44'/1'/... on test networks
+        let coin_type = if self.network == Network::Bitcoin { 3 } else { 1 };
+        DerivationPath::new(44, coin_type, account, 0, index)
+    }
+
+    fn account_xpub(&self, keys: &KeyTree, account: u32) -> ApiResult<ExtendedPublicKey> {
+        let account_path = self.derivation_path(account, 0).truncated(3);
+        derive_account_xpub(ChainType::Dogecoin, self.network, keys, &account_path, account, XpubFormat::Dgub)
+    }
+
+    fn address_from_private_key(&self, private_key: &SecretString) -> ApiResult<ImportedKey> {
+        let (secret_key, compressed, key_format) =
+            decode_private_key(private_key, &[self.wif_version()], &self.info().name)?;
+        import_secp256k1_key(self, &secret_key, compressed, key_format)
+    }
+
+    fn address_from_public_key(&self, public_key: &[u8]) -> ApiResult<String> {
+        // Validates the point; the hash commits to the encoding as given, so
+        // uncompressed (65-byte) keys from old WIFs keep their own address
+        secp256k1_public_key(public_key)?;
         
         // Dogecoin address generation (P2PKH)
         // 1. SHA256 hash of public key
-        let sha256_hash = Sha256::digest(&pubkey_bytes);
+        let sha256_hash = Sha256::digest(public_key);
         
         // 2. RIPEMD160 hash of SHA256 result
-        let ripemd_hash = Ripemd160::digest(&sha256_hash);
+        let ripemd_hash = Ripemd160::digest(sha256_hash);
         
         // 3. Add Dogecoin version byte (0x1E for mainnet)
-        let mut payload = vec![0x1E];
+        let mut payload = vec![self.p2pkh_version()];
         payload.extend_from_slice(&ripemd_hash);
         
         // 4. Double SHA256 for checksum
-        let checksum = Sha256::digest(&Sha256::digest(&payload));
+        let checksum = Sha256::digest(Sha256::digest(&payload));
         payload.extend_from_slice(&checksum[..4]);
         
         // 5. Base58 encode
         let address = bs58::encode(payload).into_string();
         
-        Ok(WalletAddress {
-            address,
-            chain_type: ChainType::Dogecoin,
-            chain_info: self.info(),
-            derivation_path: path.to_string(),
-            index,
-            public_key: hex::encode(secp_pubkey.serialize()),
-            private_key: hex::encode(private_key.secret_bytes()),
-        })
-    }
-
-    fn derivation_path(&self, index: u32) -> DerivationPath {
-        // Dogecoin uses m/44'/3'/0'/0/{index}
-        DerivationPath::new(44, 3, 0, 0, index)
+        Ok(address)
     }
 
     async fn validate_address(&self, address: &str) -> bool {
-        // Dogecoin addresses start with 'D' for mainnet and are 34 characters
-        if !address.starts_with('D') || address.len() != 34 {
-            return false;
-        }
-        
-        // Decode and verify checksum
+        // Decode, then check the network's version byte and the checksum
         match bs58::decode(address).into_vec() {
             Ok(data) => {
-                if data.len() != 25 {
+                if data.len() != 25 || data[0] != self.p2pkh_version() {
                     return false;
                 }
                 
                 let payload = &data[..21];
                 let checksum = &data[21..];
                 
-                let computed_checksum = Sha256::digest(&Sha256::digest(payload));
+                let computed_checksum = Sha256::digest(Sha256::digest(payload));
                 checksum == &computed_checksum[..4]
             }
             Err(_) => false,
@@ -130,9 +209,10 @@ mod tests {
         let mnemonic = "test walk nut penalty hip pave soap entry language right filter choice";
         let mnemonic = Mnemonic::parse(mnemonic).unwrap();
         let seed = mnemonic.to_seed("");
+        let keys = KeyTree::new(&seed);
         
         let dogecoin = Dogecoin::new(Network::Bitcoin); // Mainnet
-        let wallet = dogecoin.generate_address(&seed, "", 0).await.unwrap();
+        let wallet = dogecoin.generate_address(&keys, "", 0, 0, None, Some(KeyFormat::Hex)).unwrap();
         
         println!("Generated Dogecoin address: {}", wallet.address);
         assert!(wallet.address.starts_with('D'));
@@ -155,4 +235,53 @@ mod tests {
         assert!(!dogecoin.validate_address("DInvalidAddress").await);
         assert!(!dogecoin.validate_address("").await);
     }
+    
+    #[test]
+    fn test_import_wif_round_trip() {
+        let seed = Mnemonic::parse("test walk nut penalty hip pave soap entry language right filter choice")
+            .unwrap()
+            .to_seed("");
+        let keys = KeyTree::new(&seed);
+        
+        let dogecoin = Dogecoin::new(Network::Bitcoin);
+        let wallet = dogecoin.generate_address(&keys, "", 0, 0, None, Some(KeyFormat::Native)).unwrap();
+        let wif = wallet.private_key.unwrap();
+        assert!(wif.expose_secret().starts_with('Q'));
+        
+        let imported = dogecoin.address_from_private_key(&wif).unwrap();
+        assert_eq!(imported.address, wallet.address);
+        assert_eq!(imported.public_key, wallet.public_key);
+        assert_eq!(imported.compressed, Some(true));
+        
+        // Bitcoin WIF carries the wrong version byte
+        assert!(dogecoin
+            .address_from_private_key(&"KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98617".into())
+            .is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_dogecoin_testnet() {
+        let seed = Mnemonic::parse("test walk nut penalty hip pave soap entry language right filter choice")
+            .unwrap()
+            .to_seed("");
+        let keys = KeyTree::new(&seed);
+        
+        let testnet = Dogecoin::new(Network::Testnet);
+        let wallet = testnet.generate_address(&keys, "", 0, 0, None, Some(KeyFormat::Native)).unwrap();
+        assert_eq!(wallet.derivation_path, "m/44'/1'/0'/0/0");
+        assert!(wallet.address.starts_with('n'));
+        assert!(testnet.validate_address(&wallet.address).await);
+        assert!(!Dogecoin::new(Network::Bitcoin).validate_address(&wallet.address).await);
+        assert!(!testnet.validate_address("DH5yaieqoZN36fDVciNyRueRGvGLR3mr7L").await);
+        
+        let imported = testnet.address_from_private_key(&wallet.private_key.unwrap()).unwrap();
+        assert_eq!(imported.address, wallet.address);
+        
+        // Regtest uses Bitcoin's test version bytes
+        let regtest = Dogecoin::new(Network::Regtest);
+        let wallet = regtest.generate_address(&keys, "", 0, 0, None, None).unwrap();
+        assert!(wallet.address.starts_with('m') || wallet.address.starts_with('n'));
+        assert!(regtest.validate_address(&wallet.address).await);
+        assert!(!testnet.validate_address(&wallet.address).await);
+    }
 }
\ No newline at end of file
@@ -1,14 +1,19 @@
 use async_trait::async_trait;
-use bitcoin::bip32::{Xpriv, DerivationPath as BtcDerivationPath, ChildNumber};
-use bitcoin::secp256k1::Secp256k1;
 use bitcoin::Network;
 use tiny_keccak::{Hasher, Keccak};
 
-use crate::core::{Chain, ChainInfo, ChainType, WalletAddress, DerivationPath, get_chain_info};
-use crate::errors::{ApiError, ApiResult};
+use crate::chains::bitcoin::{derive_account_xpub, secp256k1_public_key, XpubFormat};
+use crate::core::{Chain, KeyTree, ChainInfo, ChainType, WalletAddress, SecretString, KeyFormat, DerivationPath, ExtendedPublicKey, get_chain_info};
+use crate::errors::ApiResult;
 
 pub struct Ethereum;
 
+impl Default for Ethereum {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Ethereum {
     pub fn new() -> Self {
         Self
@@ -21,30 +26,47 @@ impl Chain for Ethereum {
         get_chain_info(&ChainType::Ethereum)
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
-        let secp = Secp256k1::new();
-        let master = Xpriv::new_master(Network::Bitcoin, seed)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-
-        let path = self.derivation_path(index);
-        let btc_path = BtcDerivationPath::from(vec![
-            ChildNumber::from_hardened_idx(path.purpose).unwrap(),
-            ChildNumber::from_hardened_idx(path.coin_type).unwrap(),
-            ChildNumber::from_hardened_idx(path.account).unwrap(),
-            ChildNumber::from_normal_idx(path.change).unwrap(),
-            ChildNumber::from_normal_idx(path.index).unwrap(),
-        ]);
-        
-        let child = master.derive_priv(&secp, &btc_path)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
+        let child = keys.secp256k1_key(&path)?;
         
         let private_key = child.private_key;
-        let secp_pubkey = private_key.public_key(&secp);
+        let secp_pubkey = private_key.public_key(keys.secp());
+        
+        let address = self.address_from_public_key(&secp_pubkey.serialize())?;
+        
+        Ok(WalletAddress {
+            address,
+            chain_type: ChainType::Ethereum,
+            chain_info: self.info(),
+            derivation_path: path.to_string(),
+            account,
+            index,
+            public_key: hex::encode(secp_pubkey.serialize()),
+            public_key_formats: self.public_key_formats(&secp_pubkey.serialize())?,
+            private_key: key_format.map(|_| SecretString::hex_encode(private_key.secret_bytes())),
+        })
+    }
+
+    fn derivation_path(&self, account: u32, index: u32) -> DerivationPath {
+        DerivationPath::new(44, 60, account, 0, index)
+    }
+
+    fn account_xpub(&self, keys: &KeyTree, account: u32) -> ApiResult<ExtendedPublicKey> {
+        let account_path = self.derivation_path(account, 0).truncated(3);
+        derive_account_xpub(ChainType::Ethereum, Network::Bitcoin, keys, &account_path, account, XpubFormat::Xpub)
+    }
+
+    fn address_from_public_key(&self, public_key: &[u8]) -> ApiResult<String> {
+        let secp_pubkey = secp256k1_public_key(public_key)?;
         
         // Get uncompressed public key bytes (65 bytes with 0x04 prefix)
         let pubkey_bytes = secp_pubkey.serialize_uncompressed();
@@ -64,19 +86,7 @@ impl Chain for Ethereum {
         // Apply EIP-55 checksum encoding
         let address = self.to_checksum_address(address_bytes);
         
-        Ok(WalletAddress {
-            address,
-            chain_type: ChainType::Ethereum,
-            chain_info: self.info(),
-            derivation_path: path.to_string(),
-            index,
-            public_key: hex::encode(secp_pubkey.serialize()),
-            private_key: hex::encode(private_key.secret_bytes()),
-        })
-    }
-
-    fn derivation_path(&self, index: u32) -> DerivationPath {
-        DerivationPath::new(44, 60, 0, 0, index)
+        Ok(address)
     }
 
     async fn validate_address(&self, address: &str) -> bool {
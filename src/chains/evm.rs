@@ -1,11 +1,10 @@
 use async_trait::async_trait;
-use bitcoin::bip32::{Xpriv, DerivationPath as BtcDerivationPath, ChildNumber};
-use bitcoin::secp256k1::Secp256k1;
 use bitcoin::Network;
 use tiny_keccak::{Hasher, Keccak};
 
-use crate::core::{Chain, ChainInfo, ChainType, WalletAddress, DerivationPath, get_chain_info};
-use crate::errors::{ApiError, ApiResult};
+use crate::chains::bitcoin::{derive_account_xpub, secp256k1_public_key, XpubFormat};
+use crate::core::{Chain, KeyTree, ChainInfo, ChainType, WalletAddress, SecretString, KeyFormat, DerivationPath, ExtendedPublicKey, get_chain_info};
+use crate::errors::ApiResult;
 
 /// EVM Chain implementation (works for Ethereum and all EVM-compatible L2s)
 pub struct EvmChain {
@@ -24,30 +23,48 @@ impl Chain for EvmChain {
         get_chain_info(&self.chain_type)
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
-        let secp = Secp256k1::new();
-        let master = Xpriv::new_master(Network::Bitcoin, seed)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-
-        let path = self.derivation_path(index);
-        let btc_path = BtcDerivationPath::from(vec![
-            ChildNumber::from_hardened_idx(path.purpose).unwrap(),
-            ChildNumber::from_hardened_idx(path.coin_type).unwrap(),
-            ChildNumber::from_hardened_idx(path.account).unwrap(),
-            ChildNumber::from_normal_idx(path.change).unwrap(),
-            ChildNumber::from_normal_idx(path.index).unwrap(),
-        ]);
-        
-        let child = master.derive_priv(&secp, &btc_path)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
+        let child = keys.secp256k1_key(&path)?;
         
         let private_key = child.private_key;
-        let secp_pubkey = private_key.public_key(&secp);
+        let secp_pubkey = private_key.public_key(keys.secp());
+        
+        let address = self.address_from_public_key(&secp_pubkey.serialize())?;
+        
+        Ok(WalletAddress {
+            address,
+            chain_type: self.chain_type.clone(),
+            chain_info: self.info(),
+            derivation_path: path.to_string(),
+            account,
+            index,
+            public_key: hex::encode(secp_pubkey.serialize()),
+            public_key_formats: self.public_key_formats(&secp_pubkey.serialize())?,
+            private_key: key_format.map(|_| SecretString::hex_encode(private_key.secret_bytes())),
+        })
+    }
+
+    fn derivation_path(&self, account: u32, index: u32) -> DerivationPath {
+        // All EVM chains use Ethereum's derivation path
+        DerivationPath::new(44, 60, account, 0, index)
+    }
+
+    fn account_xpub(&self, keys: &KeyTree, account: u32) -> ApiResult<ExtendedPublicKey> {
+        let account_path = self.derivation_path(account, 0).truncated(3);
+        derive_account_xpub(self.chain_type.clone(), Network::Bitcoin, keys, &account_path, account, XpubFormat::Xpub)
+    }
+
+    fn address_from_public_key(&self, public_key: &[u8]) -> ApiResult<String> {
+        let secp_pubkey = secp256k1_public_key(public_key)?;
         
         // Get uncompressed public key bytes (65 bytes with 0x04 prefix)
         let pubkey_bytes = secp_pubkey.serialize_uncompressed();
@@ -67,20 +84,7 @@ impl Chain for EvmChain {
         // Apply EIP-55 checksum encoding
         let address = self.to_checksum_address(address_bytes);
         
-        Ok(WalletAddress {
-            address,
-            chain_type: self.chain_type.clone(),
-            chain_info: self.info(),
-            derivation_path: path.to_string(),
-            index,
-            public_key: hex::encode(secp_pubkey.serialize()),
-            private_key: hex::encode(private_key.secret_bytes()),
-        })
-    }
-
-    fn derivation_path(&self, index: u32) -> DerivationPath {
-        // All EVM chains use Ethereum's derivation path
-        DerivationPath::new(44, 60, 0, 0, index)
+        Ok(address)
     }
 
     async fn validate_address(&self, address: &str) -> bool {
@@ -136,8 +140,9 @@ mod tests {
     #[tokio::test]
     async fn test_evm_address_generation() {
         let seed = [0u8; 32];
+        let keys = KeyTree::new(&seed);
         let chain = EvmChain::new(ChainType::Ethereum);
-        let address = chain.generate_address(&seed, "", 0).await.unwrap();
+        let address = chain.generate_address(&keys, "", 0, 0, None, Some(KeyFormat::Hex)).unwrap();
         
         assert!(address.address.starts_with("0x"));
         assert_eq!(address.address.len(), 42);
@@ -147,14 +152,27 @@ mod tests {
     #[tokio::test]
     async fn test_base_address_generation() {
         let seed = [0u8; 32];
+        let keys = KeyTree::new(&seed);
         let chain = EvmChain::new(ChainType::Base);
-        let address = chain.generate_address(&seed, "", 0).await.unwrap();
+        let address = chain.generate_address(&keys, "", 0, 0, None, Some(KeyFormat::Hex)).unwrap();
         
         assert!(address.address.starts_with("0x"));
         assert_eq!(address.address.len(), 42);
         assert_eq!(address.chain_type, ChainType::Base);
     }
     
+    #[tokio::test]
+    async fn test_account_and_index_in_path() {
+        let seed = [0u8; 32];
+        let keys = KeyTree::new(&seed);
+        let chain = EvmChain::new(ChainType::Ethereum);
+        let address = chain.generate_address(&keys, "", 3, 7, None, Some(KeyFormat::Hex)).unwrap();
+        
+        assert_eq!(address.derivation_path, "m/44'/60'/3'/0/7");
+        assert_eq!(address.account, 3);
+        assert_eq!(address.index, 7);
+    }
+    
     #[tokio::test]
     async fn test_address_validation() {
         let chain = EvmChain::new(ChainType::Ethereum);
@@ -168,4 +186,18 @@ mod tests {
         assert!(!chain.validate_address("742d35Cc6634C0532925a3b844Bc9e7595f0bEb8").await); // No 0x
         assert!(!chain.validate_address("0xGGGG35Cc6634C0532925a3b844Bc9e7595f0bEb8").await); // Invalid hex
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_address_from_account_xpub() {
+        let seed = [0u8; 32];
+        let keys = KeyTree::new(&seed);
+        let chain = EvmChain::new(ChainType::Ethereum);
+        let wallet = chain.generate_address(&keys, "", 0, 3, None, Some(KeyFormat::Hex)).unwrap();
+        
+        let xpub = chain.account_xpub(&keys, 0).unwrap();
+        let (_, xpub) = XpubFormat::decode(&xpub.extended_public_key).unwrap();
+        let public_key = crate::chains::bitcoin::derive_public_child(&xpub, 0, 3).unwrap();
+        
+        assert_eq!(chain.address_from_public_key(&public_key.serialize()).unwrap(), wallet.address);
+    }
+}
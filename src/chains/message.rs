@@ -0,0 +1,541 @@
+//! Signed messages proving control of a Bitcoin or Dogecoin address.
+//!
+//! BIP137 signatures are the `signmessage` format: a recoverable ECDSA signature over
+//! the double-SHA256 of the coin's message magic and the message, behind a header byte
+//! carrying the recovery id and address type. BIP322 signs a virtual transaction
+//! (`to_sign`) spending an output to the address (`to_spend`) that commits to the
+//! message, so any script can sign as it would spend; the simple format is the
+//! witness stack and the full format the whole transaction. Verification needs no
+//! keys and covers the single-key scripts, P2PKH, P2SH-P2WPKH, P2WPKH and P2TR key
+//! path, whoever holds them. Dogecoin has only BIP137.
+
+use std::str::FromStr;
+
+use base64::Engine;
+use bitcoin::blockdata::opcodes::all::OP_RETURN;
+use bitcoin::blockdata::opcodes::OP_0;
+use bitcoin::blockdata::script::{Builder, PushBytesBuf};
+use bitcoin::consensus::{self, Encodable};
+use bitcoin::hashes::{sha256, sha256d, Hash, HashEngine};
+use bitcoin::key::TapTweak;
+use bitcoin::secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use bitcoin::secp256k1::{self, Keypair, Message, Secp256k1, XOnlyPublicKey};
+use bitcoin::sighash::{EcdsaSighashType, Prevouts, SighashCache, TapSighashType};
+use bitcoin::{
+    absolute, ecdsa, taproot, transaction, Address, Amount, Network, OutPoint, PublicKey, Script, ScriptBuf,
+    Sequence, Transaction, TxIn, TxOut, Txid, VarInt, Witness,
+};
+
+use crate::chains::psbt::p2wpkh_program;
+use crate::chains::Dogecoin;
+use crate::core::{ChainType, DerivationPath, KeyTree, MessageFormat, NetworkType, WalletAddress};
+use crate::errors::{ApiError, ApiResult};
+
+const BITCOIN_MAGIC: &str = "Bitcoin Signed Message:\n";
+const DOGECOIN_MAGIC: &str = "Dogecoin Signed Message:\n";
+
+/// BIP137 header bytes: 27 + recovery id for uncompressed P2PKH keys, then 31 for
+/// compressed P2PKH, 35 for P2SH-P2WPKH and 39 for P2WPKH
+const HEADER_UNCOMPRESSED: u8 = 27;
+const HEADER_P2PKH: u8 = 31;
+const HEADER_P2SH_P2WPKH: u8 = 35;
+const HEADER_P2WPKH: u8 = 39;
+const HEADER_MAX: u8 = 42;
+
+/// A message to sign with the address `generate_address` gives for an index
+#[derive(Debug, Clone)]
+pub struct MessageRequest {
+    pub chain_type: ChainType,
+    pub network: NetworkType,
+    pub account: u32,
+    pub index: u32,
+    pub message: String,
+    /// `None` uses the address type's `default_format`
+    pub format: Option<MessageFormat>,
+}
+
+/// A message signed with one of the mnemonic's addresses
+#[derive(Debug, Clone)]
+pub struct SignedMessage {
+    pub address: String,
+    pub derivation_path: String,
+    pub format: MessageFormat,
+    /// Base64
+    pub signature: String,
+}
+
+/// Outcome of checking a signature; malformed or wrong signatures are just invalid
+#[derive(Debug, Clone)]
+pub struct Verification {
+    pub valid: bool,
+    /// The format the signature was read as
+    pub format: Option<MessageFormat>,
+}
+
+/// BIP137 for P2PKH addresses, the BIP322 simple format for native SegWit and Taproot,
+/// and the full format for P2SH-P2WPKH, whose scriptSig the simple one leaves out
+pub fn default_format(chain_type: &ChainType) -> MessageFormat {
+    match chain_type {
+        ChainType::BitcoinNestedSegwit => MessageFormat::Bip322Full,
+        ChainType::BitcoinSegwit | ChainType::BitcoinTaproot => MessageFormat::Bip322Simple,
+        _ => MessageFormat::Bip137,
+    }
+}
+
+/// Sign `message` with the key of `wallet`, an address from `generate_address`
+pub fn sign(
+    keys: &KeyTree,
+    wallet: &WalletAddress,
+    network: Network,
+    message: &str,
+    format: Option<MessageFormat>,
+) -> ApiResult<SignedMessage> {
+    let chain_type = &wallet.chain_type;
+    let format = format.unwrap_or_else(|| default_format(chain_type));
+    let path: DerivationPath = wallet.derivation_path.parse()?;
+    let secret_key = keys.secp256k1_key(&path)?.private_key;
+
+    let signature = match (format, chain_type) {
+        (MessageFormat::Bip137, ChainType::BitcoinTaproot) => {
+            return Err(ApiError::BadRequest(
+                "BIP137 has no Taproot signatures; use bip322_simple".to_string(),
+            ))
+        }
+        (MessageFormat::Bip137, _) => {
+            let header = match chain_type {
+                ChainType::BitcoinNestedSegwit => HEADER_P2SH_P2WPKH,
+                ChainType::BitcoinSegwit => HEADER_P2WPKH,
+                _ => HEADER_P2PKH,
+            };
+            let magic = if *chain_type == ChainType::Dogecoin { DOGECOIN_MAGIC } else { BITCOIN_MAGIC };
+            let digest = Message::from_digest(signed_message_hash(message, magic));
+            let (recovery_id, compact) = keys
+                .secp()
+                .sign_ecdsa_recoverable(&digest, &secret_key)
+                .serialize_compact();
+
+            let mut signature = vec![header + recovery_id.to_i32() as u8];
+            signature.extend_from_slice(&compact);
+            signature
+        }
+        (_, ChainType::Dogecoin) => {
+            return Err(ApiError::BadRequest("Dogecoin messages can only be signed with BIP137".to_string()))
+        }
+        (MessageFormat::Bip322Simple, ChainType::BitcoinLegacy) => {
+            return Err(ApiError::BadRequest(
+                "P2PKH has no witness for a simple BIP322 signature; use bip137 or bip322_full".to_string(),
+            ))
+        }
+        (_, _) => {
+            let script_pubkey = address_script(&wallet.address, network, false)?;
+            let to_sign = sign_to_sign(keys.secp(), &secret_key, chain_type, &script_pubkey, message)?;
+            if format == MessageFormat::Bip322Full {
+                consensus::serialize(&to_sign)
+            } else {
+                consensus::serialize(&to_sign.input[0].witness)
+            }
+        }
+    };
+
+    Ok(SignedMessage {
+        address: wallet.address.clone(),
+        derivation_path: wallet.derivation_path.clone(),
+        format,
+        signature: base64::engine::general_purpose::STANDARD.encode(signature),
+    })
+}
+
+/// Check a BIP137 or BIP322 `signature` of `message` by `address`
+pub fn verify(address: &str, message: &str, signature: &str, network: Network, dogecoin: bool) -> ApiResult<Verification> {
+    let script_pubkey = address_script(address, network, dogecoin)?;
+    let invalid = Verification { valid: false, format: None };
+    let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(signature.trim()) else {
+        return Ok(invalid);
+    };
+
+    if bytes.len() == 65 && (HEADER_UNCOMPRESSED..=HEADER_MAX).contains(&bytes[0]) {
+        let magic = if dogecoin { DOGECOIN_MAGIC } else { BITCOIN_MAGIC };
+        return Ok(Verification {
+            valid: verify_bip137(&bytes, message, magic, &script_pubkey),
+            format: Some(MessageFormat::Bip137),
+        });
+    }
+    if dogecoin {
+        return Ok(invalid);
+    }
+
+    let to_spend = to_spend(&script_pubkey, message);
+    let (format, to_sign) = if let Ok(to_sign) = consensus::deserialize::<Transaction>(&bytes) {
+        if !is_to_sign(&to_sign, &to_spend) {
+            return Ok(Verification { valid: false, format: Some(MessageFormat::Bip322Full) });
+        }
+        (MessageFormat::Bip322Full, to_sign)
+    } else if let Ok(witness) = consensus::deserialize::<Witness>(&bytes) {
+        let mut to_sign = to_sign(&to_spend);
+        // The simple format leaves out P2SH-P2WPKH's scriptSig, which only depends on
+        // the key in the witness
+        if script_pubkey.is_p2sh() {
+            if let Some(key) = witness.nth(1).and_then(|key| PublicKey::from_slice(key).ok()) {
+                to_sign.input[0].script_sig = redeem_script_sig(&p2wpkh_program(&key.inner))?;
+            }
+        }
+        to_sign.input[0].witness = witness;
+        (MessageFormat::Bip322Simple, to_sign)
+    } else {
+        return Ok(invalid);
+    };
+
+    Ok(Verification {
+        valid: verify_bip322(&to_sign, &to_spend.output[0])?,
+        format: Some(format),
+    })
+}
+
+/// Output script of an address on `network`
+fn address_script(address: &str, network: Network, dogecoin: bool) -> ApiResult<ScriptBuf> {
+    if dogecoin {
+        return Dogecoin::new(network).script_pubkey(address);
+    }
+    Address::from_str(address)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid address {}: {}", address, e)))?
+        .require_network(network)
+        .map(|address| address.script_pubkey())
+        .map_err(|e| ApiError::BadRequest(format!("Invalid address {}: {}", address, e)))
+}
+
+/// Double SHA256 of the length-prefixed magic and message, as `signmessage` signs
+pub fn signed_message_hash(message: &str, magic: &str) -> [u8; 32] {
+    let mut engine = sha256d::Hash::engine();
+    for part in [magic, message] {
+        VarInt(part.len() as u64)
+            .consensus_encode(&mut engine)
+            .expect("engines don't error");
+        engine.input(part.as_bytes());
+    }
+    sha256d::Hash::from_engine(engine).to_byte_array()
+}
+
+/// BIP340 tagged hash of the message under "BIP0322-signed-message"
+pub fn bip322_message_hash(message: &str) -> [u8; 32] {
+    let tag = sha256::Hash::hash(b"BIP0322-signed-message");
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_ref());
+    engine.input(tag.as_ref());
+    engine.input(message.as_bytes());
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+/// The virtual transaction creating the output to be spent, committing to the message
+fn to_spend(script_pubkey: &Script, message: &str) -> Transaction {
+    Transaction {
+        version: transaction::Version(0),
+        lock_time: absolute::LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint { txid: Txid::all_zeros(), vout: 0xFFFFFFFF },
+            script_sig: Builder::new()
+                .push_opcode(OP_0)
+                .push_slice(bip322_message_hash(message))
+                .into_script(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut { value: Amount::ZERO, script_pubkey: script_pubkey.to_owned() }],
+    }
+}
+
+/// The unsigned virtual transaction spending `to_spend` to an empty OP_RETURN
+fn to_sign(to_spend: &Transaction) -> Transaction {
+    Transaction {
+        version: transaction::Version(0),
+        lock_time: absolute::LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint { txid: to_spend.txid(), vout: 0 },
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey: Builder::new().push_opcode(OP_RETURN).into_script(),
+        }],
+    }
+}
+
+/// Whether a full signature's transaction has `to_sign`'s shape; other versions,
+/// locktimes and sequences are allowed, proof-of-funds inputs are not supported
+fn is_to_sign(tx: &Transaction, to_spend: &Transaction) -> bool {
+    let expected = to_sign(to_spend);
+    tx.input.len() == 1 && tx.input[0].previous_output == expected.input[0].previous_output && tx.output == expected.output
+}
+
+fn redeem_script_sig(redeem_script: &Script) -> ApiResult<ScriptBuf> {
+    let push = PushBytesBuf::try_from(redeem_script.to_bytes()).map_err(|e| ApiError::CryptoError(e.to_string()))?;
+    Ok(Builder::new().push_slice(push).into_script())
+}
+
+fn sign_to_sign(
+    secp: &Secp256k1<secp256k1::All>,
+    secret_key: &secp256k1::SecretKey,
+    chain_type: &ChainType,
+    script_pubkey: &Script,
+    message: &str,
+) -> ApiResult<Transaction> {
+    let to_spend = to_spend(script_pubkey, message);
+    let mut to_sign = to_sign(&to_spend);
+    let public_key = PublicKey::new(secret_key.public_key(secp));
+    let mut cache = SighashCache::new(to_sign.clone());
+    let sighash_error = |e: &dyn std::fmt::Display| ApiError::CryptoError(e.to_string());
+
+    let ecdsa_signature = |sighash: [u8; 32]| {
+        let sig = secp.sign_ecdsa_low_r(&Message::from_digest(sighash), secret_key);
+        ecdsa::Signature { sig, hash_ty: EcdsaSighashType::All }.to_vec()
+    };
+
+    let input = &mut to_sign.input[0];
+    match chain_type {
+        ChainType::BitcoinTaproot => {
+            let sighash = cache
+                .taproot_key_spend_signature_hash(0, &Prevouts::All(&to_spend.output), TapSighashType::Default)
+                .map_err(|e| sighash_error(&e))?;
+            let keypair = Keypair::from_secret_key(secp, secret_key).tap_tweak(secp, None).to_inner();
+            let sig = secp.sign_schnorr_with_aux_rand(
+                &Message::from_digest(sighash.to_byte_array()),
+                &keypair,
+                &rand::random(),
+            );
+            input.witness = Witness::from_slice(&[taproot::Signature { sig, hash_ty: TapSighashType::Default }.to_vec()]);
+        }
+        ChainType::BitcoinNestedSegwit | ChainType::BitcoinSegwit => {
+            let program = p2wpkh_program(&public_key.inner);
+            let sighash = cache
+                .p2wpkh_signature_hash(0, &program, Amount::ZERO, EcdsaSighashType::All)
+                .map_err(|e| sighash_error(&e))?;
+            input.witness = Witness::from_slice(&[ecdsa_signature(sighash.to_byte_array()), public_key.to_bytes()]);
+            if *chain_type == ChainType::BitcoinNestedSegwit {
+                input.script_sig = redeem_script_sig(&program)?;
+            }
+        }
+        _ => {
+            let sighash = cache
+                .legacy_signature_hash(0, script_pubkey, EcdsaSighashType::All.to_u32())
+                .map_err(|e| sighash_error(&e))?;
+            let push = PushBytesBuf::try_from(ecdsa_signature(sighash.to_byte_array()))
+                .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+            input.script_sig = Builder::new().push_slice(push).push_key(&public_key).into_script();
+        }
+    }
+    Ok(to_sign)
+}
+
+fn verify_bip137(signature: &[u8], message: &str, magic: &str, script_pubkey: &Script) -> bool {
+    let header = signature[0];
+    let Ok(recovery_id) = RecoveryId::from_i32(((header - HEADER_UNCOMPRESSED) % 4) as i32) else {
+        return false;
+    };
+    let Ok(signature) = RecoverableSignature::from_compact(&signature[1..], recovery_id) else {
+        return false;
+    };
+    let digest = Message::from_digest(signed_message_hash(message, magic));
+    let Ok(key) = Secp256k1::verification_only().recover_ecdsa(&digest, &signature) else {
+        return false;
+    };
+
+    // Any compressed header is accepted for SegWit addresses, as Electrum signs
+    // them with the P2PKH one
+    let public_key = PublicKey { compressed: header >= HEADER_P2PKH, inner: key };
+    if script_pubkey.is_p2pkh() {
+        ScriptBuf::new_p2pkh(&public_key.pubkey_hash()) == *script_pubkey
+    } else if !public_key.compressed {
+        false
+    } else if script_pubkey.is_p2sh() {
+        ScriptBuf::new_p2sh(&p2wpkh_program(&key).script_hash()) == *script_pubkey
+    } else {
+        p2wpkh_program(&key) == *script_pubkey
+    }
+}
+
+/// Run `to_sign`'s only input against the single-key script it spends
+fn verify_bip322(to_sign: &Transaction, spent: &TxOut) -> ApiResult<bool> {
+    let secp = Secp256k1::verification_only();
+    let script_pubkey = &spent.script_pubkey;
+    let input = &to_sign.input[0];
+    let mut cache = SighashCache::new(to_sign);
+
+    if script_pubkey.is_p2tr() {
+        let (Some(signature), 1) = (input.witness.nth(0), input.witness.len()) else { return Ok(false) };
+        let Ok(signature) = taproot::Signature::from_slice(signature) else { return Ok(false) };
+        let Ok(output_key) = XOnlyPublicKey::from_slice(&script_pubkey.as_bytes()[2..]) else { return Ok(false) };
+        let Ok(sighash) = cache.taproot_key_spend_signature_hash(0, &Prevouts::All(&[spent]), signature.hash_ty) else {
+            return Ok(false);
+        };
+        let digest = Message::from_digest(sighash.to_byte_array());
+        return Ok(input.script_sig.is_empty() && secp.verify_schnorr(&signature.sig, &digest, &output_key).is_ok());
+    }
+
+    let (signature, public_key, sighash) = if script_pubkey.is_p2pkh() {
+        let pushes: Vec<Vec<u8>> = input
+            .script_sig
+            .instructions()
+            .map(|instruction| instruction.ok().and_then(|i| i.push_bytes().map(|bytes| bytes.as_bytes().to_vec())))
+            .collect::<Option<_>>()
+            .unwrap_or_default();
+        let [signature, key] = pushes.as_slice() else { return Ok(false) };
+        let (Ok(signature), Ok(public_key)) = (ecdsa::Signature::from_slice(signature), PublicKey::from_slice(key)) else {
+            return Ok(false);
+        };
+        if ScriptBuf::new_p2pkh(&public_key.pubkey_hash()) != *script_pubkey || !input.witness.is_empty() {
+            return Ok(false);
+        }
+        let Ok(sighash) = cache.legacy_signature_hash(0, script_pubkey, signature.hash_ty.to_u32()) else {
+            return Ok(false);
+        };
+        (signature, public_key, sighash.to_byte_array())
+    } else if script_pubkey.is_p2wpkh() || script_pubkey.is_p2sh() {
+        let (Some(signature), Some(key), 2) = (input.witness.nth(0), input.witness.nth(1), input.witness.len()) else {
+            return Ok(false);
+        };
+        let (Ok(signature), Ok(public_key)) = (ecdsa::Signature::from_slice(signature), PublicKey::from_slice(key)) else {
+            return Ok(false);
+        };
+        let program = p2wpkh_program(&public_key.inner);
+        let spends = if script_pubkey.is_p2sh() {
+            ScriptBuf::new_p2sh(&program.script_hash()) == *script_pubkey
+                && input.script_sig == redeem_script_sig(&program)?
+        } else {
+            program == *script_pubkey && input.script_sig.is_empty()
+        };
+        if !public_key.compressed || !spends {
+            return Ok(false);
+        }
+        let Ok(sighash) = cache.p2wpkh_signature_hash(0, &program, spent.value, signature.hash_ty) else {
+            return Ok(false);
+        };
+        (signature, public_key, sighash.to_byte_array())
+    } else {
+        return Err(ApiError::BadRequest(
+            "BIP322 verification supports P2PKH, P2SH-P2WPKH, P2WPKH and Taproot key-path addresses".to_string(),
+        ));
+    };
+
+    Ok(secp
+        .verify_ecdsa(&Message::from_digest(sighash), &signature.sig, &public_key.inner)
+        .is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains::create_chain;
+    use bip39::Mnemonic;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    // BIP322 test vectors, for the key L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k
+    const P2WPKH: &str = "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l";
+    const P2TR: &str = "bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3";
+
+    fn wallet(keys: &KeyTree, chain_type: &ChainType) -> WalletAddress {
+        create_chain(chain_type, Network::Bitcoin)
+            .unwrap()
+            .generate_address(keys, "", 0, 0, None, None)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_bip322_transactions() {
+        assert_eq!(
+            hex::encode(bip322_message_hash("")),
+            "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1"
+        );
+        assert_eq!(
+            hex::encode(bip322_message_hash("Hello World")),
+            "f0eb03b1a75ac6d9847f55c624a99169b5dccba2a31f5b23bea77ba270de0a7a"
+        );
+
+        let script_pubkey = address_script(P2WPKH, Network::Bitcoin, false).unwrap();
+        let to_spend = to_spend(&script_pubkey, "Hello World");
+        assert_eq!(
+            to_spend.txid().to_string(),
+            "b79d196740ad5217771c1098fc4a4b51e0535c32236c71f1ea4d61a2d603352b"
+        );
+        assert_eq!(
+            to_sign(&to_spend).txid().to_string(),
+            "88737ae86f2077145f93cc4b153ae9a1cb8d56afa511988c149c5c8c9d93bddf"
+        );
+    }
+
+    #[test]
+    fn test_verify_bip322_vectors() {
+        let vectors = [
+            (P2WPKH, "", "AkcwRAIgM2gBAQqvZX15ZiysmKmQpDrG83avLIT492QBzLnQIxYCIBaTpOaD20qRlEylyxFSeEA2ba9YOixpX8z46TSDtS40ASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI="),
+            (P2WPKH, "Hello World", "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI="),
+            (P2TR, "Hello World", "AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ=="),
+        ];
+        for (address, message, signature) in vectors {
+            let verification = verify(address, message, signature, Network::Bitcoin, false).unwrap();
+            assert!(verification.valid, "{} {:?}", address, message);
+            assert_eq!(verification.format, Some(MessageFormat::Bip322Simple));
+
+            assert!(!verify(address, "Hello World!", signature, Network::Bitcoin, false).unwrap().valid);
+        }
+        // Another address's signature
+        assert!(!verify(P2TR, "", vectors[0].2, Network::Bitcoin, false).unwrap().valid);
+        assert!(!verify(P2WPKH, "", "not base64!", Network::Bitcoin, false).unwrap().valid);
+        assert!(verify("bc1qinvalid", "", vectors[0].2, Network::Bitcoin, false).is_err());
+    }
+
+    #[test]
+    fn test_sign_and_verify_every_format() {
+        let keys = KeyTree::new(&Mnemonic::parse(MNEMONIC).unwrap().to_seed(""));
+        let formats = [MessageFormat::Bip137, MessageFormat::Bip322Simple, MessageFormat::Bip322Full];
+        let signable = [
+            (ChainType::BitcoinLegacy, vec![MessageFormat::Bip137, MessageFormat::Bip322Full]),
+            (ChainType::BitcoinNestedSegwit, formats.to_vec()),
+            (ChainType::BitcoinSegwit, formats.to_vec()),
+            (ChainType::BitcoinTaproot, vec![MessageFormat::Bip322Simple, MessageFormat::Bip322Full]),
+        ];
+
+        for (chain_type, signable_formats) in signable {
+            let wallet = wallet(&keys, &chain_type);
+            let default = sign(&keys, &wallet, Network::Bitcoin, "Hello World", None).unwrap();
+            assert_eq!(default.format, default_format(&chain_type));
+
+            for format in formats {
+                let signed = sign(&keys, &wallet, Network::Bitcoin, "Hello World", Some(format));
+                let Ok(signed) = signed else {
+                    assert!(!signable_formats.contains(&format), "{:?} {}", chain_type, format);
+                    continue;
+                };
+                assert_eq!(signed.address, wallet.address);
+
+                let verification = verify(&wallet.address, "Hello World", &signed.signature, Network::Bitcoin, false).unwrap();
+                assert!(verification.valid, "{:?} {}", chain_type, format);
+                assert_eq!(verification.format, Some(format));
+                assert!(!verify(&wallet.address, "Hello", &signed.signature, Network::Bitcoin, false).unwrap().valid);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dogecoin_bip137() {
+        let keys = KeyTree::new(&Mnemonic::parse(MNEMONIC).unwrap().to_seed(""));
+        let wallet = wallet(&keys, &ChainType::Dogecoin);
+        assert_eq!(wallet.address, "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC");
+
+        let signed = sign(&keys, &wallet, Network::Bitcoin, "Hello Doge", None).unwrap();
+        assert_eq!(signed.format, MessageFormat::Bip137);
+        assert!(verify(&wallet.address, "Hello Doge", &signed.signature, Network::Bitcoin, true).unwrap().valid);
+
+        // Bitcoin's message magic gives another hash, so another key is recovered
+        let bitcoin = wallet_address_with_key(&keys, &wallet);
+        assert!(!verify(&bitcoin, "Hello Doge", &signed.signature, Network::Bitcoin, false).unwrap().valid);
+        assert!(sign(&keys, &wallet, Network::Bitcoin, "Hello Doge", Some(MessageFormat::Bip322Simple)).is_err());
+    }
+
+    /// The Bitcoin P2PKH address of a Dogecoin address's key
+    fn wallet_address_with_key(keys: &KeyTree, wallet: &WalletAddress) -> String {
+        let path: DerivationPath = wallet.derivation_path.parse().unwrap();
+        let public_key = PublicKey::new(keys.secp256k1_key(&path).unwrap().private_key.public_key(keys.secp()));
+        Address::p2pkh(&public_key, Network::Bitcoin).to_string()
+    }
+}
@@ -1,9 +1,16 @@
 use std::sync::Arc;
 
+use crate::chains::bitcoin::Network;
 use crate::core::{Chain, ChainType};
 
 // Chain modules
 pub mod bitcoin;
+pub mod coin_selection;
+pub mod descriptor;
+pub mod message;
+pub mod multisig;
+pub mod policy;
+pub mod psbt;
 pub mod ethereum;
 pub mod evm;
 pub mod ripple;
@@ -15,9 +22,10 @@ pub mod dogecoin;
 pub mod cosmos;
 pub mod tezos;
 pub mod filecoin;
+pub mod stellar;
 
 // Re-export for convenience
-pub use bitcoin::{BitcoinLegacy, BitcoinSegwit, BitcoinTaproot};
+pub use bitcoin::{BitcoinLegacy, BitcoinNestedSegwit, BitcoinSegwit, BitcoinTaproot};
 pub use ethereum::Ethereum;
 pub use evm::EvmChain;
 pub use ripple::Ripple;
@@ -29,22 +37,34 @@ pub use dogecoin::Dogecoin;
 pub use cosmos::CosmosChain;
 pub use tezos::Tezos;
 pub use filecoin::Filecoin;
+pub use stellar::Stellar;
 
-/// Create a chain instance based on the chain type
-pub fn create_chain(chain_type: &ChainType) -> Arc<dyn Chain> {
-    use crate::chains::bitcoin::Network;
-    
+/// Chains whose ed25519 derivation predates SLIP-0010, built with that old
+/// derivation so previously issued addresses can still be re-derived
+pub fn create_legacy_chain(chain_type: &ChainType) -> Option<Arc<dyn Chain>> {
     match chain_type {
-        ChainType::BitcoinLegacy => Arc::new(BitcoinLegacy::new(Network::Bitcoin)),
-        ChainType::BitcoinSegwit => Arc::new(BitcoinSegwit::new(Network::Bitcoin)),
-        ChainType::BitcoinTaproot => Arc::new(BitcoinTaproot::new(Network::Bitcoin)),
+        ChainType::Tezos => Some(Arc::new(Tezos::legacy())),
+        _ => None,
+    }
+}
+
+/// Create a chain instance based on the chain type. Bitcoin and Dogecoin encode
+/// addresses and keys for `network`; other chains ignore it. `None` when the chain
+/// has no such network (Dogecoin has no signet).
+pub fn create_chain(chain_type: &ChainType, network: Network) -> Option<Arc<dyn Chain>> {
+    let chain: Arc<dyn Chain> = match chain_type {
+        ChainType::BitcoinLegacy => Arc::new(BitcoinLegacy::new(network)),
+        ChainType::BitcoinNestedSegwit => Arc::new(BitcoinNestedSegwit::new(network)),
+        ChainType::BitcoinSegwit => Arc::new(BitcoinSegwit::new(network)),
+        ChainType::BitcoinTaproot => Arc::new(BitcoinTaproot::new(network)),
         ChainType::Ethereum => Arc::new(Ethereum::new()),
         ChainType::Ripple => Arc::new(Ripple::new()),
         ChainType::Solana => Arc::new(Solana::new()),
         ChainType::Tron => Arc::new(Tron::new()),
         ChainType::Sui => Arc::new(Sui::new()),
         ChainType::Near => Arc::new(Near::new()),
-        ChainType::Dogecoin => Arc::new(Dogecoin::new(Network::Bitcoin)),
+        ChainType::Dogecoin if network == Network::Signet => return None,
+        ChainType::Dogecoin => Arc::new(Dogecoin::new(network)),
         ChainType::Cosmos => Arc::new(CosmosChain::new(ChainType::Cosmos)),
         ChainType::Osmosis => Arc::new(CosmosChain::new(ChainType::Osmosis)),
         ChainType::Juno => Arc::new(CosmosChain::new(ChainType::Juno)),
@@ -55,11 +75,26 @@ pub fn create_chain(chain_type: &ChainType) -> Arc<dyn Chain> {
         ChainType::Injective => Arc::new(CosmosChain::new(ChainType::Injective)),
         ChainType::Tezos => Arc::new(Tezos::new()),
         ChainType::Filecoin => Arc::new(Filecoin::new()),
+        ChainType::Stellar => Arc::new(Stellar::new()),
         // Layer 2 EVM chains
         ChainType::Base => Arc::new(EvmChain::new(ChainType::Base)),
         ChainType::Arbitrum => Arc::new(EvmChain::new(ChainType::Arbitrum)),
         ChainType::Optimism => Arc::new(EvmChain::new(ChainType::Optimism)),
         ChainType::Polygon => Arc::new(EvmChain::new(ChainType::Polygon)),
         ChainType::Avalanche => Arc::new(EvmChain::new(ChainType::Avalanche)),
-    }
+    };
+    
+    Some(chain)
+}
+
+/// Whether a chain's addresses, keys and extended keys differ between networks
+pub fn is_network_specific(chain_type: &ChainType) -> bool {
+    matches!(
+        chain_type,
+        ChainType::BitcoinLegacy
+            | ChainType::BitcoinNestedSegwit
+            | ChainType::BitcoinSegwit
+            | ChainType::BitcoinTaproot
+            | ChainType::Dogecoin
+    )
 }
\ No newline at end of file
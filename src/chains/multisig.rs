@@ -0,0 +1,342 @@
+//! Sorted multisig wallets: `threshold`-of-n P2WSH or P2SH-P2WSH addresses over the
+//! cosigners' BIP48 account keys. Each address's keys are sorted as in BIP67
+//! (`sortedmulti` in descriptors), so the order cosigners are listed in does not matter.
+
+use bitcoin::bip32::Xpub;
+use bitcoin::blockdata::opcodes::all::OP_CHECKMULTISIG;
+use bitcoin::blockdata::script::Builder;
+use bitcoin::{Address, PublicKey, ScriptBuf};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::chains::bitcoin::{coin_type, derive_public_child, Network, XpubFormat};
+use crate::chains::descriptor;
+use crate::core::{DerivationPath, KeyTree, MultisigScriptType};
+use crate::errors::{ApiError, ApiResult};
+
+/// Most keys in one wallet: `OP_CHECKMULTISIG` takes at most 20, and witness scripts are
+/// not held to the 520-byte P2SH redeem script limit that caps bare P2SH at 15
+pub const MAX_COSIGNERS: usize = 20;
+
+/// BIP48 account path of this service's own cosigner key, `m/48'/coin'/account'/script'`
+/// with script type 1' for P2SH-P2WSH and 2' for P2WSH
+pub fn bip48_path(network: Network, account: u32, script_type: MultisigScriptType) -> DerivationPath {
+    let script = match script_type {
+        MultisigScriptType::P2shP2wsh => 1,
+        MultisigScriptType::P2wsh => 2,
+    };
+    DerivationPath::hardened(&[48, coin_type(network), account, script])
+}
+
+/// One cosigner's account-level extended public key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cosigner {
+    /// `[fingerprint/path]` as written in descriptors; empty when not given
+    pub key_origin: String,
+    pub xpub: Xpub,
+}
+
+impl Cosigner {
+    /// Parse `[fingerprint/path]key` or a bare key, with any SLIP-0132 prefix
+    pub fn parse(s: &str) -> ApiResult<Self> {
+        let s = s.trim();
+        let (key_origin, key) = match s.strip_prefix('[') {
+            Some(rest) => {
+                let (origin, key) = rest
+                    .split_once(']')
+                    .ok_or_else(|| ApiError::BadRequest(format!("Unterminated key origin: {}", s)))?;
+                (parse_key_origin(origin)?, key)
+            }
+            None => (String::new(), s),
+        };
+
+        let (_, xpub) = XpubFormat::decode(key)?;
+        Ok(Self { key_origin, xpub })
+    }
+
+    /// This wallet's own BIP48 cosigner key
+    pub fn from_keys(
+        keys: &KeyTree,
+        network: Network,
+        account: u32,
+        script_type: MultisigScriptType,
+    ) -> ApiResult<Self> {
+        let path = bip48_path(network, account, script_type);
+        let master = keys.secp256k1_key(&DerivationPath::hardened(&[]))?;
+
+        let mut account_key = keys.secp256k1_key(&path)?;
+        account_key.network = network;
+
+        Ok(Self {
+            key_origin: format!(
+                "[{}{}]",
+                master.fingerprint(keys.secp()),
+                path.to_string().trim_start_matches('m')
+            ),
+            xpub: Xpub::from_priv(keys.secp(), &account_key),
+        })
+    }
+}
+
+/// `[fingerprint/path]xpub`, the form Sparrow and hardware wallets exchange cosigners in
+impl fmt::Display for Cosigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.key_origin, self.xpub)
+    }
+}
+
+/// Normalize the inside of a key origin, e.g. `D34DB33F/48h/0h/0h/2h` to `[d34db33f/48'/0'/0'/2']`
+fn parse_key_origin(origin: &str) -> ApiResult<String> {
+    let (fingerprint, path) = match origin.split_once('/') {
+        Some((fingerprint, path)) => (fingerprint, Some(path)),
+        None => (origin, None),
+    };
+    if fingerprint.len() != 8 || !fingerprint.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ApiError::BadRequest(format!("Invalid key origin fingerprint: {}", fingerprint)));
+    }
+
+    let path = match path {
+        Some(path) => format!("m/{}", path).parse::<DerivationPath>()?.to_string(),
+        None => "m".to_string(),
+    };
+    Ok(format!("[{}{}]", fingerprint.to_lowercase(), path.trim_start_matches('m')))
+}
+
+/// A `threshold`-of-n sorted multisig wallet
+#[derive(Debug, Clone)]
+pub struct MultisigWallet {
+    threshold: u32,
+    script_type: MultisigScriptType,
+    network: Network,
+    cosigners: Vec<Cosigner>,
+}
+
+impl MultisigWallet {
+    pub fn new(
+        threshold: u32,
+        script_type: MultisigScriptType,
+        network: Network,
+        cosigners: Vec<Cosigner>,
+    ) -> ApiResult<Self> {
+        if cosigners.is_empty() || cosigners.len() > MAX_COSIGNERS {
+            return Err(ApiError::BadRequest(format!(
+                "A multisig wallet needs 1 to {} keys, got {}",
+                MAX_COSIGNERS,
+                cosigners.len()
+            )));
+        }
+        if threshold == 0 || threshold as usize > cosigners.len() {
+            return Err(ApiError::BadRequest(format!(
+                "Threshold must be between 1 and {}, got {}",
+                cosigners.len(),
+                threshold
+            )));
+        }
+
+        for (i, cosigner) in cosigners.iter().enumerate() {
+            if (cosigner.xpub.network == Network::Bitcoin) != (network == Network::Bitcoin) {
+                return Err(ApiError::BadRequest(format!(
+                    "Cosigner {} is not a {} key",
+                    i + 1,
+                    if network == Network::Bitcoin { "mainnet" } else { "test network" }
+                )));
+            }
+            if cosigners[..i].iter().any(|other| other.xpub.public_key == cosigner.xpub.public_key) {
+                return Err(ApiError::BadRequest(format!("Cosigner {} is listed twice", i + 1)));
+            }
+        }
+
+        Ok(Self { threshold, script_type, network, cosigners })
+    }
+
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    pub fn script_type(&self) -> MultisigScriptType {
+        self.script_type
+    }
+
+    pub fn cosigners(&self) -> &[Cosigner] {
+        &self.cosigners
+    }
+
+    /// Descriptor, with checksum, of the addresses on `branch` (0 receive, 1 change)
+    pub fn descriptor(&self, branch: u32) -> ApiResult<String> {
+        let keys: Vec<String> = self
+            .cosigners
+            .iter()
+            .map(|cosigner| descriptor::key_expression(&cosigner.key_origin, &cosigner.xpub, branch))
+            .collect();
+        let multi = format!("sortedmulti({},{})", self.threshold, keys.join(","));
+
+        descriptor::with_checksum(&match self.script_type {
+            MultisigScriptType::P2wsh => format!("wsh({})", multi),
+            MultisigScriptType::P2shP2wsh => format!("sh(wsh({}))", multi),
+        })
+    }
+
+    /// `threshold <keys> n OP_CHECKMULTISIG` over the cosigners' `change/index` keys
+    pub fn witness_script(&self, change: u32, index: u32) -> ApiResult<ScriptBuf> {
+        let mut keys = self
+            .cosigners
+            .iter()
+            .map(|cosigner| derive_public_child(&cosigner.xpub, change, index))
+            .collect::<ApiResult<Vec<_>>>()?;
+        keys.sort_by_key(|key| key.serialize());
+
+        let builder = keys
+            .iter()
+            .fold(Builder::new().push_int(self.threshold as i64), |builder, key| {
+                builder.push_key(&PublicKey::new(*key))
+            });
+        Ok(builder
+            .push_int(keys.len() as i64)
+            .push_opcode(OP_CHECKMULTISIG)
+            .into_script())
+    }
+
+    pub fn address(&self, change: u32, index: u32) -> ApiResult<MultisigAddress> {
+        let witness_script = self.witness_script(change, index)?;
+        let address = match self.script_type {
+            MultisigScriptType::P2wsh => Address::p2wsh(&witness_script, self.network),
+            MultisigScriptType::P2shP2wsh => Address::p2shwsh(&witness_script, self.network),
+        };
+
+        Ok(MultisigAddress {
+            address: address.to_string(),
+            relative_path: format!("{}/{}", change, index),
+            change,
+            index,
+            witness_script: hex::encode(witness_script.as_bytes()),
+        })
+    }
+}
+
+/// Address of a multisig wallet and the script that spends from it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultisigAddress {
+    pub address: String,
+    /// Path below every cosigner's account key, e.g. `0/5`
+    pub relative_path: String,
+    pub change: u32,
+    pub index: u32,
+    pub witness_script: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bip39::Mnemonic;
+    use miniscript::{Descriptor, DescriptorPublicKey};
+    use std::str::FromStr;
+
+    fn cosigner(mnemonic: &str, network: Network, script_type: MultisigScriptType) -> Cosigner {
+        let seed = Mnemonic::parse(mnemonic).unwrap().to_seed("");
+        Cosigner::from_keys(&KeyTree::new(&seed), network, 0, script_type).unwrap()
+    }
+
+    fn cosigners(network: Network, script_type: MultisigScriptType) -> Vec<Cosigner> {
+        [
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+        ]
+        .into_iter()
+        .map(|mnemonic| cosigner(mnemonic, network, script_type))
+        .collect()
+    }
+
+    #[test]
+    fn test_sorted_multisig_addresses() {
+        let keys = cosigners(Network::Bitcoin, MultisigScriptType::P2wsh);
+        assert!(keys[0].to_string().starts_with("[73c5da0a/48'/0'/0'/2']xpub"));
+
+        let wallet = MultisigWallet::new(2, MultisigScriptType::P2wsh, Network::Bitcoin, keys.clone()).unwrap();
+        let first = wallet.address(0, 0).unwrap();
+        assert!(first.address.starts_with("bc1q") && first.address.len() == 62);
+
+        // 2 <three sorted 33-byte pushes> 3 OP_CHECKMULTISIG
+        let script = hex::decode(&first.witness_script).unwrap();
+        assert_eq!(script.len(), 1 + 3 * 34 + 2);
+        assert_eq!((script[0], script[103], script[104]), (0x52, 0x53, 0xae));
+        let pushed: Vec<&[u8]> = (0..3).map(|i| &script[2 + i * 34..2 + i * 34 + 33]).collect();
+        assert!(pushed.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // Cosigner order does not matter
+        let mut reversed = keys.clone();
+        reversed.reverse();
+        let reversed = MultisigWallet::new(2, MultisigScriptType::P2wsh, Network::Bitcoin, reversed).unwrap();
+        assert_eq!(reversed.address(0, 0).unwrap().address, first.address);
+        assert_ne!(wallet.address(1, 0).unwrap().address, first.address);
+
+        // P2SH-P2WSH wraps the same kind of script in a 3... address
+        let keys = cosigners(Network::Bitcoin, MultisigScriptType::P2shP2wsh);
+        let nested = MultisigWallet::new(2, MultisigScriptType::P2shP2wsh, Network::Bitcoin, keys).unwrap();
+        let address = nested.address(0, 0).unwrap();
+        let witness_script = ScriptBuf::from(hex::decode(&address.witness_script).unwrap());
+        assert_eq!(address.address, Address::p2shwsh(&witness_script, Network::Bitcoin).to_string());
+        assert!(address.address.starts_with('3'));
+    }
+
+    #[test]
+    fn test_descriptors() {
+        let keys = cosigners(Network::Testnet, MultisigScriptType::P2wsh);
+        let wallet = MultisigWallet::new(2, MultisigScriptType::P2wsh, Network::Testnet, keys.clone()).unwrap();
+
+        let receive = wallet.descriptor(0).unwrap();
+        let (body, checksum) = receive.split_once('#').unwrap();
+        assert_eq!(descriptor::checksum(body).unwrap(), checksum);
+        assert_eq!(
+            body,
+            format!("wsh(sortedmulti(2,{}/0/*,{}/0/*,{}/0/*))", keys[0], keys[1], keys[2])
+        );
+        assert!(body.contains("[73c5da0a/48'/1'/0'/2']tpub"));
+        assert!(wallet.descriptor(1).unwrap().contains("/1/*"));
+    }
+
+    #[test]
+    fn test_descriptors_derive_our_addresses() {
+        // rust-miniscript reads our descriptors the way Bitcoin Core does and must derive
+        // the same addresses from them
+        for network in [Network::Bitcoin, Network::Testnet] {
+            for script_type in [MultisigScriptType::P2wsh, MultisigScriptType::P2shP2wsh] {
+                let wallet = MultisigWallet::new(2, script_type, network, cosigners(network, script_type)).unwrap();
+                for change in [0, 1] {
+                    let descriptor = Descriptor::<DescriptorPublicKey>::from_str(&wallet.descriptor(change).unwrap()).unwrap();
+                    assert_eq!(descriptor.to_string(), wallet.descriptor(change).unwrap());
+                    for index in [0, 7] {
+                        let derived = descriptor.at_derivation_index(index).unwrap();
+                        let ours = wallet.address(change, index).unwrap();
+                        assert_eq!(derived.address(network).unwrap().to_string(), ours.address);
+                        assert_eq!(hex::encode(derived.explicit_script().unwrap().as_bytes()), ours.witness_script);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cosigner_parsing() {
+        let own = cosigner(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            Network::Bitcoin,
+            MultisigScriptType::P2wsh,
+        );
+
+        // SLIP-0132 Zpub as exported by hardware wallets, with an `h`-style origin
+        let zpub = XpubFormat::MultisigZpub.encode(&own.xpub);
+        assert!(zpub.starts_with("Zpub"));
+        let parsed = Cosigner::parse(&format!("[73C5DA0A/48h/0h/0h/2h]{}", zpub)).unwrap();
+        assert_eq!(parsed, own);
+        assert_eq!(Cosigner::parse(&own.xpub.to_string()).unwrap().key_origin, "");
+
+        assert!(Cosigner::parse(&format!("[73c5da0a/48'/0'{}", own.xpub)).is_err());
+        assert!(Cosigner::parse(&format!("[73c5da/48'/0']{}", own.xpub)).is_err());
+
+        let mainnet = vec![own.clone()];
+        assert!(MultisigWallet::new(1, MultisigScriptType::P2wsh, Network::Testnet, mainnet.clone()).is_err());
+        assert!(MultisigWallet::new(2, MultisigScriptType::P2wsh, Network::Bitcoin, mainnet).is_err());
+        assert!(MultisigWallet::new(1, MultisigScriptType::P2wsh, Network::Bitcoin, vec![own.clone(), parsed]).is_err());
+    }
+}
@@ -1,17 +1,28 @@
 use async_trait::async_trait;
-use ed25519_dalek::SigningKey;
-use sha2::{Sha512, Digest};
-use hmac::{Hmac, Mac};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use zeroize::Zeroizing;
+use std::collections::BTreeMap;
 
-use crate::core::{Chain, ChainInfo, ChainType, WalletAddress, DerivationPath, get_chain_info};
+use crate::core::{Chain, KeyTree, ChainInfo, ChainType, WalletAddress, SecretString, KeyFormat, DerivationPath, AddressLevel, ImportedKey, get_chain_info, decode_hex_key, ed25519_keypair, import_ed25519_key};
 use crate::errors::{ApiError, ApiResult};
 
 pub struct Near;
 
+impl Default for Near {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Near {
     pub fn new() -> Self {
         Self
     }
+
+    /// NEAR implicit address is the hex-encoded public key
+    fn address(&self, public_key: &VerifyingKey) -> String {
+        hex::encode(public_key.as_bytes())
+    }
 }
 
 #[async_trait]
@@ -20,38 +31,79 @@ impl Chain for Near {
         get_chain_info(&ChainType::Near)
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
         // NEAR uses Ed25519 with m/44'/397'/0' path (3 levels only)
-        let path = self.derivation_path(index);
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
         
         // Derive key using BIP32-Ed25519
-        let derived_key = self.derive_ed25519_key(seed, index)?;
+        let derived_key = Zeroizing::new(keys.ed25519_node(&path)?.private_key);
         
         let signing_key = SigningKey::from_bytes(&derived_key);
         let verifying_key = signing_key.verifying_key();
         
-        // NEAR implicit address is the hex-encoded public key
-        let address = hex::encode(verifying_key.as_bytes());
+        let address = self.address(&verifying_key);
         
         Ok(WalletAddress {
             address,
             chain_type: ChainType::Near,
             chain_info: self.info(),
-            derivation_path: format!("m/44'/397'/{}'", index),
+            derivation_path: path.to_string(),
+            account,
             index,
             public_key: hex::encode(verifying_key.as_bytes()),
-            private_key: hex::encode(signing_key.to_bytes()),
+            public_key_formats: self.public_key_formats(verifying_key.as_bytes())?,
+            private_key: key_format.map(|format| match format {
+                KeyFormat::Hex => SecretString::hex_encode(signing_key.to_bytes()),
+                // near-cli and NEAR wallets store `ed25519:` plus the base58 64-byte keypair
+                KeyFormat::Native => SecretString::new(format!(
+                    "ed25519:{}",
+                    bs58::encode(Zeroizing::new(signing_key.to_keypair_bytes())).into_string()
+                )),
+            }),
         })
     }
 
-    fn derivation_path(&self, index: u32) -> DerivationPath {
-        // NEAR uses m/44'/397'/index' (only 3 levels)
-        DerivationPath::new(44, 397, index, 0, 0)
+    fn derivation_path(&self, account: u32, _index: u32) -> DerivationPath {
+        // NEAR uses m/44'/397'/account' (only 3 levels)
+        DerivationPath::hardened(&[44, 397, account])
+    }
+
+    fn address_level(&self) -> AddressLevel {
+        // The path ends at the account level, one implicit account per key
+        AddressLevel::Account
+    }
+
+    fn address_from_private_key(&self, private_key: &SecretString) -> ApiResult<ImportedKey> {
+        let invalid = || ApiError::InvalidPrivateKey(self.info().name);
+        let encoded = private_key.expose_secret().trim();
+        
+        let (signing_key, key_format) = match (decode_hex_key(encoded), encoded.strip_prefix("ed25519:")) {
+            (Some(key), _) => (SigningKey::from_bytes(&key), KeyFormat::Hex),
+            (None, Some(keypair)) => {
+                let keypair = Zeroizing::new(bs58::decode(keypair).into_vec().map_err(|_| invalid())?);
+                (ed25519_keypair(&keypair).ok_or_else(invalid)?, KeyFormat::Native)
+            }
+            (None, None) => return Err(invalid()),
+        };
+        
+        let address = self.address(&signing_key.verifying_key());
+        import_ed25519_key(self, &signing_key, address, key_format)
+    }
+
+    fn public_key_formats(&self, public_key: &[u8]) -> ApiResult<BTreeMap<String, String>> {
+        // The form near-cli and access key RPCs use
+        Ok(BTreeMap::from([(
+            "ed25519".to_string(),
+            format!("ed25519:{}", bs58::encode(public_key).into_string()),
+        )]))
     }
 
     async fn validate_address(&self, address: &str) -> bool {
@@ -67,44 +119,3 @@ impl Chain for Near {
         "3b83b07cab54824a59c3d3f2e203a7cd913b7fcdc4439595983e2402c2cf791d"
     }
 }
-
-impl Near {
-    // BIP32-Ed25519 derivation (Trust Wallet style for NEAR)
-    fn derive_ed25519_key(&self, seed: &[u8], index: u32) -> ApiResult<[u8; 32]> {
-        type HmacSha512 = Hmac<Sha512>;
-        
-        // Master key
-        let mut mac = HmacSha512::new_from_slice(b"ed25519 seed")
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-        mac.update(seed);
-        let master = mac.finalize().into_bytes();
-        
-        let mut key = master[..32].to_vec();
-        let mut chain_code = master[32..].to_vec();
-        
-        // NEAR uses m/44'/397'/index' (only 3 levels, all hardened)
-        let indices = vec![
-            0x80000000u32 + 44,    // 44'
-            0x80000000u32 + 397,   // 397'
-            0x80000000u32 + index, // index'
-        ];
-        
-        for idx in indices {
-            let mut mac = HmacSha512::new_from_slice(&chain_code)
-                .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-            
-            // Hardened derivation
-            mac.update(&[0x00]);
-            mac.update(&key);
-            mac.update(&idx.to_be_bytes());
-            
-            let result = mac.finalize().into_bytes();
-            key = result[..32].to_vec();
-            chain_code = result[32..].to_vec();
-        }
-        
-        let mut private_key = [0u8; 32];
-        private_key.copy_from_slice(&key);
-        Ok(private_key)
-    }
-}
\ No newline at end of file
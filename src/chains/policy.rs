@@ -0,0 +1,380 @@
+//! Spending policies compiled to miniscript, for vaults with timelocked recovery paths.
+//!
+//! Policies are written in the language Bitcoin Core and rust-miniscript share:
+//! `pk(KEY)`, `after(n)`, `older(n)`, `sha256(H)`, `hash256(H)`, `ripemd160(H)`,
+//! `hash160(H)`, `and(X,Y)`, `or([N@]X,[N@]Y)` and `thresh(k,X,...)`, e.g.
+//! `or(99@pk(owner),and(pk(recovery),older(52560)))`. Compiling them is left to
+//! rust-miniscript's policy compiler; this module only resolves key names and lays the
+//! result out for the API. For Taproot the compiler makes the likeliest key that can
+//! spend alone the key path and every other top-level alternative a leaf, placed
+//! shallower the likelier its `N@` weight makes it.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use bitcoin::hashes::{hash160, ripemd160, sha256};
+use bitcoin::secp256k1::XOnlyPublicKey;
+use bitcoin::taproot::LeafVersion;
+use bitcoin::{Network, PublicKey};
+use miniscript::iter::TreeLike;
+use miniscript::policy::Concrete;
+use miniscript::{hash256, Descriptor, MiniscriptKey, Segwitv0, Translator};
+
+use crate::core::PolicyScriptType;
+use crate::errors::{ApiError, ApiResult};
+
+/// Longest policy accepted, and most keys and most `thresh` sub-policies in it; the
+/// compiler's work grows combinatorially with the latter two
+const MAX_POLICY_LENGTH: usize = 10_000;
+const MAX_POLICY_KEYS: usize = 20;
+const MAX_THRESH_SUBS: usize = 20;
+/// BIP341's provably unspendable key, the key path when no single key may spend alone
+const UNSPENDABLE_KEY: &str = "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0";
+
+/// A policy compiled to an address
+#[derive(Debug, Clone)]
+pub struct CompiledPolicy {
+    pub script_type: PolicyScriptType,
+    /// `wsh(...)` or `tr(...)` with checksum, for Bitcoin Core's `importdescriptors`
+    pub descriptor: String,
+    pub address: String,
+    /// The whole policy's miniscript and script (P2WSH)
+    pub miniscript: Option<String>,
+    pub witness_script: Option<String>,
+    /// Key path spender (Taproot); BIP341's unspendable key when no key spends alone
+    pub internal_key: Option<String>,
+    pub leaves: Vec<TapLeaf>,
+}
+
+/// One Taproot script path
+#[derive(Debug, Clone)]
+pub struct TapLeaf {
+    pub miniscript: String,
+    pub script: String,
+    pub leaf_version: u8,
+    /// Proof that the script is in the output's tree, placed after it in the witness
+    pub control_block: String,
+}
+
+/// Keys a policy can be compiled with: compressed keys for P2WSH, x-only for Taproot
+trait PolicyKey:
+    MiniscriptKey<Sha256 = sha256::Hash, Hash256 = hash256::Hash, Ripemd160 = ripemd160::Hash, Hash160 = hash160::Hash>
+{
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String>;
+}
+
+impl PolicyKey for PublicKey {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        match bytes.len() {
+            33 => PublicKey::from_slice(bytes).map_err(|e| e.to_string()),
+            32 => Err("x-only keys are only accepted in Taproot policies".to_string()),
+            _ => Err("expected a 33-byte compressed public key".to_string()),
+        }
+    }
+}
+
+impl PolicyKey for XOnlyPublicKey {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        match bytes.len() {
+            33 => PublicKey::from_slice(bytes)
+                .map(|key| key.inner.x_only_public_key().0)
+                .map_err(|e| e.to_string()),
+            32 => XOnlyPublicKey::from_slice(bytes).map_err(|e| e.to_string()),
+            _ => Err("expected a 33-byte or x-only public key".to_string()),
+        }
+    }
+}
+
+/// Resolves the key names of a parsed policy through `keys`, falling back on hex keys
+struct KeyNames<'a> {
+    keys: &'a BTreeMap<String, String>,
+}
+
+impl<Q: PolicyKey> Translator<String, Q, ApiError> for KeyNames<'_> {
+    fn pk(&mut self, name: &String) -> Result<Q, ApiError> {
+        let hex_key = self.keys.get(name).map(String::as_str).unwrap_or(name);
+        let bytes = hex::decode(hex_key.trim())
+            .map_err(|_| ApiError::BadRequest(format!("Unknown key name or invalid hex key: {}", name)))?;
+        Q::from_bytes(&bytes).map_err(|e| ApiError::BadRequest(format!("Invalid key {}: {}", name, e)))
+    }
+
+    fn sha256(&mut self, hash: &String) -> Result<sha256::Hash, ApiError> {
+        parse_hash("sha256", hash)
+    }
+
+    fn hash256(&mut self, hash: &String) -> Result<hash256::Hash, ApiError> {
+        parse_hash("hash256", hash)
+    }
+
+    fn ripemd160(&mut self, hash: &String) -> Result<ripemd160::Hash, ApiError> {
+        parse_hash("ripemd160", hash)
+    }
+
+    fn hash160(&mut self, hash: &String) -> Result<hash160::Hash, ApiError> {
+        parse_hash("hash160", hash)
+    }
+}
+
+fn parse_hash<H: FromStr>(name: &str, hash: &str) -> ApiResult<H> {
+    H::from_str(hash).map_err(|_| ApiError::BadRequest(format!("{} needs a hex hash of its length: {}", name, hash)))
+}
+
+fn compile_error(e: impl std::fmt::Display) -> ApiError {
+    ApiError::BadRequest(format!("Cannot compile policy: {}", e))
+}
+
+/// Compile `policy` to a P2WSH or Taproot address; key names in it are looked up in `keys`.
+/// Policies where some spending path needs no signature, a key appears twice or one
+/// path mixes height and time locks are refused by the compiler.
+pub fn compile(
+    policy: &str,
+    keys: &BTreeMap<String, String>,
+    script_type: PolicyScriptType,
+    network: Network,
+) -> ApiResult<CompiledPolicy> {
+    if policy.len() > MAX_POLICY_LENGTH {
+        return Err(ApiError::BadRequest(format!(
+            "Policy is longer than {} characters",
+            MAX_POLICY_LENGTH
+        )));
+    }
+    let policy = Concrete::<String>::from_str(policy.trim())
+        .map_err(|e| ApiError::BadRequest(format!("Invalid policy: {}", e)))?;
+    check_size(&policy)?;
+    let mut key_names = KeyNames { keys };
+
+    match script_type {
+        PolicyScriptType::P2wsh => compile_wsh(&policy.translate_pk(&mut key_names)?, network),
+        PolicyScriptType::Taproot => compile_tr(&policy.translate_pk(&mut key_names)?, network),
+    }
+}
+
+fn check_size(policy: &Concrete<String>) -> ApiResult<()> {
+    let keys = policy.keys().len();
+    if keys > MAX_POLICY_KEYS {
+        return Err(ApiError::BadRequest(format!(
+            "Policy has {} keys; at most {} are accepted",
+            keys, MAX_POLICY_KEYS
+        )));
+    }
+
+    let widest = policy
+        .pre_order_iter()
+        .filter_map(|node| match node {
+            Concrete::Threshold(_, subs) => Some(subs.len()),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    if widest > MAX_THRESH_SUBS {
+        return Err(ApiError::BadRequest(format!(
+            "A thresh has {} sub-policies; at most {} are accepted",
+            widest, MAX_THRESH_SUBS
+        )));
+    }
+    Ok(())
+}
+
+fn compile_wsh(policy: &Concrete<PublicKey>, network: Network) -> ApiResult<CompiledPolicy> {
+    let miniscript = policy.compile::<Segwitv0>().map_err(compile_error)?;
+    let descriptor = Descriptor::new_wsh(miniscript.clone()).map_err(compile_error)?;
+    let witness_script = descriptor.explicit_script().map_err(compile_error)?;
+
+    Ok(CompiledPolicy {
+        script_type: PolicyScriptType::P2wsh,
+        address: descriptor.address(network).map_err(compile_error)?.to_string(),
+        descriptor: descriptor.to_string(),
+        miniscript: Some(miniscript.to_string()),
+        witness_script: Some(hex::encode(witness_script.as_bytes())),
+        internal_key: None,
+        leaves: Vec::new(),
+    })
+}
+
+fn compile_tr(policy: &Concrete<XOnlyPublicKey>, network: Network) -> ApiResult<CompiledPolicy> {
+    let unspendable_key = XOnlyPublicKey::from_str(UNSPENDABLE_KEY).map_err(|e| ApiError::CryptoError(e.to_string()))?;
+    // A lone key needs no script tree, which the compiler refuses to produce
+    let descriptor = match policy {
+        Concrete::Key(key) => Descriptor::new_tr(*key, None),
+        policy => policy.compile_tr(Some(unspendable_key)),
+    }
+    .map_err(compile_error)?;
+    let Descriptor::Tr(tr) = &descriptor else {
+        return Err(ApiError::CryptoError("Policy did not compile to a Taproot descriptor".to_string()));
+    };
+
+    let spend_info = tr.spend_info();
+    let leaves = tr
+        .iter_scripts()
+        .map(|(_, miniscript)| {
+            let script = miniscript.encode();
+            let control_block = spend_info
+                .control_block(&(script.clone(), LeafVersion::TapScript))
+                .ok_or_else(|| ApiError::CryptoError("Leaf missing from script tree".to_string()))?;
+            Ok(TapLeaf {
+                miniscript: miniscript.to_string(),
+                script: hex::encode(script.as_bytes()),
+                leaf_version: LeafVersion::TapScript.to_consensus(),
+                control_block: hex::encode(control_block.serialize()),
+            })
+        })
+        .collect::<ApiResult<Vec<_>>>()?;
+
+    Ok(CompiledPolicy {
+        script_type: PolicyScriptType::Taproot,
+        address: descriptor.address(network).map_err(compile_error)?.to_string(),
+        descriptor: descriptor.to_string(),
+        miniscript: None,
+        witness_script: None,
+        internal_key: Some(tr.internal_key().to_string()),
+        leaves,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::taproot::ControlBlock;
+    use bitcoin::{Address, ScriptBuf};
+    use miniscript::DescriptorPublicKey;
+
+    use crate::chains::descriptor;
+
+    // The public keys of private keys 1, 2 and 3
+    const OWNER: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    const RECOVERY: &str = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+    const HEIR: &str = "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9";
+
+    fn keys() -> BTreeMap<String, String> {
+        [("owner", OWNER), ("recovery", RECOVERY), ("heir", HEIR)]
+            .into_iter()
+            .map(|(name, key)| (name.to_string(), key.to_string()))
+            .collect()
+    }
+
+    /// Compile with the test keys and check that rust-miniscript reads the descriptor back
+    fn compile_checked(policy: &str, script_type: PolicyScriptType, network: Network) -> CompiledPolicy {
+        let compiled = compile(policy, &keys(), script_type, network).unwrap();
+        let parsed = Descriptor::<DescriptorPublicKey>::from_str(&compiled.descriptor).unwrap();
+        assert_eq!(parsed.to_string(), compiled.descriptor);
+        parsed.sanity_check().unwrap();
+        compiled
+    }
+
+    #[test]
+    fn test_timelocked_recovery_in_p2wsh() {
+        let compiled = compile_checked("or(pk(owner),and(pk(recovery),older(52560)))", PolicyScriptType::P2wsh, Network::Bitcoin);
+
+        assert_eq!(
+            compiled.miniscript.as_deref(),
+            Some(format!("or_d(pk({}),and_v(v:pk({}),older(52560)))", OWNER, RECOVERY).as_str())
+        );
+        // <owner> CHECKSIG IFDUP NOTIF <recovery> CHECKSIGVERIFY <52560> CSV ENDIF
+        assert_eq!(
+            compiled.witness_script.as_deref(),
+            Some(format!("21{}ac736421{}ad0350cd00b268", OWNER, RECOVERY).as_str())
+        );
+
+        let (body, checksum) = compiled.descriptor.split_once('#').unwrap();
+        assert_eq!(body, format!("wsh({})", compiled.miniscript.unwrap()));
+        assert_eq!(descriptor::checksum(body).unwrap(), checksum);
+
+        let witness_script = ScriptBuf::from(hex::decode(compiled.witness_script.unwrap()).unwrap());
+        assert_eq!(compiled.address, Address::p2wsh(&witness_script, Network::Bitcoin).to_string());
+        assert!(compiled.leaves.is_empty());
+    }
+
+    #[test]
+    fn test_thresh_wrappers() {
+        // 2-of-2, or either key alone after a timeout
+        let compiled = compile_checked("thresh(2,pk(owner),pk(recovery),after(800000))", PolicyScriptType::P2wsh, Network::Bitcoin);
+        assert_eq!(
+            compiled.miniscript.unwrap(),
+            format!("thresh(2,pk({}),s:pk({}),snl:after(800000))", OWNER, RECOVERY)
+        );
+        // thresh adds up 0/1 results: <owner> CHECKSIG, SWAP <recovery> CHECKSIG ADD, then
+        // SWAP IF 0 ELSE <800000> CLTV ENDIF 0NOTEQUAL ADD 2 EQUAL
+        assert_eq!(
+            compiled.witness_script.unwrap(),
+            format!("21{}ac7c21{}ac937c6300670300350cb16892935287", OWNER, RECOVERY)
+        );
+
+        let compiled = compile_checked("thresh(2,pk(owner),pk(recovery),pk(heir))", PolicyScriptType::P2wsh, Network::Bitcoin);
+        assert_eq!(compiled.miniscript.unwrap(), format!("multi(2,{},{},{})", OWNER, RECOVERY, HEIR));
+    }
+
+    #[test]
+    fn test_taproot_script_tree() {
+        let compiled = compile_checked("or(9@pk(owner),or(pk(heir),and(pk(recovery),older(52560))))", PolicyScriptType::Taproot, Network::Bitcoin);
+
+        // The owner spends by key path, the other two by script path
+        let owner = &OWNER[2..];
+        assert_eq!(compiled.internal_key.as_deref(), Some(owner));
+        assert!(compiled.address.starts_with("bc1p"));
+        assert_eq!(compiled.leaves.len(), 2);
+        assert_eq!(compiled.leaves[0].miniscript, format!("and_v(v:pk({}),older(52560))", &RECOVERY[2..]));
+        assert_eq!(compiled.leaves[0].script, format!("20{}ad0350cd00b2", &RECOVERY[2..]));
+        assert_eq!(compiled.leaves[1].miniscript, format!("pk({})", &HEIR[2..]));
+
+        let output_key = Address::from_str(&compiled.address).unwrap().assume_checked().script_pubkey().as_bytes()[2..].to_vec();
+        let output_key = XOnlyPublicKey::from_slice(&output_key).unwrap();
+        let secp = Secp256k1::verification_only();
+        for leaf in &compiled.leaves {
+            assert_eq!(leaf.leaf_version, 0xc0);
+            let control_block = ControlBlock::decode(&hex::decode(&leaf.control_block).unwrap()).unwrap();
+            let script = ScriptBuf::from(hex::decode(&leaf.script).unwrap());
+            assert!(control_block.verify_taproot_commitment(&secp, output_key, &script));
+            // Two leaves: the control block carries one sibling hash
+            assert_eq!(control_block.serialize().len(), 33 + 32);
+        }
+
+        let (body, checksum) = compiled.descriptor.split_once('#').unwrap();
+        assert_eq!(descriptor::checksum(body).unwrap(), checksum);
+        assert!(body.starts_with(&format!("tr({},{{", owner)));
+
+        // Without a lone key the key path is unspendable
+        let compiled = compile_checked("thresh(2,pk(owner),pk(recovery),pk(heir))", PolicyScriptType::Taproot, Network::Testnet);
+        assert_eq!(compiled.internal_key.as_deref(), Some(UNSPENDABLE_KEY));
+        assert_eq!(
+            compiled.leaves[0].miniscript,
+            format!("multi_a(2,{},{},{})", &OWNER[2..], &RECOVERY[2..], &HEIR[2..])
+        );
+        assert!(compiled.address.starts_with("tb1p"));
+    }
+
+    #[test]
+    fn test_invalid_policies() {
+        let invalid = |policy: &str, script_type| compile(policy, &keys(), script_type, Network::Bitcoin).is_err();
+
+        // Spendable without a signature once the timelock passes
+        assert!(invalid("or(pk(owner),older(144))", PolicyScriptType::P2wsh));
+        assert!(invalid("or(pk(owner),pk(owner))", PolicyScriptType::P2wsh));
+        assert!(invalid("and(pk(owner),and(after(800000),after(1700000000)))", PolicyScriptType::P2wsh));
+        assert!(invalid("pk(stranger)", PolicyScriptType::P2wsh));
+        assert!(invalid(&format!("pk({})", &OWNER[2..]), PolicyScriptType::P2wsh));
+        assert!(invalid("and(pk(owner),older(0))", PolicyScriptType::P2wsh));
+        assert!(invalid("and(pk(owner),sha256(abcd))", PolicyScriptType::P2wsh));
+        assert!(invalid("or(pk(owner),pk(recovery)", PolicyScriptType::P2wsh));
+        assert!(invalid("thresh(3,pk(owner),pk(recovery))", PolicyScriptType::Taproot));
+        assert!(invalid("xor(pk(owner),pk(recovery))", PolicyScriptType::Taproot));
+
+        // Too many keys, or too wide a thresh, for the compiler to search in reasonable time
+        let too_large = |policy: &str| match compile(policy, &keys(), PolicyScriptType::Taproot, Network::Bitcoin) {
+            Err(ApiError::BadRequest(message)) => message.contains("at most"),
+            _ => false,
+        };
+        let hashes = vec![format!("sha256({})", "00".repeat(32)); 21];
+        assert!(too_large(&format!("and(pk(owner),thresh(2,{}))", hashes.join(","))));
+        let names: Vec<String> = (1..=21).map(|i| format!("pk(key{})", i)).collect();
+        assert!(too_large(&format!("thresh(2,{})", names.join(","))));
+
+        // Height and time locks in different branches are fine
+        assert!(!invalid(
+            "or(and(pk(owner),after(800000)),and(pk(recovery),after(1700000000)))",
+            PolicyScriptType::P2wsh
+        ));
+        let compiled = compile_checked(&format!("pk({})", &OWNER[2..]), PolicyScriptType::Taproot, Network::Bitcoin);
+        assert!(compiled.leaves.is_empty());
+    }
+}
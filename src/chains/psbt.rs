@@ -0,0 +1,1367 @@
+//! BIP174 PSBTs for this service's single-key Bitcoin and Dogecoin addresses.
+//!
+//! Inputs are matched to the request's keys by their BIP32 origins when these carry the
+//! seed's master fingerprint, and otherwise by script among the first receive and change
+//! addresses of every address type. Matched inputs and outputs get their origins filled
+//! in, so hardware signers and coordinators recognize them too. Inputs are signed with
+//! ECDSA for P2PKH, P2SH-P2WPKH and P2WPKH and with a BIP340 signature over the BIP341
+//! key-path sighash for P2TR, then finalized into their scriptSig and witness. Dogecoin
+//! transactions use Bitcoin's legacy format and sighash, so its P2PKH inputs sign the
+//! same way. New PSBTs spend either every given UTXO or those picked by
+//! `coin_selection` at a fee rate. Everything is offline: amounts and scripts come from
+//! the PSBT itself.
+//!
+//! Legacy and Dogecoin inputs need the transaction that created their UTXO, since
+//! their sighash does not commit to the amount, and inputs are only signed with
+//! SIGHASH_ALL (or Taproot's DEFAULT) unless the request opts out of either check.
+//! Only version 0 PSBTs are read: BIP370 version 2 PSBTs are refused as such.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use base64::Engine;
+use bitcoin::bip32::{DerivationPath as BtcDerivationPath, Fingerprint};
+use bitcoin::blockdata::script::{Builder, PushBytesBuf};
+use bitcoin::hashes::Hash;
+use bitcoin::key::TapTweak;
+use bitcoin::psbt::{Input, Output, Psbt};
+use bitcoin::secp256k1::{self, Keypair, Message};
+use bitcoin::sighash::{EcdsaSighashType, Prevouts, SighashCache, TapSighashType};
+use bitcoin::{
+    absolute, consensus, ecdsa, taproot, transaction, Address, Amount, FeeRate, Network, OutPoint, PublicKey,
+    ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, VarInt, Witness,
+};
+use rand::Rng;
+
+use crate::chains::bitcoin::coin_type;
+use crate::chains::coin_selection::{self, Algorithm, Candidate};
+use crate::chains::Dogecoin;
+use crate::core::{get_chain_types_by_symbol, Chain, ChainType, DerivationPath, KeyTree};
+use crate::errors::{ApiError, ApiResult};
+
+/// Receive and change addresses of each address type searched for inputs and outputs
+/// without BIP32 origins
+pub const ADDRESS_SCAN_LIMIT: u32 = 100;
+
+/// Address types whose inputs this module signs
+pub fn is_signable(chain_type: &ChainType) -> bool {
+    matches!(
+        chain_type,
+        ChainType::BitcoinLegacy
+            | ChainType::BitcoinNestedSegwit
+            | ChainType::BitcoinSegwit
+            | ChainType::BitcoinTaproot
+            | ChainType::Dogecoin
+    )
+}
+
+/// BIP370's PSBT_GLOBAL_VERSION key type
+const PSBT_GLOBAL_VERSION: u8 = 0xfb;
+
+pub fn decode(psbt: &str) -> ApiResult<Psbt> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(psbt.trim())
+        .map_err(|e| ApiError::BadRequest(format!("PSBT is not valid base64: {}", e)))?;
+    if let Some(version) = global_version(&bytes).filter(|version| *version != 0) {
+        return Err(ApiError::BadRequest(format!(
+            "PSBT version {} is not supported; only version 0 PSBTs are",
+            version
+        )));
+    }
+    Psbt::deserialize(&bytes).map_err(|e| ApiError::BadRequest(format!("Invalid PSBT: {}", e)))
+}
+
+/// The PSBT_GLOBAL_VERSION of a serialized PSBT, 0 when it has none; `None` when its
+/// global map cannot be read
+fn global_version(bytes: &[u8]) -> Option<u32> {
+    // Each key and value is a compact size length and that many bytes
+    fn field<'b>(rest: &mut &'b [u8]) -> Option<&'b [u8]> {
+        let (len, read) = consensus::deserialize_partial::<VarInt>(rest).ok()?;
+        let field = rest.get(read..read.checked_add(usize::try_from(len.0).ok()?)?)?;
+        *rest = &rest[read + field.len()..];
+        Some(field)
+    }
+
+    let mut rest = bytes.strip_prefix(b"psbt\xff")?;
+    loop {
+        let key = field(&mut rest)?;
+        // An empty key ends the global map
+        if key.is_empty() {
+            return Some(0);
+        }
+        let value = field(&mut rest)?;
+        if key == [PSBT_GLOBAL_VERSION] {
+            return value.try_into().ok().map(u32::from_le_bytes);
+        }
+    }
+}
+
+pub fn encode(psbt: &Psbt) -> String {
+    base64::engine::general_purpose::STANDARD.encode(psbt.serialize())
+}
+
+/// The address types of `symbol` that PSBTs can be signed for
+pub fn chain_types(symbol: &str) -> ApiResult<Vec<ChainType>> {
+    let chain_types: Vec<ChainType> = get_chain_types_by_symbol(symbol).into_iter().filter(is_signable).collect();
+    if chain_types.is_empty() {
+        return Err(ApiError::BadRequest(format!(
+            "PSBT signing supports BTC and DOGE, not {}",
+            symbol
+        )));
+    }
+    Ok(chain_types)
+}
+
+/// The address type named `name`, e.g. "BitcoinSegwit"; may be empty when there is only one
+pub fn address_type(chain_types: &[ChainType], name: &str) -> ApiResult<ChainType> {
+    match (chain_types, name) {
+        ([chain_type], "") => Ok(chain_type.clone()),
+        (_, "") => Err(ApiError::BadRequest(format!(
+            "An address_type is needed, one of {:?}",
+            chain_types
+        ))),
+        _ => chain_types
+            .iter()
+            .find(|chain_type| format!("{:?}", chain_type).eq_ignore_ascii_case(name))
+            .cloned()
+            .ok_or_else(|| ApiError::BadRequest(format!("Unknown address type: {}", name))),
+    }
+}
+
+pub fn parse_outpoint(txid: &str, vout: u32) -> ApiResult<OutPoint> {
+    let txid = Txid::from_str(txid.trim()).map_err(|e| ApiError::BadRequest(format!("Invalid txid {}: {}", txid, e)))?;
+    Ok(OutPoint { txid, vout })
+}
+
+pub fn parse_transaction(hex_transaction: &str) -> ApiResult<Transaction> {
+    let bytes = hex::decode(hex_transaction.trim())
+        .map_err(|_| ApiError::BadRequest("Previous transaction is not valid hex".to_string()))?;
+    consensus::deserialize(&bytes).map_err(|e| ApiError::BadRequest(format!("Invalid previous transaction: {}", e)))
+}
+
+/// Fee rate from satoshis per virtual byte, rounded up to a whole sat/kwu
+pub fn fee_rate(sat_per_vb: f64) -> ApiResult<FeeRate> {
+    if !sat_per_vb.is_finite() || sat_per_vb <= 0.0 {
+        return Err(ApiError::BadRequest(format!("Invalid fee rate: {}", sat_per_vb)));
+    }
+    Ok(FeeRate::from_sat_per_kwu((sat_per_vb * 250.0).ceil() as u64))
+}
+
+/// `sat` as an amount of the coin of `chain_types`, refused above its money range:
+/// 21 million BTC, or Dogecoin Core's 10 billion DOGE
+pub fn parse_amount(chain_types: &[ChainType], sat: u64) -> ApiResult<Amount> {
+    let max_money = if chain_types.contains(&ChainType::Dogecoin) {
+        coin_selection::DOGECOIN_MAX_MONEY
+    } else {
+        Amount::MAX_MONEY
+    };
+    if sat > max_money.to_sat() {
+        return Err(ApiError::BadRequest(format!(
+            "Amount of {} is above the money range of {}",
+            sat,
+            max_money.to_sat()
+        )));
+    }
+    Ok(Amount::from_sat(sat))
+}
+
+/// Sum of caller-supplied amounts, refused instead of overflowing
+fn checked_sum(values: impl IntoIterator<Item = u64>) -> ApiResult<u64> {
+    values
+        .into_iter()
+        .try_fold(0u64, u64::checked_add)
+        .ok_or_else(amount_overflow)
+}
+
+fn amount_overflow() -> ApiError {
+    ApiError::BadRequest("Amounts and fees add up to more than can be represented".to_string())
+}
+
+/// A UTXO of one of the request's addresses
+#[derive(Debug, Clone)]
+pub struct Utxo {
+    pub outpoint: OutPoint,
+    pub value: Amount,
+    pub chain_type: ChainType,
+    pub change: u32,
+    pub index: u32,
+    /// The transaction that created the UTXO, which BIP174 asks for on non-SegWit inputs
+    pub previous_transaction: Option<Transaction>,
+}
+
+/// An amount to pay to an address
+#[derive(Debug, Clone)]
+pub struct Payout {
+    pub address: String,
+    pub value: Amount,
+}
+
+/// What to sign: a caller's PSBT, or UTXOs to spend to payouts
+#[derive(Debug, Clone)]
+pub enum Unsigned {
+    Psbt(Psbt),
+    Spend { utxos: Vec<Utxo>, payouts: Vec<Payout> },
+}
+
+/// A signing request for one coin's address types
+#[derive(Debug, Clone)]
+pub struct SigningRequest {
+    pub chain_types: Vec<ChainType>,
+    pub network: Network,
+    pub account: u32,
+    pub transaction: Unsigned,
+    /// Leave signed inputs unfinalized, for coordinators that finalize themselves
+    pub sign_only: bool,
+    /// Spend legacy and Dogecoin UTXOs without their previous transaction
+    pub allow_missing_previous_transactions: bool,
+    /// Sign inputs asking for a sighash type other than ALL, or Taproot's DEFAULT
+    pub allow_any_sighash: bool,
+}
+
+/// Replacement and locktime settings of a new transaction
+#[derive(Debug, Clone, Copy)]
+pub struct Finality {
+    /// Signal BIP125 replaceability
+    pub rbf: bool,
+    pub lock_time: absolute::LockTime,
+}
+
+impl Default for Finality {
+    fn default() -> Self {
+        Self { rbf: false, lock_time: absolute::LockTime::ZERO }
+    }
+}
+
+impl Finality {
+    /// Every input's nSequence: replaceable, final but honoring the locktime, or final
+    fn sequence(&self) -> Sequence {
+        if self.rbf {
+            Sequence::ENABLE_RBF_NO_LOCKTIME
+        } else if self.lock_time != absolute::LockTime::ZERO {
+            Sequence::ENABLE_LOCKTIME_NO_RBF
+        } else {
+            Sequence::MAX
+        }
+    }
+}
+
+/// Fee rate, change and finality of a transaction built by coin selection
+#[derive(Debug, Clone)]
+pub struct BuildOptions {
+    pub fee_rate: FeeRate,
+    /// Address type of the change output; the first UTXO's when `None`
+    pub change_type: Option<ChainType>,
+    /// Index of the next unused address on the change chain
+    pub change_index: u32,
+    pub finality: Finality,
+}
+
+/// A request to select UTXOs for payouts, for one coin's address types
+#[derive(Debug, Clone)]
+pub struct BuildRequest {
+    pub chain_types: Vec<ChainType>,
+    pub network: Network,
+    pub account: u32,
+    pub utxos: Vec<Utxo>,
+    pub payouts: Vec<Payout>,
+    pub options: BuildOptions,
+    /// Spend legacy and Dogecoin UTXOs without their previous transaction
+    pub allow_missing_previous_transactions: bool,
+}
+
+/// The change output of a built transaction
+#[derive(Debug, Clone)]
+pub struct ChangeOutput {
+    /// Position among the transaction's outputs
+    pub output: usize,
+    pub address: String,
+    pub path: DerivationPath,
+    pub value: Amount,
+}
+
+/// An unsigned PSBT from coin selection, with its expected size once signed
+#[derive(Debug, Clone)]
+pub struct BuiltPsbt {
+    pub psbt: Psbt,
+    pub algorithm: Algorithm,
+    pub fee: Amount,
+    pub vsize: u64,
+    pub change: Option<ChangeOutput>,
+}
+
+/// The PSBT after signing, and the transaction once every input is final
+#[derive(Debug, Clone)]
+pub struct SignedPsbt {
+    pub psbt: Psbt,
+    pub signed_inputs: Vec<usize>,
+    pub complete: bool,
+    pub transaction: Option<Transaction>,
+    /// Unknown while an input's UTXO is missing
+    pub fee: Option<Amount>,
+}
+
+/// One of the request's keys and where it sits
+#[derive(Debug, Clone)]
+struct OwnedKey {
+    chain_type: ChainType,
+    path: DerivationPath,
+    public_key: secp256k1::PublicKey,
+}
+
+/// The request's keys for one coin's single-key address types
+pub struct Keychain<'a> {
+    keys: &'a KeyTree,
+    network: Network,
+    chain_types: Vec<ChainType>,
+    account: u32,
+    fingerprint: Fingerprint,
+    /// Scripts of the first addresses, computed on first use
+    scanned: Option<HashMap<ScriptBuf, OwnedKey>>,
+    allow_missing_previous_transactions: bool,
+    allow_any_sighash: bool,
+}
+
+impl<'a> Keychain<'a> {
+    pub fn new(keys: &'a KeyTree, chain_types: Vec<ChainType>, network: Network, account: u32) -> ApiResult<Self> {
+        if chain_types.is_empty() {
+            return Err(ApiError::BadRequest("No address types to sign for".to_string()));
+        }
+        if let Some(chain_type) = chain_types.iter().find(|chain_type| !is_signable(chain_type)) {
+            return Err(ApiError::BadRequest(format!("{:?} transactions cannot be signed as PSBTs", chain_type)));
+        }
+        if chain_types.contains(&ChainType::Dogecoin) && network == Network::Signet {
+            return Err(ApiError::BadRequest("Dogecoin has no signet".to_string()));
+        }
+
+        let master = keys.secp256k1_key(&DerivationPath::hardened(&[]))?;
+        Ok(Self {
+            keys,
+            network,
+            chain_types,
+            account,
+            fingerprint: master.fingerprint(keys.secp()),
+            scanned: None,
+            allow_missing_previous_transactions: false,
+            allow_any_sighash: false,
+        })
+    }
+
+    /// Accept legacy and Dogecoin inputs without the transaction that created their
+    /// UTXO. Their sighash does not commit to the amount, so nothing then stops a
+    /// wrong one from turning into fees; new PSBTs carry the UTXO as a `witness_utxo`.
+    pub fn allow_missing_previous_transactions(mut self, allow: bool) -> Self {
+        self.allow_missing_previous_transactions = allow;
+        self
+    }
+
+    /// Sign inputs asking for sighash types other than ALL, or Taproot's DEFAULT,
+    /// which leave outputs or other inputs open to change after signing
+    pub fn allow_any_sighash(mut self, allow: bool) -> Self {
+        self.allow_any_sighash = allow;
+        self
+    }
+
+    fn is_dogecoin(&self) -> bool {
+        self.chain_types.contains(&ChainType::Dogecoin)
+    }
+
+    /// `m/purpose'/coin'/account'/change/index` of an address type
+    fn path(&self, chain_type: &ChainType, change: u32, index: u32) -> DerivationPath {
+        let purpose = match chain_type {
+            ChainType::BitcoinNestedSegwit => 49,
+            ChainType::BitcoinSegwit => 84,
+            ChainType::BitcoinTaproot => 86,
+            _ => 44,
+        };
+        let coin = match (chain_type, self.network) {
+            (ChainType::Dogecoin, Network::Bitcoin) => 3,
+            _ => coin_type(self.network),
+        };
+        DerivationPath::new(purpose, coin, self.account, change, index)
+    }
+
+    fn key(&self, chain_type: &ChainType, path: DerivationPath) -> ApiResult<OwnedKey> {
+        let public_key = self.keys.secp256k1_key(&path)?.private_key.public_key(self.keys.secp());
+        Ok(OwnedKey { chain_type: chain_type.clone(), path, public_key })
+    }
+
+    fn script_pubkey(&self, key: &OwnedKey) -> ScriptBuf {
+        let public_key = PublicKey::new(key.public_key);
+        match key.chain_type {
+            ChainType::BitcoinNestedSegwit => ScriptBuf::new_p2sh(&p2wpkh_program(&key.public_key).script_hash()),
+            ChainType::BitcoinSegwit => p2wpkh_program(&key.public_key),
+            ChainType::BitcoinTaproot => ScriptBuf::new_p2tr(self.keys.secp(), key.public_key.x_only_public_key().0, None),
+            _ => ScriptBuf::new_p2pkh(&public_key.pubkey_hash()),
+        }
+    }
+
+    /// Output script paying `address` on this keychain's coin and network
+    pub fn address_script(&self, address: &str) -> ApiResult<ScriptBuf> {
+        if self.is_dogecoin() {
+            return Dogecoin::new(self.network).script_pubkey(address);
+        }
+
+        Address::from_str(address)
+            .map_err(|e| ApiError::BadRequest(format!("Invalid address {}: {}", address, e)))?
+            .require_network(self.network)
+            .map(|address| address.script_pubkey())
+            .map_err(|e| ApiError::BadRequest(format!("Invalid address {}: {}", address, e)))
+    }
+
+    /// Address of one of the request's keys
+    fn address(&self, key: &OwnedKey) -> ApiResult<String> {
+        if self.is_dogecoin() {
+            return Dogecoin::new(self.network).address_from_public_key(&key.public_key.serialize());
+        }
+        Address::from_script(&self.script_pubkey(key), self.network)
+            .map(|address| address.to_string())
+            .map_err(|e| ApiError::CryptoError(e.to_string()))
+    }
+
+    /// The key spending `script`, found by a BIP32 origin with this seed's fingerprint or
+    /// among the first addresses
+    fn find(&mut self, script: &ScriptBuf, origins: &[(Fingerprint, &BtcDerivationPath)]) -> ApiResult<Option<OwnedKey>> {
+        for (fingerprint, path) in origins {
+            if *fingerprint != self.fingerprint {
+                continue;
+            }
+            let path: DerivationPath = path.to_string().parse()?;
+            for chain_type in &self.chain_types {
+                let key = self.key(chain_type, path.clone())?;
+                if self.script_pubkey(&key) == *script {
+                    return Ok(Some(key));
+                }
+            }
+        }
+
+        if self.scanned.is_none() {
+            let mut scanned = HashMap::new();
+            for chain_type in &self.chain_types {
+                for change in 0..=1 {
+                    for index in 0..ADDRESS_SCAN_LIMIT {
+                        let key = self.key(chain_type, self.path(chain_type, change, index))?;
+                        scanned.insert(self.script_pubkey(&key), key);
+                    }
+                }
+            }
+            self.scanned = Some(scanned);
+        }
+        Ok(self.scanned.as_ref().and_then(|scanned| scanned.get(script).cloned()))
+    }
+
+    fn key_source(&self, key: &OwnedKey) -> ApiResult<(Fingerprint, BtcDerivationPath)> {
+        Ok((self.fingerprint, key.path.to_bip32()?))
+    }
+
+    /// Add the key's origin, and the redeem script of P2SH-P2WPKH, to an input
+    fn update_input(&self, input: &mut Input, key: &OwnedKey) -> ApiResult<()> {
+        let source = self.key_source(key)?;
+        match key.chain_type {
+            ChainType::BitcoinTaproot => {
+                let x_only = key.public_key.x_only_public_key().0;
+                input.tap_internal_key = Some(x_only);
+                input.tap_key_origins.insert(x_only, (Vec::new(), source));
+            }
+            ChainType::BitcoinNestedSegwit => {
+                input.redeem_script = Some(p2wpkh_program(&key.public_key));
+                input.bip32_derivation.insert(key.public_key, source);
+            }
+            _ => {
+                input.bip32_derivation.insert(key.public_key, source);
+            }
+        }
+        Ok(())
+    }
+
+    /// Mark an output paying one of the request's addresses, e.g. change, as ours
+    fn update_output(&self, output: &mut Output, key: &OwnedKey) -> ApiResult<()> {
+        let source = self.key_source(key)?;
+        match key.chain_type {
+            ChainType::BitcoinTaproot => {
+                let x_only = key.public_key.x_only_public_key().0;
+                output.tap_internal_key = Some(x_only);
+                output.tap_key_origins.insert(x_only, (Vec::new(), source));
+            }
+            ChainType::BitcoinNestedSegwit => {
+                output.redeem_script = Some(p2wpkh_program(&key.public_key));
+                output.bip32_derivation.insert(key.public_key, source);
+            }
+            _ => {
+                output.bip32_derivation.insert(key.public_key, source);
+            }
+        }
+        Ok(())
+    }
+
+    /// Unsigned PSBT spending `utxos` to `payouts`, with every input's origin filled in
+    pub fn create(&self, utxos: &[Utxo], payouts: &[Payout]) -> ApiResult<Psbt> {
+        if utxos.is_empty() || payouts.is_empty() {
+            return Err(ApiError::BadRequest("A transaction needs at least one UTXO and one output".to_string()));
+        }
+        check_unique(utxos)?;
+
+        let outputs = self.outputs(payouts)?;
+        let spent = checked_sum(utxos.iter().map(|utxo| utxo.value.to_sat()))?;
+        let paid = checked_sum(outputs.iter().map(|output| output.value.to_sat()))?;
+        if paid > spent {
+            return Err(ApiError::BadRequest(format!(
+                "Outputs pay {} but the UTXOs only hold {}",
+                Amount::from_sat(paid),
+                Amount::from_sat(spent)
+            )));
+        }
+
+        self.unsigned(utxos.iter(), outputs, Finality::default())
+    }
+
+    /// Unsigned PSBT paying `payouts` from UTXOs picked by coin selection at the fee
+    /// rate, with a change output on the change chain when worth keeping
+    pub fn build<R: Rng + ?Sized>(
+        &self,
+        utxos: &[Utxo],
+        payouts: &[Payout],
+        options: &BuildOptions,
+        rng: &mut R,
+    ) -> ApiResult<BuiltPsbt> {
+        if utxos.is_empty() || payouts.is_empty() {
+            return Err(ApiError::BadRequest("A transaction needs at least one UTXO and one output".to_string()));
+        }
+        check_unique(utxos)?;
+
+        let dogecoin = self.is_dogecoin();
+        let fee_rate = options.fee_rate;
+        if fee_rate < FeeRate::BROADCAST_MIN {
+            return Err(ApiError::BadRequest("Fee rate must be at least 1 sat/vB".to_string()));
+        }
+        let max_fee_rate = if dogecoin { coin_selection::DOGECOIN_MAX_FEE_RATE } else { Psbt::DEFAULT_MAX_FEE_RATE };
+        if fee_rate > max_fee_rate {
+            return Err(ApiError::BadRequest(format!(
+                "Fee rate {} is above the {} limit",
+                fee_rate,
+                max_fee_rate
+            )));
+        }
+
+        let mut outputs = self.outputs(payouts)?;
+        for (output, payout) in outputs.iter().zip(payouts) {
+            let dust = coin_selection::dust_limit(&output.script_pubkey, dogecoin);
+            if output.value < dust {
+                return Err(ApiError::BadRequest(format!(
+                    "Output to {} of {} is below the dust limit of {}",
+                    payout.address, output.value, dust
+                )));
+            }
+        }
+
+        let change_type = options.change_type.clone().unwrap_or_else(|| utxos[0].chain_type.clone());
+        if !self.chain_types.contains(&change_type) {
+            return Err(ApiError::BadRequest(format!("This coin has no {:?} change addresses", change_type)));
+        }
+        let change_key = self.key(&change_type, self.path(&change_type, 1, options.change_index))?;
+        let change_script = self.script_pubkey(&change_key);
+        let change_dust = coin_selection::dust_limit(&change_script, dogecoin).to_sat();
+        let change_fee = coin_selection::fee(fee_rate, coin_selection::output_weight(&change_script));
+        // Creating change now and spending it later, which a changeless selection may
+        // give to the fee instead
+        let cost_of_change = change_fee
+            .checked_add(coin_selection::fee(fee_rate, coin_selection::input_weight(&change_type)))
+            .ok_or_else(amount_overflow)?;
+
+        let mut base_weight = coin_selection::transaction_weight(&[], &outputs);
+        if utxos.iter().any(|utxo| coin_selection::is_segwit(&utxo.chain_type)) {
+            base_weight += coin_selection::SEGWIT_MARKER_WEIGHT;
+        }
+        let available = checked_sum(utxos.iter().map(|utxo| utxo.value.to_sat()))?;
+        let paid = checked_sum(outputs.iter().map(|output| output.value.to_sat()))?;
+        let target = paid
+            .checked_add(coin_selection::fee(fee_rate, base_weight))
+            .ok_or_else(amount_overflow)?;
+        let candidates: Vec<_> = utxos
+            .iter()
+            .map(|utxo| Candidate {
+                effective_value: utxo
+                    .value
+                    .to_sat()
+                    .saturating_sub(coin_selection::fee(fee_rate, coin_selection::input_weight(&utxo.chain_type))),
+            })
+            .collect();
+
+        let insufficient = || {
+            ApiError::BadRequest(format!(
+                "Insufficient funds: the UTXOs hold {} but paying {} at {} needs more",
+                Amount::from_sat(available),
+                Amount::from_sat(paid),
+                fee_rate
+            ))
+        };
+        let mut selection = coin_selection::select(&candidates, target, cost_of_change, change_fee, change_dust, rng)
+            .ok_or_else(insufficient)?;
+        selection.indices.sort_unstable();
+        let selected: Vec<&Utxo> = selection.indices.iter().map(|&i| &utxos[i]).collect();
+        let input_types: Vec<ChainType> = selected.iter().map(|utxo| utxo.chain_type.clone()).collect();
+        let spent = checked_sum(selected.iter().map(|utxo| utxo.value.to_sat()))?;
+
+        // Branch and bound's excess costs less as fee than as change; knapsack's
+        // remainder becomes change unless it would be dust
+        let mut change = None;
+        if selection.algorithm == Algorithm::Knapsack {
+            let mut with_change = outputs.clone();
+            with_change.push(TxOut { value: Amount::ZERO, script_pubkey: change_script.clone() });
+            let fee = coin_selection::fee(fee_rate, coin_selection::transaction_weight(&input_types, &with_change));
+            let value = spent.saturating_sub(paid.checked_add(fee).ok_or_else(amount_overflow)?);
+            if value >= change_dust {
+                let output = rng.gen_range(0..=outputs.len());
+                outputs.insert(output, TxOut { value: Amount::from_sat(value), script_pubkey: change_script });
+                change = Some(ChangeOutput {
+                    output,
+                    address: self.address(&change_key)?,
+                    path: change_key.path.clone(),
+                    value: Amount::from_sat(value),
+                });
+            }
+        }
+
+        let weight = coin_selection::transaction_weight(&input_types, &outputs);
+        let total_out = checked_sum(outputs.iter().map(|output| output.value.to_sat()))?;
+        let fee = spent.checked_sub(total_out).ok_or_else(insufficient)?;
+        if fee < coin_selection::fee(fee_rate, weight) {
+            return Err(insufficient());
+        }
+
+        let mut psbt = self.unsigned(selected.into_iter(), outputs, options.finality)?;
+        if let Some(change) = &change {
+            self.update_output(&mut psbt.outputs[change.output], &change_key)?;
+        }
+
+        Ok(BuiltPsbt {
+            psbt,
+            algorithm: selection.algorithm,
+            fee: Amount::from_sat(fee),
+            vsize: coin_selection::vsize(weight),
+            change,
+        })
+    }
+
+    fn outputs(&self, payouts: &[Payout]) -> ApiResult<Vec<TxOut>> {
+        payouts
+            .iter()
+            .map(|payout| {
+                Ok(TxOut {
+                    value: payout.value,
+                    script_pubkey: self.address_script(&payout.address)?,
+                })
+            })
+            .collect()
+    }
+
+    fn unsigned<'u>(
+        &self,
+        utxos: impl Iterator<Item = &'u Utxo> + Clone,
+        outputs: Vec<TxOut>,
+        finality: Finality,
+    ) -> ApiResult<Psbt> {
+        let inputs = utxos
+            .clone()
+            .map(|utxo| TxIn {
+                previous_output: utxo.outpoint,
+                sequence: finality.sequence(),
+                ..Default::default()
+            })
+            .collect();
+
+        // Dogecoin Core still creates version 1 transactions
+        let version = if self.is_dogecoin() { transaction::Version::ONE } else { transaction::Version::TWO };
+        let mut psbt = Psbt::from_unsigned_tx(Transaction {
+            version,
+            lock_time: finality.lock_time,
+            input: inputs,
+            output: outputs,
+        })
+        .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+
+        for (input, utxo) in psbt.inputs.iter_mut().zip(utxos) {
+            self.update_utxo_input(input, utxo)?;
+        }
+        Ok(psbt)
+    }
+
+    fn update_utxo_input(&self, input: &mut Input, utxo: &Utxo) -> ApiResult<()> {
+        if !self.chain_types.contains(&utxo.chain_type) {
+            return Err(ApiError::BadRequest(format!(
+                "UTXO {} is of address type {:?}, which this coin does not have",
+                utxo.outpoint, utxo.chain_type
+            )));
+        }
+
+        let key = self.key(&utxo.chain_type, self.path(&utxo.chain_type, utxo.change, utxo.index))?;
+        let spent = TxOut { value: utxo.value, script_pubkey: self.script_pubkey(&key) };
+
+        if let Some(previous) = &utxo.previous_transaction {
+            let output = previous
+                .output
+                .get(utxo.outpoint.vout as usize)
+                .filter(|_| previous.txid() == utxo.outpoint.txid)
+                .ok_or_else(|| {
+                    ApiError::BadRequest(format!("Previous transaction does not create UTXO {}", utxo.outpoint))
+                })?;
+            if *output != spent {
+                return Err(ApiError::BadRequest(format!(
+                    "UTXO {} does not pay {} to the address at {}",
+                    utxo.outpoint, utxo.value, key.path
+                )));
+            }
+            input.non_witness_utxo = Some(previous.clone());
+        }
+
+        if is_segwit(&utxo.chain_type) {
+            input.witness_utxo = Some(spent);
+        } else if input.non_witness_utxo.is_none() {
+            if !self.allow_missing_previous_transactions {
+                return Err(ApiError::BadRequest(format!(
+                    "UTXO {} is a {:?} output and needs its previous transaction",
+                    utxo.outpoint, utxo.chain_type
+                )));
+            }
+            // Signed from the amount and script alone, which nothing vouches for
+            input.witness_utxo = Some(spent);
+        }
+        self.update_input(input, &key)
+    }
+
+    /// Sign every input of the request's addresses, then finalize unless `sign_only`
+    pub fn sign(&mut self, mut psbt: Psbt, sign_only: bool) -> ApiResult<SignedPsbt> {
+        let tx = psbt.unsigned_tx.clone();
+        let spent = (0..psbt.inputs.len())
+            .map(|i| spent_output(&psbt, i))
+            .collect::<ApiResult<Vec<_>>>()?;
+        let mut cache = SighashCache::new(&tx);
+
+        let mut signed_inputs = Vec::new();
+        for (i, output) in spent.iter().enumerate() {
+            let input = &psbt.inputs[i];
+            let Some(output) = output else { continue };
+            if input.final_script_sig.is_some() || input.final_script_witness.is_some() {
+                continue;
+            }
+
+            let origins: Vec<_> = input
+                .bip32_derivation
+                .values()
+                .chain(input.tap_key_origins.values().map(|(_, source)| source))
+                .map(|(fingerprint, path)| (*fingerprint, path))
+                .collect();
+            let Some(key) = self.find(&output.script_pubkey, &origins)? else { continue };
+            let unverified = !is_segwit(&key.chain_type) && input.non_witness_utxo.is_none();
+            if unverified && !self.allow_missing_previous_transactions {
+                return Err(ApiError::BadRequest(format!(
+                    "Input {} spends a {:?} output and needs its previous transaction",
+                    i, key.chain_type
+                )));
+            }
+
+            self.update_input(&mut psbt.inputs[i], &key)?;
+            self.sign_input(&mut psbt.inputs[i], &mut cache, i, &key, &spent)?;
+            signed_inputs.push(i);
+        }
+
+        for i in 0..psbt.outputs.len() {
+            let script_pubkey = psbt.unsigned_tx.output[i].script_pubkey.clone();
+            if let Some(key) = self.find(&script_pubkey, &[])? {
+                self.update_output(&mut psbt.outputs[i], &key)?;
+            }
+        }
+
+        let fee = psbt.fee().ok();
+        let complete = !sign_only && finalize(&mut psbt, &spent);
+        let transaction = if complete { Some(self.extract(psbt.clone())?) } else { None };
+
+        Ok(SignedPsbt { psbt, signed_inputs, complete, transaction, fee })
+    }
+
+    fn sign_input(
+        &self,
+        input: &mut Input,
+        cache: &mut SighashCache<&Transaction>,
+        index: usize,
+        key: &OwnedKey,
+        spent: &[Option<TxOut>],
+    ) -> ApiResult<()> {
+        let secp = self.keys.secp();
+        let secret_key = self.keys.secp256k1_key(&key.path)?.private_key;
+        let sighash_error = |e: &dyn std::fmt::Display| ApiError::BadRequest(format!("Input {}: {}", index, e));
+        let output = spent[index].as_ref().ok_or_else(|| sighash_error(&"missing UTXO"))?;
+
+        if key.chain_type == ChainType::BitcoinTaproot {
+            let hash_ty = input.taproot_hash_ty().map_err(|e| sighash_error(&e))?;
+            if !matches!(hash_ty, TapSighashType::Default | TapSighashType::All) && !self.allow_any_sighash {
+                return Err(sighash_error(&format_args!("refusing to sign with {}", hash_ty)));
+            }
+            // The BIP341 sighash commits to every input's amount and script
+            let prevouts = spent
+                .iter()
+                .cloned()
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| sighash_error(&"signing a Taproot input needs every input's UTXO"))?;
+            let sighash = cache
+                .taproot_key_spend_signature_hash(index, &Prevouts::All(&prevouts), hash_ty)
+                .map_err(|e| sighash_error(&e))?;
+
+            let keypair = Keypair::from_secret_key(secp, &secret_key).tap_tweak(secp, None).to_inner();
+            let sig = secp.sign_schnorr_with_aux_rand(
+                &Message::from_digest(sighash.to_byte_array()),
+                &keypair,
+                &rand::random(),
+            );
+            input.tap_key_sig = Some(taproot::Signature { sig, hash_ty });
+            return Ok(());
+        }
+
+        let hash_ty = input.ecdsa_hash_ty().map_err(|e| sighash_error(&e))?;
+        if hash_ty != EcdsaSighashType::All && !self.allow_any_sighash {
+            return Err(sighash_error(&format_args!("refusing to sign with {}", hash_ty)));
+        }
+        let sighash = match key.chain_type {
+            ChainType::BitcoinNestedSegwit | ChainType::BitcoinSegwit => {
+                cache
+                    .p2wpkh_signature_hash(index, &p2wpkh_program(&key.public_key), output.value, hash_ty)
+                    .map_err(|e| sighash_error(&e))?
+                    .to_byte_array()
+            }
+            _ => cache
+                .legacy_signature_hash(index, &output.script_pubkey, hash_ty.to_u32())
+                .map_err(|e| sighash_error(&e))?
+                .to_byte_array(),
+        };
+
+        let sig = secp.sign_ecdsa_low_r(&Message::from_digest(sighash), &secret_key);
+        input.partial_sigs.insert(PublicKey::new(key.public_key), ecdsa::Signature { sig, hash_ty });
+        Ok(())
+    }
+
+    /// The finished transaction; Bitcoin's is refused at an absurd fee rate, Dogecoin's
+    /// much higher fees per byte are normal
+    fn extract(&self, psbt: Psbt) -> ApiResult<Transaction> {
+        if self.is_dogecoin() {
+            return Ok(psbt.extract_tx_unchecked_fee_rate());
+        }
+        psbt.extract_tx().map_err(|e| ApiError::BadRequest(format!("Cannot extract the transaction: {}", e)))
+    }
+}
+
+/// Whether inputs of the address type sign a sighash that commits to their amount
+fn is_segwit(chain_type: &ChainType) -> bool {
+    !matches!(chain_type, ChainType::BitcoinLegacy | ChainType::Dogecoin)
+}
+
+fn check_unique(utxos: &[Utxo]) -> ApiResult<()> {
+    for (i, utxo) in utxos.iter().enumerate() {
+        if utxos[..i].iter().any(|other| other.outpoint == utxo.outpoint) {
+            return Err(ApiError::BadRequest(format!("UTXO {} is listed twice", utxo.outpoint)));
+        }
+    }
+    Ok(())
+}
+
+/// `OP_0 <hash160(key)>`, the P2WPKH output script and P2SH-P2WPKH redeem script
+pub(crate) fn p2wpkh_program(public_key: &secp256k1::PublicKey) -> ScriptBuf {
+    let hash = bitcoin::WPubkeyHash::hash(&public_key.serialize());
+    ScriptBuf::new_p2wpkh(&hash)
+}
+
+/// The output an input spends, if the PSBT carries it
+fn spent_output(psbt: &Psbt, index: usize) -> ApiResult<Option<TxOut>> {
+    let input = &psbt.inputs[index];
+    if let Some(output) = &input.witness_utxo {
+        return Ok(Some(output.clone()));
+    }
+
+    let Some(previous) = &input.non_witness_utxo else { return Ok(None) };
+    let outpoint = psbt.unsigned_tx.input[index].previous_output;
+    if previous.txid() != outpoint.txid {
+        return Err(ApiError::BadRequest(format!(
+            "Input {} carries a previous transaction other than {}",
+            index, outpoint.txid
+        )));
+    }
+    Ok(previous.output.get(outpoint.vout as usize).cloned())
+}
+
+/// BIP174 finalizer for single-key inputs: move signatures into the scriptSig and
+/// witness and drop everything else but the UTXO. Whether every input is now final.
+pub fn finalize(psbt: &mut Psbt, spent: &[Option<TxOut>]) -> bool {
+    for (input, output) in psbt.inputs.iter_mut().zip(spent) {
+        if input.final_script_sig.is_some() || input.final_script_witness.is_some() {
+            continue;
+        }
+        let Some((script_sig, witness)) = output.as_ref().and_then(|output| final_scripts(input, &output.script_pubkey))
+        else {
+            continue;
+        };
+
+        *input = Input {
+            non_witness_utxo: input.non_witness_utxo.take(),
+            witness_utxo: input.witness_utxo.take(),
+            final_script_sig: script_sig,
+            final_script_witness: witness,
+            proprietary: std::mem::take(&mut input.proprietary),
+            unknown: std::mem::take(&mut input.unknown),
+            ..Default::default()
+        };
+    }
+
+    psbt.inputs
+        .iter()
+        .all(|input| input.final_script_sig.is_some() || input.final_script_witness.is_some())
+}
+
+/// scriptSig and witness spending `script` with the input's signature, if it has one
+fn final_scripts(input: &Input, script: &ScriptBuf) -> Option<(Option<ScriptBuf>, Option<Witness>)> {
+    let signature_for = |script: &ScriptBuf, key_script: fn(&PublicKey) -> Option<ScriptBuf>| {
+        input
+            .partial_sigs
+            .iter()
+            .find(|(key, _)| key_script(key).as_ref() == Some(script))
+            .map(|(key, sig)| (*key, *sig))
+    };
+    let p2wpkh = |key: &PublicKey| key.wpubkey_hash().map(|hash| ScriptBuf::new_p2wpkh(&hash));
+
+    if script.is_p2tr() {
+        let sig = input.tap_key_sig?;
+        Some((None, Some(Witness::from_slice(&[sig.to_vec()]))))
+    } else if script.is_p2wpkh() {
+        let (key, sig) = signature_for(script, p2wpkh)?;
+        Some((None, Some(Witness::from_slice(&[sig.to_vec(), key.to_bytes()]))))
+    } else if script.is_p2sh() {
+        let redeem_script = input.redeem_script.as_ref().filter(|redeem_script| redeem_script.is_p2wpkh())?;
+        let (key, sig) = signature_for(redeem_script, p2wpkh)?;
+        let push = PushBytesBuf::try_from(redeem_script.to_bytes()).ok()?;
+        Some((
+            Some(Builder::new().push_slice(push).into_script()),
+            Some(Witness::from_slice(&[sig.to_vec(), key.to_bytes()])),
+        ))
+    } else if script.is_p2pkh() {
+        let (key, sig) = signature_for(script, |key| Some(ScriptBuf::new_p2pkh(&key.pubkey_hash())))?;
+        let push = PushBytesBuf::try_from(sig.to_vec()).ok()?;
+        Some((Some(Builder::new().push_slice(push).push_key(&key).into_script()), None))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bip39::Mnemonic;
+    use bitcoin::XOnlyPublicKey;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const PAYEE: &str = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+
+    fn key_tree() -> KeyTree {
+        KeyTree::new(&Mnemonic::parse(MNEMONIC).unwrap().to_seed(""))
+    }
+
+    fn utxo(chain_type: ChainType, vout: u32, value: u64) -> Utxo {
+        Utxo {
+            outpoint: OutPoint { txid: Txid::from_byte_array([7; 32]), vout },
+            value: Amount::from_sat(value),
+            chain_type,
+            change: 0,
+            index: 0,
+            previous_transaction: None,
+        }
+    }
+
+    /// `utxo` with a previous transaction that creates it, as legacy inputs need
+    fn with_previous_transaction(keychain: &Keychain, mut utxo: Utxo) -> Utxo {
+        let key = keychain.key(&utxo.chain_type, keychain.path(&utxo.chain_type, utxo.change, utxo.index)).unwrap();
+        let unrelated = TxOut { value: Amount::ZERO, script_pubkey: ScriptBuf::new() };
+        let mut output = vec![unrelated; utxo.outpoint.vout as usize];
+        output.push(TxOut { value: utxo.value, script_pubkey: keychain.script_pubkey(&key) });
+        let previous = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output,
+        };
+        utxo.outpoint.txid = previous.txid();
+        utxo.previous_transaction = Some(previous);
+        utxo
+    }
+
+    fn payout(address: &str, value: u64) -> Payout {
+        Payout { address: address.to_string(), value: Amount::from_sat(value) }
+    }
+
+    /// Check a final input's signature against its recomputed sighash
+    fn verify_input(tx: &Transaction, prevouts: &[TxOut], i: usize) {
+        let secp = secp256k1::Secp256k1::verification_only();
+        let mut cache = SighashCache::new(tx);
+        let script = &prevouts[i].script_pubkey;
+        let input = &tx.input[i];
+
+        if script.is_p2tr() {
+            let sig = taproot::Signature::from_slice(&input.witness[0]).unwrap();
+            let sighash = cache.taproot_key_spend_signature_hash(i, &Prevouts::All(prevouts), sig.hash_ty).unwrap();
+            let output_key = XOnlyPublicKey::from_slice(&script.as_bytes()[2..]).unwrap();
+            secp.verify_schnorr(&sig.sig, &Message::from_digest(sighash.to_byte_array()), &output_key).unwrap();
+            return;
+        }
+
+        let (sig, key, sighash) = if script.is_p2pkh() {
+            let pushes: Vec<Vec<u8>> = input
+                .script_sig
+                .instructions()
+                .map(|instruction| instruction.unwrap().push_bytes().unwrap().as_bytes().to_vec())
+                .collect();
+            let key = PublicKey::from_slice(&pushes[1]).unwrap();
+            assert_eq!(ScriptBuf::new_p2pkh(&key.pubkey_hash()), *script);
+            let sig = ecdsa::Signature::from_slice(&pushes[0]).unwrap();
+            let sighash = cache.legacy_signature_hash(i, script, sig.hash_ty.to_u32()).unwrap();
+            (sig, key, sighash.to_byte_array())
+        } else {
+            let sig = ecdsa::Signature::from_slice(&input.witness[0]).unwrap();
+            let key = PublicKey::from_slice(&input.witness[1]).unwrap();
+            let program = p2wpkh_program(&key.inner);
+            if script.is_p2sh() {
+                assert_eq!(ScriptBuf::new_p2sh(&program.script_hash()), *script);
+                assert_eq!(input.script_sig.as_bytes()[1..], *program.as_bytes());
+            } else {
+                assert_eq!(program, *script);
+            }
+            let sighash = cache.p2wpkh_signature_hash(i, &program, prevouts[i].value, sig.hash_ty).unwrap();
+            (sig, key, sighash.to_byte_array())
+        };
+        secp.verify_ecdsa(&Message::from_digest(sighash), &sig.sig, &key.inner).unwrap();
+    }
+
+    #[test]
+    fn test_sign_every_bitcoin_address_type() {
+        let keys = key_tree();
+        let chain_types = chain_types("BTC").unwrap();
+        let mut keychain = Keychain::new(&keys, chain_types.clone(), Network::Bitcoin, 0).unwrap();
+
+        let utxos: Vec<_> = chain_types
+            .iter()
+            .enumerate()
+            .map(|(vout, chain_type)| utxo(chain_type.clone(), vout as u32, 50_000))
+            .map(|utxo| with_previous_transaction(&keychain, utxo))
+            .collect();
+        let psbt = keychain.create(&utxos, &[payout(PAYEE, 190_000)]).unwrap();
+
+        // The inputs spend the first address of each BIP44/49/84/86 account
+        let spent: Vec<_> = (0..psbt.inputs.len()).map(|i| spent_output(&psbt, i).unwrap().unwrap()).collect();
+        let addresses: Vec<_> = spent
+            .iter()
+            .map(|output| Address::from_script(&output.script_pubkey, Network::Bitcoin).unwrap().to_string())
+            .collect();
+        for address in [
+            "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA",
+            "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf",
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+        ] {
+            assert!(addresses.iter().any(|a| a == address), "{} not spent", address);
+        }
+
+        let signed = keychain.sign(decode(&encode(&psbt)).unwrap(), false).unwrap();
+        assert_eq!(signed.signed_inputs, vec![0, 1, 2, 3]);
+        assert!(signed.complete);
+        assert_eq!(signed.fee, Some(Amount::from_sat(10_000)));
+
+        let tx = signed.transaction.unwrap();
+        assert_eq!(tx.version, transaction::Version::TWO);
+        for i in 0..tx.input.len() {
+            verify_input(&tx, &spent, i);
+        }
+    }
+
+    #[test]
+    fn test_sign_dogecoin_with_previous_transaction() {
+        let keys = key_tree();
+        let mut keychain = Keychain::new(&keys, vec![ChainType::Dogecoin], Network::Bitcoin, 0).unwrap();
+
+        let previous = Transaction {
+            version: transaction::Version::ONE,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value: Amount::from_sat(500_000_000),
+                script_pubkey: keychain.address_script("DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC").unwrap(),
+            }],
+        };
+        let mut utxo = utxo(ChainType::Dogecoin, 0, 500_000_000);
+        utxo.outpoint.txid = previous.txid();
+
+        // The previous transaction has to create the UTXO as given
+        utxo.previous_transaction = Some(previous.clone());
+        utxo.value = Amount::from_sat(400_000_000);
+        assert!(keychain.create(&[utxo.clone()], &[payout("DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC", 1)]).is_err());
+        utxo.value = Amount::from_sat(500_000_000);
+
+        let psbt = keychain
+            .create(&[utxo], &[payout("A8c3xNz2mqsDLFwv5KL5fpH12QEwDaoTXo", 400_000_000)])
+            .unwrap();
+        assert!(psbt.inputs[0].witness_utxo.is_none());
+
+        let signed = keychain.sign(psbt, false).unwrap();
+        assert!(signed.complete);
+        let tx = signed.transaction.unwrap();
+        assert_eq!(tx.version, transaction::Version::ONE);
+        verify_input(&tx, &previous.output, 0);
+    }
+
+    #[test]
+    fn test_sign_caller_psbt() {
+        let keys = key_tree();
+        let mut keychain = Keychain::new(&keys, vec![ChainType::BitcoinSegwit], Network::Bitcoin, 0).unwrap();
+        let path = |change, index| DerivationPath::new(84, 0, 0, change, index);
+        let script = |change, index| {
+            let key = keychain.key(&ChainType::BitcoinSegwit, path(change, index)).unwrap();
+            keychain.script_pubkey(&key)
+        };
+        let foreign = keychain.address_script(PAYEE).unwrap();
+
+        // Input 0 is found by scanning, input 1 by its origin past the scanned
+        // addresses, and input 2 belongs to someone else
+        let tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: (0..3)
+                .map(|vout| TxIn {
+                    previous_output: OutPoint { txid: Txid::from_byte_array([9; 32]), vout },
+                    ..Default::default()
+                })
+                .collect(),
+            output: vec![
+                TxOut { value: Amount::from_sat(60_000), script_pubkey: foreign.clone() },
+                TxOut { value: Amount::from_sat(30_000), script_pubkey: script(1, 3) },
+            ],
+        };
+        let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+        for (input, script_pubkey) in psbt.inputs.iter_mut().zip([script(0, 5), script(0, 150), foreign]) {
+            input.witness_utxo = Some(TxOut { value: Amount::from_sat(35_000), script_pubkey });
+        }
+        let far = keychain.key(&ChainType::BitcoinSegwit, path(0, 150)).unwrap();
+        psbt.inputs[1]
+            .bip32_derivation
+            .insert(far.public_key, (keychain.fingerprint, path(0, 150).to_bip32().unwrap()));
+
+        let signed = keychain.sign(psbt, true).unwrap();
+        assert_eq!(signed.signed_inputs, vec![0, 1]);
+        assert!(!signed.complete && signed.transaction.is_none());
+        assert_eq!(signed.fee, Some(Amount::from_sat(15_000)));
+
+        let inputs = &signed.psbt.inputs;
+        assert!(inputs[..2].iter().all(|input| input.partial_sigs.len() == 1 && input.final_script_witness.is_none()));
+        assert_eq!(inputs[0].bip32_derivation.values().next().unwrap().1.to_string(), "m/84'/0'/0'/0/5");
+        assert!(inputs[2].partial_sigs.is_empty() && inputs[2].bip32_derivation.is_empty());
+
+        // The change output is marked as ours, the payment is not
+        assert!(signed.psbt.outputs[0].bip32_derivation.is_empty());
+        assert_eq!(signed.psbt.outputs[1].bip32_derivation.values().next().unwrap().1.to_string(), "m/84'/0'/0'/1/3");
+
+        // Finalizing leaves the foreign input waiting for its own signer
+        let mut psbt = signed.psbt;
+        let spent: Vec<_> = psbt.inputs.iter().map(|input| input.witness_utxo.clone()).collect();
+        assert!(!finalize(&mut psbt, &spent));
+        assert!(psbt.inputs[0].final_script_witness.is_some() && psbt.inputs[0].partial_sigs.is_empty());
+    }
+
+    #[test]
+    fn test_legacy_inputs_and_sighash_types_checked() {
+        let keys = key_tree();
+        let legacy = utxo(ChainType::BitcoinLegacy, 0, 50_000);
+        let keychain = || Keychain::new(&keys, chain_types("BTC").unwrap(), Network::Bitcoin, 0).unwrap();
+
+        // Without its previous transaction a legacy UTXO's amount is only taken on trust
+        assert!(keychain().create(std::slice::from_ref(&legacy), &[payout(PAYEE, 40_000)]).is_err());
+        let psbt = keychain()
+            .allow_missing_previous_transactions(true)
+            .create(std::slice::from_ref(&legacy), &[payout(PAYEE, 40_000)])
+            .unwrap();
+        assert!(psbt.inputs[0].witness_utxo.is_some());
+        assert!(keychain().sign(psbt.clone(), false).is_err());
+        assert!(keychain().allow_missing_previous_transactions(true).sign(psbt, false).unwrap().complete);
+
+        let legacy = with_previous_transaction(&keychain(), legacy);
+        let segwit = utxo(ChainType::BitcoinSegwit, 1, 50_000);
+        let taproot = utxo(ChainType::BitcoinTaproot, 2, 50_000);
+        let psbt = keychain().create(&[legacy, segwit, taproot], &[payout(PAYEE, 140_000)]).unwrap();
+        assert!(psbt.inputs[0].witness_utxo.is_none());
+        assert!(keychain().sign(psbt.clone(), false).unwrap().complete);
+
+        // Anything but ALL, or DEFAULT for Taproot, leaves the transaction open to change
+        let hash_types = [
+            (0, EcdsaSighashType::Single.into()),
+            (1, EcdsaSighashType::AllPlusAnyoneCanPay.into()),
+            (2, TapSighashType::None.into()),
+        ];
+        for (i, hash_ty) in hash_types {
+            let mut psbt = psbt.clone();
+            psbt.inputs[i].sighash_type = Some(hash_ty);
+            assert!(keychain().sign(psbt.clone(), true).is_err(), "{}", hash_ty);
+            assert_eq!(keychain().allow_any_sighash(true).sign(psbt, true).unwrap().signed_inputs, [0, 1, 2]);
+        }
+        let mut psbt = psbt;
+        psbt.inputs[0].sighash_type = Some(EcdsaSighashType::All.into());
+        psbt.inputs[2].sighash_type = Some(TapSighashType::All.into());
+        assert!(keychain().sign(psbt, false).unwrap().complete);
+    }
+
+    fn build_options(sat_per_vb: f64) -> BuildOptions {
+        BuildOptions {
+            fee_rate: fee_rate(sat_per_vb).unwrap(),
+            change_type: None,
+            change_index: 0,
+            finality: Finality { rbf: true, lock_time: absolute::LockTime::ZERO },
+        }
+    }
+
+    #[test]
+    fn test_build_with_change() {
+        let keys = key_tree();
+        let mut keychain = Keychain::new(&keys, chain_types("BTC").unwrap(), Network::Bitcoin, 0).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let utxos = [
+            utxo(ChainType::BitcoinSegwit, 0, 30_000),
+            utxo(ChainType::BitcoinTaproot, 1, 50_000),
+            with_previous_transaction(&keychain, utxo(ChainType::BitcoinLegacy, 2, 200_000)),
+        ];
+
+        let built = keychain.build(&utxos, &[payout(PAYEE, 100_000)], &build_options(5.0), &mut rng).unwrap();
+        assert_eq!(built.algorithm, Algorithm::Knapsack);
+        let change = built.change.clone().unwrap();
+        assert_eq!(change.address, "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el");
+        assert_eq!(change.path.to_string(), "m/84'/0'/0'/1/0");
+        assert_eq!(
+            built.psbt.outputs[change.output].bip32_derivation.values().next().unwrap().1.to_string(),
+            "m/84'/0'/0'/1/0"
+        );
+
+        let tx = built.psbt.unsigned_tx.clone();
+        assert!(tx.input.iter().all(|input| input.sequence == Sequence::ENABLE_RBF_NO_LOCKTIME));
+        let spent: u64 = tx
+            .input
+            .iter()
+            .map(|input| utxos[input.previous_output.vout as usize].value.to_sat())
+            .sum();
+        let paid: u64 = tx.output.iter().map(|output| output.value.to_sat()).sum();
+        assert_eq!(spent - paid, built.fee.to_sat());
+        assert!(built.fee.to_sat() >= 5 * built.vsize);
+
+        // The estimate never falls short of the signed size, and overshoots by at most
+        // a byte per input
+        let signed = keychain.sign(built.psbt, false).unwrap();
+        let vsize = signed.transaction.unwrap().vsize() as u64;
+        assert!(built.vsize >= vsize && built.vsize <= vsize + tx.input.len() as u64, "{} {}", built.vsize, vsize);
+    }
+
+    #[test]
+    fn test_build_changeless() {
+        let keys = key_tree();
+        let keychain = Keychain::new(&keys, vec![ChainType::BitcoinSegwit], Network::Bitcoin, 0).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        // At 1 sat/vB the transaction costs 42 vbytes and each input 68, so 50,110
+        // covers the payment exactly
+        let utxos = [
+            utxo(ChainType::BitcoinSegwit, 0, 1_000_000),
+            utxo(ChainType::BitcoinSegwit, 1, 50_110),
+            utxo(ChainType::BitcoinSegwit, 2, 20_000),
+        ];
+        let mut options = build_options(1.0);
+        options.finality = Finality { rbf: false, lock_time: absolute::LockTime::from_consensus(850_000) };
+
+        let built = keychain.build(&utxos, &[payout(PAYEE, 50_000)], &options, &mut rng).unwrap();
+        assert_eq!(built.algorithm, Algorithm::BranchAndBound);
+        assert!(built.change.is_none());
+        assert_eq!((built.fee.to_sat(), built.vsize), (110, 110));
+
+        let tx = &built.psbt.unsigned_tx;
+        assert_eq!(tx.input.len(), 1);
+        assert_eq!(tx.input[0].previous_output.vout, 1);
+        assert_eq!(tx.input[0].sequence, Sequence::ENABLE_LOCKTIME_NO_RBF);
+        assert_eq!(tx.lock_time.to_consensus_u32(), 850_000);
+    }
+
+    #[test]
+    fn test_build_limits() {
+        let keys = key_tree();
+        let keychain = Keychain::new(&keys, vec![ChainType::BitcoinSegwit], Network::Bitcoin, 0).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let utxos = [utxo(ChainType::BitcoinSegwit, 0, 10_000)];
+
+        // Below the 294 sat P2WPKH dust threshold
+        assert!(keychain.build(&utxos, &[payout(PAYEE, 293)], &build_options(1.0), &mut rng).is_err());
+        assert!(keychain.build(&utxos, &[payout(PAYEE, 294)], &build_options(1.0), &mut rng).is_ok());
+        // 10,000 less 110 vbytes of fees
+        assert!(keychain.build(&utxos, &[payout(PAYEE, 9_890)], &build_options(1.0), &mut rng).is_ok());
+        assert!(keychain.build(&utxos, &[payout(PAYEE, 9_891)], &build_options(1.0), &mut rng).is_err());
+
+        assert!(fee_rate(0.0).is_err() && fee_rate(f64::NAN).is_err());
+        assert!(keychain.build(&utxos, &[payout(PAYEE, 5_000)], &build_options(0.5), &mut rng).is_err());
+        assert!(keychain.build(&utxos, &[payout(PAYEE, 5_000)], &build_options(30_000.0), &mut rng).is_err());
+
+        // The change address type has to be one of the request's
+        let mut options = build_options(1.0);
+        options.change_type = Some(ChainType::BitcoinTaproot);
+        assert!(keychain.build(&utxos, &[payout(PAYEE, 5_000)], &options, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_oversized_amounts_and_fee_rates_rejected() {
+        let bitcoin = chain_types("BTC").unwrap();
+        let dogecoin = chain_types("DOGE").unwrap();
+        assert!(parse_amount(&bitcoin, Amount::MAX_MONEY.to_sat()).is_ok());
+        assert!(parse_amount(&bitcoin, Amount::MAX_MONEY.to_sat() + 1).is_err());
+        assert!(parse_amount(&dogecoin, Amount::MAX_MONEY.to_sat() + 1).is_ok());
+        assert!(parse_amount(&dogecoin, coin_selection::DOGECOIN_MAX_MONEY.to_sat() + 1).is_err());
+
+        let keys = key_tree();
+        let keychain = Keychain::new(&keys, dogecoin, Network::Bitcoin, 0).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let payee = "DBus3bamQjgJULBJtYXpEzDWQRwF5iwxgC";
+
+        // A fee rate that saturates to u64::MAX sat/kwu has no fee, not a wrapped one
+        let utxos = [with_previous_transaction(&keychain, utxo(ChainType::Dogecoin, 0, 500_000_000))];
+        assert!(keychain.build(&utxos, &[payout(payee, 100_000_000)], &build_options(1e30), &mut rng).is_err());
+        assert!(keychain.build(&utxos, &[payout(payee, 100_000_000)], &build_options(100_000.0), &mut rng).is_ok());
+
+        // Each within the money range, together more than a u64 holds
+        let max = coin_selection::DOGECOIN_MAX_MONEY.to_sat();
+        let utxos: Vec<_> = (0..19).map(|vout| utxo(ChainType::Dogecoin, vout, max)).collect();
+        assert!(keychain.create(&utxos, &[payout(payee, max)]).is_err());
+        assert!(keychain.build(&utxos, &[payout(payee, max)], &build_options(1.0), &mut rng).is_err());
+        let payouts: Vec<_> = (0..19).map(|_| payout(payee, max)).collect();
+        assert!(keychain.create(&utxos[..1], &payouts).is_err());
+        assert!(keychain.build(&utxos[..1], &payouts, &build_options(1.0), &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_address_type_and_inputs_validated() {
+        let bitcoin = chain_types("BTC").unwrap();
+        assert_eq!(address_type(&bitcoin, "bitcointaproot").unwrap(), ChainType::BitcoinTaproot);
+        assert!(address_type(&bitcoin, "").is_err());
+        assert_eq!(address_type(&[ChainType::Dogecoin], "").unwrap(), ChainType::Dogecoin);
+        assert!(chain_types("ETH").is_err());
+        assert!(decode("cHNidP8=").is_err());
+        // A BIP370 global map: version 2, transaction version 2, no inputs or outputs
+        let v2 = b"psbt\xff\x01\xfb\x04\x02\x00\x00\x00\x01\x02\x04\x02\x00\x00\x00\x01\x04\x01\x00\x01\x05\x01\x00\x00";
+        let error = decode(&base64::engine::general_purpose::STANDARD.encode(v2)).unwrap_err();
+        assert!(error.to_string().contains("PSBT version 2 is not supported"), "{}", error);
+
+        let keys = key_tree();
+        assert!(Keychain::new(&keys, vec![ChainType::Dogecoin], Network::Signet, 0).is_err());
+        let keychain = Keychain::new(&keys, vec![ChainType::BitcoinSegwit], Network::Testnet, 0).unwrap();
+        let segwit = utxo(ChainType::BitcoinSegwit, 0, 10_000);
+        // Mainnet address on testnet, more paid than spent, the same UTXO twice
+        assert!(keychain.create(std::slice::from_ref(&segwit), &[payout(PAYEE, 5_000)]).is_err());
+        let testnet_payee = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+        assert!(keychain.create(std::slice::from_ref(&segwit), &[payout(testnet_payee, 20_000)]).is_err());
+        assert!(keychain.create(&[segwit.clone(), segwit.clone()], &[payout(testnet_payee, 5_000)]).is_err());
+        assert!(keychain.create(&[segwit], &[payout(testnet_payee, 5_000)]).is_ok());
+    }
+}
@@ -1,65 +1,120 @@
 use async_trait::async_trait;
-use bitcoin::bip32::{Xpriv, DerivationPath as BtcDerivationPath, ChildNumber};
-use bitcoin::secp256k1::Secp256k1;
 use bitcoin::Network;
+use bitcoin::secp256k1;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use zeroize::Zeroizing;
 use sha2::{Sha256, Digest};
 use ripemd::Ripemd160;
 
-use crate::core::{Chain, ChainInfo, ChainType, WalletAddress, DerivationPath, get_chain_info};
+use crate::chains::bitcoin::{derive_account_xpub, secp256k1_public_key, XpubFormat};
+use crate::core::{Chain, KeyTree, ChainInfo, ChainType, WalletAddress, SecretString, KeyFormat, DerivationPath, ExtendedPublicKey, ImportedKey, get_chain_info, decode_hex_key, import_secp256k1_key};
 use crate::errors::{ApiError, ApiResult};
 
 pub struct Ripple;
 
+impl Default for Ripple {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Ripple {
     pub fn new() -> Self {
         Self
     }
 }
 
+/// The private key as ripple-keypairs and xrpl.js hold it: `00` followed by the key
+/// in uppercase hex. XRPL family seeds (`s...`) cannot be produced here: they encode
+/// the entropy the XRPL derivation starts from, not a BIP44-derived key.
+fn ripple_private_key(private_key: &secp256k1::SecretKey) -> SecretString {
+    let mut encoded = String::with_capacity(66);
+    encoded.push_str("00");
+    for byte in Zeroizing::new(private_key.secret_bytes()).iter() {
+        // Writing to a String with enough capacity cannot fail or reallocate
+        let _ = write!(encoded, "{:02X}", byte);
+    }
+    SecretString::new(encoded)
+}
+
 #[async_trait]
 impl Chain for Ripple {
     fn info(&self) -> ChainInfo {
         get_chain_info(&ChainType::Ripple)
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
-        let secp = Secp256k1::new();
-        let master = Xpriv::new_master(Network::Bitcoin, seed)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-
-        let path = self.derivation_path(index);
-        let btc_path = BtcDerivationPath::from(vec![
-            ChildNumber::from_hardened_idx(path.purpose).unwrap(),
-            ChildNumber::from_hardened_idx(path.coin_type).unwrap(),
-            ChildNumber::from_hardened_idx(path.account).unwrap(),
-            ChildNumber::from_normal_idx(path.change).unwrap(),
-            ChildNumber::from_normal_idx(path.index).unwrap(),
-        ]);
-        
-        let child = master.derive_priv(&secp, &btc_path)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
+        let child = keys.secp256k1_key(&path)?;
         
         let private_key = child.private_key;
-        let secp_pubkey = private_key.public_key(&secp);
+        let secp_pubkey = private_key.public_key(keys.secp());
+        
+        let address = self.address_from_public_key(&secp_pubkey.serialize())?;
+        
+        Ok(WalletAddress {
+            address,
+            chain_type: ChainType::Ripple,
+            chain_info: self.info(),
+            derivation_path: path.to_string(),
+            account,
+            index,
+            public_key: hex::encode(secp_pubkey.serialize()),
+            public_key_formats: self.public_key_formats(&secp_pubkey.serialize())?,
+            private_key: key_format.map(|format| match format {
+                KeyFormat::Hex => SecretString::hex_encode(private_key.secret_bytes()),
+                KeyFormat::Native => ripple_private_key(&private_key),
+            }),
+        })
+    }
+
+    fn derivation_path(&self, account: u32, index: u32) -> DerivationPath {
+        DerivationPath::new(44, 144, account, 0, index)
+    }
+
+    fn account_xpub(&self, keys: &KeyTree, account: u32) -> ApiResult<ExtendedPublicKey> {
+        let account_path = self.derivation_path(account, 0).truncated(3);
+        derive_account_xpub(ChainType::Ripple, Network::Bitcoin, keys, &account_path, account, XpubFormat::Xpub)
+    }
+
+    fn address_from_private_key(&self, private_key: &SecretString) -> ApiResult<ImportedKey> {
+        let encoded = private_key.expose_secret().trim();
+        let (key, key_format) = match encoded.strip_prefix("00") {
+            Some(key) if encoded.len() == 66 => (decode_hex_key(key), KeyFormat::Native),
+            _ => (decode_hex_key(encoded), KeyFormat::Hex),
+        };
+        
+        let secret_key = key
+            .and_then(|key| secp256k1::SecretKey::from_slice(&key[..]).ok())
+            .ok_or_else(|| ApiError::InvalidPrivateKey(self.info().name))?;
+        import_secp256k1_key(self, &secret_key, true, key_format)
+    }
+
+    fn address_from_public_key(&self, public_key: &[u8]) -> ApiResult<String> {
+        let secp_pubkey = secp256k1_public_key(public_key)?;
         
         // XRP uses compressed public key
         let pubkey_bytes = secp_pubkey.serialize();
         
         // Hash public key with SHA256 then RIPEMD160
-        let sha256_hash = Sha256::digest(&pubkey_bytes);
-        let ripemd_hash = Ripemd160::digest(&sha256_hash);
+        let sha256_hash = Sha256::digest(pubkey_bytes);
+        let ripemd_hash = Ripemd160::digest(sha256_hash);
         
         // Add XRP address prefix (0x00)
         let mut payload = vec![0x00];
         payload.extend_from_slice(&ripemd_hash);
         
         // Double SHA256 for checksum
-        let checksum = Sha256::digest(&Sha256::digest(&payload));
+        let checksum = Sha256::digest(Sha256::digest(&payload));
         payload.extend_from_slice(&checksum[..4]);
         
         // Encode with XRP's base58 alphabet
@@ -67,19 +122,23 @@ impl Chain for Ripple {
             .with_alphabet(bs58::Alphabet::RIPPLE)
             .into_string();
         
-        Ok(WalletAddress {
-            address,
-            chain_type: ChainType::Ripple,
-            chain_info: self.info(),
-            derivation_path: path.to_string(),
-            index,
-            public_key: hex::encode(secp_pubkey.serialize()),
-            private_key: hex::encode(private_key.secret_bytes()),
-        })
+        Ok(address)
     }
 
-    fn derivation_path(&self, index: u32) -> DerivationPath {
-        DerivationPath::new(44, 144, 0, 0, index)
+    fn public_key_formats(&self, public_key: &[u8]) -> ApiResult<BTreeMap<String, String>> {
+        let pubkey_bytes = secp256k1_public_key(public_key)?.serialize();
+        
+        // Account public key type prefix 0x23, which renders as `aB...`
+        let mut payload = vec![0x23];
+        payload.extend_from_slice(&pubkey_bytes);
+        let checksum = Sha256::digest(Sha256::digest(&payload));
+        payload.extend_from_slice(&checksum[..4]);
+        
+        let encoded = bs58::encode(payload)
+            .with_alphabet(bs58::Alphabet::RIPPLE)
+            .into_string();
+        
+        Ok(BTreeMap::from([("xrpl".to_string(), encoded)]))
     }
 
     async fn validate_address(&self, address: &str) -> bool {
@@ -98,7 +157,7 @@ impl Chain for Ripple {
                 let payload = &data[..data.len() - 4];
                 let checksum = &data[data.len() - 4..];
                 
-                let computed_checksum = Sha256::digest(&Sha256::digest(payload));
+                let computed_checksum = Sha256::digest(Sha256::digest(payload));
                 checksum == &computed_checksum[..4]
             }
             Err(_) => false,
@@ -2,19 +2,29 @@
 // Trust Wallet uses m/44'/501'/0' for first address (only 3 levels!)
 
 use async_trait::async_trait;
-use ed25519_dalek::SigningKey;
-use sha2::{Sha512, Digest};
-use hmac::{Hmac, Mac};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use zeroize::Zeroizing;
 
-use crate::core::{Chain, ChainInfo, ChainType, WalletAddress, DerivationPath, get_chain_info};
+use crate::core::{Chain, KeyTree, ChainInfo, ChainType, WalletAddress, SecretString, KeyFormat, DerivationPath, AddressLevel, ImportedKey, get_chain_info, decode_hex_key, ed25519_keypair, import_ed25519_key};
 use crate::errors::{ApiError, ApiResult};
 
 pub struct Solana;
 
+impl Default for Solana {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Solana {
     pub fn new() -> Self {
         Self
     }
+
+    /// Solana addresses are the base58 public key
+    fn address(&self, public_key: &VerifyingKey) -> String {
+        bs58::encode(public_key.as_bytes()).into_string()
+    }
 }
 
 #[async_trait]
@@ -23,35 +33,67 @@ impl Chain for Solana {
         get_chain_info(&ChainType::Solana)
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
         // Phantom wallet uses m/44'/501'/0'/0' for Solana (4 levels)
-        let path = self.derivation_path(index);
-        let derived_key = self.derive_ed25519_key(seed, &path, index)?;
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
+        let derived_key = Zeroizing::new(keys.ed25519_node(&path)?.private_key);
         
         let signing_key = SigningKey::from_bytes(&derived_key);
         let verifying_key = signing_key.verifying_key();
         
-        let address = bs58::encode(verifying_key.as_bytes()).into_string();
+        let address = self.address(&verifying_key);
         
         Ok(WalletAddress {
             address,
             chain_type: ChainType::Solana,
             chain_info: self.info(),
-            derivation_path: format!("m/44'/501'/{}'/0'", index),
+            derivation_path: path.to_string(),
+            account,
             index,
             public_key: hex::encode(verifying_key.as_bytes()),
-            private_key: hex::encode(signing_key.to_bytes()),
+            public_key_formats: self.public_key_formats(verifying_key.as_bytes())?,
+            private_key: key_format.map(|format| match format {
+                KeyFormat::Hex => SecretString::hex_encode(signing_key.to_bytes()),
+                // Phantom and the Solana CLI import the 64-byte keypair: secret key then public key
+                KeyFormat::Native => {
+                    SecretString::new(bs58::encode(Zeroizing::new(signing_key.to_keypair_bytes())).into_string())
+                }
+            }),
         })
     }
 
-    fn derivation_path(&self, index: u32) -> DerivationPath {
+    fn derivation_path(&self, account: u32, _index: u32) -> DerivationPath {
         // Phantom wallet uses m/44'/501'/account'/0' for Solana
-        DerivationPath::new(44, 501, index, 0, 0)
+        DerivationPath::hardened(&[44, 501, account, 0])
+    }
+
+    fn address_level(&self) -> AddressLevel {
+        // Each Phantom address is its own account
+        AddressLevel::Account
+    }
+
+    fn address_from_private_key(&self, private_key: &SecretString) -> ApiResult<ImportedKey> {
+        let invalid = || ApiError::InvalidPrivateKey(self.info().name);
+        let encoded = private_key.expose_secret().trim();
+        
+        let (signing_key, key_format) = match decode_hex_key(encoded) {
+            Some(key) => (SigningKey::from_bytes(&key), KeyFormat::Hex),
+            None => {
+                let keypair = Zeroizing::new(bs58::decode(encoded).into_vec().map_err(|_| invalid())?);
+                (ed25519_keypair(&keypair).ok_or_else(invalid)?, KeyFormat::Native)
+            }
+        };
+        
+        let address = self.address(&signing_key.verifying_key());
+        import_ed25519_key(self, &signing_key, address, key_format)
     }
 
     async fn validate_address(&self, address: &str) -> bool {
@@ -65,44 +107,3 @@ impl Chain for Solana {
         "ErD4UTnfDXEMkruTTFPKR3sbikboT3RhNMAKwcocH8gW"
     }
 }
-
-impl Solana {
-    fn derive_ed25519_key(&self, seed: &[u8], _path: &DerivationPath, index: u32) -> ApiResult<[u8; 32]> {
-        type HmacSha512 = Hmac<Sha512>;
-        
-        // Master key
-        let mut mac = HmacSha512::new_from_slice(b"ed25519 seed")
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-        mac.update(seed);
-        let master = mac.finalize().into_bytes();
-        
-        let mut key = master[..32].to_vec();
-        let mut chain_code = master[32..].to_vec();
-        
-        // Phantom Wallet Solana: m/44'/501'/0'/0' (4 levels, all hardened)
-        let indices = vec![
-            0x80000000u32 + 44,    // 44'
-            0x80000000u32 + 501,   // 501'
-            0x80000000u32 + index, // account' (typically 0)
-            0x80000000u32 + 0,     // 0' (change)
-        ];
-        
-        for idx in indices {
-            let mut mac = HmacSha512::new_from_slice(&chain_code)
-                .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-            
-            // Hardened derivation
-            mac.update(&[0x00]);
-            mac.update(&key);
-            mac.update(&idx.to_be_bytes());
-            
-            let result = mac.finalize().into_bytes();
-            key = result[..32].to_vec();
-            chain_code = result[32..].to_vec();
-        }
-        
-        let mut private_key = [0u8; 32];
-        private_key.copy_from_slice(&key);
-        Ok(private_key)
-    }
-}
\ No newline at end of file
@@ -1,13 +1,26 @@
 use async_trait::async_trait;
 use ed25519_dalek::SigningKey;
-use sha2::{Sha512, Digest};
+use zeroize::Zeroizing;
 use crc::{Crc, CRC_16_XMODEM};
 
-use crate::core::{Chain, ChainInfo, ChainType, WalletAddress, DerivationPath, get_chain_info};
-use crate::errors::ApiResult;
+use crate::core::{Chain, KeyTree, ChainInfo, ChainType, WalletAddress, SecretString, KeyFormat, DerivationPath, AddressLevel, ImportedKey, get_chain_info, decode_hex_key, import_ed25519_key};
+use crate::errors::{ApiError, ApiResult};
+
+/// StrKey version byte for ed25519 public keys (`G...`)
+pub const VERSION_ACCOUNT_ID: u8 = 6 << 3;
+/// StrKey version byte for ed25519 secret seeds (`S...`)
+pub const VERSION_SEED: u8 = 18 << 3;
+
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
 
 pub struct Stellar;
 
+impl Default for Stellar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Stellar {
     pub fn new() -> Self {
         Self
@@ -20,120 +33,229 @@ impl Chain for Stellar {
         get_chain_info(&ChainType::Stellar)
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
         // Stellar uses Ed25519
-        let path = self.derivation_path(index);
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
         
         // Derive key using SLIP-0010 for Ed25519
-        let derived_key = self.derive_ed25519_key(seed, &path)?;
+        let derived_key = Zeroizing::new(keys.ed25519_node(&path)?.private_key);
         
         let signing_key = SigningKey::from_bytes(&derived_key);
         let verifying_key = signing_key.verifying_key();
         
-        // Stellar address encoding
-        let mut payload = vec![6 << 3]; // Version byte for public key (G...)
-        payload.extend_from_slice(verifying_key.as_bytes());
-        
-        // CRC16 checksum
-        let crc = Crc::<u16>::new(&CRC_16_XMODEM);
-        let checksum = crc.checksum(&payload);
-        payload.extend_from_slice(&checksum.to_le_bytes());
-        
-        // Base32 encode
-        let address = self.base32_encode(&payload);
+        // Account ID (G...) and secret seed (S...) in StrKey encoding
+        let address = encode_strkey(VERSION_ACCOUNT_ID, verifying_key.as_bytes());
+        let secret_seed = key_format.map(|format| match format {
+            KeyFormat::Hex => SecretString::hex_encode(signing_key.to_bytes()),
+            KeyFormat::Native => {
+                SecretString::new(encode_strkey(VERSION_SEED, &Zeroizing::new(signing_key.to_bytes())[..]))
+            }
+        });
         
         Ok(WalletAddress {
             address,
             chain_type: ChainType::Stellar,
             chain_info: self.info(),
-            derivation_path: path.to_string_all_hardened(),
+            derivation_path: path.to_string(),
+            account,
             index,
             public_key: hex::encode(verifying_key.as_bytes()),
-            private_key: hex::encode(signing_key.to_bytes()),
+            public_key_formats: self.public_key_formats(verifying_key.as_bytes())?,
+            private_key: secret_seed,
         })
     }
 
-    fn derivation_path(&self, index: u32) -> DerivationPath {
-        // Stellar uses m/44'/148'/index'
-        DerivationPath::new(44, 148, index, 0, 0)
+    fn derivation_path(&self, account: u32, _index: u32) -> DerivationPath {
+        // Stellar uses m/44'/148'/account' (SEP-0005)
+        DerivationPath::hardened(&[44, 148, account])
     }
 
-    async fn validate_address(&self, address: &str) -> bool {
-        // Stellar addresses start with 'G' and are 56 characters
-        if !address.starts_with('G') || address.len() != 56 {
-            return false;
-        }
+    fn default_key_format(&self) -> KeyFormat {
+        // Stellar wallets only import the StrKey secret seed
+        KeyFormat::Native
+    }
+
+    fn address_level(&self) -> AddressLevel {
+        // SEP-0005 gives every Stellar account its own BIP44 account
+        AddressLevel::Account
+    }
+
+    fn address_from_private_key(&self, private_key: &SecretString) -> ApiResult<ImportedKey> {
+        let invalid = || ApiError::InvalidPrivateKey(self.info().name);
+        let encoded = private_key.expose_secret().trim();
         
-        // Validate base32 characters
-        address.chars().all(|c| {
-            matches!(c, 'A'..='Z' | '2'..='7')
-        })
+        let (signing_key, key_format) = match decode_hex_key(encoded) {
+            Some(key) => (SigningKey::from_bytes(&key), KeyFormat::Hex),
+            None => {
+                let seed = Zeroizing::new(decode_strkey(VERSION_SEED, encoded).ok_or_else(invalid)?);
+                (SigningKey::from_bytes(seed[..].try_into().map_err(|_| invalid())?), KeyFormat::Native)
+            }
+        };
+        
+        let address = encode_strkey(VERSION_ACCOUNT_ID, signing_key.verifying_key().as_bytes());
+        import_ed25519_key(self, &signing_key, address, key_format)
+    }
+
+    async fn validate_address(&self, address: &str) -> bool {
+        // A 56-character StrKey with the account ID version byte and a valid CRC16
+        address.len() == 56 && decode_strkey(VERSION_ACCOUNT_ID, address).is_some()
     }
 
     fn example_address(&self) -> &str {
-        "GBJCHUKZMTFSLOMNC7P4TS4VJJBTCYL3XKSOLXAUJSD56C4LHND5TWUQ"
+        "GDRXE2BQUC3AZNPVFSCEZ76NJ3WWL25FYFK6RGZGIEKWE4SOOHSUJUJ6"
     }
 }
 
-impl Stellar {
-    // SLIP-0010 Ed25519 derivation
-    fn derive_ed25519_key(&self, seed: &[u8], path: &DerivationPath) -> ApiResult<[u8; 32]> {
-        // Start with the master key
-        let mut hasher = Sha512::new();
-        hasher.update(b"ed25519 seed");
-        hasher.update(seed);
-        let mut key = hasher.finalize();
+/// CRC16-XModem over the version byte and payload, appended little-endian
+fn strkey_checksum(data: &[u8]) -> [u8; 2] {
+    Crc::<u16>::new(&CRC_16_XMODEM).checksum(data).to_le_bytes()
+}
+
+/// StrKey: base32 of `version || payload || crc16`
+pub fn encode_strkey(version: u8, payload: &[u8]) -> String {
+    let mut data = vec![version];
+    data.extend_from_slice(payload);
+    let checksum = strkey_checksum(&data);
+    data.extend_from_slice(&checksum);
+    base32_encode(&data)
+}
+
+/// Decode a StrKey and return its payload if the version byte and checksum match
+pub fn decode_strkey(version: u8, strkey: &str) -> Option<Vec<u8>> {
+    let data = base32_decode(strkey)?;
+    if data.len() < 3 || data[0] != version {
+        return None;
+    }
+    
+    let (body, checksum) = data.split_at(data.len() - 2);
+    if strkey_checksum(body) != checksum {
+        return None;
+    }
+    
+    Some(body[1..].to_vec())
+}
+
+// Stellar uses RFC 4648 base32 without padding
+fn base32_encode(data: &[u8]) -> String {
+    let mut result = String::new();
+    
+    let mut bits = 0u32;
+    let mut value = 0u32;
+    
+    for &byte in data {
+        value = (value << 8) | byte as u32;
+        bits += 8;
         
-        // Derive through the path - Stellar uses hardened derivation
-        let indices = vec![
-            0x80000000 + path.purpose,    // 44'
-            0x80000000 + path.coin_type,  // 148'
-            0x80000000 + path.account,    // index'
-        ];
+        while bits >= 5 {
+            let index = (value >> (bits - 5)) & 0x1F;
+            result.push(BASE32_ALPHABET[index as usize] as char);
+            bits -= 5;
+        }
+    }
+    
+    if bits > 0 {
+        let index = (value << (5 - bits)) & 0x1F;
+        result.push(BASE32_ALPHABET[index as usize] as char);
+    }
+    
+    result
+}
+
+fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut result = Vec::new();
+    
+    let mut bits = 0u32;
+    let mut value = 0u32;
+    
+    for c in encoded.bytes() {
+        let digit = BASE32_ALPHABET.iter().position(|&a| a == c)? as u32;
+        value = (value << 5) | digit;
+        bits += 5;
         
-        for index in indices {
-            let mut hasher = Sha512::new();
-            hasher.update(&[0x00]); // hardened derivation
-            hasher.update(&key[..32]); // private key part
-            hasher.update(&index.to_be_bytes());
-            key = hasher.finalize();
+        if bits >= 8 {
+            result.push((value >> (bits - 8)) as u8);
+            bits -= 8;
         }
+        value &= (1 << bits) - 1;
+    }
+    
+    // Leftover bits must be zero padding, otherwise the encoding is not canonical
+    if bits >= 5 || value != 0 {
+        return None;
+    }
+    
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bip39::Mnemonic;
+    
+    #[tokio::test]
+    async fn test_sep0005_vectors() {
+        // SEP-0005 test vector 1
+        let mnemonic = "illness spike retreat truth genius clock brain pass fit cave bargain toe";
+        let seed = Mnemonic::parse(mnemonic).unwrap().to_seed("");
+        let keys = KeyTree::new(&seed);
+        let stellar = Stellar::new();
+        
+        let wallet = stellar.generate_address(&keys, "", 0, 0, None, Some(KeyFormat::Native)).unwrap();
+        assert_eq!(wallet.derivation_path, "m/44'/148'/0'");
+        assert_eq!(wallet.address, "GDRXE2BQUC3AZNPVFSCEZ76NJ3WWL25FYFK6RGZGIEKWE4SOOHSUJUJ6");
+        assert_eq!(wallet.private_key.unwrap().expose_secret(), "SBGWSG6BTNCKCOB3DIFBGCVMUPQFYPA2G4O34RMTB343OYPXU5DJDVMN");
         
-        // Return the first 32 bytes as the private key
-        let mut private_key = [0u8; 32];
-        private_key.copy_from_slice(&key[..32]);
-        Ok(private_key)
+        let wallet = stellar.generate_address(&keys, "", 1, 0, None, Some(KeyFormat::Native)).unwrap();
+        assert_eq!(wallet.address, "GBAW5XGWORWVFE2XTJYDTLDHXTY2Q2MO73HYCGB3XMFMQ562Q2W2GJQX");
+        assert_eq!(wallet.private_key.unwrap().expose_secret(), "SCEPFFWGAG5P2VX5DHIYK3XEMZYLTYWIPWYEKXFHSK25RVMIUNJ7CTIS");
     }
     
-    // Stellar uses its own base32 encoding
-    fn base32_encode(&self, data: &[u8]) -> String {
-        const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
-        let mut result = String::new();
+    #[tokio::test]
+    async fn test_stellar_address_validation() {
+        let stellar = Stellar::new();
         
-        let mut bits = 0u32;
-        let mut value = 0u32;
+        assert!(stellar.validate_address("GDRXE2BQUC3AZNPVFSCEZ76NJ3WWL25FYFK6RGZGIEKWE4SOOHSUJUJ6").await);
+        assert!(stellar.validate_address(stellar.example_address()).await);
         
-        for &byte in data {
-            value = (value << 8) | byte as u32;
-            bits += 8;
-            
-            while bits >= 5 {
-                let index = (value >> (bits - 5)) & 0x1F;
-                result.push(CHARSET[index as usize] as char);
-                bits -= 5;
-            }
-        }
+        // Checksum mismatch (last character changed)
+        assert!(!stellar.validate_address("GDRXE2BQUC3AZNPVFSCEZ76NJ3WWL25FYFK6RGZGIEKWE4SOOHSUJUJ7").await);
+        // Secret seed has the wrong version byte
+        assert!(!stellar.validate_address("SBGWSG6BTNCKCOB3DIFBGCVMUPQFYPA2G4O34RMTB343OYPXU5DJDVMN").await);
+        assert!(!stellar.validate_address("GDRXE2BQUC3AZNPVFSCEZ76NJ3WWL25FYFK6RGZGIEKWE4SOOHSUJUJ").await);
+        assert!(!stellar.validate_address("").await);
+    }
+    
+    #[test]
+    fn test_import_secret_seed() {
+        let stellar = Stellar::new();
         
-        if bits > 0 {
-            let index = (value << (5 - bits)) & 0x1F;
-            result.push(CHARSET[index as usize] as char);
-        }
+        let imported = stellar
+            .address_from_private_key(&"SBGWSG6BTNCKCOB3DIFBGCVMUPQFYPA2G4O34RMTB343OYPXU5DJDVMN".into())
+            .unwrap();
+        assert_eq!(imported.address, "GDRXE2BQUC3AZNPVFSCEZ76NJ3WWL25FYFK6RGZGIEKWE4SOOHSUJUJ6");
+        assert_eq!(imported.key_format, KeyFormat::Native);
+        assert_eq!(imported.compressed, None);
         
-        result
+        // An account ID is not a secret seed
+        assert!(stellar
+            .address_from_private_key(&"GDRXE2BQUC3AZNPVFSCEZ76NJ3WWL25FYFK6RGZGIEKWE4SOOHSUJUJ6".into())
+            .is_err());
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_strkey_round_trip() {
+        let payload = [7u8; 32];
+        let encoded = encode_strkey(VERSION_SEED, &payload);
+        assert!(encoded.starts_with('S'));
+        assert_eq!(decode_strkey(VERSION_SEED, &encoded).unwrap(), payload);
+        assert!(decode_strkey(VERSION_ACCOUNT_ID, &encoded).is_none());
+    }
+}
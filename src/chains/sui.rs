@@ -1,18 +1,53 @@
 use async_trait::async_trait;
-use ed25519_dalek::SigningKey;
-use sha2::{Sha512, Digest};
-use hmac::{Hmac, Mac};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use zeroize::Zeroizing;
 use blake2::{Blake2b, Digest as Blake2Digest};
+use bech32::{FromBase32, ToBase32, Variant};
+use base64::Engine;
+use std::collections::BTreeMap;
 
-use crate::core::{Chain, ChainInfo, ChainType, WalletAddress, DerivationPath, get_chain_info};
+use crate::core::{Chain, KeyTree, ChainInfo, ChainType, WalletAddress, SecretString, KeyFormat, DerivationPath, ImportedKey, get_chain_info, decode_hex_key, import_ed25519_key};
 use crate::errors::{ApiError, ApiResult};
 
 pub struct Sui;
 
+impl Default for Sui {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Sui {
     pub fn new() -> Self {
         Self
     }
+
+    fn address(&self, public_key: &VerifyingKey) -> String {
+        // Sui address is derived from public key with Blake2b
+        // Sui uses: flag(0x00) + pubkey
+        let mut data_to_hash = Vec::new();
+        data_to_hash.push(0x00); // Ed25519 signature scheme flag
+        data_to_hash.extend_from_slice(public_key.as_bytes());
+        
+        let mut hasher = Blake2b::<typenum::U32>::new(); // 32 bytes = 256 bits
+        hasher.update(&data_to_hash);
+        let hash = hasher.finalize();
+        
+        // Format as 0x prefixed hex (lowercase)
+        format!("0x{}", hex::encode(hash))
+    }
+}
+
+/// Sui's `suiprivkey1...` export format: bech32 of the signature scheme flag
+/// (0x00 for ed25519) followed by the 32-byte private key
+fn sui_private_key(signing_key: &SigningKey) -> ApiResult<SecretString> {
+    let mut data = Zeroizing::new(Vec::with_capacity(33));
+    data.push(0x00);
+    data.extend_from_slice(signing_key.as_bytes());
+    
+    bech32::encode("suiprivkey", data.to_base32(), Variant::Bech32)
+        .map(SecretString::new)
+        .map_err(|e| ApiError::CryptoError(e.to_string()))
 }
 
 #[async_trait]
@@ -21,48 +56,81 @@ impl Chain for Sui {
         get_chain_info(&ChainType::Sui)
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
-        // Sui uses Ed25519 with m/44'/784'/0'/0'/0' path
-        let path = self.derivation_path(index);
+        // Sui uses Ed25519 with m/44'/784'/account'/0'/index' path
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
         
         // Derive key using BIP32-Ed25519
-        let derived_key = self.derive_ed25519_key(seed)?;
+        let derived_key = Zeroizing::new(keys.ed25519_node(&path)?.private_key);
         
         let signing_key = SigningKey::from_bytes(&derived_key);
         let verifying_key = signing_key.verifying_key();
         
-        // Sui address is derived from public key with Blake2b
-        // Sui uses: flag(0x00) + pubkey
-        let mut data_to_hash = Vec::new();
-        data_to_hash.push(0x00); // Ed25519 signature scheme flag
-        data_to_hash.extend_from_slice(verifying_key.as_bytes());
-        
-        let mut hasher = Blake2b::<typenum::U32>::new(); // 32 bytes = 256 bits
-        hasher.update(&data_to_hash);
-        let hash = hasher.finalize();
-        
-        // Format as 0x prefixed hex (lowercase)
-        let address = format!("0x{}", hex::encode(hash));
+        let address = self.address(&verifying_key);
         
         Ok(WalletAddress {
             address,
             chain_type: ChainType::Sui,
             chain_info: self.info(),
-            derivation_path: path.to_string_all_hardened(),
+            derivation_path: path.to_string(),
+            account,
             index,
             public_key: hex::encode(verifying_key.as_bytes()),
-            private_key: hex::encode(signing_key.to_bytes()),
+            public_key_formats: self.public_key_formats(verifying_key.as_bytes())?,
+            private_key: key_format
+                .map(|format| match format {
+                    KeyFormat::Hex => Ok(SecretString::hex_encode(signing_key.to_bytes())),
+                    KeyFormat::Native => sui_private_key(&signing_key),
+                })
+                .transpose()?,
         })
     }
 
-    fn derivation_path(&self, _index: u32) -> DerivationPath {
-        // Sui uses m/44'/784'/0'/0'/0' (all hardened, fixed path)
-        DerivationPath::new(44, 784, 0, 0, 0)
+    fn derivation_path(&self, account: u32, index: u32) -> DerivationPath {
+        // Sui uses m/44'/784'/account'/0'/index' (all hardened)
+        DerivationPath::hardened(&[44, 784, account, 0, index])
+    }
+
+    fn address_from_private_key(&self, private_key: &SecretString) -> ApiResult<ImportedKey> {
+        let invalid = || ApiError::InvalidPrivateKey(self.info().name);
+        let encoded = private_key.expose_secret().trim();
+        
+        let (signing_key, key_format) = match decode_hex_key(encoded) {
+            Some(key) => (SigningKey::from_bytes(&key), KeyFormat::Hex),
+            None => {
+                let (hrp, data, _) = bech32::decode(encoded).map_err(|_| invalid())?;
+                let data = Zeroizing::new(Vec::<u8>::from_base32(&data).map_err(|_| invalid())?);
+                // Only the ed25519 flag; secp256k1 and secp256r1 Sui keys use other curves
+                match (hrp.as_str(), data.split_first()) {
+                    ("suiprivkey", Some((0x00, key))) if key.len() == 32 => {
+                        (SigningKey::from_bytes(key.try_into().map_err(|_| invalid())?), KeyFormat::Native)
+                    }
+                    _ => return Err(invalid()),
+                }
+            }
+        };
+        
+        let address = self.address(&signing_key.verifying_key());
+        import_ed25519_key(self, &signing_key, address, key_format)
+    }
+
+    fn public_key_formats(&self, public_key: &[u8]) -> ApiResult<BTreeMap<String, String>> {
+        // Sui's base64 public key: signature scheme flag (0x00 = ed25519) then the key
+        let mut flagged = vec![0x00];
+        flagged.extend_from_slice(public_key);
+        
+        Ok(BTreeMap::from([(
+            "base64".to_string(),
+            base64::engine::general_purpose::STANDARD.encode(flagged),
+        )]))
     }
 
     async fn validate_address(&self, address: &str) -> bool {
@@ -78,46 +146,3 @@ impl Chain for Sui {
         "0x02a212de6a9dfa3a69e22387acfbafbb1a9e591bd9d636e7895dcfc8de05f331"
     }
 }
-
-impl Sui {
-    // BIP32-Ed25519 derivation for Sui (Trust Wallet style)
-    fn derive_ed25519_key(&self, seed: &[u8]) -> ApiResult<[u8; 32]> {
-        type HmacSha512 = Hmac<Sha512>;
-        
-        // Master key
-        let mut mac = HmacSha512::new_from_slice(b"ed25519 seed")
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-        mac.update(seed);
-        let master = mac.finalize().into_bytes();
-        
-        let mut key = master[..32].to_vec();
-        let mut chain_code = master[32..].to_vec();
-        
-        // Sui uses m/44'/784'/0'/0'/0' (all hardened)
-        let indices = vec![
-            0x80000000u32 + 44,    // 44'
-            0x80000000u32 + 784,   // 784'
-            0x80000000u32 + 0,     // 0'
-            0x80000000u32 + 0,     // 0'
-            0x80000000u32 + 0,     // 0'
-        ];
-        
-        for idx in indices {
-            let mut mac = HmacSha512::new_from_slice(&chain_code)
-                .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-            
-            // Hardened derivation
-            mac.update(&[0x00]);
-            mac.update(&key);
-            mac.update(&idx.to_be_bytes());
-            
-            let result = mac.finalize().into_bytes();
-            key = result[..32].to_vec();
-            chain_code = result[32..].to_vec();
-        }
-        
-        let mut private_key = [0u8; 32];
-        private_key.copy_from_slice(&key);
-        Ok(private_key)
-    }
-}
\ No newline at end of file
@@ -1,17 +1,71 @@
 use async_trait::async_trait;
-use ed25519_dalek::SigningKey;
-use sha2::{Sha512, Digest};
-use blake2::{Blake2b, Digest as Blake2Digest};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use zeroize::Zeroizing;
+use sha2::Digest;
+use blake2::Blake2b;
+use std::collections::BTreeMap;
 
-use crate::core::{Chain, ChainInfo, ChainType, WalletAddress, DerivationPath, get_chain_info};
-use crate::errors::ApiResult;
+use crate::core::slip10;
+use crate::core::{Chain, KeyTree, ChainInfo, ChainType, WalletAddress, SecretString, KeyFormat, DerivationPath, ImportedKey, get_chain_info, decode_hex_key, ed25519_keypair, import_ed25519_key};
+use crate::errors::{ApiError, ApiResult};
 
-pub struct Tezos;
+/// base58check prefixes of `edsk...` secret keys: the 32-byte seed form (54 characters)
+/// and the older 64-byte seed-plus-public-key form (98 characters)
+const EDSK_SEED_PREFIX: [u8; 4] = [13, 15, 58, 7];
+const EDSK_KEYPAIR_PREFIX: [u8; 4] = [43, 246, 78, 7];
+
+pub struct Tezos {
+    /// Re-derive with the pre-SLIP-0010 derivation, for addresses issued before the fix
+    legacy_derivation: bool,
+}
+
+impl Default for Tezos {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Tezos {
     pub fn new() -> Self {
-        Self
+        Self { legacy_derivation: false }
+    }
+
+    /// Tezos using the legacy plain SHA-512 derivation; see `slip10::derive_legacy_ed25519_key`
+    pub fn legacy() -> Self {
+        Self { legacy_derivation: true }
     }
+
+    fn address(&self, public_key: &VerifyingKey) -> String {
+        // Tezos tz1 address (Ed25519)
+        // Prefix: tz1 = [6, 161, 159]
+        let mut payload = vec![6, 161, 159];
+        
+        // Hash the public key with Blake2b
+        let mut hasher = Blake2b::<typenum::U32>::new();
+        hasher.update(public_key.as_bytes());
+        let hash = hasher.finalize();
+        payload.extend_from_slice(&hash[..20]); // Take first 20 bytes
+        
+        // Double SHA256 for checksum
+        let checksum = sha2::Sha256::digest(sha2::Sha256::digest(&payload));
+        payload.extend_from_slice(&checksum[..4]);
+        
+        // Base58 encode
+        bs58::encode(payload).into_string()
+    }
+}
+
+/// The unencrypted `edsk...` secret key octez-client and Taquito import: the 32-byte
+/// seed behind prefix `[13, 15, 58, 7]`, base58check-encoded like tz1 addresses
+fn tezos_secret_key(signing_key: &SigningKey) -> SecretString {
+    let mut payload = Zeroizing::new(Vec::with_capacity(40));
+    payload.extend_from_slice(&EDSK_SEED_PREFIX);
+    payload.extend_from_slice(signing_key.as_bytes());
+    
+    let checksum = sha2::Sha256::digest(sha2::Sha256::digest(&payload[..]));
+    payload.extend_from_slice(&checksum[..4]);
+    
+    SecretString::new(bs58::encode(&payload[..]).into_string())
 }
 
 #[async_trait]
@@ -20,52 +74,84 @@ impl Chain for Tezos {
         get_chain_info(&ChainType::Tezos)
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
         // Tezos uses Ed25519
-        let path = self.derivation_path(index);
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
         
         // Derive key using SLIP-0010 for Ed25519
-        let derived_key = self.derive_ed25519_key(seed, &path)?;
+        let derived_key = if self.legacy_derivation {
+            slip10::derive_legacy_ed25519_key(keys.seed(), &path)?
+        } else {
+            Zeroizing::new(keys.ed25519_node(&path)?.private_key)
+        };
         
         let signing_key = SigningKey::from_bytes(&derived_key);
         let verifying_key = signing_key.verifying_key();
         
-        // Tezos tz1 address (Ed25519)
-        // Prefix: tz1 = [6, 161, 159]
-        let mut payload = vec![6, 161, 159];
-        
-        // Hash the public key with Blake2b
-        let mut hasher = Blake2b::<typenum::U32>::new();
-        hasher.update(verifying_key.as_bytes());
-        let hash = hasher.finalize();
-        payload.extend_from_slice(&hash[..20]); // Take first 20 bytes
-        
-        // Double SHA256 for checksum
-        let checksum = sha2::Sha256::digest(&sha2::Sha256::digest(&payload));
-        payload.extend_from_slice(&checksum[..4]);
-        
-        // Base58 encode
-        let address = bs58::encode(payload).into_string();
+        let address = self.address(&verifying_key);
         
         Ok(WalletAddress {
             address,
             chain_type: ChainType::Tezos,
             chain_info: self.info(),
-            derivation_path: path.to_string_all_hardened(),
+            derivation_path: path.to_string(),
+            account,
             index,
             public_key: hex::encode(verifying_key.as_bytes()),
-            private_key: hex::encode(signing_key.to_bytes()),
+            public_key_formats: self.public_key_formats(verifying_key.as_bytes())?,
+            private_key: key_format.map(|format| match format {
+                KeyFormat::Hex => SecretString::hex_encode(signing_key.to_bytes()),
+                KeyFormat::Native => tezos_secret_key(&signing_key),
+            }),
         })
     }
 
-    fn derivation_path(&self, index: u32) -> DerivationPath {
-        // Tezos uses m/44'/1729'/0'/0'/index'
-        DerivationPath::new(44, 1729, 0, 0, index)
+    fn derivation_path(&self, account: u32, index: u32) -> DerivationPath {
+        // Tezos uses m/44'/1729'/account'/0'/index'
+        DerivationPath::hardened(&[44, 1729, account, 0, index])
+    }
+
+    fn address_from_private_key(&self, private_key: &SecretString) -> ApiResult<ImportedKey> {
+        let invalid = || ApiError::InvalidPrivateKey(self.info().name);
+        let encoded = private_key.expose_secret().trim();
+        
+        let (signing_key, key_format) = match decode_hex_key(encoded) {
+            Some(key) => (SigningKey::from_bytes(&key), KeyFormat::Hex),
+            None => {
+                let data = Zeroizing::new(bitcoin::base58::decode_check(encoded).map_err(|_| invalid())?);
+                let signing_key = match data.split_at(data.len().min(4)) {
+                    (prefix, seed) if prefix == EDSK_SEED_PREFIX && seed.len() == 32 => {
+                        SigningKey::from_bytes(seed.try_into().map_err(|_| invalid())?)
+                    }
+                    (prefix, keypair) if prefix == EDSK_KEYPAIR_PREFIX => {
+                        ed25519_keypair(keypair).ok_or_else(invalid)?
+                    }
+                    _ => return Err(invalid()),
+                };
+                (signing_key, KeyFormat::Native)
+            }
+        };
+        
+        let address = self.address(&signing_key.verifying_key());
+        import_ed25519_key(self, &signing_key, address, key_format)
+    }
+
+    fn public_key_formats(&self, public_key: &[u8]) -> ApiResult<BTreeMap<String, String>> {
+        // edpk: prefix [13, 15, 37, 217], base58check like tz1 addresses
+        let mut payload = vec![13, 15, 37, 217];
+        payload.extend_from_slice(public_key);
+        let checksum = sha2::Sha256::digest(sha2::Sha256::digest(&payload));
+        payload.extend_from_slice(&checksum[..4]);
+        
+        Ok(BTreeMap::from([("edpk".to_string(), bs58::encode(payload).into_string())]))
     }
 
     async fn validate_address(&self, address: &str) -> bool {
@@ -85,7 +171,7 @@ impl Chain for Tezos {
                 let payload = &data[..23];
                 let checksum = &data[23..];
                 
-                let computed_checksum = sha2::Sha256::digest(&sha2::Sha256::digest(payload));
+                let computed_checksum = sha2::Sha256::digest(sha2::Sha256::digest(payload));
                 checksum == &computed_checksum[..4]
             }
             Err(_) => false,
@@ -96,36 +182,3 @@ impl Chain for Tezos {
         "tz1VSUr8wwNhLAzempoch5d6hLRiTh8Cjcjb"
     }
 }
-
-impl Tezos {
-    // SLIP-0010 Ed25519 derivation
-    fn derive_ed25519_key(&self, seed: &[u8], path: &DerivationPath) -> ApiResult<[u8; 32]> {
-        // Start with the master key
-        let mut hasher = Sha512::new();
-        hasher.update(b"ed25519 seed");
-        hasher.update(seed);
-        let mut key = hasher.finalize();
-        
-        // Derive through the path - Tezos uses all hardened derivation
-        let indices = vec![
-            0x80000000 + path.purpose,    // 44'
-            0x80000000 + path.coin_type,  // 1729'
-            0x80000000 + path.account,    // 0'
-            0x80000000 + path.change,     // 0'
-            0x80000000 + path.index,      // index'
-        ];
-        
-        for index in indices {
-            let mut hasher = Sha512::new();
-            hasher.update(&[0x00]); // hardened derivation
-            hasher.update(&key[..32]); // private key part
-            hasher.update(&index.to_be_bytes());
-            key = hasher.finalize();
-        }
-        
-        // Return the first 32 bytes as the private key
-        let mut private_key = [0u8; 32];
-        private_key.copy_from_slice(&key[..32]);
-        Ok(private_key)
-    }
-}
\ No newline at end of file
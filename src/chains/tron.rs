@@ -1,15 +1,20 @@
 use async_trait::async_trait;
-use bitcoin::bip32::{Xpriv, DerivationPath as BtcDerivationPath, ChildNumber};
-use bitcoin::secp256k1::Secp256k1;
 use bitcoin::Network;
 use tiny_keccak::{Hasher, Keccak};
 use sha2::{Sha256, Digest};
 
-use crate::core::{Chain, ChainInfo, ChainType, WalletAddress, DerivationPath, get_chain_info};
-use crate::errors::{ApiError, ApiResult};
+use crate::chains::bitcoin::{derive_account_xpub, secp256k1_public_key, XpubFormat};
+use crate::core::{Chain, KeyTree, ChainInfo, ChainType, WalletAddress, SecretString, KeyFormat, DerivationPath, ExtendedPublicKey, get_chain_info};
+use crate::errors::ApiResult;
 
 pub struct Tron;
 
+impl Default for Tron {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Tron {
     pub fn new() -> Self {
         Self
@@ -22,30 +27,47 @@ impl Chain for Tron {
         get_chain_info(&ChainType::Tron)
     }
 
-    async fn generate_address(
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         _passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress> {
-        let secp = Secp256k1::new();
-        let master = Xpriv::new_master(Network::Bitcoin, seed)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
-
-        let path = self.derivation_path(index);
-        let btc_path = BtcDerivationPath::from(vec![
-            ChildNumber::from_hardened_idx(path.purpose).unwrap(),
-            ChildNumber::from_hardened_idx(path.coin_type).unwrap(),
-            ChildNumber::from_hardened_idx(path.account).unwrap(),
-            ChildNumber::from_normal_idx(path.change).unwrap(),
-            ChildNumber::from_normal_idx(path.index).unwrap(),
-        ]);
-        
-        let child = master.derive_priv(&secp, &btc_path)
-            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        let path = path.cloned().unwrap_or_else(|| self.derivation_path(account, index));
+        let child = keys.secp256k1_key(&path)?;
         
         let private_key = child.private_key;
-        let secp_pubkey = private_key.public_key(&secp);
+        let secp_pubkey = private_key.public_key(keys.secp());
+        
+        let address = self.address_from_public_key(&secp_pubkey.serialize())?;
+        
+        Ok(WalletAddress {
+            address,
+            chain_type: ChainType::Tron,
+            chain_info: self.info(),
+            derivation_path: path.to_string(),
+            account,
+            index,
+            public_key: hex::encode(secp_pubkey.serialize()),
+            public_key_formats: self.public_key_formats(&secp_pubkey.serialize())?,
+            private_key: key_format.map(|_| SecretString::hex_encode(private_key.secret_bytes())),
+        })
+    }
+
+    fn derivation_path(&self, account: u32, index: u32) -> DerivationPath {
+        DerivationPath::new(44, 195, account, 0, index)
+    }
+
+    fn account_xpub(&self, keys: &KeyTree, account: u32) -> ApiResult<ExtendedPublicKey> {
+        let account_path = self.derivation_path(account, 0).truncated(3);
+        derive_account_xpub(ChainType::Tron, Network::Bitcoin, keys, &account_path, account, XpubFormat::Xpub)
+    }
+
+    fn address_from_public_key(&self, public_key: &[u8]) -> ApiResult<String> {
+        let secp_pubkey = secp256k1_public_key(public_key)?;
         
         // Get uncompressed public key bytes (65 bytes with 0x04 prefix)
         let pubkey_bytes = secp_pubkey.serialize_uncompressed();
@@ -64,25 +86,13 @@ impl Chain for Tron {
         address_bytes.extend_from_slice(&hash[12..]);
         
         // Double SHA256 for checksum
-        let checksum = Sha256::digest(&Sha256::digest(&address_bytes));
+        let checksum = Sha256::digest(Sha256::digest(&address_bytes));
         address_bytes.extend_from_slice(&checksum[..4]);
         
         // Base58 encode
         let address = bs58::encode(address_bytes).into_string();
         
-        Ok(WalletAddress {
-            address,
-            chain_type: ChainType::Tron,
-            chain_info: self.info(),
-            derivation_path: path.to_string(),
-            index,
-            public_key: hex::encode(secp_pubkey.serialize()),
-            private_key: hex::encode(private_key.secret_bytes()),
-        })
-    }
-
-    fn derivation_path(&self, index: u32) -> DerivationPath {
-        DerivationPath::new(44, 195, 0, 0, index)
+        Ok(address)
     }
 
     async fn validate_address(&self, address: &str) -> bool {
@@ -101,7 +111,7 @@ impl Chain for Tron {
                 let payload = &data[..21];
                 let checksum = &data[21..];
                 
-                let computed_checksum = Sha256::digest(&Sha256::digest(payload));
+                let computed_checksum = Sha256::digest(Sha256::digest(payload));
                 checksum == &computed_checksum[..4]
             }
             Err(_) => false,
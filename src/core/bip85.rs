@@ -0,0 +1,284 @@
+//! BIP85 deterministic entropy from a master key.
+//!
+//! Each application derives a hardened secp256k1 key under `m/83696968'`, and the
+//! HMAC-SHA512 of that key (keyed with `bip-entropy-from-k`) is the entropy the
+//! application encodes: a BIP39 mnemonic, a WIF key, an xprv, hex bytes or a
+//! password. Children are independent of each other and can only be recomputed
+//! from the master, so one backup of the master covers every child.
+
+use base64::Engine;
+use bip39::{Language, Mnemonic};
+use bitcoin::bip32::{ChainCode, ChildNumber, Fingerprint, Xpriv};
+use bitcoin::secp256k1::{All, Secp256k1, SecretKey};
+use bitcoin::Network;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use zeroize::Zeroizing;
+
+use crate::chains::bitcoin::encode_private_key;
+use crate::core::secret::SecretString;
+use crate::core::types::{DerivationPath, KeyFormat};
+use crate::errors::{ApiError, ApiResult};
+
+type HmacSha512 = Hmac<Sha512>;
+
+const PURPOSE: u32 = 83696968;
+const HMAC_KEY: &[u8] = b"bip-entropy-from-k";
+const BASE85_ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+/// A BIP85 application and its parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bip85Application {
+    /// Application 39: a 12, 18 or 24 word mnemonic in `language`
+    Bip39 { language: Language, word_count: u32 },
+    /// Application 2: a compressed mainnet WIF key
+    Wif,
+    /// Application 32: an extended private key
+    Xprv,
+    /// Application 128169: 16 to 64 bytes of hex
+    Hex { num_bytes: u32 },
+    /// Application 707764: a base64 password of 20 to 86 characters
+    PasswordBase64 { length: u32 },
+    /// Application 707785: a base85 password of 10 to 80 characters
+    PasswordBase85 { length: u32 },
+}
+
+impl Bip85Application {
+    /// Application `number` with its parameters. `language` and `word_count` apply to
+    /// BIP39 (defaulting to English and 24 words); `length` is the byte count for hex
+    /// and the character count for passwords, and is required for both.
+    pub fn new(number: u32, language: Option<Language>, word_count: Option<u32>, length: Option<u32>) -> ApiResult<Self> {
+        let length = |range: std::ops::RangeInclusive<u32>, unit: &str| match length {
+            Some(length) if range.contains(&length) => Ok(length),
+            Some(length) => Err(ApiError::BadRequest(format!(
+                "BIP85 application {} takes {} to {} {}, not {}",
+                number, range.start(), range.end(), unit, length
+            ))),
+            None => Err(ApiError::BadRequest(format!("BIP85 application {} requires a length", number))),
+        };
+
+        match number {
+            39 => {
+                let word_count = word_count.unwrap_or(24);
+                if ![12, 18, 24].contains(&word_count) {
+                    return Err(ApiError::InvalidWordCount(word_count));
+                }
+                Ok(Self::Bip39 { language: language.unwrap_or(Language::English), word_count })
+            }
+            2 => Ok(Self::Wif),
+            32 => Ok(Self::Xprv),
+            128169 => Ok(Self::Hex { num_bytes: length(16..=64, "bytes")? }),
+            707764 => Ok(Self::PasswordBase64 { length: length(20..=86, "characters")? }),
+            707785 => Ok(Self::PasswordBase85 { length: length(10..=80, "characters")? }),
+            _ => Err(ApiError::BadRequest(format!(
+                "Unsupported BIP85 application: {}. Must be 39, 2, 32, 128169, 707764 or 707785",
+                number
+            ))),
+        }
+    }
+
+    /// The application number
+    pub fn number(&self) -> u32 {
+        match self {
+            Self::Bip39 { .. } => 39,
+            Self::Wif => 2,
+            Self::Xprv => 32,
+            Self::Hex { .. } => 128169,
+            Self::PasswordBase64 { .. } => 707764,
+            Self::PasswordBase85 { .. } => 707785,
+        }
+    }
+
+    /// Whether the child is wallet key material (a mnemonic, key or seed entropy) rather
+    /// than a password
+    pub fn is_key_material(&self) -> bool {
+        !matches!(self, Self::PasswordBase64 { .. } | Self::PasswordBase85 { .. })
+    }
+
+    /// Derivation path of the child at `index`, e.g. `m/83696968'/39'/0'/12'/0'`
+    pub fn path(&self, index: u32) -> DerivationPath {
+        let number = self.number();
+        match *self {
+            Self::Bip39 { language, word_count } => {
+                DerivationPath::hardened(&[PURPOSE, number, language_index(language), word_count, index])
+            }
+            Self::Wif | Self::Xprv => DerivationPath::hardened(&[PURPOSE, number, index]),
+            Self::Hex { num_bytes: length }
+            | Self::PasswordBase64 { length }
+            | Self::PasswordBase85 { length } => DerivationPath::hardened(&[PURPOSE, number, length, index]),
+        }
+    }
+}
+
+/// The child of `master` for `application` at `index`
+pub fn derive(master: &Xpriv, secp: &Secp256k1<All>, application: &Bip85Application, index: u32) -> ApiResult<SecretString> {
+    let entropy = entropy(master, secp, &application.path(index))?;
+
+    match *application {
+        Bip85Application::Bip39 { language, word_count } => {
+            let length = word_count as usize * 4 / 3;
+            let mnemonic = Mnemonic::from_entropy_in(language, &entropy[..length])
+                .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+            Ok(SecretString::new(mnemonic.to_string()))
+        }
+        Bip85Application::Wif => {
+            let key = SecretKey::from_slice(&entropy[..32]).map_err(|e| ApiError::CryptoError(e.to_string()))?;
+            Ok(encode_private_key(&key, Network::Bitcoin, KeyFormat::Native))
+        }
+        Bip85Application::Xprv => {
+            let mut chain_code = [0u8; 32];
+            chain_code.copy_from_slice(&entropy[..32]);
+            let xprv = Xpriv {
+                network: Network::Bitcoin,
+                depth: 0,
+                parent_fingerprint: Fingerprint::default(),
+                child_number: ChildNumber::Normal { index: 0 },
+                private_key: SecretKey::from_slice(&entropy[32..]).map_err(|e| ApiError::CryptoError(e.to_string()))?,
+                chain_code: ChainCode::from(chain_code),
+            };
+            Ok(SecretString::new(xprv.to_string()))
+        }
+        Bip85Application::Hex { num_bytes } => Ok(SecretString::hex_encode(entropy[..num_bytes as usize].to_vec())),
+        Bip85Application::PasswordBase64 { length } => {
+            let mut password = base64::engine::general_purpose::STANDARD.encode(*entropy);
+            password.truncate(length as usize);
+            Ok(SecretString::new(password))
+        }
+        Bip85Application::PasswordBase85 { length } => {
+            let mut password = base85_encode(&*entropy);
+            password.truncate(length as usize);
+            Ok(SecretString::new(password))
+        }
+    }
+}
+
+/// HMAC-SHA512 of the private key at `path`
+fn entropy(master: &Xpriv, secp: &Secp256k1<All>, path: &DerivationPath) -> ApiResult<Zeroizing<[u8; 64]>> {
+    let child = master.derive_priv(secp, &path.to_bip32()?)
+        .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+
+    let mut mac = HmacSha512::new_from_slice(HMAC_KEY).map_err(|e| ApiError::CryptoError(e.to_string()))?;
+    mac.update(&child.private_key.secret_bytes());
+
+    let mut entropy = Zeroizing::new([0u8; 64]);
+    entropy.copy_from_slice(&mac.finalize().into_bytes());
+    Ok(entropy)
+}
+
+/// BIP85's language index, the order of the BIP39 wordlists directory
+fn language_index(language: Language) -> u32 {
+    match language {
+        Language::English => 0,
+        Language::Japanese => 1,
+        Language::Korean => 2,
+        Language::Spanish => 3,
+        Language::SimplifiedChinese => 4,
+        Language::TraditionalChinese => 5,
+        Language::French => 6,
+        Language::Italian => 7,
+        Language::Czech => 8,
+        Language::Portuguese => 9,
+    }
+}
+
+/// Base85 (RFC 1924 alphabet, as Python's `base64.b85encode`) of whole 4-byte chunks
+fn base85_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len() / 4 * 5);
+    for chunk in bytes.chunks_exact(4) {
+        let mut value = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        let mut digits = [0u8; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = BASE85_ALPHABET[(value % 85) as usize];
+            value /= 85;
+        }
+        encoded.extend(digits.iter().map(|&d| d as char));
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    // BIP85 test vectors
+    const MASTER: &str = "xprv9s21ZrQH143K2LBWUUQRFXhucrQqBpKdRRxNVq2zBqsx8HVqFk2uYo8kmbaLLHRdqtQpUm98uKfu3vca1LqdGhUtyoFnCNkfmXRyPXLjbKb";
+
+    fn child(application: Bip85Application, index: u32) -> String {
+        let master = Xpriv::from_str(MASTER).unwrap();
+        derive(&master, &Secp256k1::new(), &application, index).unwrap().expose_secret().to_string()
+    }
+
+    #[test]
+    fn test_entropy_vectors() {
+        let master = Xpriv::from_str(MASTER).unwrap();
+        let secp = Secp256k1::new();
+
+        let entropy0 = entropy(&master, &secp, &DerivationPath::hardened(&[PURPOSE, 0, 0])).unwrap();
+        assert_eq!(
+            hex::encode(*entropy0),
+            "efecfbccffea313214232d29e71563d941229afb4338c21f9517c41aaa0d16f00b83d2a09ef747e7a64e8e2bd5a14869e693da66ce94ac2da570ab7ee48618f7"
+        );
+
+        let entropy1 = entropy(&master, &secp, &DerivationPath::hardened(&[PURPOSE, 0, 1])).unwrap();
+        assert_eq!(
+            hex::encode(*entropy1),
+            "70c6e3e8ebee8dc4c0dbba66076819bb8c09672527c4277ca8729532ad711872218f826919f6b67218adde99018a6df9095ab2b58d803b5b93ec9802085a690e"
+        );
+    }
+
+    #[test]
+    fn test_bip39_vectors() {
+        let bip39 = |word_count| Bip85Application::new(39, None, Some(word_count), None).unwrap();
+
+        assert_eq!(child(bip39(12), 0), "girl mad pet galaxy egg matter matrix prison refuse sense ordinary nose");
+        assert_eq!(
+            child(bip39(18), 0),
+            "near account window bike charge season chef number sketch tomorrow excuse sniff circle vital hockey outdoor supply token"
+        );
+        assert_eq!(
+            child(bip39(24), 0),
+            "puppy ocean match cereal symbol another shed magic wrap hammer bulb intact gadget divorce twin tonight reason outdoor destroy simple truth cigar social volcano"
+        );
+        assert_eq!(bip39(12).path(0).to_string(), "m/83696968'/39'/0'/12'/0'");
+
+        // Other languages change the path, not only the wordlist
+        let japanese = Bip85Application::new(39, Some(Language::Japanese), Some(12), None).unwrap();
+        assert_eq!(japanese.path(0).to_string(), "m/83696968'/39'/1'/12'/0'");
+        assert!(Mnemonic::parse_in(Language::Japanese, child(japanese, 0)).is_ok());
+    }
+
+    #[test]
+    fn test_key_vectors() {
+        assert_eq!(child(Bip85Application::Wif, 0), "Kzyv4uF39d4Jrw2W7UryTHwZr1zQVNk4dAFyqE6BuMrMh1Za7uhp");
+        assert_eq!(
+            child(Bip85Application::Xprv, 0),
+            "xprv9s21ZrQH143K2srSbCSg4m4kLvPMzcWydgmKEnMmoZUurYuBuYG46c6P71UGXMzmriLzCCBvKQWBUv3vPB3m1SATMhp3uEjXHJ42jFg7myX"
+        );
+    }
+
+    #[test]
+    fn test_hex_and_password_vectors() {
+        let hex = Bip85Application::new(128169, None, None, Some(64)).unwrap();
+        assert_eq!(
+            child(hex, 0),
+            "492db4698cf3b73a5a24998aa3e9d7fa96275d85724a91e71aa2d645442f878555d078fd1f1f67e368976f04137b1f7a0d19232136ca50c44614af72b5582a5c"
+        );
+
+        let base64 = Bip85Application::new(707764, None, None, Some(21)).unwrap();
+        assert_eq!(child(base64, 0), "dKLoepugzdVJvdL56ogNV");
+
+        let base85 = Bip85Application::new(707785, None, None, Some(12)).unwrap();
+        assert_eq!(child(base85, 0), "_s`{TW89)i4`");
+    }
+
+    #[test]
+    fn test_application_parameters_are_checked() {
+        assert!(matches!(Bip85Application::new(39, None, Some(15), None), Err(ApiError::InvalidWordCount(15))));
+        assert!(Bip85Application::new(128169, None, None, Some(15)).is_err());
+        assert!(Bip85Application::new(128169, None, None, None).is_err());
+        assert!(Bip85Application::new(707764, None, None, Some(87)).is_err());
+        assert!(Bip85Application::new(707785, None, None, Some(9)).is_err());
+        assert!(Bip85Application::new(0, None, None, None).is_err());
+    }
+}
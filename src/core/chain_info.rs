@@ -23,6 +23,7 @@ pub enum AddressFormat {
 #[serde(rename_all = "snake_case")]
 pub enum ChainType {
     BitcoinLegacy,
+    BitcoinNestedSegwit,
     BitcoinSegwit,
     BitcoinTaproot,
     Ethereum,
@@ -42,6 +43,7 @@ pub enum ChainType {
     Injective,
     Tezos,
     Filecoin,
+    Stellar,
     // Layer 2 EVM chains
     Base,
     Arbitrum,
@@ -69,6 +71,15 @@ pub fn get_chain_info(chain_type: &ChainType) -> ChainInfo {
                 prefix: "1".to_string() 
             },
         },
+        ChainType::BitcoinNestedSegwit => ChainInfo {
+            name: "Bitcoin".to_string(),
+            symbol: "BTC".to_string(),
+            coin_type: 0,
+            decimals: 8,
+            address_format: AddressFormat::Bitcoin { 
+                prefix: "3".to_string() 
+            },
+        },
         ChainType::BitcoinSegwit => ChainInfo {
             name: "Bitcoin".to_string(),
             symbol: "BTC".to_string(),
@@ -217,6 +228,13 @@ pub fn get_chain_info(chain_type: &ChainType) -> ChainInfo {
             decimals: 6,
             address_format: AddressFormat::Custom("tz1".to_string()),
         },
+        ChainType::Stellar => ChainInfo {
+            name: "Stellar".to_string(),
+            symbol: "XLM".to_string(),
+            coin_type: 148,
+            decimals: 7,
+            address_format: AddressFormat::Custom("G".to_string()),
+        },
         ChainType::Filecoin => ChainInfo {
             name: "Filecoin".to_string(),
             symbol: "FIL".to_string(),
@@ -267,6 +285,7 @@ pub fn get_chain_info(chain_type: &ChainType) -> ChainInfo {
 pub fn get_all_chain_types() -> Vec<ChainType> {
     vec![
         ChainType::BitcoinLegacy,
+        ChainType::BitcoinNestedSegwit,
         ChainType::BitcoinSegwit,
         ChainType::BitcoinTaproot,
         ChainType::Ethereum,
@@ -286,6 +305,7 @@ pub fn get_all_chain_types() -> Vec<ChainType> {
         ChainType::Injective,
         ChainType::Tezos,
         ChainType::Filecoin,
+        ChainType::Stellar,
         // Layer 2s
         ChainType::Base,
         ChainType::Arbitrum,
@@ -300,6 +320,7 @@ pub fn get_chain_types_by_symbol(symbol: &str) -> Vec<ChainType> {
     match symbol.to_uppercase().as_str() {
         "BTC" => vec![
             ChainType::BitcoinLegacy,
+            ChainType::BitcoinNestedSegwit,
             ChainType::BitcoinSegwit,
             ChainType::BitcoinTaproot,
         ],
@@ -325,6 +346,7 @@ pub fn get_chain_types_by_symbol(symbol: &str) -> Vec<ChainType> {
         "INJ" => vec![ChainType::Injective],
         "XTZ" => vec![ChainType::Tezos],
         "FIL" => vec![ChainType::Filecoin],
+        "XLM" => vec![ChainType::Stellar],
         "MATIC" => vec![ChainType::Polygon],
         "AVAX" => vec![ChainType::Avalanche],
         _ => vec![],
@@ -0,0 +1,180 @@
+//! Caller-supplied entropy for BIP39 mnemonics.
+//!
+//! Entropy can be given three ways:
+//!
+//! - `hex`: the raw entropy, 16 to 32 bytes in steps of 4 (12 to 24 words).
+//! - `coins`: one flip per bit, most significant first, `H`/`1` for 1 and `T`/`0` for 0.
+//!   128, 160, 192, 224 or 256 flips give 12 to 24 words and can be checked by hand.
+//! - `dice`: rolls of a six-sided die as the digits `1`-`6`. The entropy is the SHA-256
+//!   of the rolls as an ASCII string (the scheme Coldcard uses), truncated to the word
+//!   count's length. At least `bits / log2(6)` rolls are required: 50 for 12 words,
+//!   99 for 24.
+//!
+//! Whitespace and commas between flips or rolls are ignored.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+use crate::errors::{ApiError, ApiResult};
+
+/// How the entropy of a mnemonic request is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntropySource {
+    #[default]
+    Hex,
+    Dice,
+    Coins,
+}
+
+impl FromStr for EntropySource {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> ApiResult<Self> {
+        match s.to_lowercase().as_str() {
+            "hex" => Ok(EntropySource::Hex),
+            "dice" => Ok(EntropySource::Dice),
+            "coins" => Ok(EntropySource::Coins),
+            _ => Err(ApiError::BadRequest(format!("Invalid entropy source: {}. Must be hex, dice or coins", s))),
+        }
+    }
+}
+
+/// Entropy length in bytes of a mnemonic with `word_count` words
+pub fn entropy_length(word_count: u32) -> ApiResult<usize> {
+    match word_count {
+        12 => Ok(16), // 128 bits
+        15 => Ok(20), // 160 bits
+        18 => Ok(24), // 192 bits
+        21 => Ok(28), // 224 bits
+        24 => Ok(32), // 256 bits
+        _ => Err(ApiError::InvalidWordCount(word_count)),
+    }
+}
+
+/// Word count of a mnemonic encoding `length` bytes of entropy
+fn word_count_for(length: usize) -> ApiResult<u32> {
+    match length {
+        16 | 20 | 24 | 28 | 32 => Ok((length * 3 / 4) as u32),
+        _ => Err(ApiError::InvalidEntropy(format!(
+            "{} bits is not a BIP39 entropy length; use 128, 160, 192, 224 or 256",
+            length * 8
+        ))),
+    }
+}
+
+/// Decode `input` into BIP39 entropy. `word_count` is required for dice (where it
+/// defaults to 24) and otherwise only checked against the input's length.
+pub fn decode_entropy(source: EntropySource, input: &str, word_count: Option<u32>) -> ApiResult<Zeroizing<Vec<u8>>> {
+    let symbols = Zeroizing::new(
+        input
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != ',')
+            .collect::<String>(),
+    );
+
+    let entropy = match source {
+        EntropySource::Hex => {
+            let hex = symbols.strip_prefix("0x").unwrap_or(&symbols);
+            Zeroizing::new(hex::decode(hex).map_err(|e| ApiError::InvalidEntropy(e.to_string()))?)
+        }
+        EntropySource::Coins => coin_flips_to_entropy(&symbols)?,
+        EntropySource::Dice => dice_rolls_to_entropy(&symbols, word_count.unwrap_or(24))?,
+    };
+
+    let actual = word_count_for(entropy.len())?;
+    match word_count {
+        Some(expected) if expected != actual => Err(ApiError::InvalidEntropy(format!(
+            "{} bits of entropy make a {} word mnemonic, not {}",
+            entropy.len() * 8,
+            actual,
+            expected
+        ))),
+        _ => Ok(entropy),
+    }
+}
+
+fn coin_flips_to_entropy(flips: &str) -> ApiResult<Zeroizing<Vec<u8>>> {
+    if !flips.len().is_multiple_of(8) {
+        return Err(ApiError::InvalidEntropy(format!(
+            "{} coin flips is not a BIP39 entropy length; use 128, 160, 192, 224 or 256",
+            flips.len()
+        )));
+    }
+
+    let mut entropy = Zeroizing::new(vec![0u8; flips.len() / 8]);
+    for (i, flip) in flips.chars().enumerate() {
+        let bit = match flip.to_ascii_uppercase() {
+            'H' | '1' => 1,
+            'T' | '0' => 0,
+            other => return Err(ApiError::InvalidEntropy(format!("'{}' is not a coin flip; use H/T or 1/0", other))),
+        };
+        entropy[i / 8] |= bit << (7 - i % 8);
+    }
+
+    Ok(entropy)
+}
+
+fn dice_rolls_to_entropy(rolls: &str, word_count: u32) -> ApiResult<Zeroizing<Vec<u8>>> {
+    let length = entropy_length(word_count)?;
+
+    if let Some(other) = rolls.chars().find(|c| !('1'..='6').contains(c)) {
+        return Err(ApiError::InvalidEntropy(format!("'{}' is not a die roll; use 1-6", other)));
+    }
+
+    // Each roll of a fair die carries log2(6) bits
+    let required = ((length * 8) as f64 / 6f64.log2()).ceil() as usize;
+    if rolls.len() < required {
+        return Err(ApiError::InvalidEntropy(format!(
+            "{} dice rolls carry too little entropy for {} words; at least {} are needed",
+            rolls.len(),
+            word_count,
+            required
+        )));
+    }
+
+    let digest = Sha256::digest(rolls.as_bytes());
+    Ok(Zeroizing::new(digest[..length].to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coin_flips_are_bits() {
+        let flips = "HT".repeat(64);
+        let entropy = decode_entropy(EntropySource::Coins, &flips, None).unwrap();
+        assert_eq!(hex::encode(&*entropy), "aa".repeat(16));
+
+        let spaced = "1010 1010 ".repeat(16);
+        assert_eq!(decode_entropy(EntropySource::Coins, &spaced, Some(12)).unwrap(), entropy);
+
+        assert!(decode_entropy(EntropySource::Coins, &flips[..120], None).is_err());
+        assert!(decode_entropy(EntropySource::Coins, &flips, Some(24)).is_err());
+        assert!(decode_entropy(EntropySource::Coins, &"HX".repeat(64), None).is_err());
+    }
+
+    #[test]
+    fn test_dice_rolls_hash_to_entropy() {
+        let entropy = decode_entropy(EntropySource::Dice, &"1".repeat(50), Some(12)).unwrap();
+        assert_eq!(hex::encode(&*entropy), "3dac51a65ec9fcfc409a1b5f1defe92b");
+
+        let entropy = decode_entropy(EntropySource::Dice, &"123456".repeat(17), None).unwrap();
+        assert_eq!(hex::encode(&*entropy), "8fd128918b2e29d6dcbfa5b9a118e5c16d60498c7ba107922a8eb6eb1d36c112");
+
+        // 98 rolls are short of 256 bits, 7 is not a die face
+        assert!(decode_entropy(EntropySource::Dice, &"1".repeat(98), None).is_err());
+        assert!(decode_entropy(EntropySource::Dice, &"7".repeat(99), None).is_err());
+    }
+
+    #[test]
+    fn test_hex_entropy_length() {
+        let entropy = decode_entropy(EntropySource::Hex, "0x00000000000000000000000000000000", None).unwrap();
+        assert_eq!(entropy.len(), 16);
+        assert!(decode_entropy(EntropySource::Hex, "0000", None).is_err());
+        assert!(decode_entropy(EntropySource::Hex, &"00".repeat(16), Some(15)).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! Seed-derived key material for one request.
+//!
+//! Deriving a BIP39 seed runs 2048 rounds of PBKDF2, and every BIP32/SLIP-0010 level
+//! from the master down to the account is another HMAC (plus an EC multiplication for
+//! secp256k1). A `KeyTree` is built once per request and caches the parent node of
+//! every path it derives, so consecutive addresses of a batch only derive their last level.
+//! The seed and every cached node are wiped when the tree is dropped.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use bitcoin::bip32::{ChildNumber, Xpriv};
+use bitcoin::secp256k1::{All, Secp256k1};
+use bitcoin::Network;
+
+use crate::core::secret::SecretBytes;
+use crate::core::slip10::Ed25519Node;
+use crate::core::types::{ChildIndex, DerivationPath};
+use crate::errors::{ApiError, ApiResult};
+
+pub struct KeyTree {
+    seed: SecretBytes,
+    secp: Secp256k1<All>,
+    secp256k1_nodes: Mutex<HashMap<DerivationPath, Xpriv>>,
+    ed25519_nodes: Mutex<HashMap<DerivationPath, Ed25519Node>>,
+}
+
+impl KeyTree {
+    pub fn new(seed: &[u8]) -> Self {
+        Self {
+            seed: SecretBytes::from(seed),
+            secp: Secp256k1::new(),
+            secp256k1_nodes: Mutex::new(HashMap::new()),
+            ed25519_nodes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The BIP39 seed, for derivations that do not go through the cache
+    pub fn seed(&self) -> &[u8] {
+        self.seed.expose_secret()
+    }
+
+    pub fn secp(&self) -> &Secp256k1<All> {
+        &self.secp
+    }
+
+    /// BIP32 secp256k1 key at `path`. The key's `network` is always mainnet; it only
+    /// matters when the key is serialized.
+    pub fn secp256k1_key(&self, path: &DerivationPath) -> ApiResult<Xpriv> {
+        let Some((parent_path, last)) = split_last(path) else {
+            return self.secp256k1_node(path);
+        };
+
+        let parent = self.secp256k1_node(&parent_path)?;
+        let child = match last {
+            ChildIndex::Normal(i) => ChildNumber::from_normal_idx(i),
+            ChildIndex::Hardened(i) => ChildNumber::from_hardened_idx(i),
+        }
+        .map_err(|e| ApiError::InvalidDerivationPath(format!("{}: {}", path, e)))?;
+
+        parent.derive_priv(&self.secp, &[child])
+            .map_err(|e| ApiError::CryptoError(e.to_string()))
+    }
+
+    /// SLIP-0010 ed25519 node at `path`; every level must be hardened
+    pub fn ed25519_node(&self, path: &DerivationPath) -> ApiResult<Ed25519Node> {
+        let indices = path.hardened_indices()?;
+        let Some((parent_path, _)) = split_last(path) else {
+            return self.cached_ed25519_node(path, &indices);
+        };
+
+        let parent = self.cached_ed25519_node(&parent_path, &indices[..indices.len() - 1])?;
+        parent.derive_hardened(indices[indices.len() - 1])
+    }
+
+    fn secp256k1_node(&self, path: &DerivationPath) -> ApiResult<Xpriv> {
+        let mut nodes = self.secp256k1_nodes.lock().map_err(|_| ApiError::InternalError)?;
+        if let Some(node) = nodes.get(path) {
+            return Ok(*node);
+        }
+
+        let master = Xpriv::new_master(Network::Bitcoin, self.seed())
+            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        let node = master.derive_priv(&self.secp, &path.to_bip32()?)
+            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+
+        nodes.insert(path.clone(), node);
+        Ok(node)
+    }
+
+    fn cached_ed25519_node(&self, path: &DerivationPath, indices: &[u32]) -> ApiResult<Ed25519Node> {
+        let mut nodes = self.ed25519_nodes.lock().map_err(|_| ApiError::InternalError)?;
+        if let Some(node) = nodes.get(path) {
+            return Ok(node.clone());
+        }
+
+        let node = indices
+            .iter()
+            .try_fold(Ed25519Node::master(self.seed())?, |node, &index| node.derive_hardened(index))?;
+
+        nodes.insert(path.clone(), node.clone());
+        Ok(node)
+    }
+}
+
+impl Drop for KeyTree {
+    fn drop(&mut self) {
+        // `Xpriv` has no zeroize support, so overwrite the cached secret keys in place;
+        // ed25519 nodes wipe themselves
+        let nodes = self.secp256k1_nodes.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner());
+        for node in nodes.values_mut() {
+            node.private_key.non_secure_erase();
+        }
+    }
+}
+
+fn split_last(path: &DerivationPath) -> Option<(DerivationPath, ChildIndex)> {
+    let last = *path.components().last()?;
+    Some((path.truncated(path.components().len() - 1), last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_derivation_matches_direct() {
+        let seed = [7u8; 64];
+        let keys = KeyTree::new(&seed);
+        let secp = Secp256k1::new();
+        let master = Xpriv::new_master(Network::Bitcoin, &seed).unwrap();
+
+        for index in 0..3 {
+            let path = DerivationPath::new(44, 60, 0, 0, index);
+            let direct = master.derive_priv(&secp, &path.to_bip32().unwrap()).unwrap();
+            assert_eq!(keys.secp256k1_key(&path).unwrap().private_key, direct.private_key);
+
+            let path = DerivationPath::hardened(&[44, 501, index, 0]);
+            let direct = Ed25519Node::derive(&seed, &path).unwrap();
+            assert_eq!(keys.ed25519_node(&path).unwrap().private_key, direct.private_key);
+        }
+
+        // One parent node per path shape, shared by every index
+        assert_eq!(keys.secp256k1_nodes.lock().unwrap().len(), 1);
+        assert_eq!(keys.ed25519_nodes.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_master_path() {
+        let seed = [1u8; 32];
+        let keys = KeyTree::new(&seed);
+        let master = Xpriv::new_master(Network::Bitcoin, &seed).unwrap();
+
+        assert_eq!(keys.secp256k1_key(&"m".parse().unwrap()).unwrap(), master);
+        assert_eq!(
+            keys.ed25519_node(&"m".parse().unwrap()).unwrap().private_key,
+            Ed25519Node::master(&seed).unwrap().private_key
+        );
+    }
+}
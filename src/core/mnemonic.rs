@@ -0,0 +1,126 @@
+//! BIP39 mnemonic parsing shared by every request that carries a mnemonic.
+//!
+//! BIP39 hashes the NFKD form of both the mnemonic and the passphrase, so a Japanese
+//! or Czech phrase typed with composed characters must be decomposed first. Both are
+//! normalized here into `SecretString`s rather than the unwiped copies `bip39` would
+//! make. When the caller names no language the wordlist is detected, and a phrase
+//! whose words all exist in several wordlists is rejected rather than guessed.
+
+use bip39::{Language, Mnemonic};
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroizing;
+
+use crate::core::secret::SecretString;
+use crate::errors::{ApiError, ApiResult};
+
+/// Wordlist for a language code as listed by `/languages`
+pub fn parse_language(language: &str) -> ApiResult<Language> {
+    match language.to_lowercase().as_str() {
+        "english" => Ok(Language::English),
+        "japanese" => Ok(Language::Japanese),
+        "korean" => Ok(Language::Korean),
+        "spanish" => Ok(Language::Spanish),
+        "chinese_simplified" => Ok(Language::SimplifiedChinese),
+        "chinese_traditional" => Ok(Language::TraditionalChinese),
+        "french" => Ok(Language::French),
+        "italian" => Ok(Language::Italian),
+        "czech" => Ok(Language::Czech),
+        "portuguese" => Ok(Language::Portuguese),
+        _ => Err(ApiError::InvalidLanguage(language.to_string())),
+    }
+}
+
+/// Language code of a wordlist, the inverse of `parse_language`
+pub fn language_code(language: Language) -> &'static str {
+    match language {
+        Language::English => "english",
+        Language::Japanese => "japanese",
+        Language::Korean => "korean",
+        Language::Spanish => "spanish",
+        Language::SimplifiedChinese => "chinese_simplified",
+        Language::TraditionalChinese => "chinese_traditional",
+        Language::French => "french",
+        Language::Italian => "italian",
+        Language::Czech => "czech",
+        Language::Portuguese => "portuguese",
+    }
+}
+
+/// NFKD form of a mnemonic or passphrase
+pub fn normalize(secret: &SecretString) -> SecretString {
+    // Decomposition rarely more than triples the length; reserving up front keeps
+    // reallocation from leaving partial copies behind
+    let mut normalized = String::with_capacity(secret.expose_secret().len() * 3);
+    normalized.extend(secret.expose_secret().nfkd());
+    SecretString::new(normalized)
+}
+
+/// Wordlist of a mnemonic: `language` if given, otherwise detected from the words
+pub fn detect_language(mnemonic: &SecretString, language: Option<Language>) -> ApiResult<Language> {
+    if let Some(language) = language {
+        return Ok(language);
+    }
+
+    match Mnemonic::language_of(normalize(mnemonic).expose_secret()) {
+        Ok(language) => Ok(language),
+        Err(bip39::Error::AmbiguousLanguages(candidates)) => Err(ApiError::AmbiguousMnemonicLanguage(
+            candidates.iter().map(language_code).collect::<Vec<_>>().join(", "),
+        )),
+        Err(_) => Err(ApiError::InvalidMnemonic),
+    }
+}
+
+/// Parse a mnemonic in `language`, or in its detected language when `None`
+pub fn parse_mnemonic(mnemonic: &SecretString, language: Option<Language>) -> ApiResult<(Mnemonic, Language)> {
+    let language = detect_language(mnemonic, language)?;
+    let mnemonic = Mnemonic::parse_in_normalized(language, normalize(mnemonic).expose_secret())
+        .map_err(|_| ApiError::InvalidMnemonic)?;
+
+    Ok((mnemonic, language))
+}
+
+/// The 64-byte BIP39 seed; runs 2048 rounds of PBKDF2
+pub fn to_seed(mnemonic: &Mnemonic, passphrase: &SecretString) -> Zeroizing<[u8; 64]> {
+    Zeroizing::new(mnemonic.to_seed_normalized(normalize(passphrase).expose_secret()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // BIP39 Japanese test vector (bip39-jp), entropy 00..00, passphrase in NFC form
+    const JAPANESE_MNEMONIC: &str = "あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あおぞら";
+    const JAPANESE_PASSPHRASE: &str = "㍍ガバヴァぱばぐゞちぢ十人十色";
+    const JAPANESE_SEED: &str = "a262d6fb6122ecf45be09c50492b31f92e9beb7d9a845987a02cefda57a15f9c467a17872029a9e92299b5cbdf306e3a0ee620245cbd508959b6cb7ca637bd55";
+
+    #[test]
+    fn test_japanese_vector_is_normalized() {
+        let (mnemonic, language) = parse_mnemonic(&JAPANESE_MNEMONIC.into(), None).unwrap();
+        assert_eq!(language, Language::Japanese);
+
+        let seed = to_seed(&mnemonic, &JAPANESE_PASSPHRASE.into());
+        assert_eq!(hex::encode(*seed), JAPANESE_SEED);
+    }
+
+    #[test]
+    fn test_ambiguous_language_is_rejected() {
+        // Characters shared by the simplified and traditional Chinese wordlists
+        let mnemonic = SecretString::from("的 的 的 的 的 的 的 的 的 的 的 在");
+        match detect_language(&mnemonic, None) {
+            Err(ApiError::AmbiguousMnemonicLanguage(candidates)) => {
+                assert_eq!(candidates, "chinese_simplified, chinese_traditional");
+            }
+            other => panic!("expected an ambiguity error, got {:?}", other),
+        }
+
+        let (_, language) = parse_mnemonic(&mnemonic, Some(Language::SimplifiedChinese)).unwrap();
+        assert_eq!(language, Language::SimplifiedChinese);
+    }
+
+    #[test]
+    fn test_language_codes_round_trip() {
+        for language in Language::ALL {
+            assert_eq!(parse_language(language_code(*language)).unwrap(), *language);
+        }
+    }
+}
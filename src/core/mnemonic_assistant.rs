@@ -0,0 +1,313 @@
+//! Help for mistyped and incomplete mnemonics.
+//!
+//! Every word is looked up in the phrase's wordlist, and each one that is missing gets
+//! suggestions: the word its first four letters identify (all but the Chinese, Japanese
+//! and Korean lists have unique 4-letter prefixes, and backups often record only those),
+//! or else the wordlist entries closest to it by edit distance. Combinations of the
+//! suggestions that pass the checksum are offered as repairs. A phrase one word short of
+//! a valid length gets the final words that complete its checksum.
+
+use bip39::{Language, Mnemonic};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::core::mnemonic::{language_code, normalize};
+use crate::core::secret::SecretString;
+use crate::errors::{ApiError, ApiResult};
+
+/// Most suggestion combinations checked for repairs
+const MAX_REPAIR_CANDIDATES: usize = 256;
+
+/// A word that is not in the wordlist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidWord {
+    /// 1-based, as numbered on a backup card
+    pub position: usize,
+    pub suggestions: Vec<&'static str>,
+}
+
+/// What is wrong with a mnemonic and how it could be fixed
+#[derive(Debug, Clone)]
+pub struct MnemonicReport {
+    pub language: Language,
+    pub word_count: usize,
+    /// A complete phrase with a valid checksum
+    pub valid: bool,
+    pub invalid_words: Vec<InvalidWord>,
+    /// Last words completing a phrase one word short, e.g. 11 or 23 words
+    pub final_words: Vec<&'static str>,
+    /// Phrases with the invalid words replaced by suggestions that pass the checksum
+    pub repairs: Vec<SecretString>,
+}
+
+/// Check a mnemonic in `language`, or in the wordlist most of its words are from when `None`
+pub fn analyze(mnemonic: &SecretString, language: Option<Language>) -> ApiResult<MnemonicReport> {
+    let normalized = Zeroizing::new(normalize(mnemonic).expose_secret().to_lowercase());
+    let words: Vec<&str> = normalized.split_whitespace().collect();
+    if words.is_empty() {
+        return Err(ApiError::InvalidMnemonic);
+    }
+
+    let language = match language {
+        Some(language) => language,
+        None => likely_language(&words)?,
+    };
+
+    let invalid_words: Vec<InvalidWord> = words
+        .iter()
+        .enumerate()
+        .filter(|(_, word)| language.find_word(word).is_none())
+        .map(|(i, word)| InvalidWord {
+            position: i + 1,
+            suggestions: suggestions(language, word),
+        })
+        .collect();
+
+    let valid = invalid_words.is_empty() && has_valid_checksum(language, &words);
+    let final_words = if invalid_words.is_empty() {
+        final_words(language, &words)
+    } else {
+        Vec::new()
+    };
+    let repairs = repairs(language, &words, &invalid_words);
+
+    Ok(MnemonicReport {
+        language,
+        word_count: words.len(),
+        valid,
+        invalid_words,
+        final_words,
+        repairs,
+    })
+}
+
+/// The wordlist containing the most of `words`; fails if several tie
+fn likely_language(words: &[&str]) -> ApiResult<Language> {
+    let counts: Vec<(Language, usize)> = Language::ALL
+        .iter()
+        .map(|&language| (language, words.iter().filter(|word| language.find_word(word).is_some()).count()))
+        .collect();
+
+    let best = counts.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    if best == 0 {
+        return Err(ApiError::InvalidMnemonic);
+    }
+
+    let candidates: Vec<Language> = counts
+        .into_iter()
+        .filter(|(_, count)| *count == best)
+        .map(|(language, _)| language)
+        .collect();
+    match candidates.as_slice() {
+        [language] => Ok(*language),
+        _ => Err(ApiError::AmbiguousMnemonicLanguage(
+            candidates.iter().map(|language| language_code(*language)).collect::<Vec<_>>().join(", "),
+        )),
+    }
+}
+
+/// Wordlist entries `word` was probably meant to be
+fn suggestions(language: Language, word: &str) -> Vec<&'static str> {
+    let prefix: String = word.chars().take(4).collect();
+    if prefix.chars().count() == 4 {
+        if let [unique] = language.words_by_prefix(&prefix) {
+            return vec![*unique];
+        }
+    }
+
+    // Allow one edit in short words and two in longer ones; a single character (every
+    // Chinese word) is one edit away from everything
+    let mut best = (word.chars().count().saturating_sub(1) / 2).min(2);
+    let mut closest = Vec::new();
+    if best == 0 {
+        return closest;
+    }
+
+    for candidate in language.word_list() {
+        let distance = edit_distance(word, candidate);
+        if distance < best {
+            best = distance;
+            closest.clear();
+        }
+        if distance == best {
+            closest.push(*candidate);
+        }
+    }
+
+    closest
+}
+
+/// Optimal string alignment distance: insertions, deletions, substitutions and
+/// transpositions of adjacent characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+
+    rows[a.len()][b.len()]
+}
+
+fn has_valid_checksum(language: Language, words: &[&str]) -> bool {
+    let phrase = Zeroizing::new(words.join(" "));
+    Mnemonic::parse_in_normalized(language, &phrase).is_ok()
+}
+
+/// Every last word that gives `words` a valid checksum, when one more word makes a
+/// valid length
+fn final_words(language: Language, words: &[&str]) -> Vec<&'static str> {
+    let word_count = words.len() + 1;
+    if ![12, 15, 18, 21, 24].contains(&word_count) {
+        return Vec::new();
+    }
+
+    // The last word is the entropy's final bits followed by the checksum
+    let checksum_bits = word_count / 3;
+    let free_bits = 11 - checksum_bits;
+    let mut entropy = Zeroizing::new(vec![0u8; word_count * 11 * 32 / 33 / 8]);
+    for (i, word) in words.iter().enumerate() {
+        let index = language.find_word(word).unwrap_or_default();
+        write_bits(&mut entropy, i * 11, index as usize, 11);
+    }
+
+    (0..1usize << free_bits)
+        .map(|free| {
+            write_bits(&mut entropy, words.len() * 11, free, free_bits);
+            let checksum = Sha256::digest(&*entropy)[0] as usize >> (8 - checksum_bits);
+            language.word_list()[free << checksum_bits | checksum]
+        })
+        .collect()
+}
+
+/// Write the low `width` bits of `value` at bit `offset`, most significant first
+fn write_bits(bytes: &mut [u8], offset: usize, value: usize, width: usize) {
+    for bit in 0..width {
+        let position = offset + bit;
+        let mask = 0x80 >> (position % 8);
+        if value >> (width - 1 - bit) & 1 == 1 {
+            bytes[position / 8] |= mask;
+        } else {
+            bytes[position / 8] &= !mask;
+        }
+    }
+}
+
+/// Combinations of suggestions for the invalid words that pass the checksum
+fn repairs(language: Language, words: &[&str], invalid_words: &[InvalidWord]) -> Vec<SecretString> {
+    if invalid_words.is_empty() || invalid_words.iter().any(|word| word.suggestions.is_empty()) {
+        return Vec::new();
+    }
+
+    let mut choices = vec![0usize; invalid_words.len()];
+    let mut candidate = words.to_vec();
+    let mut repairs = Vec::new();
+    for _ in 0..MAX_REPAIR_CANDIDATES {
+        for (invalid, &choice) in invalid_words.iter().zip(&choices) {
+            candidate[invalid.position - 1] = invalid.suggestions[choice];
+        }
+        if has_valid_checksum(language, &candidate) {
+            repairs.push(SecretString::new(candidate.join(" ")));
+        }
+
+        // Advance to the next combination, odometer style
+        let Some(next) = (0..choices.len()).find(|&i| choices[i] + 1 < invalid_words[i].suggestions.len()) else {
+            break;
+        };
+        choices[next] += 1;
+        choices[..next].fill(0);
+    }
+
+    repairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_final_words_complete_the_checksum() {
+        // "abandon" is in the French wordlist too
+        let prefix = SecretString::from(MNEMONIC.rsplit_once(' ').unwrap().0);
+        assert!(matches!(analyze(&prefix, None), Err(ApiError::AmbiguousMnemonicLanguage(_))));
+
+        let report = analyze(&prefix, Some(Language::English)).unwrap();
+        assert!(!report.valid);
+        assert_eq!(report.word_count, 11);
+        assert_eq!(report.final_words.len(), 128);
+        assert!(report.final_words.contains(&"about"));
+
+        let prefix = SecretString::from(vec!["zoo"; 23].join(" "));
+        let report = analyze(&prefix, Some(Language::English)).unwrap();
+        assert_eq!(report.final_words.len(), 8);
+        assert!(report.final_words.contains(&"vote"));
+        for word in report.final_words {
+            let phrase = format!("{} {}", prefix.expose_secret(), word);
+            assert!(Mnemonic::parse_in(Language::English, phrase).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_typos_are_repaired() {
+        // "abandonn" keeps its unique prefix, "abuot" is a transposition of "about"
+        let typed = SecretString::from(MNEMONIC.replacen("abandon", "abandonn", 1).replace("about", "abuot"));
+        let report = analyze(&typed, Some(Language::English)).unwrap();
+        assert!(!report.valid);
+        assert_eq!(
+            report.invalid_words,
+            vec![
+                InvalidWord { position: 1, suggestions: vec!["abandon"] },
+                InvalidWord { position: 12, suggestions: vec!["about"] },
+            ]
+        );
+        assert_eq!(report.repairs.len(), 1);
+        assert_eq!(report.repairs[0].expose_secret(), MNEMONIC);
+
+        let report = analyze(&MNEMONIC.into(), None).unwrap();
+        assert_eq!(report.language, Language::English);
+        assert!(report.valid);
+        assert!(report.invalid_words.is_empty() && report.repairs.is_empty());
+    }
+
+    #[test]
+    fn test_every_language_is_supported() {
+        for &language in Language::ALL {
+            let mnemonic = Mnemonic::from_entropy_in(language, &[7u8; 16]).unwrap().to_string();
+            let (prefix, _) = mnemonic.rsplit_once(' ').unwrap();
+            let report = analyze(&SecretString::from(prefix), Some(language)).unwrap();
+            assert_eq!(report.final_words.len(), 128, "{:?}", language);
+
+            let words: Vec<&str> = mnemonic.split_whitespace().collect();
+            let mut typed = words.clone();
+            typed[3] = "xq";
+            let report = analyze(&SecretString::from(typed.join(" ")), Some(language)).unwrap();
+            assert_eq!(report.invalid_words.len(), 1);
+            assert_eq!(report.invalid_words[0].position, 4);
+        }
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("about", "about"), 0);
+        assert_eq!(edit_distance("abuot", "about"), 1);
+        assert_eq!(edit_distance("abot", "about"), 1);
+        assert_eq!(edit_distance("zoo", "zone"), 2);
+    }
+}
@@ -1,9 +1,20 @@
 pub mod chain_info;
 pub mod traits;
 pub mod types;
+pub mod secret;
+pub mod entropy;
+pub mod mnemonic;
+pub mod mnemonic_assistant;
+pub mod slip39;
+pub mod bip85;
+pub mod slip10;
+pub mod key_tree;
 pub mod token_registry;
 
 pub use chain_info::{ChainInfo, ChainType, AddressFormat, get_chain_info, get_all_chain_types, get_chain_types_by_symbol};
-pub use traits::Chain;
-pub use types::{WalletAddress, DerivationPath};
+pub use traits::{Chain, ed25519_keypair, import_ed25519_key, import_secp256k1_key};
+pub use key_tree::KeyTree;
+pub use secret::{SecretBytes, SecretString, decode_hex_key};
+pub use entropy::EntropySource;
+pub use types::{WalletAddress, DerivationPath, ChildIndex, AddressLevel, KeyFormat, NetworkType, MultisigScriptType, PolicyScriptType, MessageFormat, Curve, ImportedKey, ExtendedPublicKey, WatchOnlyAddress};
 pub use token_registry::{TokenRegistry, UnifiedToken, ChainDeployment, AssetType, get_token_registry};
\ No newline at end of file
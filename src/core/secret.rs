@@ -0,0 +1,208 @@
+//! Containers for key material: mnemonics, passphrases, seeds and private keys.
+//!
+//! Both types wipe their whole allocation on drop, print `[REDACTED]` in `Debug`, and
+//! ask the OS to keep their pages out of swap with `mlock`. Locking is best-effort:
+//! it silently does nothing where unsupported or once `RLIMIT_MEMLOCK` is exhausted,
+//! and because locks are per page, unlocking one secret can unlock a neighbour
+//! sharing its page. Zeroizing still applies in every case.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::fmt::Write;
+use zeroize::{Zeroize, Zeroizing};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Secret text such as a mnemonic, passphrase or encoded private key
+#[derive(Default)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(secret: String) -> Self {
+        memory::lock(secret.as_ptr(), secret.capacity());
+        Self(secret)
+    }
+
+    /// Lowercase hex of `bytes`, written straight into the secret's own buffer.
+    /// `bytes` is wiped afterwards.
+    pub fn hex_encode(mut bytes: impl AsMut<[u8]>) -> Self {
+        let raw = bytes.as_mut();
+        let mut encoded = String::with_capacity(raw.len() * 2);
+        for byte in raw.iter() {
+            // Writing to a String with enough capacity cannot fail or reallocate
+            let _ = write!(encoded, "{:02x}", byte);
+        }
+        raw.zeroize();
+        Self::new(encoded)
+    }
+
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        let (ptr, capacity) = (self.0.as_ptr(), self.0.capacity());
+        self.0.zeroize();
+        memory::unlock(ptr, capacity);
+    }
+}
+
+impl Clone for SecretString {
+    fn clone(&self) -> Self {
+        Self::new(self.0.clone())
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(secret: &str) -> Self {
+        Self::new(secret.to_string())
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+// Responses carry secrets on purpose (mnemonics, private keys); serialization is the
+// one place the value leaves the wrapper
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+/// Secret binary data such as a BIP39 seed
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    pub fn new(secret: Vec<u8>) -> Self {
+        memory::lock(secret.as_ptr(), secret.capacity());
+        Self(secret)
+    }
+
+    pub fn expose_secret(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        let (ptr, capacity) = (self.0.as_ptr(), self.0.capacity());
+        self.0.zeroize();
+        memory::unlock(ptr, capacity);
+    }
+}
+
+impl Clone for SecretBytes {
+    fn clone(&self) -> Self {
+        Self::new(self.0.clone())
+    }
+}
+
+impl From<&[u8]> for SecretBytes {
+    fn from(secret: &[u8]) -> Self {
+        Self::new(secret.to_vec())
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// A 32-byte private key given as hex, with or without a `0x` prefix
+pub fn decode_hex_key(encoded: &str) -> Option<Zeroizing<[u8; 32]>> {
+    let encoded = encoded.trim();
+    let encoded = encoded.strip_prefix("0x").unwrap_or(encoded);
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    hex::decode_to_slice(encoded, &mut key[..]).ok()?;
+    Some(key)
+}
+
+#[cfg(unix)]
+mod memory {
+    pub fn lock(ptr: *const u8, len: usize) {
+        if len > 0 {
+            // Failure (no privilege, limit reached) only means the pages may be swapped
+            unsafe {
+                libc::mlock(ptr.cast(), len);
+            }
+        }
+    }
+
+    pub fn unlock(ptr: *const u8, len: usize) {
+        if len > 0 {
+            unsafe {
+                libc::munlock(ptr.cast(), len);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod memory {
+    pub fn lock(_ptr: *const u8, _len: usize) {}
+
+    pub fn unlock(_ptr: *const u8, _len: usize) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let mnemonic = SecretString::from("abandon abandon abandon about");
+        let seed = SecretBytes::from(&[0xab; 64][..]);
+
+        assert_eq!(format!("{:?}", mnemonic), REDACTED);
+        assert_eq!(format!("{:#?}", seed), REDACTED);
+        assert_eq!(mnemonic.expose_secret(), "abandon abandon abandon about");
+    }
+
+    #[test]
+    fn test_hex_encode_wipes_input() {
+        let mut key = [0xde, 0xad, 0xbe, 0xef];
+        let secret = SecretString::hex_encode(&mut key);
+
+        assert_eq!(secret.expose_secret(), "deadbeef");
+        assert_eq!(key, [0u8; 4]);
+    }
+
+    #[test]
+    fn test_decode_hex_key() {
+        let key = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+        assert_eq!(hex::encode(*decode_hex_key(key).unwrap()), key);
+        assert_eq!(decode_hex_key(&format!("0x{}", key)), decode_hex_key(key));
+        assert!(decode_hex_key(&key[2..]).is_none());
+        assert!(decode_hex_key("KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98617").is_none());
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let secret: SecretString = serde_json::from_str("\"hunter2\"").unwrap();
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"hunter2\"");
+    }
+}
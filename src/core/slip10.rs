@@ -0,0 +1,195 @@
+//! SLIP-0010 key derivation for ed25519, shared by every ed25519 chain.
+//!
+//! ed25519 only defines hardened child derivation, so paths must be fully hardened.
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha512};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::core::types::DerivationPath;
+use crate::errors::{ApiError, ApiResult};
+
+type HmacSha512 = Hmac<Sha512>;
+
+const ED25519_CURVE_KEY: &[u8] = b"ed25519 seed";
+
+/// A node of the SLIP-0010 ed25519 tree: private key plus chain code, wiped on drop
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct Ed25519Node {
+    pub private_key: [u8; 32],
+    pub chain_code: [u8; 32],
+}
+
+impl Ed25519Node {
+    /// Master node: `HMAC-SHA512(key = "ed25519 seed", data = seed)`
+    pub fn master(seed: &[u8]) -> ApiResult<Self> {
+        Self::from_hmac(ED25519_CURVE_KEY, &[seed])
+    }
+
+    /// Hardened child; `index` must already carry the hardened bit
+    pub fn derive_hardened(&self, index: u32) -> ApiResult<Self> {
+        Self::from_hmac(&self.chain_code, &[&[0x00], &self.private_key, &index.to_be_bytes()])
+    }
+
+    /// Derive the node at `path` from `seed`
+    pub fn derive(seed: &[u8], path: &DerivationPath) -> ApiResult<Self> {
+        path.hardened_indices()?
+            .into_iter()
+            .try_fold(Self::master(seed)?, |node, index| node.derive_hardened(index))
+    }
+
+    /// SLIP-0010 public key: `0x00` followed by the 32-byte ed25519 public key
+    pub fn public_key(&self) -> [u8; 33] {
+        let verifying_key = ed25519_dalek::SigningKey::from_bytes(&self.private_key).verifying_key();
+        let mut public_key = [0u8; 33];
+        public_key[1..].copy_from_slice(verifying_key.as_bytes());
+        public_key
+    }
+
+    fn from_hmac(key: &[u8], data: &[&[u8]]) -> ApiResult<Self> {
+        let mut mac = HmacSha512::new_from_slice(key)
+            .map_err(|e| ApiError::CryptoError(e.to_string()))?;
+        for part in data {
+            mac.update(part);
+        }
+        let mut result = mac.finalize().into_bytes();
+
+        let mut node = Self {
+            private_key: [0u8; 32],
+            chain_code: [0u8; 32],
+        };
+        node.private_key.copy_from_slice(&result[..32]);
+        node.chain_code.copy_from_slice(&result[32..]);
+        result.as_mut_slice().zeroize();
+        Ok(node)
+    }
+}
+
+/// Private key at `path` using SLIP-0010
+pub fn derive_ed25519_key(seed: &[u8], path: &DerivationPath) -> ApiResult<Zeroizing<[u8; 32]>> {
+    Ed25519Node::derive(seed, path).map(|node| Zeroizing::new(node.private_key))
+}
+
+/// The derivation Tezos used before it moved to SLIP-0010: plain `SHA-512` in
+/// place of HMAC and no chain code. Its keys do not match any other wallet; it
+/// is kept only so addresses already issued with it can be re-derived.
+pub fn derive_legacy_ed25519_key(seed: &[u8], path: &DerivationPath) -> ApiResult<Zeroizing<[u8; 32]>> {
+    let mut hasher = Sha512::new();
+    hasher.update(ED25519_CURVE_KEY);
+    hasher.update(seed);
+    let mut key = hasher.finalize();
+
+    for index in path.hardened_indices()? {
+        let mut hasher = Sha512::new();
+        hasher.update([0x00]);
+        hasher.update(&key[..32]);
+        hasher.update(index.to_be_bytes());
+        key.as_mut_slice().zeroize();
+        key = hasher.finalize();
+    }
+
+    let mut private_key = Zeroizing::new([0u8; 32]);
+    private_key.copy_from_slice(&key[..32]);
+    key.as_mut_slice().zeroize();
+    Ok(private_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Vector {
+        path: &'static str,
+        chain_code: &'static str,
+        private_key: &'static str,
+        public_key: &'static str,
+    }
+
+    // SLIP-0010 test vector 1 for ed25519
+    const SEED_1: &str = "000102030405060708090a0b0c0d0e0f";
+    const VECTORS_1: &[Vector] = &[
+        Vector {
+            path: "m",
+            chain_code: "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb",
+            private_key: "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+            public_key: "00a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed",
+        },
+        Vector {
+            path: "m/0'",
+            chain_code: "8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69",
+            private_key: "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+            public_key: "008c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c",
+        },
+        Vector {
+            path: "m/0'/1'",
+            chain_code: "a320425f77d1b5c2505a6b1b27382b37368ee640e3557c315416801243552f14",
+            private_key: "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2",
+            public_key: "001932a5270f335bed617d5b935c80aedb1a35bd9fc1e31acafd5372c30f5c1187",
+        },
+        Vector {
+            path: "m/0'/1'/2'",
+            chain_code: "2e69929e00b5ab250f49c3fb1c12f252de4fed2c1db88387094a0f8c4c9ccd6c",
+            private_key: "92a5b23c0b8a99e37d07df3fb9966917f5d06e02ddbd909c7e184371463e9fc9",
+            public_key: "00ae98736566d30ed0e9d2f4486a64bc95740d89c7db33f52121f8ea8f76ff0fc1",
+        },
+        Vector {
+            path: "m/0'/1'/2'/2'",
+            chain_code: "8f6d87f93d750e0efccda017d662a1b31a266e4a6f5993b15f5c1f07f74dd5cc",
+            private_key: "30d1dc7e5fc04c31219ab25a27ae00b50f6fd66622f6e9c913253d6511d1e662",
+            public_key: "008abae2d66361c879b900d204ad2cc4984fa2aa344dd7ddc46007329ac76c429c",
+        },
+        Vector {
+            path: "m/0'/1'/2'/2'/1000000000'",
+            chain_code: "68789923a0cac2cd5a29172a475fe9e0fb14cd6adb5ad98a3fa70333e7afa230",
+            private_key: "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+            public_key: "003c24da049451555d51a7014a37337aa4e12d41e485abccfa46b47dfb2af54b7a",
+        },
+    ];
+
+    #[test]
+    fn test_slip10_ed25519_vector_1() {
+        let seed = hex::decode(SEED_1).unwrap();
+        for vector in VECTORS_1 {
+            let path: DerivationPath = vector.path.parse().unwrap();
+            let node = Ed25519Node::derive(&seed, &path).unwrap();
+
+            assert_eq!(hex::encode(node.chain_code), vector.chain_code, "chain code at {}", vector.path);
+            assert_eq!(hex::encode(node.private_key), vector.private_key, "private key at {}", vector.path);
+            assert_eq!(hex::encode(node.public_key()), vector.public_key, "public key at {}", vector.path);
+        }
+    }
+
+    #[test]
+    fn test_slip10_ed25519_vector_2() {
+        let seed = hex::decode(
+            "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542",
+        )
+        .unwrap();
+
+        let master = Ed25519Node::master(&seed).unwrap();
+        assert_eq!(hex::encode(master.chain_code), "ef70a74db9c3a5af931b5fe73ed8e1a53464133654fd55e7a66f8570b8e33c3b");
+        assert_eq!(hex::encode(master.private_key), "171cb88b1b3c1db25add599712e36245d75bc65a1a5c9e18d76f9f2b1eab4012");
+
+        let path: DerivationPath = "m/0'/2147483647'/1'/2147483646'/2'".parse().unwrap();
+        let node = Ed25519Node::derive(&seed, &path).unwrap();
+        assert_eq!(hex::encode(node.chain_code), "5d70af781f3a37b829f0d060924d5e960bdc02e85423494afc0b1a41bbe196d4");
+        assert_eq!(hex::encode(node.private_key), "551d333177df541ad876a60ea71f00447931c0a9da16f227c11ea080d7391b8d");
+        assert_eq!(hex::encode(node.public_key()), "0047150c75db263559a70d5778bf36abbab30fb061ad69f69ece61a72b0cfa4fc0");
+    }
+
+    #[test]
+    fn test_rejects_non_hardened_path() {
+        let path: DerivationPath = "m/44'/501'/0'/0".parse().unwrap();
+        assert!(matches!(Ed25519Node::derive(&[0u8; 32], &path), Err(ApiError::InvalidDerivationPath(_))));
+    }
+
+    #[test]
+    fn test_legacy_derivation_differs() {
+        let seed = hex::decode(SEED_1).unwrap();
+        let path = DerivationPath::hardened(&[44, 1729, 0, 0]);
+        assert_ne!(
+            derive_legacy_ed25519_key(&seed, &path).unwrap(),
+            derive_ed25519_key(&seed, &path).unwrap()
+        );
+    }
+}
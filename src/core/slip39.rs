@@ -0,0 +1,696 @@
+//! SLIP-0039 Shamir backups of a master secret.
+//!
+//! A master secret is encrypted with the passphrase (a four-round Feistel network over
+//! PBKDF2-HMAC-SHA256), then split twice: into groups, any `group_threshold` of which
+//! recover it, and each group into member shares, any `member_threshold` of which
+//! recover the group. Every share is a mnemonic of 10-bit words from the SLIP-39
+//! wordlist, protected by an RS1024 checksum. The recovered master secret is used
+//! directly as the BIP32 seed, in place of a BIP39 seed.
+//!
+//! Shares are interoperable with Trezor and the reference implementation
+//! (python-shamir-mnemonic), including extendable backups.
+
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap};
+use zeroize::Zeroizing;
+
+use crate::core::secret::{SecretBytes, SecretString};
+use crate::errors::{ApiError, ApiResult};
+
+static WORDLIST: Lazy<Vec<&'static str>> = Lazy::new(|| include_str!("slip39_wordlist.txt").lines().collect());
+static WORD_INDEX: Lazy<HashMap<&'static str, u16>> =
+    Lazy::new(|| WORDLIST.iter().enumerate().map(|(i, word)| (*word, i as u16)).collect());
+
+const RADIX_BITS: usize = 10;
+const ID_LENGTH_BITS: u32 = 15;
+const CHECKSUM_LENGTH_WORDS: usize = 3;
+/// Identifier, extendable flag and iteration exponent (2 words), then the group and
+/// member parameters (2 words)
+const HEADER_LENGTH_WORDS: usize = 4;
+const METADATA_LENGTH_WORDS: usize = HEADER_LENGTH_WORDS + CHECKSUM_LENGTH_WORDS;
+const MIN_STRENGTH_BYTES: usize = 16;
+const MIN_MNEMONIC_LENGTH_WORDS: usize = METADATA_LENGTH_WORDS + (MIN_STRENGTH_BYTES * 8).div_ceil(RADIX_BITS);
+const MAX_SHARE_COUNT: usize = 16;
+
+const BASE_ITERATION_COUNT: u32 = 10000;
+const ROUND_COUNT: u8 = 4;
+const DIGEST_LENGTH_BYTES: usize = 4;
+const DIGEST_INDEX: u8 = 254;
+const SECRET_INDEX: u8 = 255;
+
+const CUSTOMIZATION_STRING: &[u8] = b"shamir";
+const CUSTOMIZATION_STRING_EXTENDABLE: &[u8] = b"shamir_extendable";
+
+/// Shares a group is split into, and how many of them recover it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupSpec {
+    pub member_threshold: u8,
+    pub member_count: u8,
+}
+
+/// How a master secret is split
+#[derive(Debug, Clone)]
+pub struct Slip39Scheme {
+    /// Groups needed to recover the secret
+    pub group_threshold: u8,
+    pub groups: Vec<GroupSpec>,
+    /// PBKDF2 runs `10000 << iteration_exponent` iterations in total
+    pub iteration_exponent: u8,
+    /// Extendable backups can later gain shares with a new identifier-free salt
+    pub extendable: bool,
+}
+
+impl Default for Slip39Scheme {
+    // The reference implementation's defaults: one 1-of-1 group
+    fn default() -> Self {
+        Self {
+            group_threshold: 1,
+            groups: vec![GroupSpec { member_threshold: 1, member_count: 1 }],
+            iteration_exponent: 1,
+            extendable: true,
+        }
+    }
+}
+
+/// Member shares of one group
+#[derive(Debug)]
+pub struct ShareGroup {
+    pub member_threshold: u8,
+    pub shares: Vec<SecretString>,
+}
+
+/// Every share of a split master secret, grouped
+#[derive(Debug)]
+pub struct Slip39Shares {
+    pub identifier: u16,
+    pub extendable: bool,
+    pub iteration_exponent: u8,
+    pub group_threshold: u8,
+    pub groups: Vec<ShareGroup>,
+}
+
+/// One decoded share mnemonic
+struct Share {
+    identifier: u16,
+    extendable: bool,
+    iteration_exponent: u8,
+    group_index: u8,
+    group_threshold: u8,
+    group_count: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: Zeroizing<Vec<u8>>,
+}
+
+impl Share {
+    fn from_mnemonic(mnemonic: &str) -> ApiResult<Self> {
+        let words = Zeroizing::new(
+            mnemonic
+                .split_whitespace()
+                .map(|word| {
+                    WORD_INDEX
+                        .get(word.to_lowercase().as_str())
+                        .copied()
+                        .ok_or_else(|| invalid(format!("'{}' is not in the SLIP-39 wordlist", word)))
+                })
+                .collect::<ApiResult<Vec<u16>>>()?,
+        );
+
+        if words.len() < MIN_MNEMONIC_LENGTH_WORDS {
+            return Err(invalid(format!(
+                "a share has {} words; every share has at least {}",
+                words.len(),
+                MIN_MNEMONIC_LENGTH_WORDS
+            )));
+        }
+
+        // The value is padded at the front to a whole number of words
+        let padding = (RADIX_BITS * (words.len() - METADATA_LENGTH_WORDS)) % 16;
+        if padding > 8 {
+            return Err(invalid(format!("a share of {} words has no valid length", words.len())));
+        }
+
+        let id_exp = join_words(&words[..2]);
+        let identifier = (id_exp >> 5) as u16;
+        let extendable = (id_exp >> 4) & 1 == 1;
+        let iteration_exponent = (id_exp & 0xf) as u8;
+
+        if rs1024_polymod(customization_string(extendable), &words) != 1 {
+            return Err(invalid(format!("checksum mismatch in the share starting \"{}\"", share_prefix(&words))));
+        }
+
+        let params = join_words(&words[2..4]);
+        let nibble = |shift: u32| ((params >> shift) & 0xf) as u8;
+        let (group_index, group_threshold, group_count) = (nibble(16), nibble(12) + 1, nibble(8) + 1);
+        let (member_index, member_threshold) = (nibble(4), nibble(0) + 1);
+
+        if group_threshold > group_count {
+            return Err(invalid(format!(
+                "the share starting \"{}\" needs {} groups of only {}",
+                share_prefix(&words),
+                group_threshold,
+                group_count
+            )));
+        }
+
+        let value = words_to_bytes(&words[HEADER_LENGTH_WORDS..words.len() - CHECKSUM_LENGTH_WORDS], padding)
+            .ok_or_else(|| invalid(format!("invalid padding in the share starting \"{}\"", share_prefix(&words))))?;
+
+        Ok(Self {
+            identifier,
+            extendable,
+            iteration_exponent,
+            group_index,
+            group_threshold,
+            group_count,
+            member_index,
+            member_threshold,
+            value,
+        })
+    }
+
+    fn to_mnemonic(&self) -> SecretString {
+        let id_exp = (u32::from(self.identifier) << 5) | (u32::from(self.extendable) << 4) | u32::from(self.iteration_exponent);
+        let params = (u32::from(self.group_index) << 16)
+            | (u32::from(self.group_threshold - 1) << 12)
+            | (u32::from(self.group_count - 1) << 8)
+            | (u32::from(self.member_index) << 4)
+            | u32::from(self.member_threshold - 1);
+
+        let mut words = Zeroizing::new(vec![(id_exp >> 10) as u16, (id_exp & 0x3ff) as u16, (params >> 10) as u16, (params & 0x3ff) as u16]);
+        words.extend_from_slice(&bytes_to_words(&self.value));
+
+        let padded = Zeroizing::new([&words[..], &[0; CHECKSUM_LENGTH_WORDS]].concat());
+        let checksum = rs1024_polymod(customization_string(self.extendable), &padded) ^ 1;
+        words.extend((0..CHECKSUM_LENGTH_WORDS).map(|i| ((checksum >> (RADIX_BITS * (2 - i))) & 0x3ff) as u16));
+
+        let mut mnemonic = String::with_capacity(words.len() * 9);
+        for (i, word) in words.iter().enumerate() {
+            if i > 0 {
+                mnemonic.push(' ');
+            }
+            mnemonic.push_str(WORDLIST[*word as usize]);
+        }
+        SecretString::new(mnemonic)
+    }
+
+    /// Parameters every share of one backup has in common
+    fn common_parameters(&self) -> (u16, bool, u8, u8, u8) {
+        (self.identifier, self.extendable, self.iteration_exponent, self.group_threshold, self.group_count)
+    }
+}
+
+/// Split `master_secret` into SLIP-39 shares encrypted with `passphrase`
+pub fn split(master_secret: &[u8], passphrase: &SecretString, scheme: &Slip39Scheme) -> ApiResult<Slip39Shares> {
+    let passphrase = check_passphrase(passphrase)?;
+
+    if master_secret.len() < MIN_STRENGTH_BYTES || !master_secret.len().is_multiple_of(2) {
+        return Err(ApiError::BadRequest(format!(
+            "A {}-bit master secret cannot be split; it must be at least 128 bits and a multiple of 16",
+            master_secret.len() * 8
+        )));
+    }
+    if scheme.iteration_exponent > 15 {
+        return Err(ApiError::BadRequest("Iteration exponent must be between 0 and 15".to_string()));
+    }
+    if scheme.groups.is_empty() || scheme.groups.len() > MAX_SHARE_COUNT {
+        return Err(ApiError::BadRequest(format!("Between 1 and {} groups are needed", MAX_SHARE_COUNT)));
+    }
+    if scheme.group_threshold == 0 || usize::from(scheme.group_threshold) > scheme.groups.len() {
+        return Err(ApiError::BadRequest(format!(
+            "Group threshold must be between 1 and the number of groups ({})",
+            scheme.groups.len()
+        )));
+    }
+    for group in &scheme.groups {
+        if group.member_threshold == 0 || group.member_threshold > group.member_count || usize::from(group.member_count) > MAX_SHARE_COUNT {
+            return Err(ApiError::BadRequest(format!(
+                "A group of {} needing {} shares is invalid; groups have 1 to {} shares and a threshold no larger than that",
+                group.member_count, group.member_threshold, MAX_SHARE_COUNT
+            )));
+        }
+        // Every share would be a copy of the group secret
+        if group.member_threshold == 1 && group.member_count > 1 {
+            return Err(ApiError::BadRequest(
+                "A group with member threshold 1 must have exactly 1 share".to_string(),
+            ));
+        }
+    }
+
+    let identifier = rand::thread_rng().gen_range(0..1u16 << ID_LENGTH_BITS);
+    let encrypted = feistel(master_secret, &passphrase, scheme.iteration_exponent, identifier, scheme.extendable, true);
+
+    let group_secrets = split_secret(scheme.group_threshold, scheme.groups.len() as u8, &encrypted)?;
+    let groups = group_secrets
+        .into_iter()
+        .zip(&scheme.groups)
+        .map(|((group_index, group_secret), spec)| {
+            let shares = split_secret(spec.member_threshold, spec.member_count, &group_secret)?
+                .into_iter()
+                .map(|(member_index, value)| {
+                    Share {
+                        identifier,
+                        extendable: scheme.extendable,
+                        iteration_exponent: scheme.iteration_exponent,
+                        group_index,
+                        group_threshold: scheme.group_threshold,
+                        group_count: scheme.groups.len() as u8,
+                        member_index,
+                        member_threshold: spec.member_threshold,
+                        value,
+                    }
+                    .to_mnemonic()
+                })
+                .collect();
+
+            Ok(ShareGroup { member_threshold: spec.member_threshold, shares })
+        })
+        .collect::<ApiResult<_>>()?;
+
+    Ok(Slip39Shares {
+        identifier,
+        extendable: scheme.extendable,
+        iteration_exponent: scheme.iteration_exponent,
+        group_threshold: scheme.group_threshold,
+        groups,
+    })
+}
+
+/// Recover the master secret from shares of one backup. Groups with fewer shares than
+/// their threshold are ignored as long as enough complete groups remain.
+pub fn combine(mnemonics: &[SecretString], passphrase: &SecretString) -> ApiResult<SecretBytes> {
+    let passphrase = check_passphrase(passphrase)?;
+
+    let shares = mnemonics
+        .iter()
+        .map(|mnemonic| Share::from_mnemonic(mnemonic.expose_secret()))
+        .collect::<ApiResult<Vec<_>>>()?;
+    let Some(first) = shares.first() else {
+        return Err(invalid("no shares given".to_string()));
+    };
+
+    if shares.iter().any(|share| share.common_parameters() != first.common_parameters()) {
+        return Err(invalid(
+            "the shares belong to different backups; all must start with the same words and have the same group threshold and count".to_string(),
+        ));
+    }
+    if shares.iter().any(|share| share.value.len() != first.value.len()) {
+        return Err(invalid("the shares have different lengths".to_string()));
+    }
+
+    let mut groups: BTreeMap<u8, Vec<&Share>> = BTreeMap::new();
+    for share in &shares {
+        let group = groups.entry(share.group_index).or_default();
+        if group.iter().any(|other| other.member_threshold != share.member_threshold) {
+            return Err(invalid(format!("the shares of group {} need different numbers of members", share.group_index + 1)));
+        }
+        match group.iter().find(|other| other.member_index == share.member_index) {
+            // The same share given twice
+            Some(other) if other.value == share.value => {}
+            Some(_) => {
+                return Err(invalid(format!(
+                    "group {} has two different shares with member index {}",
+                    share.group_index + 1,
+                    share.member_index + 1
+                )));
+            }
+            None => group.push(share),
+        }
+    }
+
+    if groups.len() < usize::from(first.group_threshold) {
+        return Err(invalid(format!(
+            "shares of {} group(s) given; {} are needed",
+            groups.len(),
+            first.group_threshold
+        )));
+    }
+
+    let group_secrets = groups
+        .into_iter()
+        .filter(|(_, members)| members.len() >= usize::from(members[0].member_threshold))
+        .map(|(group_index, members)| {
+            let values: Vec<_> = members.iter().map(|share| (share.member_index, &share.value[..])).collect();
+            Ok((group_index, recover_secret(members[0].member_threshold, &values)?))
+        })
+        .collect::<ApiResult<Vec<_>>>()?;
+
+    if group_secrets.len() < usize::from(first.group_threshold) {
+        return Err(invalid(format!(
+            "only {} group(s) have enough shares; {} complete groups are needed",
+            group_secrets.len(),
+            first.group_threshold
+        )));
+    }
+
+    let values: Vec<_> = group_secrets.iter().map(|(index, secret)| (*index, &secret[..])).collect();
+    let encrypted = recover_secret(first.group_threshold, &values)?;
+    let master_secret = feistel(&encrypted, &passphrase, first.iteration_exponent, first.identifier, first.extendable, false);
+
+    Ok(SecretBytes::from(&master_secret[..]))
+}
+
+/// A random master secret of `strength` bits
+pub fn generate_master_secret(strength: u32) -> ApiResult<SecretBytes> {
+    if strength < (MIN_STRENGTH_BYTES * 8) as u32 || !strength.is_multiple_of(16) || strength > 512 {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid strength: {} bits. Must be a multiple of 16 between 128 and 512",
+            strength
+        )));
+    }
+
+    let mut secret = Zeroizing::new(vec![0u8; strength as usize / 8]);
+    rand::thread_rng().fill_bytes(&mut secret);
+    Ok(SecretBytes::from(&secret[..]))
+}
+
+fn invalid(reason: String) -> ApiError {
+    ApiError::InvalidShares(reason)
+}
+
+// SLIP-39 only admits printable ASCII, so the passphrase is the same on every device
+fn check_passphrase(passphrase: &SecretString) -> ApiResult<Zeroizing<Vec<u8>>> {
+    let passphrase = passphrase.expose_secret();
+    if !passphrase.bytes().all(|c| (32..=126).contains(&c)) {
+        return Err(ApiError::BadRequest(
+            "SLIP-39 passphrases may only contain printable ASCII characters".to_string(),
+        ));
+    }
+    Ok(Zeroizing::new(passphrase.as_bytes().to_vec()))
+}
+
+/// First three words of a share, which name its backup and group without revealing it
+fn share_prefix(words: &[u16]) -> String {
+    words[..3].iter().map(|word| WORDLIST[*word as usize]).collect::<Vec<_>>().join(" ")
+}
+
+fn join_words(words: &[u16]) -> u32 {
+    words.iter().fold(0, |acc, word| (acc << RADIX_BITS) | u32::from(*word))
+}
+
+fn customization_string(extendable: bool) -> &'static [u8] {
+    if extendable {
+        CUSTOMIZATION_STRING_EXTENDABLE
+    } else {
+        CUSTOMIZATION_STRING
+    }
+}
+
+fn rs1024_polymod(customization: &[u8], words: &[u16]) -> u32 {
+    const GEN: [u32; 10] = [
+        0xe0e040, 0x1c1c080, 0x3838100, 0x7070200, 0xe0e0009, 0x1c0c2412, 0x38086c24, 0x3090fc48, 0x21b1f890, 0x3f3f120,
+    ];
+
+    let values = customization.iter().map(|c| u32::from(*c)).chain(words.iter().map(|w| u32::from(*w)));
+    values.fold(1, |chk, value| {
+        let top = chk >> 20;
+        let chk = ((chk & 0xfffff) << 10) ^ value;
+        (0..10).filter(|i| (top >> i) & 1 == 1).fold(chk, |chk, i| chk ^ GEN[i])
+    })
+}
+
+/// Pack bytes into 10-bit words, zero-padded at the front
+fn bytes_to_words(bytes: &[u8]) -> Zeroizing<Vec<u16>> {
+    let word_count = (bytes.len() * 8).div_ceil(RADIX_BITS);
+    let mut words = Zeroizing::new(Vec::with_capacity(word_count));
+
+    let mut acc = 0u32;
+    let mut bits = word_count * RADIX_BITS - bytes.len() * 8;
+    for byte in bytes {
+        acc = (acc << 8) | u32::from(*byte);
+        bits += 8;
+        if bits >= RADIX_BITS {
+            bits -= RADIX_BITS;
+            words.push(((acc >> bits) & 0x3ff) as u16);
+        }
+    }
+    words
+}
+
+/// Unpack 10-bit words whose first `padding` bits must be zero
+fn words_to_bytes(words: &[u16], padding: usize) -> Option<Zeroizing<Vec<u8>>> {
+    let (first, rest) = words.split_first()?;
+    if first >> (RADIX_BITS - padding) != 0 {
+        return None;
+    }
+
+    let mut bytes = Zeroizing::new(Vec::with_capacity((words.len() * RADIX_BITS - padding) / 8));
+    let mut acc = u32::from(*first);
+    let mut bits = RADIX_BITS - padding;
+    for word in rest {
+        acc = (acc << RADIX_BITS) | u32::from(*word);
+        bits += RADIX_BITS;
+        while bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    Some(bytes)
+}
+
+/// The Feistel network that encrypts (or decrypts) the master secret with the passphrase
+fn feistel(input: &[u8], passphrase: &[u8], iteration_exponent: u8, identifier: u16, extendable: bool, encrypt: bool) -> Zeroizing<Vec<u8>> {
+    let half = input.len() / 2;
+    let mut left = Zeroizing::new(input[..half].to_vec());
+    let mut right = Zeroizing::new(input[half..].to_vec());
+
+    // Extendable backups leave the identifier out, so new shares can get a new one
+    let mut salt_prefix = Vec::new();
+    if !extendable {
+        salt_prefix.extend_from_slice(CUSTOMIZATION_STRING);
+        salt_prefix.extend_from_slice(&identifier.to_be_bytes());
+    }
+    let iterations = (BASE_ITERATION_COUNT << iteration_exponent) / u32::from(ROUND_COUNT);
+
+    let rounds: Vec<u8> = if encrypt { (0..ROUND_COUNT).collect() } else { (0..ROUND_COUNT).rev().collect() };
+    for round in rounds {
+        let mut password = Zeroizing::new(Vec::with_capacity(1 + passphrase.len()));
+        password.push(round);
+        password.extend_from_slice(passphrase);
+
+        let mut salt = Zeroizing::new(salt_prefix.clone());
+        salt.extend_from_slice(&right);
+
+        let mut f = Zeroizing::new(vec![0u8; half]);
+        pbkdf2::pbkdf2_hmac::<Sha256>(&password, &salt, iterations, &mut f);
+
+        for (l, f) in left.iter_mut().zip(f.iter()) {
+            *l ^= f;
+        }
+        std::mem::swap(&mut left, &mut right);
+    }
+
+    let mut output = Zeroizing::new(Vec::with_capacity(input.len()));
+    output.extend_from_slice(&right);
+    output.extend_from_slice(&left);
+    output
+}
+
+/// Logarithm and exponent tables of GF(256) with the Rijndael polynomial, generator 3
+const GF256: ([u8; 255], [u8; 256]) = {
+    let mut exp = [0u8; 255];
+    let mut log = [0u8; 256];
+    let mut poly: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = poly as u8;
+        log[poly as usize] = i as u8;
+        poly = (poly << 1) ^ poly;
+        if poly & 0x100 != 0 {
+            poly ^= 0x11b;
+        }
+        i += 1;
+    }
+    (exp, log)
+};
+
+/// Evaluate at `x` the polynomial through `shares` by Lagrange interpolation
+fn interpolate(shares: &[(u8, &[u8])], x: u8) -> ApiResult<Zeroizing<Vec<u8>>> {
+    let (exp, log) = (&GF256.0, &GF256.1);
+
+    if let Some((_, value)) = shares.iter().find(|(index, _)| *index == x) {
+        return Ok(Zeroizing::new(value.to_vec()));
+    }
+    for (i, (index, _)) in shares.iter().enumerate() {
+        if shares[..i].iter().any(|(other, _)| other == index) {
+            return Err(invalid("share indices must be unique".to_string()));
+        }
+    }
+
+    let log_product: i32 = shares.iter().map(|(index, _)| i32::from(log[usize::from(index ^ x)])).sum();
+    let mut result = Zeroizing::new(vec![0u8; shares[0].1.len()]);
+
+    for (index, value) in shares {
+        let log_others: i32 = shares
+            .iter()
+            .filter(|(other, _)| other != index)
+            .map(|(other, _)| i32::from(log[usize::from(index ^ other)]))
+            .sum();
+        let log_basis = (log_product - i32::from(log[usize::from(index ^ x)]) - log_others).rem_euclid(255);
+
+        for (r, v) in result.iter_mut().zip(value.iter()) {
+            if *v != 0 {
+                *r ^= exp[(i32::from(log[usize::from(*v)]) + log_basis) as usize % 255];
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// First bytes of HMAC-SHA256(random_part, secret), stored with the shares to detect
+/// a wrong set of shares
+fn share_digest(random_part: &[u8], secret: &[u8]) -> [u8; DIGEST_LENGTH_BYTES] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(random_part).expect("HMAC accepts any key length");
+    mac.update(secret);
+    let mut digest = [0u8; DIGEST_LENGTH_BYTES];
+    digest.copy_from_slice(&mac.finalize().into_bytes()[..DIGEST_LENGTH_BYTES]);
+    digest
+}
+
+fn split_secret(threshold: u8, count: u8, secret: &[u8]) -> ApiResult<Vec<(u8, Zeroizing<Vec<u8>>)>> {
+    if threshold == 1 {
+        return Ok((0..count).map(|index| (index, Zeroizing::new(secret.to_vec()))).collect());
+    }
+
+    let mut rng = rand::thread_rng();
+    let random_count = threshold - 2;
+    let mut shares: Vec<(u8, Zeroizing<Vec<u8>>)> = (0..random_count)
+        .map(|index| {
+            let mut value = Zeroizing::new(vec![0u8; secret.len()]);
+            rng.fill_bytes(&mut value);
+            (index, value)
+        })
+        .collect();
+
+    let mut digest_share = Zeroizing::new(vec![0u8; secret.len()]);
+    rng.fill_bytes(&mut digest_share[DIGEST_LENGTH_BYTES..]);
+    let digest = share_digest(&digest_share[DIGEST_LENGTH_BYTES..], secret);
+    digest_share[..DIGEST_LENGTH_BYTES].copy_from_slice(&digest);
+
+    let base: Vec<(u8, &[u8])> = shares
+        .iter()
+        .map(|(index, value)| (*index, &value[..]))
+        .chain([(DIGEST_INDEX, &digest_share[..]), (SECRET_INDEX, secret)])
+        .collect();
+    let derived = (random_count..count)
+        .map(|index| Ok((index, interpolate(&base, index)?)))
+        .collect::<ApiResult<Vec<_>>>()?;
+
+    shares.extend(derived);
+    Ok(shares)
+}
+
+fn recover_secret(threshold: u8, shares: &[(u8, &[u8])]) -> ApiResult<Zeroizing<Vec<u8>>> {
+    if threshold == 1 {
+        return Ok(Zeroizing::new(shares[0].1.to_vec()));
+    }
+
+    let secret = interpolate(shares, SECRET_INDEX)?;
+    let digest_share = interpolate(shares, DIGEST_INDEX)?;
+    if share_digest(&digest_share[DIGEST_LENGTH_BYTES..], &secret) != digest_share[..DIGEST_LENGTH_BYTES] {
+        return Err(invalid("the shares do not fit together; the digest of the shared secret is wrong".to_string()));
+    }
+
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The SLIP-0039 reference implementation's `vectors.json`: `[description, mnemonics,
+    /// master secret]`, later revisions adding the BIP32 master xprv as a fourth column.
+    /// Every share is encrypted with the passphrase "TREZOR" and an empty secret marks an
+    /// invalid set.
+    const VECTORS: &str = include_str!("slip39_vectors.json");
+
+    /// Reference cases for extendable backups, which postdate the vectors file above
+    const EXTENDABLE_VECTORS: &str = r#"[
+      [
+        "Valid extendable mnemonic without sharing (128 bits)",
+        ["testify swimming academic academic column loyalty smear include exotic bedroom exotic wrist lobe cover grief golden smart junior estimate learn"],
+        "1679b4516e0ee5954351d288a838f45e"
+      ]
+    ]"#;
+
+    #[test]
+    fn test_reference_vectors() {
+        let passphrase = SecretString::from("TREZOR");
+
+        for vectors in [VECTORS, EXTENDABLE_VECTORS] {
+            let vectors: Vec<Vec<serde_json::Value>> = serde_json::from_str(vectors).unwrap();
+            for vector in vectors {
+                let description = vector[0].as_str().unwrap();
+                let mnemonics: Vec<SecretString> = vector[1]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|mnemonic| SecretString::from(mnemonic.as_str().unwrap()))
+                    .collect();
+                let secret = vector[2].as_str().unwrap();
+                let xprv = vector.get(3).and_then(|xprv| xprv.as_str()).unwrap_or_default();
+
+                match combine(&mnemonics, &passphrase) {
+                    Ok(recovered) => {
+                        assert_eq!(hex::encode(recovered.expose_secret()), secret, "{}", description);
+                        if !xprv.is_empty() {
+                            let master = bitcoin::bip32::Xpriv::new_master(bitcoin::Network::Bitcoin, recovered.expose_secret()).unwrap();
+                            assert_eq!(master.to_string(), xprv, "{}", description);
+                        }
+                    }
+                    Err(e) => assert!(secret.is_empty(), "{}: {}", description, e),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_and_combine_groups() {
+        let master_secret = hex::decode("bb54aac4b89dc868ba37d9cc21b2cece").unwrap();
+        let passphrase = SecretString::from("TREZOR");
+        let scheme = Slip39Scheme {
+            group_threshold: 2,
+            groups: vec![
+                GroupSpec { member_threshold: 1, member_count: 1 },
+                GroupSpec { member_threshold: 2, member_count: 3 },
+                GroupSpec { member_threshold: 3, member_count: 5 },
+            ],
+            iteration_exponent: 0,
+            extendable: false,
+        };
+
+        let backup = split(&master_secret, &passphrase, &scheme).unwrap();
+        assert_eq!(backup.groups.len(), 3);
+        assert!(backup.groups[2].shares.iter().all(|share| share.expose_secret().split(' ').count() == 20));
+
+        // The 1-of-1 group and two members of the 2-of-3 group
+        let shares = vec![backup.groups[0].shares[0].clone(), backup.groups[1].shares[2].clone(), backup.groups[1].shares[0].clone()];
+        assert_eq!(combine(&shares, &passphrase).unwrap().expose_secret(), &master_secret[..]);
+
+        // An incomplete third group is ignored
+        let mut with_partial = shares.clone();
+        with_partial.push(backup.groups[2].shares[4].clone());
+        assert_eq!(combine(&with_partial, &passphrase).unwrap().expose_secret(), &master_secret[..]);
+
+        // One complete group is not enough, and another passphrase gives another secret
+        assert!(combine(&shares[1..], &passphrase).is_err());
+        let other = combine(&shares, &SecretString::from("")).unwrap();
+        assert_ne!(other.expose_secret(), &master_secret[..]);
+    }
+
+    #[test]
+    fn test_word_packing_round_trip() {
+        for length in [16, 32, 64] {
+            let bytes: Vec<u8> = (0..length as u8).map(|b| b.wrapping_mul(37)).collect();
+            let words = bytes_to_words(&bytes);
+            let padding = words.len() * RADIX_BITS - length * 8;
+            assert_eq!(&words_to_bytes(&words, padding).unwrap()[..], &bytes[..]);
+        }
+    }
+}
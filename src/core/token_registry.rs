@@ -1,6 +1,6 @@
-/// Complete Unified Token Registry
-/// This module contains ALL tokens from the unified registry properly categorized
-/// and mapped to their respective chains
+//! Complete Unified Token Registry
+//! This module contains ALL tokens from the unified registry properly categorized
+//! and mapped to their respective chains
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -44,6 +44,12 @@ pub struct CompleteTokenRegistry {
     tokens: HashMap<String, UnifiedToken>,
 }
 
+impl Default for CompleteTokenRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CompleteTokenRegistry {
     pub fn new() -> Self {
         let mut registry = CompleteTokenRegistry {
@@ -84,6 +90,16 @@ impl CompleteTokenRegistry {
                     is_native: true,
                     token_standard: "Native".to_string(),
                 },
+                ChainDeployment {
+                    chain: "Bitcoin".to_string(),
+                    chain_type: "BitcoinNestedSegwit".to_string(),
+                    chain_id: None,
+                    contract_address: None,
+                    decimals: 8,
+                    symbol: "BTC".to_string(),
+                    is_native: true,
+                    token_standard: "Native".to_string(),
+                },
                 ChainDeployment {
                     chain: "Bitcoin".to_string(),
                     chain_type: "BitcoinSegwit".to_string(),
@@ -369,6 +385,7 @@ impl CompleteTokenRegistry {
         self.add_native_token("SUI", "Sui", 20947, "sui", "Sui", 9);
         self.add_native_token("XRP", "XRP", 52, "ripple", "Ripple", 6);
         self.add_native_token("XTZ", "Tezos", 2011, "tezos", "Tezos", 6);
+        self.add_native_token("XLM", "Stellar", 512, "stellar", "Stellar", 7);
         self.add_native_token("LTC", "Litecoin", 2, "litecoin", "Litecoin", 8);
         self.add_native_token("BCH", "Bitcoin Cash", 1831, "bitcoin-cash", "BitcoinCash", 8);
         self.add_native_token("ADA", "Cardano", 2010, "cardano", "Cardano", 6);
@@ -963,15 +980,9 @@ impl CompleteTokenRegistry {
         });
     }
     
+    #[allow(clippy::too_many_arguments)]
     fn add_single_chain_token(&mut self, symbol: &str, name: &str, cmc_id: u32, coingecko: &str, chain: &str, address: &str, decimals: u8) {
-        let chain_type = match chain {
-            "Ethereum" => "Ethereum",
-            "Base" => "Base",
-            "Arbitrum" => "Arbitrum",
-            "Optimism" => "Optimism",
-            "Polygon" => "Polygon",
-            _ => chain,
-        };
+        let chain_type = chain;
         
         self.add_token(UnifiedToken {
             base_symbol: symbol.to_string(),
@@ -1085,7 +1096,7 @@ impl CompleteTokenRegistry {
 pub type TokenRegistry = CompleteTokenRegistry;
 
 // Global registry instance
-static REGISTRY: Lazy<TokenRegistry> = Lazy::new(|| TokenRegistry::new());
+static REGISTRY: Lazy<TokenRegistry> = Lazy::new(TokenRegistry::new);
 
 // Function to get the global registry
 pub fn get_token_registry() -> &'static TokenRegistry {
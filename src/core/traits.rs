@@ -1,23 +1,83 @@
 use async_trait::async_trait;
-use crate::core::types::{WalletAddress, DerivationPath};
+use bitcoin::secp256k1::{self, Secp256k1};
+use ed25519_dalek::SigningKey;
+use std::collections::BTreeMap;
+use crate::core::types::{WalletAddress, DerivationPath, AddressLevel, ExtendedPublicKey, KeyFormat, Curve, ImportedKey};
 use crate::core::chain_info::ChainInfo;
-use crate::errors::ApiResult;
+use crate::core::key_tree::KeyTree;
+use crate::core::secret::{decode_hex_key, SecretString};
+use crate::errors::{ApiError, ApiResult};
 
 #[async_trait]
 pub trait Chain: Send + Sync {
     /// Get chain information
     fn info(&self) -> ChainInfo;
     
-    /// Generate a wallet address from seed
-    async fn generate_address(
+    /// Generate a wallet address from the request's seed-derived keys.
+    /// Derivation is CPU-bound and synchronous so callers can run it on a blocking pool.
+    /// `path` overrides the chain's default derivation path for `account`/`index`;
+    /// chains reject paths their curve cannot derive with `InvalidDerivationPath`.
+    /// The private key is encoded in `key_format`; with `None` it is never encoded at all.
+    fn generate_address(
         &self,
-        seed: &[u8],
+        keys: &KeyTree,
         passphrase: &str,
+        account: u32,
         index: u32,
+        path: Option<&DerivationPath>,
+        key_format: Option<KeyFormat>,
     ) -> ApiResult<WalletAddress>;
     
-    /// Get the derivation path for this chain
-    fn derivation_path(&self, index: u32) -> DerivationPath;
+    /// Private key encoding used when a request does not ask for one
+    fn default_key_format(&self) -> KeyFormat {
+        KeyFormat::Hex
+    }
+    
+    /// Get the derivation path for this chain.
+    /// Chains whose `address_level` is `Account` ignore `index`.
+    fn derivation_path(&self, account: u32, index: u32) -> DerivationPath;
+    
+    /// Which level varies from one address to the next
+    fn address_level(&self) -> AddressLevel {
+        AddressLevel::Index
+    }
+    
+    /// Export the account-level extended public key for watch-only use.
+    /// Only BIP32 secp256k1 chains have one; ed25519 (SLIP-0010) has no public derivation.
+    fn account_xpub(&self, _keys: &KeyTree, _account: u32) -> ApiResult<ExtendedPublicKey> {
+        Err(ApiError::BadRequest(format!(
+            "{} does not support extended public keys",
+            self.info().name
+        )))
+    }
+    
+    /// Compute the address for a SEC1-encoded secp256k1 public key, so watch-only
+    /// wallets can derive addresses from an extended public key without any secret.
+    /// ed25519 chains derive only hardened children and cannot support this.
+    fn address_from_public_key(&self, _public_key: &[u8]) -> ApiResult<String> {
+        Err(ApiError::BadRequest(format!(
+            "{} does not support watch-only derivation: ed25519 (SLIP-0010) has no public child derivation",
+            self.info().name
+        )))
+    }
+    
+    /// Address and public key controlled by a raw private key in hex or the chain's
+    /// native format (see `KeyFormat::Native`). The default accepts hex secp256k1 keys;
+    /// chains with a native format or another curve override it.
+    fn address_from_private_key(&self, private_key: &SecretString) -> ApiResult<ImportedKey> {
+        let key = decode_hex_key(private_key.expose_secret())
+            .ok_or_else(|| ApiError::InvalidPrivateKey(self.info().name))?;
+        let secret_key = secp256k1::SecretKey::from_slice(&key[..])
+            .map_err(|_| ApiError::InvalidPrivateKey(self.info().name))?;
+        
+        import_secp256k1_key(self, &secret_key, true, KeyFormat::Hex)
+    }
+    
+    /// Encodings of a public key (as returned in `public_key`) that the chain's own
+    /// tooling uses in place of hex, keyed by format name
+    fn public_key_formats(&self, _public_key: &[u8]) -> ApiResult<BTreeMap<String, String>> {
+        Ok(BTreeMap::new())
+    }
     
     /// Validate an address for this chain
     async fn validate_address(&self, address: &str) -> bool;
@@ -26,4 +86,56 @@ pub trait Chain: Send + Sync {
     fn example_address(&self) -> &str {
         "example_address"
     }
-}
\ No newline at end of file
+}
+
+/// Build the import result for a secp256k1 key through the chain's public-key hashing.
+/// Uncompressed keys hash the 65-byte public key.
+pub fn import_secp256k1_key<C: Chain + ?Sized>(
+    chain: &C,
+    secret_key: &secp256k1::SecretKey,
+    compressed: bool,
+    key_format: KeyFormat,
+) -> ApiResult<ImportedKey> {
+    let public_key = secret_key.public_key(&Secp256k1::signing_only());
+    let public_key = if compressed {
+        public_key.serialize().to_vec()
+    } else {
+        public_key.serialize_uncompressed().to_vec()
+    };
+    
+    Ok(ImportedKey {
+        address: chain.address_from_public_key(&public_key)?,
+        chain_info: chain.info(),
+        public_key: hex::encode(&public_key),
+        public_key_formats: chain.public_key_formats(&public_key)?,
+        curve: Curve::Secp256k1,
+        compressed: Some(compressed),
+        key_format,
+    })
+}
+
+/// Signing key from a 64-byte `secret || public` keypair, as Solana and NEAR wallets
+/// store it. `None` if the length is wrong or the public half does not match.
+pub fn ed25519_keypair(keypair: &[u8]) -> Option<SigningKey> {
+    SigningKey::from_keypair_bytes(keypair.try_into().ok()?).ok()
+}
+
+/// Build the import result for an ed25519 key whose address the chain has computed
+pub fn import_ed25519_key<C: Chain + ?Sized>(
+    chain: &C,
+    signing_key: &SigningKey,
+    address: String,
+    key_format: KeyFormat,
+) -> ApiResult<ImportedKey> {
+    let public_key = signing_key.verifying_key();
+    
+    Ok(ImportedKey {
+        address,
+        chain_info: chain.info(),
+        public_key: hex::encode(public_key.as_bytes()),
+        public_key_formats: chain.public_key_formats(public_key.as_bytes())?,
+        curve: Curve::Ed25519,
+        compressed: None,
+        key_format,
+    })
+}
@@ -1,66 +1,515 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use bitcoin::bip32::{ChildNumber, DerivationPath as BtcDerivationPath};
+
 use crate::core::chain_info::{ChainInfo, ChainType};
+use crate::core::secret::SecretString;
+use crate::errors::{ApiError, ApiResult};
+
+/// Highest child number allowed before the hardened bit (2^31)
+const HARDENED_OFFSET: u32 = 0x8000_0000;
 
+/// `private_key` is redacted from `Debug` output and wiped on drop; it is `None`
+/// when the request or the server policy excludes private keys
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WalletAddress {
     pub address: String,
     pub chain_type: ChainType,
     pub chain_info: ChainInfo,
     pub derivation_path: String,
+    pub account: u32,
     pub index: u32,
     pub public_key: String,
-    pub private_key: String,
+    /// Chain-native public key encodings, e.g. `"bech32"` for Cosmos SDK chains
+    pub public_key_formats: BTreeMap<String, String>,
+    pub private_key: Option<SecretString>,
 }
 
-#[derive(Debug, Clone)]
-pub struct DerivationPath {
-    pub purpose: u32,
-    pub coin_type: u32,
+/// Account-level extended public key for watch-only wallets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtendedPublicKey {
+    pub chain_type: ChainType,
+    pub chain_info: ChainInfo,
+    /// Base58check key with the chain's SLIP-0132 prefix (xpub, zpub, dgub, ...)
+    pub extended_public_key: String,
+    /// The prefix used, e.g. "zpub"
+    pub format: String,
+    /// First 4 bytes of HASH160 of the master public key, hex
+    pub master_fingerprint: String,
+    /// Path of the account node, e.g. `m/84'/0'/0'`
+    pub derivation_path: String,
+    /// Key origin as used in output descriptors, e.g. `[73c5da0a/84'/0'/0']`
+    pub key_origin: String,
     pub account: u32,
+    /// BIP380 descriptors of the receive and change addresses; Bitcoin only
+    pub receive_descriptor: Option<String>,
+    pub change_descriptor: Option<String>,
+}
+
+/// Address derived from an extended public key; there is no private key to return
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchOnlyAddress {
+    pub address: String,
+    pub chain_type: ChainType,
+    pub chain_info: ChainInfo,
+    /// Path below the extended key, e.g. `0/5`
+    pub relative_path: String,
     pub change: u32,
     pub index: u32,
+    pub public_key: String,
+    pub public_key_formats: BTreeMap<String, String>,
+}
+
+/// Elliptic curve a chain's keys live on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Curve {
+    Secp256k1,
+    Ed25519,
+}
+
+impl fmt::Display for Curve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Curve::Secp256k1 => write!(f, "secp256k1"),
+            Curve::Ed25519 => write!(f, "ed25519"),
+        }
+    }
+}
+
+/// What a raw private key controls on one chain, derived without any mnemonic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportedKey {
+    pub address: String,
+    pub chain_info: ChainInfo,
+    pub public_key: String,
+    pub public_key_formats: BTreeMap<String, String>,
+    pub curve: Curve,
+    /// Whether the address commits to the compressed public key (secp256k1 only).
+    /// Uncompressed WIF keys produce different legacy addresses.
+    pub compressed: Option<bool>,
+    /// The encoding the private key was given in
+    pub key_format: KeyFormat,
+}
+
+/// Which BIP44 level a chain's wallets step through to produce new addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressLevel {
+    /// `m/purpose'/coin'/account'/change/index`: one account holds many addresses
+    Index,
+    /// Every address is its own account (Solana, NEAR, Stellar), so there is no
+    /// separate address index below the account level
+    Account,
+}
+
+/// How a chain encodes the private keys it returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyFormat {
+    /// Raw 32-byte key as lowercase hex
+    Hex,
+    /// What the chain's own wallets and CLIs import: WIF, `edsk...`, `suiprivkey1...`, ...
+    Native,
+}
+
+impl FromStr for KeyFormat {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> ApiResult<Self> {
+        match s.to_lowercase().as_str() {
+            "hex" => Ok(KeyFormat::Hex),
+            "native" => Ok(KeyFormat::Native),
+            _ => Err(ApiError::BadRequest(format!("Invalid key_format: {}. Must be hex or native", s))),
+        }
+    }
+}
+
+impl fmt::Display for KeyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyFormat::Hex => write!(f, "hex"),
+            KeyFormat::Native => write!(f, "native"),
+        }
+    }
+}
+
+/// Which network Bitcoin and Dogecoin addresses, keys and extended keys are encoded
+/// for. Test networks use BIP44 coin type 1. Other chains use the same addresses on
+/// their test networks and ignore it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkType {
+    #[default]
+    Mainnet,
+    Testnet,
+    Signet,
+    Regtest,
+}
+
+impl NetworkType {
+    pub const ALL: [NetworkType; 4] = [NetworkType::Mainnet, NetworkType::Testnet, NetworkType::Signet, NetworkType::Regtest];
+
+    /// The `bitcoin` crate's network, which selects address and key prefixes
+    pub fn bitcoin_network(self) -> bitcoin::Network {
+        match self {
+            NetworkType::Mainnet => bitcoin::Network::Bitcoin,
+            NetworkType::Testnet => bitcoin::Network::Testnet,
+            NetworkType::Signet => bitcoin::Network::Signet,
+            NetworkType::Regtest => bitcoin::Network::Regtest,
+        }
+    }
+}
+
+impl FromStr for NetworkType {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> ApiResult<Self> {
+        match s.to_lowercase().as_str() {
+            "mainnet" => Ok(NetworkType::Mainnet),
+            "testnet" => Ok(NetworkType::Testnet),
+            "signet" => Ok(NetworkType::Signet),
+            "regtest" => Ok(NetworkType::Regtest),
+            _ => Err(ApiError::BadRequest(format!(
+                "Invalid network: {}. Must be mainnet, testnet, signet or regtest",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for NetworkType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkType::Mainnet => write!(f, "mainnet"),
+            NetworkType::Testnet => write!(f, "testnet"),
+            NetworkType::Signet => write!(f, "signet"),
+            NetworkType::Regtest => write!(f, "regtest"),
+        }
+    }
+}
+
+/// Script a sorted multisig wallet's addresses pay to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MultisigScriptType {
+    /// Native SegWit, `bc1q...` addresses
+    #[default]
+    P2wsh,
+    /// SegWit wrapped in P2SH, `3...` addresses
+    P2shP2wsh,
+}
+
+impl FromStr for MultisigScriptType {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> ApiResult<Self> {
+        match s.to_lowercase().as_str() {
+            "p2wsh" => Ok(MultisigScriptType::P2wsh),
+            "p2sh_p2wsh" => Ok(MultisigScriptType::P2shP2wsh),
+            _ => Err(ApiError::BadRequest(format!(
+                "Invalid multisig script type: {}. Must be p2wsh or p2sh_p2wsh",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for MultisigScriptType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultisigScriptType::P2wsh => write!(f, "p2wsh"),
+            MultisigScriptType::P2shP2wsh => write!(f, "p2sh_p2wsh"),
+        }
+    }
+}
+
+/// Output a spending policy is compiled to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyScriptType {
+    /// One witness script holding the whole policy
+    #[default]
+    P2wsh,
+    /// A key path plus one script leaf per alternative spending condition
+    Taproot,
+}
+
+impl FromStr for PolicyScriptType {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> ApiResult<Self> {
+        match s.to_lowercase().as_str() {
+            "p2wsh" => Ok(PolicyScriptType::P2wsh),
+            "taproot" => Ok(PolicyScriptType::Taproot),
+            _ => Err(ApiError::BadRequest(format!(
+                "Invalid policy script type: {}. Must be p2wsh or taproot",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for PolicyScriptType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyScriptType::P2wsh => write!(f, "p2wsh"),
+            PolicyScriptType::Taproot => write!(f, "taproot"),
+        }
+    }
+}
+
+/// Encoding of a signed message proving control of an address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageFormat {
+    /// 65-byte recoverable ECDSA signature with a header naming the address type
+    Bip137,
+    /// BIP322 witness stack
+    Bip322Simple,
+    /// Whole BIP322 `to_sign` transaction
+    Bip322Full,
+}
+
+impl FromStr for MessageFormat {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> ApiResult<Self> {
+        match s.to_lowercase().as_str() {
+            "bip137" => Ok(MessageFormat::Bip137),
+            "bip322_simple" => Ok(MessageFormat::Bip322Simple),
+            "bip322_full" => Ok(MessageFormat::Bip322Full),
+            _ => Err(ApiError::BadRequest(format!(
+                "Invalid message signature format: {}. Must be bip137, bip322_simple or bip322_full",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for MessageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageFormat::Bip137 => write!(f, "bip137"),
+            MessageFormat::Bip322Simple => write!(f, "bip322_simple"),
+            MessageFormat::Bip322Full => write!(f, "bip322_full"),
+        }
+    }
+}
+
+/// A single level of a BIP32 derivation path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChildIndex {
+    Normal(u32),
+    Hardened(u32),
+}
+
+impl ChildIndex {
+    pub fn is_hardened(&self) -> bool {
+        matches!(self, ChildIndex::Hardened(_))
+    }
+
+    /// The index without the hardened bit
+    pub fn value(&self) -> u32 {
+        match self {
+            ChildIndex::Normal(i) | ChildIndex::Hardened(i) => *i,
+        }
+    }
+}
+
+impl fmt::Display for ChildIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChildIndex::Normal(i) => write!(f, "{}", i),
+            ChildIndex::Hardened(i) => write!(f, "{}'", i),
+        }
+    }
+}
+
+/// A BIP32 derivation path of arbitrary depth, e.g. `m/44'/60'/0'/0/7`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DerivationPath {
+    components: Vec<ChildIndex>,
 }
 
 impl DerivationPath {
+    /// Standard BIP44 layout: `m/purpose'/coin_type'/account'/change/index`
     pub fn new(purpose: u32, coin_type: u32, account: u32, change: u32, index: u32) -> Self {
         Self {
-            purpose,
-            coin_type,
-            account,
-            change,
-            index,
-        }
-    }
-
-    pub fn to_string(&self) -> String {
-        format!(
-            "m/{}'/{}'/{}'/{}/{}",
-            self.purpose,
-            self.coin_type,
-            self.account,
-            self.change,
-            self.index
-        )
-    }
-
-    // For chains that use hardened derivation on all levels
-    pub fn to_string_all_hardened(&self) -> String {
-        format!(
-            "m/{}'/{}'/{}'/{}'/{}'",
-            self.purpose,
-            self.coin_type,
-            self.account,
-            self.change,
-            self.index
-        )
-    }
-
-    // For chains with custom paths
-    pub fn to_string_custom(&self, format: &str) -> String {
-        format.replace("{purpose}", &self.purpose.to_string())
-            .replace("{coin_type}", &self.coin_type.to_string())
-            .replace("{account}", &self.account.to_string())
-            .replace("{change}", &self.change.to_string())
-            .replace("{index}", &self.index.to_string())
-    }
-}
\ No newline at end of file
+            components: vec![
+                ChildIndex::Hardened(purpose),
+                ChildIndex::Hardened(coin_type),
+                ChildIndex::Hardened(account),
+                ChildIndex::Normal(change),
+                ChildIndex::Normal(index),
+            ],
+        }
+    }
+
+    // For chains that use hardened derivation on all levels (SLIP-0010 ed25519)
+    pub fn hardened(levels: &[u32]) -> Self {
+        Self {
+            components: levels.iter().map(|&i| ChildIndex::Hardened(i)).collect(),
+        }
+    }
+
+    pub fn components(&self) -> &[ChildIndex] {
+        &self.components
+    }
+
+    /// The ancestor at `depth`, e.g. depth 3 of `m/84'/0'/0'/0/5` is the account node `m/84'/0'/0'`
+    pub fn truncated(&self, depth: usize) -> Self {
+        Self {
+            components: self.components.iter().take(depth).copied().collect(),
+        }
+    }
+
+    /// Parse a path that may contain `{account}`/`{index}` placeholders, substituting them first.
+    /// Lets batch requests describe one path per address, e.g. `m/44'/60'/{account}'/0/{index}`.
+    pub fn from_template(template: &str, account: u32, index: u32) -> ApiResult<Self> {
+        template
+            .replace("{account}", &account.to_string())
+            .replace("{index}", &index.to_string())
+            .parse()
+    }
+
+    /// Convert to the `bitcoin` crate's path for secp256k1 BIP32 derivation
+    pub fn to_bip32(&self) -> ApiResult<BtcDerivationPath> {
+        let children = self
+            .components
+            .iter()
+            .map(|c| match c {
+                ChildIndex::Normal(i) => ChildNumber::from_normal_idx(*i),
+                ChildIndex::Hardened(i) => ChildNumber::from_hardened_idx(*i),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ApiError::InvalidDerivationPath(format!("{}: {}", self, e)))?;
+
+        Ok(BtcDerivationPath::from(children))
+    }
+
+    /// Raw child indices (hardened bit set) for SLIP-0010 ed25519, which only
+    /// defines hardened derivation
+    pub fn hardened_indices(&self) -> ApiResult<Vec<u32>> {
+        self.components
+            .iter()
+            .map(|c| match c {
+                ChildIndex::Hardened(i) if *i < HARDENED_OFFSET => Ok(i | HARDENED_OFFSET),
+                ChildIndex::Hardened(i) => Err(ApiError::InvalidDerivationPath(format!(
+                    "{}: index {} is out of range",
+                    self, i
+                ))),
+                ChildIndex::Normal(i) => Err(ApiError::InvalidDerivationPath(format!(
+                    "{}: ed25519 (SLIP-0010) only supports hardened levels, got {}",
+                    self, i
+                ))),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for component in &self.components {
+            write!(f, "/{}", component)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = ApiError;
+
+    /// Accepts `m/44'/60'/0'/0/7`; `'`, `h` and `H` all mark a hardened level
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| ApiError::InvalidDerivationPath(format!("{}: {}", s, reason));
+
+        let mut parts = s.trim().split('/');
+        match parts.next() {
+            Some("m") | Some("M") => {}
+            _ => return Err(invalid("path must start with 'm'")),
+        }
+
+        let mut components = Vec::new();
+        for part in parts {
+            let (digits, hardened) = match part.strip_suffix(['\'', 'h', 'H']) {
+                Some(digits) => (digits, true),
+                None => (part, false),
+            };
+
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid(&format!("invalid level '{}'", part)));
+            }
+
+            let value: u32 = digits
+                .parse()
+                .map_err(|_| invalid(&format!("level '{}' is out of range", part)))?;
+            if value >= HARDENED_OFFSET {
+                return Err(invalid(&format!("level '{}' is out of range", part)));
+            }
+
+            components.push(if hardened {
+                ChildIndex::Hardened(value)
+            } else {
+                ChildIndex::Normal(value)
+            });
+        }
+
+        Ok(Self { components })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_derivation_path() {
+        let path: DerivationPath = "m/44'/60'/3'/0/7".parse().unwrap();
+        assert_eq!(path, DerivationPath::new(44, 60, 3, 0, 7));
+        assert_eq!(path.to_string(), "m/44'/60'/3'/0/7");
+
+        let path: DerivationPath = "m/44h/501H/0'/0'".parse().unwrap();
+        assert_eq!(path, DerivationPath::hardened(&[44, 501, 0, 0]));
+
+        let path: DerivationPath = "m".parse().unwrap();
+        assert!(path.components().is_empty());
+    }
+
+    #[test]
+    fn test_parse_invalid_derivation_path() {
+        for path in ["", "44'/0'", "m/", "m//0", "m/-1", "m/1''", "m/abc", "m/2147483648", "m/0/x'"] {
+            assert!(
+                matches!(path.parse::<DerivationPath>(), Err(ApiError::InvalidDerivationPath(_))),
+                "{} should be rejected",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_path_template() {
+        let path = DerivationPath::from_template("m/44'/501'/{index}'/0'", 0, 4).unwrap();
+        assert_eq!(path.to_string(), "m/44'/501'/4'/0'");
+
+        let path = DerivationPath::from_template("m/84'/0'/{account}'/1/{index}", 2, 9).unwrap();
+        assert_eq!(path.to_string(), "m/84'/0'/2'/1/9");
+    }
+
+    #[test]
+    fn test_hardened_indices() {
+        let path = DerivationPath::hardened(&[44, 148, 0]);
+        assert_eq!(path.hardened_indices().unwrap(), vec![0x8000_002C, 0x8000_0094, 0x8000_0000]);
+
+        assert!(DerivationPath::new(44, 148, 0, 0, 0).hardened_indices().is_err());
+        assert!(DerivationPath::hardened(&[HARDENED_OFFSET]).hardened_indices().is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_index_does_not_panic() {
+        let path = DerivationPath::new(44, 0, 0, 0, HARDENED_OFFSET);
+        assert!(matches!(path.to_bip32(), Err(ApiError::InvalidDerivationPath(_))));
+    }
+}
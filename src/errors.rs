@@ -13,9 +13,21 @@ pub enum ApiError {
     #[error("Invalid mnemonic phrase")]
     InvalidMnemonic,
 
+    #[error("Invalid entropy: {0}")]
+    InvalidEntropy(String),
+
+    #[error("Mnemonic words exist in several wordlists ({0}); set language to one of them")]
+    AmbiguousMnemonicLanguage(String),
+
+    #[error("Invalid SLIP-39 shares: {0}")]
+    InvalidShares(String),
+
     #[error("Invalid derivation path: {0}")]
     InvalidDerivationPath(String),
 
+    #[error("Invalid private key for {0}: expected 32-byte hex or the chain's native format")]
+    InvalidPrivateKey(String),
+
     #[error("Cryptographic operation failed: {0}")]
     CryptoError(String),
 
@@ -24,6 +36,9 @@ pub enum ApiError {
 
     #[error("Bad request: {0}")]
     BadRequest(String),
+
+    #[error("Private key export is disabled on this server")]
+    PrivateKeyExportDisabled,
 }
 
 impl ResponseError for ApiError {
@@ -45,8 +60,13 @@ impl ResponseError for ApiError {
             ApiError::InvalidWordCount(_) => StatusCode::BAD_REQUEST,
             ApiError::InvalidLanguage(_) => StatusCode::BAD_REQUEST,
             ApiError::InvalidMnemonic => StatusCode::BAD_REQUEST,
+            ApiError::InvalidEntropy(_) => StatusCode::BAD_REQUEST,
+            ApiError::AmbiguousMnemonicLanguage(_) => StatusCode::BAD_REQUEST,
+            ApiError::InvalidShares(_) => StatusCode::BAD_REQUEST,
             ApiError::InvalidDerivationPath(_) => StatusCode::BAD_REQUEST,
+            ApiError::InvalidPrivateKey(_) => StatusCode::BAD_REQUEST,
             ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::PrivateKeyExportDisabled => StatusCode::FORBIDDEN,
             ApiError::CryptoError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -59,12 +79,29 @@ impl ApiError {
             ApiError::InvalidWordCount(_) => "INVALID_WORD_COUNT",
             ApiError::InvalidLanguage(_) => "INVALID_LANGUAGE",
             ApiError::InvalidMnemonic => "INVALID_MNEMONIC",
+            ApiError::InvalidEntropy(_) => "INVALID_ENTROPY",
+            ApiError::AmbiguousMnemonicLanguage(_) => "AMBIGUOUS_MNEMONIC_LANGUAGE",
+            ApiError::InvalidShares(_) => "INVALID_SLIP39_SHARES",
             ApiError::InvalidDerivationPath(_) => "INVALID_DERIVATION_PATH",
+            ApiError::InvalidPrivateKey(_) => "INVALID_PRIVATE_KEY",
             ApiError::BadRequest(_) => "BAD_REQUEST",
+            ApiError::PrivateKeyExportDisabled => "PRIVATE_KEY_EXPORT_DISABLED",
             ApiError::CryptoError(_) => "CRYPTO_ERROR",
             ApiError::InternalError => "INTERNAL_ERROR",
         }
     }
 }
 
+// Client mistakes surface as INVALID_ARGUMENT over gRPC, policy refusals as
+// PERMISSION_DENIED, everything else as INTERNAL
+impl From<ApiError> for tonic::Status {
+    fn from(err: ApiError) -> Self {
+        match err.status_code() {
+            StatusCode::BAD_REQUEST => tonic::Status::invalid_argument(err.to_string()),
+            StatusCode::FORBIDDEN => tonic::Status::permission_denied(err.to_string()),
+            _ => tonic::Status::internal(err.to_string()),
+        }
+    }
+}
+
 pub type ApiResult<T> = Result<T, ApiError>;
\ No newline at end of file
@@ -1,6 +1,5 @@
 use tonic::{Request, Response, Status};
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use tracing::info;
 
 use super::auth::check_auth;
@@ -8,20 +7,77 @@ use super::wallet_proto::{
     mnemonic_service_server::MnemonicService,
     GenerateMnemonicRequest, GenerateMnemonicResponse,
     ValidateMnemonicRequest, ValidateMnemonicResponse,
+    AssistMnemonicRequest, AssistMnemonicResponse, InvalidWord,
     GetSupportedLanguagesRequest, GetSupportedLanguagesResponse,
+    EntropyToMnemonicRequest, EntropyToMnemonicResponse,
+    MnemonicToEntropyRequest, MnemonicToEntropyResponse,
+    MnemonicToSeedRequest, MnemonicToSeedResponse,
+    SplitSlip39Request, SplitSlip39Response, Slip39ShareGroup,
+    CombineSlip39Request, CombineSlip39Response,
+    DeriveBip85Request, DeriveBip85Response,
     LanguageInfo,
 };
+use super::secrets::take_secrets;
+use crate::core::bip85::Bip85Application;
+use crate::core::mnemonic::{language_code, parse_language};
+use crate::core::slip39::{GroupSpec, Slip39Scheme};
+use crate::core::{EntropySource, SecretString};
 use crate::services::wallet::WalletService;
 
 pub struct MnemonicServiceImpl {
-    wallet_service: Arc<Mutex<WalletService>>,
+    wallet_service: Arc<WalletService>,
 }
 
 impl MnemonicServiceImpl {
-    pub fn new(wallet_service: Arc<Mutex<WalletService>>) -> Self {
+    pub fn new(wallet_service: Arc<WalletService>) -> Self {
         Self { wallet_service }
     }
     
+    // SLIP-39 thresholds, counts and exponents all fit in a byte
+    #[allow(clippy::result_large_err)]
+    fn small(value: u32, name: &str) -> Result<u8, Status> {
+        u8::try_from(value).map_err(|_| Status::invalid_argument(format!("{} {} is out of range", name, value)))
+    }
+    
+    // Unset fields take the reference implementation's defaults
+    #[allow(clippy::result_large_err)]
+    fn slip39_scheme(req: &SplitSlip39Request) -> Result<Slip39Scheme, Status> {
+        let defaults = Slip39Scheme::default();
+        let mut groups = Vec::with_capacity(req.groups.len());
+        for group in &req.groups {
+            groups.push(GroupSpec {
+                member_threshold: Self::small(group.member_threshold, "Member threshold")?,
+                member_count: Self::small(group.member_count, "Member count")?,
+            });
+        }
+        
+        Ok(Slip39Scheme {
+            group_threshold: match req.group_threshold {
+                Some(threshold) => Self::small(threshold, "Group threshold")?,
+                None => defaults.group_threshold,
+            },
+            groups,
+            iteration_exponent: match req.iteration_exponent {
+                Some(exponent) => Self::small(exponent, "Iteration exponent")?,
+                None => defaults.iteration_exponent,
+            },
+            extendable: req.extendable.unwrap_or(defaults.extendable),
+        })
+    }
+    
+    // Empty strings are proto3's unset child language
+    #[allow(clippy::result_large_err)]
+    fn bip85_application(req: &DeriveBip85Request) -> Result<Bip85Application, Status> {
+        let child_language = if req.child_language.is_empty() {
+            None
+        } else {
+            Some(parse_language(&req.child_language).map_err(Status::from)?)
+        };
+        
+        Bip85Application::new(req.application, child_language, req.word_count, req.length).map_err(Status::from)
+    }
+    
+    #[allow(clippy::result_large_err)]
     fn validate_request(req: &GenerateMnemonicRequest) -> Result<(), Status> {
         // Validate language
         let valid_languages = vec![
@@ -60,14 +116,13 @@ impl MnemonicService for MnemonicServiceImpl {
         );
 
         // Generate mnemonic
-        let service = self.wallet_service.lock().await;
-        let mnemonic = service
+        let mnemonic = self.wallet_service
             .generate_mnemonic(&req.language, req.word_count)
             .await
             .map_err(|e| Status::internal(format!("Failed to generate mnemonic: {}", e)))?;
 
         let response = GenerateMnemonicResponse {
-            mnemonic,
+            mnemonic: mnemonic.expose_secret().to_string(),
             language: req.language,
             word_count: req.word_count,
             generated_at: chrono::Utc::now().timestamp(),
@@ -81,13 +136,13 @@ impl MnemonicService for MnemonicServiceImpl {
         request: Request<ValidateMnemonicRequest>,
     ) -> Result<Response<ValidateMnemonicResponse>, Status> {
         check_auth(&request)?;
-        let req = request.into_inner();
+        let mut req = request.into_inner();
+        let mnemonic = SecretString::new(std::mem::take(&mut req.mnemonic));
         
         info!("Validating mnemonic in {}", req.language);
 
-        let service = self.wallet_service.lock().await;
-        let (valid, word_count) = service
-            .validate_mnemonic(&req.mnemonic, &req.language)
+        let (valid, word_count) = self.wallet_service
+            .validate_mnemonic(&mnemonic, &req.language)
             .await;
 
         let response = ValidateMnemonicResponse {
@@ -103,6 +158,41 @@ impl MnemonicService for MnemonicServiceImpl {
         Ok(Response::new(response))
     }
 
+    async fn assist_mnemonic(
+        &self,
+        request: Request<AssistMnemonicRequest>,
+    ) -> Result<Response<AssistMnemonicResponse>, Status> {
+        check_auth(&request)?;
+        let mut req = request.into_inner();
+        let mnemonic = SecretString::new(std::mem::take(&mut req.mnemonic));
+        let language = if req.language.is_empty() { None } else { Some(req.language.as_str()) };
+        
+        info!("Checking mnemonic words in {}", language.unwrap_or("any language"));
+
+        let report = self.wallet_service
+            .assist_mnemonic(&mnemonic, language)
+            .await
+            .map_err(Status::from)?;
+
+        let response = AssistMnemonicResponse {
+            language: language_code(report.language).to_string(),
+            word_count: report.word_count as u32,
+            valid: report.valid,
+            invalid_words: report
+                .invalid_words
+                .into_iter()
+                .map(|word| InvalidWord {
+                    position: word.position as u32,
+                    suggestions: word.suggestions.into_iter().map(str::to_string).collect(),
+                })
+                .collect(),
+            final_words: report.final_words.into_iter().map(str::to_string).collect(),
+            repairs: report.repairs.iter().map(|repair| repair.expose_secret().to_string()).collect(),
+        };
+
+        Ok(Response::new(response))
+    }
+
     async fn get_supported_languages(
         &self,
         request: Request<GetSupportedLanguagesRequest>,
@@ -164,4 +254,162 @@ impl MnemonicService for MnemonicServiceImpl {
         let response = GetSupportedLanguagesResponse { languages };
         Ok(Response::new(response))
     }
+
+    async fn entropy_to_mnemonic(
+        &self,
+        request: Request<EntropyToMnemonicRequest>,
+    ) -> Result<Response<EntropyToMnemonicResponse>, Status> {
+        check_auth(&request)?;
+        let mut req = request.into_inner();
+        let entropy = SecretString::new(std::mem::take(&mut req.entropy));
+        let source = if req.source.is_empty() {
+            EntropySource::default()
+        } else {
+            req.source.parse().map_err(Status::from)?
+        };
+
+        info!("Encoding {:?} entropy as a mnemonic in {}", source, req.language);
+
+        let mnemonic = self.wallet_service
+            .entropy_to_mnemonic(&entropy, source, req.word_count, &req.language)
+            .await
+            .map_err(Status::from)?;
+
+        let response = EntropyToMnemonicResponse {
+            word_count: mnemonic.expose_secret().split_whitespace().count() as u32,
+            mnemonic: mnemonic.expose_secret().to_string(),
+            language: req.language,
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn mnemonic_to_entropy(
+        &self,
+        request: Request<MnemonicToEntropyRequest>,
+    ) -> Result<Response<MnemonicToEntropyResponse>, Status> {
+        check_auth(&request)?;
+        let mut req = request.into_inner();
+        let mnemonic = SecretString::new(std::mem::take(&mut req.mnemonic));
+
+        info!("Decoding mnemonic entropy in {}", req.language);
+
+        let entropy = self.wallet_service
+            .mnemonic_to_entropy(&mnemonic, &req.language)
+            .await
+            .map_err(Status::from)?;
+
+        let response = MnemonicToEntropyResponse {
+            // Every word carries 11 bits, one of each 33 being checksum
+            word_count: (entropy.expose_secret().len() * 3 / 8) as u32,
+            entropy: entropy.expose_secret().to_string(),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn mnemonic_to_seed(
+        &self,
+        request: Request<MnemonicToSeedRequest>,
+    ) -> Result<Response<MnemonicToSeedResponse>, Status> {
+        check_auth(&request)?;
+        let mut req = request.into_inner();
+        let (mnemonic, passphrase) = take_secrets(&mut req.mnemonic, &mut req.passphrase);
+
+        info!("Deriving BIP39 seed in {}", req.language);
+
+        let seed = self.wallet_service
+            .mnemonic_to_seed(&mnemonic, &passphrase, &req.language)
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(MnemonicToSeedResponse { seed: seed.expose_secret().to_string() }))
+    }
+    async fn split_slip39(
+        &self,
+        request: Request<SplitSlip39Request>,
+    ) -> Result<Response<SplitSlip39Response>, Status> {
+        check_auth(&request)?;
+        let mut req = request.into_inner();
+        let (master_secret, passphrase) = take_secrets(&mut req.master_secret, &mut req.passphrase);
+
+        let scheme = Self::slip39_scheme(&req)?;
+
+        info!(
+            "Splitting a master secret into {}-of-{} SLIP-39 groups",
+            scheme.group_threshold,
+            scheme.groups.len()
+        );
+
+        let master_secret = if master_secret.is_empty() { None } else { Some(&master_secret) };
+        let backup = self.wallet_service
+            .split_slip39(master_secret, req.strength.unwrap_or(128), &passphrase, &scheme)
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(SplitSlip39Response {
+            identifier: u32::from(backup.identifier),
+            extendable: backup.extendable,
+            iteration_exponent: u32::from(backup.iteration_exponent),
+            group_threshold: u32::from(backup.group_threshold),
+            groups: backup
+                .groups
+                .into_iter()
+                .map(|group| Slip39ShareGroup {
+                    member_threshold: u32::from(group.member_threshold),
+                    shares: group.shares.iter().map(|share| share.expose_secret().to_string()).collect(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn combine_slip39(
+        &self,
+        request: Request<CombineSlip39Request>,
+    ) -> Result<Response<CombineSlip39Response>, Status> {
+        check_auth(&request)?;
+        let mut req = request.into_inner();
+        let shares: Vec<SecretString> = req.shares.iter_mut().map(|share| SecretString::new(std::mem::take(share))).collect();
+        let passphrase = SecretString::new(std::mem::take(&mut req.passphrase));
+
+        info!("Recovering a master secret from {} SLIP-39 shares", shares.len());
+
+        let master_secret = self.wallet_service
+            .combine_slip39(&shares, &passphrase)
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(CombineSlip39Response {
+            master_secret: hex::encode(master_secret.expose_secret()),
+        }))
+    }
+
+    async fn derive_bip85(
+        &self,
+        request: Request<DeriveBip85Request>,
+    ) -> Result<Response<DeriveBip85Response>, Status> {
+        check_auth(&request)?;
+        let mut req = request.into_inner();
+        let (mnemonic, passphrase) = take_secrets(&mut req.mnemonic, &mut req.passphrase);
+        let application = Self::bip85_application(&req)?;
+        let language = if req.language.is_empty() { None } else { Some(req.language.as_str()) };
+        let language = self.wallet_service
+            .mnemonic_language(&mnemonic, language)
+            .await
+            .map_err(Status::from)?;
+
+        info!("Deriving BIP85 application {} child {}", req.application, req.index);
+
+        let child = self.wallet_service
+            .derive_bip85(&mnemonic, &passphrase, Some(language), application, req.index)
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(DeriveBip85Response {
+            application: req.application,
+            index: req.index,
+            derivation_path: application.path(req.index).to_string(),
+            child: child.expose_secret().to_string(),
+        }))
+    }
 }
\ No newline at end of file
@@ -1,6 +1,7 @@
 pub mod auth;
 pub mod health;
 pub mod mnemonic;
+pub mod secrets;
 pub mod wallet;
 
 pub use health::HealthServiceImpl;
@@ -8,6 +9,7 @@ pub use mnemonic::MnemonicServiceImpl;
 pub use wallet::WalletServiceImpl;
 
 // Include the generated protobuf code
+#[allow(clippy::large_enum_variant)]
 pub mod wallet_proto {
     tonic::include_proto!("wallet.v1");
 }
\ No newline at end of file
@@ -0,0 +1,379 @@
+//! Secret handling for the generated protobuf messages.
+//!
+//! prost fields are plain `String`s, so secrets are moved out of each request into
+//! `SecretString`s (wiping the buffer when the request is done), and the messages
+//! that carry secrets get hand-written `Debug` impls in place of the derived ones
+//! (see `build.rs`).
+
+use std::fmt;
+
+use super::wallet_proto::{
+    BatchGenerateWalletRequest, EntropyToMnemonicRequest, EntropyToMnemonicResponse,
+    ExportXpubRequest, GenerateMnemonicResponse, GenerateWalletRequest, ImportKeyRequest,
+    MnemonicToEntropyRequest, MnemonicToEntropyResponse, MnemonicToSeedRequest,
+    MnemonicToSeedResponse, ValidateMnemonicRequest, WalletAddressResponse,
+    SplitSlip39Request, Slip39ShareGroup, CombineSlip39Request, CombineSlip39Response,
+    DeriveBip85Request, DeriveBip85Response, AssistMnemonicRequest, AssistMnemonicResponse,
+    DeriveMultisigRequest, SignPsbtRequest, BuildPsbtRequest, SignMessageRequest,
+};
+use crate::core::SecretString;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Move a request's mnemonic and passphrase into secret containers without copying them
+pub fn take_secrets(mnemonic: &mut String, passphrase: &mut String) -> (SecretString, SecretString) {
+    (
+        SecretString::new(std::mem::take(mnemonic)),
+        SecretString::new(std::mem::take(passphrase)),
+    )
+}
+
+impl fmt::Debug for GenerateMnemonicResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GenerateMnemonicResponse")
+            .field("mnemonic", &REDACTED)
+            .field("language", &self.language)
+            .field("word_count", &self.word_count)
+            .field("generated_at", &self.generated_at)
+            .finish()
+    }
+}
+
+impl fmt::Debug for ValidateMnemonicRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidateMnemonicRequest")
+            .field("mnemonic", &REDACTED)
+            .field("language", &self.language)
+            .finish()
+    }
+}
+
+impl fmt::Debug for AssistMnemonicRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssistMnemonicRequest")
+            .field("mnemonic", &REDACTED)
+            .field("language", &self.language)
+            .finish()
+    }
+}
+
+impl fmt::Debug for AssistMnemonicResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssistMnemonicResponse")
+            .field("language", &self.language)
+            .field("word_count", &self.word_count)
+            .field("valid", &self.valid)
+            .field("invalid_words", &self.invalid_words)
+            .field("final_words", &self.final_words)
+            .field("repairs", &REDACTED)
+            .finish()
+    }
+}
+
+impl fmt::Debug for EntropyToMnemonicRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EntropyToMnemonicRequest")
+            .field("entropy", &REDACTED)
+            .field("source", &self.source)
+            .field("word_count", &self.word_count)
+            .field("language", &self.language)
+            .finish()
+    }
+}
+
+impl fmt::Debug for EntropyToMnemonicResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EntropyToMnemonicResponse")
+            .field("mnemonic", &REDACTED)
+            .field("language", &self.language)
+            .field("word_count", &self.word_count)
+            .finish()
+    }
+}
+
+impl fmt::Debug for MnemonicToEntropyRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MnemonicToEntropyRequest")
+            .field("mnemonic", &REDACTED)
+            .field("language", &self.language)
+            .finish()
+    }
+}
+
+impl fmt::Debug for MnemonicToEntropyResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MnemonicToEntropyResponse")
+            .field("entropy", &REDACTED)
+            .field("word_count", &self.word_count)
+            .finish()
+    }
+}
+
+impl fmt::Debug for MnemonicToSeedRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MnemonicToSeedRequest")
+            .field("mnemonic", &REDACTED)
+            .field("passphrase", &REDACTED)
+            .field("language", &self.language)
+            .finish()
+    }
+}
+
+impl fmt::Debug for MnemonicToSeedResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MnemonicToSeedResponse")
+            .field("seed", &REDACTED)
+            .finish()
+    }
+}
+
+impl fmt::Debug for SplitSlip39Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SplitSlip39Request")
+            .field("master_secret", &REDACTED)
+            .field("strength", &self.strength)
+            .field("passphrase", &REDACTED)
+            .field("group_threshold", &self.group_threshold)
+            .field("groups", &self.groups)
+            .field("iteration_exponent", &self.iteration_exponent)
+            .field("extendable", &self.extendable)
+            .finish()
+    }
+}
+
+impl fmt::Debug for Slip39ShareGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Slip39ShareGroup")
+            .field("member_threshold", &self.member_threshold)
+            .field("shares", &REDACTED)
+            .finish()
+    }
+}
+
+impl fmt::Debug for CombineSlip39Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CombineSlip39Request")
+            .field("shares", &REDACTED)
+            .field("passphrase", &REDACTED)
+            .finish()
+    }
+}
+
+impl fmt::Debug for CombineSlip39Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CombineSlip39Response")
+            .field("master_secret", &REDACTED)
+            .finish()
+    }
+}
+
+impl fmt::Debug for DeriveBip85Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeriveBip85Request")
+            .field("mnemonic", &REDACTED)
+            .field("passphrase", &REDACTED)
+            .field("language", &self.language)
+            .field("application", &self.application)
+            .field("index", &self.index)
+            .field("child_language", &self.child_language)
+            .field("word_count", &self.word_count)
+            .field("length", &self.length)
+            .finish()
+    }
+}
+
+impl fmt::Debug for DeriveBip85Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeriveBip85Response")
+            .field("application", &self.application)
+            .field("index", &self.index)
+            .field("derivation_path", &self.derivation_path)
+            .field("child", &REDACTED)
+            .finish()
+    }
+}
+
+impl fmt::Debug for GenerateWalletRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GenerateWalletRequest")
+            .field("mnemonic", &REDACTED)
+            .field("passphrase", &REDACTED)
+            .field("symbol", &self.symbol)
+            .field("account", &self.account)
+            .field("index", &self.index)
+            .field("derivation_path", &self.derivation_path)
+            .field("network", &self.network)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for BatchGenerateWalletRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchGenerateWalletRequest")
+            .field("mnemonic", &REDACTED)
+            .field("passphrase", &REDACTED)
+            .field("symbols", &self.symbols)
+            .field("account", &self.account)
+            .field("start_index", &self.start_index)
+            .field("count", &self.count)
+            .field("derivation_path", &self.derivation_path)
+            .field("network", &self.network)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for ExportXpubRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExportXpubRequest")
+            .field("mnemonic", &REDACTED)
+            .field("passphrase", &REDACTED)
+            .field("symbol", &self.symbol)
+            .field("account", &self.account)
+            .field("language", &self.language)
+            .field("network", &self.network)
+            .finish()
+    }
+}
+
+impl fmt::Debug for DeriveMultisigRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeriveMultisigRequest")
+            .field("cosigners", &self.cosigners)
+            .field("threshold", &self.threshold)
+            .field("script_type", &self.script_type)
+            .field("mnemonic", &REDACTED)
+            .field("passphrase", &REDACTED)
+            .field("language", &self.language)
+            .field("account", &self.account)
+            .field("change", &self.change)
+            .field("start_index", &self.start_index)
+            .field("count", &self.count)
+            .field("network", &self.network)
+            .finish()
+    }
+}
+
+impl fmt::Debug for WalletAddressResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WalletAddressResponse")
+            .field("address", &self.address)
+            .field("chain_symbol", &self.chain_symbol)
+            .field("address_type", &self.address_type)
+            .field("derivation_path", &self.derivation_path)
+            .field("public_key", &self.public_key)
+            .field("private_key", &self.private_key.as_ref().map(|_| REDACTED))
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for ImportKeyRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImportKeyRequest")
+            .field("private_key", &REDACTED)
+            .field("symbol", &self.symbol)
+            .field("address_type", &self.address_type)
+            .field("network", &self.network)
+            .finish()
+    }
+}
+
+impl fmt::Debug for SignPsbtRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignPsbtRequest")
+            .field("mnemonic", &REDACTED)
+            .field("passphrase", &REDACTED)
+            .field("language", &self.language)
+            .field("symbol", &self.symbol)
+            .field("psbt", &self.psbt)
+            .field("utxos", &self.utxos)
+            .field("outputs", &self.outputs)
+            .field("account", &self.account)
+            .field("sign_only", &self.sign_only)
+            .field("network", &self.network)
+            .field("allow_missing_previous_transactions", &self.allow_missing_previous_transactions)
+            .field("allow_any_sighash", &self.allow_any_sighash)
+            .finish()
+    }
+}
+
+impl fmt::Debug for BuildPsbtRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BuildPsbtRequest")
+            .field("mnemonic", &REDACTED)
+            .field("passphrase", &REDACTED)
+            .field("language", &self.language)
+            .field("symbol", &self.symbol)
+            .field("utxos", &self.utxos)
+            .field("outputs", &self.outputs)
+            .field("fee_rate", &self.fee_rate)
+            .field("change_address_type", &self.change_address_type)
+            .field("change_index", &self.change_index)
+            .field("rbf", &self.rbf)
+            .field("locktime", &self.locktime)
+            .field("account", &self.account)
+            .field("network", &self.network)
+            .field("allow_missing_previous_transactions", &self.allow_missing_previous_transactions)
+            .finish()
+    }
+}
+
+impl fmt::Debug for SignMessageRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignMessageRequest")
+            .field("mnemonic", &REDACTED)
+            .field("passphrase", &REDACTED)
+            .field("language", &self.language)
+            .field("symbol", &self.symbol)
+            .field("address_type", &self.address_type)
+            .field("account", &self.account)
+            .field("index", &self.index)
+            .field("message", &self.message)
+            .field("format", &self.format)
+            .field("network", &self.network)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proto_debug_is_redacted() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let request = GenerateWalletRequest {
+            mnemonic: mnemonic.to_string(),
+            passphrase: "TREZOR".to_string(),
+            symbol: "ETH".to_string(),
+            ..Default::default()
+        };
+        let response = WalletAddressResponse {
+            private_key: Some("1ab42cc412b618bdea3a599e3c9bae199ebf030895b039e9db1e30dafb12b727".to_string()),
+            ..Default::default()
+        };
+        let import = ImportKeyRequest {
+            private_key: "KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98617".to_string(),
+            symbol: "BTC".to_string(),
+            ..Default::default()
+        };
+
+        let debug = format!("{:?} {:?} {:?}", request, response, import);
+        assert!(debug.contains("ETH"));
+        for secret in [mnemonic, "TREZOR", response.private_key.as_deref().unwrap(), &import.private_key] {
+            assert!(!debug.contains(secret), "{} leaked", secret);
+        }
+    }
+
+    #[test]
+    fn test_take_secrets_leaves_request_empty() {
+        let mut request = ExportXpubRequest {
+            mnemonic: "abandon about".to_string(),
+            passphrase: "TREZOR".to_string(),
+            ..Default::default()
+        };
+
+        let (mnemonic, passphrase) = take_secrets(&mut request.mnemonic, &mut request.passphrase);
+        assert_eq!((mnemonic.expose_secret(), passphrase.expose_secret()), ("abandon about", "TREZOR"));
+        assert!(request.mnemonic.is_empty() && request.passphrase.is_empty());
+    }
+}
@@ -1,25 +1,42 @@
 use tonic::{Request, Response, Status};
+use bip39::Language;
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use tracing::info;
+use bitcoin::absolute::LockTime;
+use bitcoin::{consensus, Amount};
 
 use super::auth::check_auth;
+use super::secrets::take_secrets;
 use super::wallet_proto::{
     wallet_service_server::WalletService as GrpcWalletService,
     GenerateWalletRequest, GenerateWalletResponse,
     BatchGenerateWalletRequest, BatchGenerateWalletResponse,
+    ExportXpubRequest, ExportXpubResponse, ExtendedPublicKeyResponse,
+    DerivePublicRequest, DerivePublicResponse, WatchOnlyAddressResponse,
+    ValidateAddressRequest, ValidateAddressResponse,
+    DeriveMultisigRequest, DeriveMultisigResponse, MultisigAddress,
+    CompilePolicyRequest, CompilePolicyResponse, TapLeaf,
+    ImportKeyRequest, ImportKeyResponse, ImportedKeyResponse,
+    SignPsbtRequest, SignPsbtResponse, BuildPsbtRequest, BuildPsbtResponse, ChangeOutput,
+    SignMessageRequest, SignMessageResponse, VerifyMessageRequest, VerifyMessageResponse,
+    Utxo as ProtoUtxo, Payout as ProtoPayout,
     WalletAddressResponse, MultipleWalletsResponse,
     TokenInfo, generate_wallet_response::Response as WalletResponseType,
 };
-use crate::services::wallet::WalletService;
-use crate::core::{get_chain_info, get_chain_types_by_symbol, get_token_registry};
+use crate::chains::bitcoin::XpubFormat;
+use crate::chains::message::MessageRequest;
+use crate::chains::multisig::{Cosigner, MultisigWallet};
+use crate::chains::psbt::{self, BuildOptions, BuildRequest, Finality, Payout, SigningRequest, Unsigned, Utxo};
+use crate::services::wallet::{DerivationOptions, WalletService};
+use crate::core::mnemonic::language_code;
+use crate::core::{get_chain_info, get_chain_types_by_symbol, get_token_registry, ChainType, KeyFormat, MessageFormat, MultisigScriptType, NetworkType, PolicyScriptType, SecretBytes, SecretString};
 
 pub struct WalletServiceImpl {
-    wallet_service: Arc<Mutex<WalletService>>,
+    wallet_service: Arc<WalletService>,
 }
 
 impl WalletServiceImpl {
-    pub fn new(wallet_service: Arc<Mutex<WalletService>>) -> Self {
+    pub fn new(wallet_service: Arc<WalletService>) -> Self {
         Self { wallet_service }
     }
     
@@ -33,6 +50,7 @@ impl WalletServiceImpl {
             crate::core::ChainType::Avalanche => "Avalanche",
             crate::core::ChainType::BitcoinSegwit => "Bitcoin",
             crate::core::ChainType::BitcoinLegacy => "Bitcoin",
+            crate::core::ChainType::BitcoinNestedSegwit => "Bitcoin",
             crate::core::ChainType::BitcoinTaproot => "Bitcoin",
             crate::core::ChainType::Solana => "Solana",
             crate::core::ChainType::Tron => "Tron",
@@ -50,6 +68,7 @@ impl WalletServiceImpl {
             crate::core::ChainType::Injective => "Injective",
             crate::core::ChainType::Tezos => "Tezos",
             crate::core::ChainType::Filecoin => "Filecoin",
+            crate::core::ChainType::Stellar => "Stellar",
         }
     }
     
@@ -75,7 +94,128 @@ impl WalletServiceImpl {
             .collect()
     }
     
-    fn convert_wallet_to_grpc(wallet: crate::core::WalletAddress) -> WalletAddressResponse {
+    // proto3 strings default to empty, which means "use the chain's default path/key format"
+    #[allow(clippy::result_large_err)]
+    fn derivation_options(
+        account: u32,
+        path: &str,
+        legacy_ed25519_derivation: bool,
+        include_private_key: Option<bool>,
+        key_format: &str,
+        language: Option<Language>,
+        network: &str,
+    ) -> Result<DerivationOptions, Status> {
+        let key_format = if key_format.is_empty() {
+            None
+        } else {
+            Some(key_format.parse::<KeyFormat>().map_err(Status::from)?)
+        };
+        
+        Ok(DerivationOptions {
+            account,
+            derivation_path: if path.is_empty() { None } else { Some(path.to_string()) },
+            legacy_ed25519_derivation,
+            include_private_key,
+            key_format,
+            language,
+            network: Self::network(network)?,
+        })
+    }
+    
+    #[allow(clippy::result_large_err)]
+    fn network(network: &str) -> Result<NetworkType, Status> {
+        if network.is_empty() {
+            Ok(NetworkType::default())
+        } else {
+            network.parse().map_err(Status::from)
+        }
+    }
+    
+    #[allow(clippy::result_large_err)]
+    fn multisig_script_type(script_type: &str) -> Result<MultisigScriptType, Status> {
+        if script_type.is_empty() {
+            Ok(MultisigScriptType::default())
+        } else {
+            script_type.parse().map_err(Status::from)
+        }
+    }
+    
+    #[allow(clippy::result_large_err)]
+    fn policy_script_type(script_type: &str) -> Result<PolicyScriptType, Status> {
+        if script_type.is_empty() {
+            Ok(PolicyScriptType::default())
+        } else {
+            script_type.parse().map_err(Status::from)
+        }
+    }
+    
+    #[allow(clippy::result_large_err)]
+    fn message_format(format: &str) -> Result<Option<MessageFormat>, Status> {
+        if format.is_empty() {
+            Ok(None)
+        } else {
+            format.parse().map(Some).map_err(Status::from)
+        }
+    }
+    
+    #[allow(clippy::result_large_err)]
+    fn utxos(chain_types: &[ChainType], utxos: &[ProtoUtxo]) -> Result<Vec<Utxo>, Status> {
+        utxos
+            .iter()
+            .map(|utxo| {
+                Ok(Utxo {
+                    outpoint: psbt::parse_outpoint(&utxo.txid, utxo.vout)?,
+                    value: psbt::parse_amount(chain_types, utxo.value)?,
+                    chain_type: psbt::address_type(chain_types, &utxo.address_type)?,
+                    change: utxo.change,
+                    index: utxo.index,
+                    previous_transaction: Some(utxo.previous_transaction.as_str())
+                        .filter(|hex| !hex.is_empty())
+                        .map(psbt::parse_transaction)
+                        .transpose()?,
+                })
+            })
+            .collect()
+    }
+    
+    #[allow(clippy::result_large_err)]
+    fn payouts(chain_types: &[ChainType], outputs: &[ProtoPayout]) -> Result<Vec<Payout>, Status> {
+        outputs
+            .iter()
+            .map(|output| {
+                Ok(Payout {
+                    address: output.address.clone(),
+                    value: psbt::parse_amount(chain_types, output.value)?,
+                })
+            })
+            .collect()
+    }
+    
+    // A request's hex master secret, moved out of it like the mnemonic; empty means unset
+    #[allow(clippy::result_large_err)]
+    fn master_secret_seed(
+        wallet_service: &WalletService,
+        mnemonic: &SecretString,
+        passphrase: &SecretString,
+        master_secret: &mut String,
+    ) -> Result<Option<SecretBytes>, Status> {
+        let master_secret = SecretString::new(std::mem::take(master_secret));
+        let master_secret = Some(&master_secret).filter(|secret| !secret.expose_secret().is_empty());
+        wallet_service
+            .master_secret_seed(mnemonic, passphrase, master_secret)
+            .map_err(Status::from)
+    }
+    
+    // The wordlist named by the request, detected from the words when it is empty
+    async fn mnemonic_language(&self, mnemonic: &SecretString, language: &str) -> Result<Language, Status> {
+        let language = if language.is_empty() { None } else { Some(language) };
+        self.wallet_service
+            .mnemonic_language(mnemonic, language)
+            .await
+            .map_err(Status::from)
+    }
+    
+    fn convert_wallet_to_grpc(wallet: crate::core::WalletAddress, language: Option<Language>) -> WalletAddressResponse {
         let supported_tokens = Self::get_supported_tokens(&wallet.chain_type);
         
         WalletAddressResponse {
@@ -84,10 +224,13 @@ impl WalletServiceImpl {
             chain_symbol: wallet.chain_info.symbol,
             address_type: format!("{:?}", wallet.chain_type),
             derivation_path: wallet.derivation_path,
+            account: wallet.account,
             index: wallet.index,
             public_key: wallet.public_key,
-            private_key: wallet.private_key,
+            public_key_formats: wallet.public_key_formats.into_iter().collect(),
+            private_key: wallet.private_key.map(|key| key.expose_secret().to_string()),
             supported_tokens,
+            mnemonic_language: language.map(language_code).unwrap_or_default().to_string(),
         }
     }
 }
@@ -99,7 +242,9 @@ impl GrpcWalletService for WalletServiceImpl {
         request: Request<GenerateWalletRequest>,
     ) -> Result<Response<GenerateWalletResponse>, Status> {
         check_auth(&request)?;
-        let req = request.into_inner();
+        let mut req = request.into_inner();
+        let (mnemonic, passphrase) = take_secrets(&mut req.mnemonic, &mut req.passphrase);
+        let master_secret = Self::master_secret_seed(&self.wallet_service, &mnemonic, &passphrase, &mut req.master_secret)?;
         
         // Get chain types for the symbol
         let chain_types = get_chain_types_by_symbol(&req.symbol);
@@ -110,7 +255,19 @@ impl GrpcWalletService for WalletServiceImpl {
             ));
         }
         
-        let mut service = self.wallet_service.lock().await;
+        let language = match master_secret {
+            Some(_) => None,
+            None => Some(self.mnemonic_language(&mnemonic, &req.language).await?),
+        };
+        let options = Self::derivation_options(
+            req.account,
+            &req.derivation_path,
+            req.legacy_ed25519_derivation,
+            req.include_private_key,
+            &req.key_format,
+            language,
+            &req.network,
+        )?;
         let mut wallets = Vec::new();
         
         // Generate wallets for all chain types associated with the symbol
@@ -118,19 +275,29 @@ impl GrpcWalletService for WalletServiceImpl {
             let chain_info = get_chain_info(&chain_type);
             
             info!(
-                "Generating {} ({}) wallet at index {}",
-                chain_info.name, chain_info.symbol, req.index
+                "Generating {} ({}) wallet at account {} index {}",
+                chain_info.name, chain_info.symbol, req.account, req.index
             );
             
-            let wallet = service
-                .generate_wallet_address(
-                    &req.mnemonic,
-                    &req.passphrase,
-                    &chain_type,
-                    req.index,
-                )
-                .await
-                .map_err(|e| Status::internal(format!("Failed to generate wallet: {}", e)))?;
+            let wallet = match &master_secret {
+                Some(seed) => {
+                    self.wallet_service
+                        .generate_wallet_address_from_seed(seed, &chain_type, req.index, &options)
+                        .await
+                }
+                None => {
+                    self.wallet_service
+                        .generate_wallet_address(
+                            &mnemonic,
+                            &passphrase,
+                            &chain_type,
+                            req.index,
+                            &options,
+                        )
+                        .await
+                }
+            }
+            .map_err(Status::from)?;
                 
             wallets.push(wallet);
         }
@@ -138,7 +305,7 @@ impl GrpcWalletService for WalletServiceImpl {
         let response = if wallets.len() == 1 {
             // Single wallet response
             let wallet = wallets.into_iter().next().unwrap();
-            let grpc_wallet = Self::convert_wallet_to_grpc(wallet);
+            let grpc_wallet = Self::convert_wallet_to_grpc(wallet, language);
             
             GenerateWalletResponse {
                 response: Some(WalletResponseType::SingleWallet(grpc_wallet)),
@@ -147,7 +314,7 @@ impl GrpcWalletService for WalletServiceImpl {
             // Multiple wallets response (e.g., Bitcoin with different address types)
             let grpc_wallets: Vec<WalletAddressResponse> = wallets
                 .into_iter()
-                .map(Self::convert_wallet_to_grpc)
+                .map(|wallet| Self::convert_wallet_to_grpc(wallet, language))
                 .collect();
                 
             GenerateWalletResponse {
@@ -165,7 +332,9 @@ impl GrpcWalletService for WalletServiceImpl {
         request: Request<BatchGenerateWalletRequest>,
     ) -> Result<Response<BatchGenerateWalletResponse>, Status> {
         check_auth(&request)?;
-        let req = request.into_inner();
+        let mut req = request.into_inner();
+        let (mnemonic, passphrase) = take_secrets(&mut req.mnemonic, &mut req.passphrase);
+        let master_secret = Self::master_secret_seed(&self.wallet_service, &mnemonic, &passphrase, &mut req.master_secret)?;
         
         // Validate count
         if req.count == 0 || req.count > 100 {
@@ -199,21 +368,43 @@ impl GrpcWalletService for WalletServiceImpl {
             req.start_index
         );
 
-        let mut service = self.wallet_service.lock().await;
-        let addresses = service
-            .batch_generate_wallet_addresses(
-                &req.mnemonic,
-                &req.passphrase,
-                &all_chain_types,
-                req.start_index,
-                req.count,
-            )
-            .await
-            .map_err(|e| Status::internal(format!("Failed to batch generate wallets: {}", e)))?;
+        let language = match master_secret {
+            Some(_) => None,
+            None => Some(self.mnemonic_language(&mnemonic, &req.language).await?),
+        };
+        let options = Self::derivation_options(
+            req.account,
+            &req.derivation_path,
+            req.legacy_ed25519_derivation,
+            req.include_private_key,
+            &req.key_format,
+            language,
+            &req.network,
+        )?;
+        let addresses = match &master_secret {
+            Some(seed) => {
+                self.wallet_service
+                    .batch_generate_wallet_addresses_from_seed(seed, &all_chain_types, req.start_index, req.count, &options)
+                    .await
+            }
+            None => {
+                self.wallet_service
+                    .batch_generate_wallet_addresses(
+                        &mnemonic,
+                        &passphrase,
+                        &all_chain_types,
+                        req.start_index,
+                        req.count,
+                        &options,
+                    )
+                    .await
+            }
+        }
+        .map_err(Status::from)?;
 
         let response_addresses: Vec<WalletAddressResponse> = addresses
             .into_iter()
-            .map(Self::convert_wallet_to_grpc)
+            .map(|wallet| Self::convert_wallet_to_grpc(wallet, language))
             .collect();
 
         let response = BatchGenerateWalletResponse {
@@ -222,4 +413,531 @@ impl GrpcWalletService for WalletServiceImpl {
 
         Ok(Response::new(response))
     }
+    async fn export_account_xpub(
+        &self,
+        request: Request<ExportXpubRequest>,
+    ) -> Result<Response<ExportXpubResponse>, Status> {
+        check_auth(&request)?;
+        let mut req = request.into_inner();
+        let (mnemonic, passphrase) = take_secrets(&mut req.mnemonic, &mut req.passphrase);
+
+        let chain_types = get_chain_types_by_symbol(&req.symbol);
+        if chain_types.is_empty() {
+            return Err(Status::invalid_argument(
+                format!("Unsupported symbol: {}", req.symbol)
+            ));
+        }
+
+        info!("Exporting {} account {} extended public keys", req.symbol, req.account);
+
+        let language = self.mnemonic_language(&mnemonic, &req.language).await?;
+        let network = Self::network(&req.network)?;
+        let mut extended_keys = Vec::new();
+
+        for chain_type in chain_types {
+            let xpub = self.wallet_service
+                .export_account_xpub(&mnemonic, &passphrase, &chain_type, req.account, Some(language), network)
+                .await
+                .map_err(Status::from)?;
+
+            extended_keys.push(ExtendedPublicKeyResponse {
+                chain_name: xpub.chain_info.name,
+                chain_symbol: xpub.chain_info.symbol,
+                address_type: format!("{:?}", xpub.chain_type),
+                extended_public_key: xpub.extended_public_key,
+                format: xpub.format,
+                master_fingerprint: xpub.master_fingerprint,
+                derivation_path: xpub.derivation_path,
+                key_origin: xpub.key_origin,
+                account: xpub.account,
+                receive_descriptor: xpub.receive_descriptor.unwrap_or_default(),
+                change_descriptor: xpub.change_descriptor.unwrap_or_default(),
+                mnemonic_language: language_code(language).to_string(),
+            });
+        }
+
+        Ok(Response::new(ExportXpubResponse { extended_keys }))
+    }
+    async fn derive_public_addresses(
+        &self,
+        request: Request<DerivePublicRequest>,
+    ) -> Result<Response<DerivePublicResponse>, Status> {
+        check_auth(&request)?;
+        let req = request.into_inner();
+
+        if req.count == 0 || req.count > 100 {
+            return Err(Status::invalid_argument(
+                "Count must be between 1 and 100".to_string(),
+            ));
+        }
+
+        // Without an address type, a ypub or zpub derives only the type it was made for
+        let (format, _) = XpubFormat::decode(&req.extended_public_key)?;
+        let chain_types: Vec<_> = get_chain_types_by_symbol(&req.symbol)
+            .into_iter()
+            .filter(|ct| match req.address_type.as_str() {
+                "" => format.fits(ct),
+                address_type => format!("{:?}", ct).eq_ignore_ascii_case(address_type),
+            })
+            .collect();
+
+        if chain_types.is_empty() {
+            return Err(Status::invalid_argument(
+                format!("Unsupported symbol or address type: {}", req.symbol)
+            ));
+        }
+
+        info!(
+            "Deriving {} watch-only {} addresses on branch {} starting at index {}",
+            req.count, req.symbol, req.change, req.start_index
+        );
+
+        let network = Self::network(&req.network)?;
+        let mut addresses = Vec::new();
+
+        for chain_type in chain_types {
+            let derived = self.wallet_service
+                .derive_public_addresses(
+                    &req.extended_public_key,
+                    &chain_type,
+                    network,
+                    req.change,
+                    req.start_index,
+                    req.count,
+                )
+                .await
+                .map_err(Status::from)?;
+
+            addresses.extend(derived.into_iter().map(|wallet| WatchOnlyAddressResponse {
+                chain_name: wallet.chain_info.name,
+                chain_symbol: wallet.chain_info.symbol,
+                address_type: format!("{:?}", wallet.chain_type),
+                address: wallet.address,
+                relative_path: wallet.relative_path,
+                change: wallet.change,
+                index: wallet.index,
+                public_key: wallet.public_key,
+                public_key_formats: wallet.public_key_formats.into_iter().collect(),
+            }));
+        }
+
+        Ok(Response::new(DerivePublicResponse { addresses }))
+    }
+
+    async fn validate_address(
+        &self,
+        request: Request<ValidateAddressRequest>,
+    ) -> Result<Response<ValidateAddressResponse>, Status> {
+        check_auth(&request)?;
+        let req = request.into_inner();
+
+        let chain_types: Vec<_> = get_chain_types_by_symbol(&req.symbol)
+            .into_iter()
+            .filter(|ct| req.address_type.is_empty() || format!("{:?}", ct).eq_ignore_ascii_case(&req.address_type))
+            .collect();
+
+        if chain_types.is_empty() {
+            return Err(Status::invalid_argument(
+                format!("Unsupported symbol or address type: {}", req.symbol)
+            ));
+        }
+
+        let network = Self::network(&req.network)?;
+        let redeem_script = Some(req.redeem_script.as_str()).filter(|script| !script.is_empty());
+        let public_key = Some(req.public_key.as_str()).filter(|key| !key.is_empty());
+
+        // Only a redeem script or public key tells nested SegWit apart from any other P2SH
+        let nested_segwit = if redeem_script.is_some() || public_key.is_some() {
+            if !chain_types.contains(&ChainType::BitcoinNestedSegwit) {
+                return Err(Status::invalid_argument(
+                    "redeem_script and public_key only apply to BitcoinNestedSegwit addresses".to_string(),
+                ));
+            }
+
+            Some(
+                self.wallet_service
+                    .is_nested_witness_address(network, &req.address, redeem_script, public_key)
+                    .await
+                    .map_err(Status::from)?,
+            )
+        } else {
+            None
+        };
+
+        let mut address_types = Vec::new();
+
+        for chain_type in chain_types {
+            let valid = match chain_type {
+                ChainType::BitcoinNestedSegwit if nested_segwit.is_some() => nested_segwit == Some(true),
+                _ => self.wallet_service.validate_address(&chain_type, network, &req.address).await,
+            };
+            if valid {
+                address_types.push(format!("{:?}", chain_type));
+            }
+        }
+
+        Ok(Response::new(ValidateAddressResponse {
+            valid: !address_types.is_empty(),
+            address_types,
+            nested_segwit,
+        }))
+    }
+
+    async fn derive_multisig(
+        &self,
+        request: Request<DeriveMultisigRequest>,
+    ) -> Result<Response<DeriveMultisigResponse>, Status> {
+        check_auth(&request)?;
+        let mut req = request.into_inner();
+        let (mnemonic, passphrase) = take_secrets(&mut req.mnemonic, &mut req.passphrase);
+
+        if req.count == 0 || req.count > 100 {
+            return Err(Status::invalid_argument(
+                "Count must be between 1 and 100".to_string(),
+            ));
+        }
+
+        let script_type = Self::multisig_script_type(&req.script_type)?;
+        let network = Self::network(&req.network)?;
+        let mut cosigners = req
+            .cosigners
+            .iter()
+            .map(|cosigner| Cosigner::parse(cosigner))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(Status::from)?;
+
+        // The mnemonic joins as one more cosigner with its BIP48 key
+        let mut own_key = String::new();
+        let mut mnemonic_language = String::new();
+        if !mnemonic.expose_secret().is_empty() {
+            let language = self.mnemonic_language(&mnemonic, &req.language).await?;
+            let cosigner = self.wallet_service
+                .multisig_cosigner(&mnemonic, &passphrase, Some(language), network, req.account, script_type)
+                .await
+                .map_err(Status::from)?;
+
+            own_key = cosigner.to_string();
+            mnemonic_language = language_code(language).to_string();
+            cosigners.push(cosigner);
+        }
+
+        let wallet = MultisigWallet::new(req.threshold, script_type, network.bitcoin_network(), cosigners)
+            .map_err(Status::from)?;
+
+        info!(
+            "Deriving {} {}-of-{} {} multisig addresses on branch {} starting at index {}",
+            req.count,
+            req.threshold,
+            wallet.cosigners().len(),
+            script_type,
+            req.change,
+            req.start_index
+        );
+
+        let total_keys = wallet.cosigners().len() as u32;
+        let receive_descriptor = wallet.descriptor(0).map_err(Status::from)?;
+        let change_descriptor = wallet.descriptor(1).map_err(Status::from)?;
+
+        let addresses = self.wallet_service
+            .derive_multisig_addresses(wallet, req.change, req.start_index, req.count)
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(DeriveMultisigResponse {
+            script_type: script_type.to_string(),
+            threshold: req.threshold,
+            total_keys,
+            receive_descriptor,
+            change_descriptor,
+            own_key,
+            mnemonic_language,
+            addresses: addresses
+                .into_iter()
+                .map(|address| MultisigAddress {
+                    address: address.address,
+                    relative_path: address.relative_path,
+                    change: address.change,
+                    index: address.index,
+                    witness_script: address.witness_script,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn compile_policy(
+        &self,
+        request: Request<CompilePolicyRequest>,
+    ) -> Result<Response<CompilePolicyResponse>, Status> {
+        check_auth(&request)?;
+        let req = request.into_inner();
+
+        let script_type = Self::policy_script_type(&req.script_type)?;
+        let network = Self::network(&req.network)?;
+        info!("Compiling a spending policy to {} on {}", script_type, network);
+
+        let keys = req.keys.into_iter().collect();
+        let compiled = self.wallet_service
+            .compile_policy(&req.policy, &keys, script_type, network)
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(CompilePolicyResponse {
+            script_type: compiled.script_type.to_string(),
+            descriptor: compiled.descriptor,
+            address: compiled.address,
+            miniscript: compiled.miniscript.unwrap_or_default(),
+            witness_script: compiled.witness_script.unwrap_or_default(),
+            internal_key: compiled.internal_key.unwrap_or_default(),
+            leaves: compiled
+                .leaves
+                .into_iter()
+                .map(|leaf| TapLeaf {
+                    miniscript: leaf.miniscript,
+                    script: leaf.script,
+                    leaf_version: leaf.leaf_version as u32,
+                    control_block: leaf.control_block,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn import_private_key(
+        &self,
+        request: Request<ImportKeyRequest>,
+    ) -> Result<Response<ImportKeyResponse>, Status> {
+        check_auth(&request)?;
+        let mut req = request.into_inner();
+        let private_key = SecretString::new(std::mem::take(&mut req.private_key));
+
+        let chain_types: Vec<_> = get_chain_types_by_symbol(&req.symbol)
+            .into_iter()
+            .filter(|ct| req.address_type.is_empty() || format!("{:?}", ct).eq_ignore_ascii_case(&req.address_type))
+            .collect();
+
+        if chain_types.is_empty() {
+            return Err(Status::invalid_argument(
+                format!("Unsupported symbol or address type: {}", req.symbol)
+            ));
+        }
+
+        info!("Importing private key for {}", req.symbol);
+
+        let network = Self::network(&req.network)?;
+        let mut keys = Vec::new();
+        let mut first_error = None;
+
+        for chain_type in chain_types {
+            // A key may fit only some of a symbol's address types: an uncompressed WIF
+            // has no SegWit address
+            let imported = match self.wallet_service.import_private_key(&private_key, &chain_type, network).await {
+                Ok(imported) => imported,
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                    continue;
+                }
+            };
+
+            keys.push(ImportedKeyResponse {
+                chain_name: imported.chain_info.name,
+                chain_symbol: imported.chain_info.symbol,
+                address_type: format!("{:?}", chain_type),
+                address: imported.address,
+                public_key: imported.public_key,
+                public_key_formats: imported.public_key_formats.into_iter().collect(),
+                curve: imported.curve.to_string(),
+                compressed: imported.compressed,
+                key_format: imported.key_format.to_string(),
+            });
+        }
+
+        if let Some(e) = first_error.filter(|_| keys.is_empty()) {
+            return Err(Status::from(e));
+        }
+
+        Ok(Response::new(ImportKeyResponse { keys }))
+    }
+
+    async fn sign_psbt(
+        &self,
+        request: Request<SignPsbtRequest>,
+    ) -> Result<Response<SignPsbtResponse>, Status> {
+        check_auth(&request)?;
+        let mut req = request.into_inner();
+        let (mnemonic, passphrase) = take_secrets(&mut req.mnemonic, &mut req.passphrase);
+
+        let chain_types = psbt::chain_types(&req.symbol)?;
+        let network = Self::network(&req.network)?;
+
+        let transaction = if req.psbt.is_empty() {
+            Unsigned::Spend {
+                utxos: Self::utxos(&chain_types, &req.utxos)?,
+                payouts: Self::payouts(&chain_types, &req.outputs)?,
+            }
+        } else if req.utxos.is_empty() && req.outputs.is_empty() {
+            Unsigned::Psbt(psbt::decode(&req.psbt)?)
+        } else {
+            return Err(Status::invalid_argument(
+                "Send either a psbt or utxos and outputs, not both".to_string()
+            ));
+        };
+
+        info!("Signing a {} PSBT on {}", req.symbol, network);
+
+        let language = self.mnemonic_language(&mnemonic, &req.language).await?;
+        let request = SigningRequest {
+            chain_types,
+            network: network.bitcoin_network(),
+            account: req.account,
+            transaction,
+            sign_only: req.sign_only,
+            allow_missing_previous_transactions: req.allow_missing_previous_transactions,
+            allow_any_sighash: req.allow_any_sighash,
+        };
+        let signed = self.wallet_service
+            .sign_psbt(&mnemonic, &passphrase, Some(language), request)
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(SignPsbtResponse {
+            psbt: psbt::encode(&signed.psbt),
+            signed_inputs: signed.signed_inputs.into_iter().map(|i| i as u32).collect(),
+            complete: signed.complete,
+            txid: signed.transaction.as_ref().map(|tx| tx.txid().to_string()),
+            transaction: signed.transaction.as_ref().map(consensus::encode::serialize_hex),
+            fee: signed.fee.map(Amount::to_sat),
+            mnemonic_language: language_code(language).to_string(),
+        }))
+    }
+
+    async fn build_psbt(
+        &self,
+        request: Request<BuildPsbtRequest>,
+    ) -> Result<Response<BuildPsbtResponse>, Status> {
+        check_auth(&request)?;
+        let mut req = request.into_inner();
+        let (mnemonic, passphrase) = take_secrets(&mut req.mnemonic, &mut req.passphrase);
+
+        let chain_types = psbt::chain_types(&req.symbol)?;
+        let network = Self::network(&req.network)?;
+        let change_type = Some(req.change_address_type.as_str())
+            .filter(|name| !name.is_empty())
+            .map(|name| psbt::address_type(&chain_types, name))
+            .transpose()?;
+        let options = BuildOptions {
+            fee_rate: psbt::fee_rate(req.fee_rate)?,
+            change_type,
+            change_index: req.change_index,
+            finality: Finality {
+                rbf: req.rbf.unwrap_or(true),
+                lock_time: LockTime::from_consensus(req.locktime),
+            },
+        };
+        let utxos = Self::utxos(&chain_types, &req.utxos)?;
+        let payouts = Self::payouts(&chain_types, &req.outputs)?;
+
+        info!(
+            "Building a {} PSBT from {} UTXOs at {} sat/vB on {}",
+            req.symbol,
+            utxos.len(),
+            req.fee_rate,
+            network
+        );
+
+        let language = self.mnemonic_language(&mnemonic, &req.language).await?;
+        let request = BuildRequest {
+            chain_types,
+            network: network.bitcoin_network(),
+            account: req.account,
+            utxos,
+            payouts,
+            options,
+            allow_missing_previous_transactions: req.allow_missing_previous_transactions,
+        };
+        let built = self.wallet_service
+            .build_psbt(&mnemonic, &passphrase, Some(language), request)
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(BuildPsbtResponse {
+            psbt: psbt::encode(&built.psbt),
+            algorithm: built.algorithm.to_string(),
+            selected_utxos: built
+                .psbt
+                .unsigned_tx
+                .input
+                .iter()
+                .map(|input| input.previous_output.to_string())
+                .collect(),
+            fee: built.fee.to_sat(),
+            vsize: built.vsize,
+            change: built.change.map(|change| ChangeOutput {
+                output_index: change.output as u32,
+                address: change.address,
+                derivation_path: change.path.to_string(),
+                value: change.value.to_sat(),
+            }),
+            mnemonic_language: language_code(language).to_string(),
+        }))
+    }
+
+    async fn sign_message(
+        &self,
+        request: Request<SignMessageRequest>,
+    ) -> Result<Response<SignMessageResponse>, Status> {
+        check_auth(&request)?;
+        let mut req = request.into_inner();
+        let (mnemonic, passphrase) = take_secrets(&mut req.mnemonic, &mut req.passphrase);
+
+        let chain_types = psbt::chain_types(&req.symbol)?;
+        let chain_type = psbt::address_type(&chain_types, &req.address_type)?;
+        let network = Self::network(&req.network)?;
+        let format = Self::message_format(&req.format)?;
+
+        info!("Signing a message with {:?} index {} on {}", chain_type, req.index, network);
+
+        let language = self.mnemonic_language(&mnemonic, &req.language).await?;
+        let request = MessageRequest {
+            chain_type,
+            network,
+            account: req.account,
+            index: req.index,
+            message: std::mem::take(&mut req.message),
+            format,
+        };
+        let signed = self.wallet_service
+            .sign_message(&mnemonic, &passphrase, Some(language), request)
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(SignMessageResponse {
+            address: signed.address,
+            derivation_path: signed.derivation_path,
+            format: signed.format.to_string(),
+            signature: signed.signature,
+            mnemonic_language: language_code(language).to_string(),
+        }))
+    }
+
+    async fn verify_message(
+        &self,
+        request: Request<VerifyMessageRequest>,
+    ) -> Result<Response<VerifyMessageResponse>, Status> {
+        check_auth(&request)?;
+        let req = request.into_inner();
+
+        let chain_types = psbt::chain_types(&req.symbol)?;
+        let network = Self::network(&req.network)?;
+
+        info!("Verifying a {} message signature on {}", req.symbol, network);
+
+        let verification = self.wallet_service
+            .verify_message(&chain_types, network, &req.address, &req.message, &req.signature)
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(VerifyMessageResponse {
+            valid: verification.valid,
+            format: verification.format.map(|format| format.to_string()),
+        }))
+    }
 }
\ No newline at end of file
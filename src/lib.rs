@@ -2,5 +2,6 @@ pub mod api;
 pub mod chains;
 pub mod core;
 pub mod errors;
+pub mod grpc;
 pub mod middleware;
 pub mod services;
\ No newline at end of file
@@ -1,23 +1,14 @@
 use actix_web::{web, App, HttpServer, middleware as actix_middleware};
 use actix_cors::Cors;
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
-mod api;
-mod chains;
-mod core;
-mod errors;
-mod services;
-mod middleware;
-mod grpc;
-
-use api::handlers;
-use services::wallet::WalletService;
-use middleware::auth::ApiKeyAuth;
-use grpc::{HealthServiceImpl, MnemonicServiceImpl, WalletServiceImpl};
-use grpc::wallet_proto::{
+use crypto_wallet_api::api::handlers;
+use crypto_wallet_api::services::wallet::{PrivateKeyPolicy, WalletService};
+use crypto_wallet_api::middleware::auth::ApiKeyAuth;
+use crypto_wallet_api::grpc::{HealthServiceImpl, MnemonicServiceImpl, WalletServiceImpl};
+use crypto_wallet_api::grpc::wallet_proto::{
     health_service_server::HealthServiceServer,
     mnemonic_service_server::MnemonicServiceServer,
     wallet_service_server::WalletServiceServer,
@@ -52,18 +43,25 @@ async fn main() -> std::io::Result<()> {
         .parse()
         .expect("GRPC_PORT must be a valid number");
     
+    // Gateways that only need addresses can switch private key output off entirely
+    let private_key_policy = match std::env::var("DISABLE_PRIVATE_KEY_EXPORT") {
+        Ok(value) if value == "true" || value == "1" => PrivateKeyPolicy::Forbid,
+        _ => PrivateKeyPolicy::Allow,
+    };
+    
     info!("Starting Multichain Wallet API:");
     info!("  HTTP server on {}:{}", host, http_port);
     info!("  gRPC server on {}:{}", host, grpc_port);
+    info!("  Private key export: {:?}", private_key_policy);
     
-    // Create shared wallet service
-    let wallet_service = Arc::new(Mutex::new(WalletService::new()));
+    // Create shared wallet service; it is immutable, so requests never wait on each other
+    let wallet_service = Arc::new(WalletService::new().with_private_key_policy(private_key_policy));
     
     // Clone for gRPC services
     let grpc_wallet_service = wallet_service.clone();
     
     // Create gRPC services
-    let health_service = HealthServiceImpl::default();
+    let health_service = HealthServiceImpl;
     let mnemonic_service = MnemonicServiceImpl::new(grpc_wallet_service.clone());
     let wallet_service_grpc = WalletServiceImpl::new(grpc_wallet_service);
     
@@ -96,9 +94,26 @@ async fn main() -> std::io::Result<()> {
                     .service(handlers::health_check)
                     .service(handlers::generate_mnemonic)
                     .service(handlers::validate_mnemonic)
+                    .service(handlers::assist_mnemonic)
+                    .service(handlers::entropy_to_mnemonic)
+                    .service(handlers::mnemonic_to_entropy)
+                    .service(handlers::mnemonic_to_seed)
+                    .service(handlers::split_slip39)
+                    .service(handlers::combine_slip39)
+                    .service(handlers::derive_bip85)
                     .service(handlers::get_supported_languages)
                     .service(handlers::generate_wallet)
                     .service(handlers::batch_generate_wallets)
+                    .service(handlers::export_account_xpub)
+                    .service(handlers::derive_public_addresses)
+                    .service(handlers::validate_address)
+                    .service(handlers::derive_multisig)
+                    .service(handlers::compile_policy)
+                    .service(handlers::import_private_key)
+                    .service(handlers::sign_psbt)
+                    .service(handlers::build_psbt)
+                    .service(handlers::sign_message)
+                    .service(handlers::verify_message)
             )
     })
     .bind(http_addr)?
@@ -1,67 +1,158 @@
 use bip39::{Language, Mnemonic};
 use rand::RngCore;
-use std::collections::HashMap;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use zeroize::Zeroizing;
 
 use crate::chains;
-use crate::core::{Chain, ChainInfo, ChainType, WalletAddress, get_all_chain_types};
+use crate::chains::bitcoin::{derive_public_child, XpubFormat};
+use crate::chains::message::{self, MessageRequest, SignedMessage, Verification};
+use crate::chains::multisig::{Cosigner, MultisigAddress, MultisigWallet};
+use crate::chains::policy::{self, CompiledPolicy};
+use crate::chains::psbt::{BuildRequest, BuiltPsbt, Keychain, SignedPsbt, SigningRequest, Unsigned};
+use crate::core::bip85::{self, Bip85Application};
+use crate::core::entropy::{decode_entropy, entropy_length};
+use crate::core::mnemonic::{self as bip39_mnemonic, parse_language, parse_mnemonic};
+use crate::core::mnemonic_assistant::{self, MnemonicReport};
+use crate::core::slip39::{self, Slip39Scheme, Slip39Shares};
+use crate::core::{AddressLevel, EntropySource, Chain, ChainInfo, ChainType, DerivationPath, ExtendedPublicKey, ImportedKey, KeyFormat, KeyTree, MultisigScriptType, NetworkType, PolicyScriptType, SecretBytes, SecretString, WalletAddress, WatchOnlyAddress, get_all_chain_types};
 use crate::errors::{ApiError, ApiResult};
 
+/// Per-request settings that shape how addresses are derived
+#[derive(Debug, Clone, Default)]
+pub struct DerivationOptions {
+    /// BIP44 account level
+    pub account: u32,
+    /// Path template overriding each chain's default path
+    pub derivation_path: Option<String>,
+    /// Use the pre-SLIP-0010 ed25519 derivation Tezos used to ship with
+    pub legacy_ed25519_derivation: bool,
+    /// Whether to return private keys; `None` leaves it to the server's policy
+    pub include_private_key: Option<bool>,
+    /// Private key encoding; `None` uses each chain's default
+    pub key_format: Option<KeyFormat>,
+    /// Mnemonic wordlist; `None` detects it from the words
+    pub language: Option<Language>,
+    /// Network Bitcoin and Dogecoin addresses and keys are encoded for
+    pub network: NetworkType,
+}
+
+/// Server-wide rule for returning private keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrivateKeyPolicy {
+    /// Requests decide with `include_private_key`; keys are returned unless they opt out
+    #[default]
+    Allow,
+    /// Private keys are never encoded, and requests asking for them are refused
+    Forbid,
+}
+
+impl PrivateKeyPolicy {
+    /// Resolve a request's `include_private_key` against the policy
+    pub fn include_private_key(self, requested: Option<bool>) -> ApiResult<bool> {
+        match (self, requested) {
+            (PrivateKeyPolicy::Allow, requested) => Ok(requested.unwrap_or(true)),
+            (PrivateKeyPolicy::Forbid, Some(true)) => Err(ApiError::PrivateKeyExportDisabled),
+            (PrivateKeyPolicy::Forbid, _) => Ok(false),
+        }
+    }
+
+    /// Refuse, under `Forbid`, requests whose whole answer is key material, such as seeds
+    pub fn check_secret_export(self) -> ApiResult<()> {
+        match self {
+            PrivateKeyPolicy::Allow => Ok(()),
+            PrivateKeyPolicy::Forbid => Err(ApiError::PrivateKeyExportDisabled),
+        }
+    }
+}
+
 pub struct WalletService {
-    // Cache chains for performance
-    chains_cache: HashMap<ChainType, Arc<dyn Chain>>,
+    // Every chain is built once per network up front and never mutated, so the
+    // service can be shared across requests without a lock
+    chains: Arc<HashMap<(ChainType, NetworkType), Arc<dyn Chain>>>,
+    private_key_policy: PrivateKeyPolicy,
+}
+
+impl Default for WalletService {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl WalletService {
     pub fn new() -> Self {
+        let chains = get_all_chain_types()
+            .into_iter()
+            .flat_map(|chain_type| {
+                NetworkType::ALL.into_iter().filter_map(move |network| {
+                    let chain = chains::create_chain(&chain_type, network.bitcoin_network())?;
+                    Some(((chain_type.clone(), network), chain))
+                })
+            })
+            .collect();
+        
         Self {
-            chains_cache: HashMap::new(),
+            chains: Arc::new(chains),
+            private_key_policy: PrivateKeyPolicy::default(),
         }
     }
 
-    fn get_or_create_chain(&mut self, chain_type: &ChainType) -> Arc<dyn Chain> {
-        self.chains_cache
-            .entry(chain_type.clone())
-            .or_insert_with(|| chains::create_chain(chain_type))
-            .clone()
+    pub fn with_private_key_policy(mut self, policy: PrivateKeyPolicy) -> Self {
+        self.private_key_policy = policy;
+        self
+    }
+
+    pub fn private_key_policy(&self) -> PrivateKeyPolicy {
+        self.private_key_policy
+    }
+
+    fn chain(&self, chain_type: &ChainType, network: NetworkType) -> ApiResult<Arc<dyn Chain>> {
+        self.chains
+            .get(&(chain_type.clone(), network))
+            .cloned()
+            .ok_or_else(|| ApiError::BadRequest(format!("Unsupported chain: {} on {}", chain_type, network)))
+    }
+
+    fn resolve_chain(&self, chain_type: &ChainType, options: &DerivationOptions) -> ApiResult<Arc<dyn Chain>> {
+        if options.legacy_ed25519_derivation {
+            chains::create_legacy_chain(chain_type).ok_or_else(|| {
+                ApiError::BadRequest(format!("{} has no legacy ed25519 derivation", chain_type))
+            })
+        } else {
+            self.chain(chain_type, options.network)
+        }
     }
 
     pub async fn generate_mnemonic(
         &self,
         language_str: &str,
         word_count: u32,
-    ) -> ApiResult<String> {
-        let language = self.parse_language(language_str)?;
+    ) -> ApiResult<SecretString> {
+        let language = parse_language(language_str)?;
         
         // Generate entropy based on word count
-        let entropy_bytes = match word_count {
-            12 => 16, // 128 bits
-            15 => 20, // 160 bits
-            18 => 24, // 192 bits
-            21 => 28, // 224 bits
-            24 => 32, // 256 bits
-            _ => return Err(ApiError::InvalidWordCount(word_count)),
-        };
+        let entropy_bytes = entropy_length(word_count)?;
 
         // Generate random entropy
-        let mut entropy = vec![0u8; entropy_bytes];
+        let mut entropy = Zeroizing::new(vec![0u8; entropy_bytes]);
         rand::thread_rng().fill_bytes(&mut entropy);
 
         // Create mnemonic from entropy
         let mnemonic = Mnemonic::from_entropy_in(language, &entropy)
             .map_err(|e| ApiError::CryptoError(e.to_string()))?;
 
-        Ok(mnemonic.to_string())
+        Ok(SecretString::new(mnemonic.to_string()))
     }
 
-    pub async fn validate_mnemonic(&self, mnemonic_str: &str, language_str: &str) -> (bool, u32) {
-        let language = match self.parse_language(language_str) {
+    pub async fn validate_mnemonic(&self, mnemonic: &SecretString, language_str: &str) -> (bool, u32) {
+        let language = match parse_language(language_str) {
             Ok(lang) => lang,
             Err(_) => return (false, 0),
         };
 
-        match Mnemonic::parse_in_normalized(language, mnemonic_str) {
-            Ok(mnemonic) => {
+        match parse_mnemonic(mnemonic, Some(language)) {
+            Ok((mnemonic, _)) => {
                 let word_count = mnemonic.word_count() as u32;
                 (true, word_count)
             }
@@ -69,50 +160,579 @@ impl WalletService {
         }
     }
 
+    /// Invalid words with suggested corrections, checksum-valid repairs and, for a
+    /// phrase one word short, the final words that complete it
+    pub async fn assist_mnemonic(&self, mnemonic: &SecretString, language: Option<&str>) -> ApiResult<MnemonicReport> {
+        let language = language.map(parse_language).transpose()?;
+        let mnemonic = mnemonic.clone();
+        
+        run_blocking(move || mnemonic_assistant::analyze(&mnemonic, language)).await
+    }
+
+    /// Mnemonic encoding caller-supplied entropy (hex, dice rolls or coin flips;
+    /// see `core::entropy` for the conversions)
+    pub async fn entropy_to_mnemonic(
+        &self,
+        entropy: &SecretString,
+        source: EntropySource,
+        word_count: Option<u32>,
+        language_str: &str,
+    ) -> ApiResult<SecretString> {
+        let language = parse_language(language_str)?;
+        let entropy = decode_entropy(source, entropy.expose_secret(), word_count)?;
+        
+        let mnemonic = Mnemonic::from_entropy_in(language, &entropy)
+            .map_err(|e| ApiError::InvalidEntropy(e.to_string()))?;
+        
+        Ok(SecretString::new(mnemonic.to_string()))
+    }
+
+    /// Hex entropy a mnemonic encodes
+    pub async fn mnemonic_to_entropy(&self, mnemonic: &SecretString, language_str: &str) -> ApiResult<SecretString> {
+        let (mnemonic, _) = parse_mnemonic(mnemonic, Some(parse_language(language_str)?))?;
+        let (mut entropy, length) = mnemonic.to_entropy_array();
+        
+        Ok(SecretString::hex_encode(&mut entropy[..length]))
+    }
+
+    /// Hex 64-byte BIP39 seed of a mnemonic and passphrase
+    pub async fn mnemonic_to_seed(
+        &self,
+        mnemonic: &SecretString,
+        passphrase: &SecretString,
+        language_str: &str,
+    ) -> ApiResult<SecretString> {
+        // The seed is the root of every private key
+        self.private_key_policy.check_secret_export()?;
+        let (mnemonic, _) = parse_mnemonic(mnemonic, Some(parse_language(language_str)?))?;
+        let passphrase = passphrase.clone();
+        
+        run_blocking(move || Ok(SecretString::hex_encode(*bip39_mnemonic::to_seed(&mnemonic, &passphrase)))).await
+    }
+
+    /// Split a master secret into SLIP-39 shares encrypted with `passphrase`. The secret
+    /// is hex; when `None`, a random one of `strength` bits is generated.
+    pub async fn split_slip39(
+        &self,
+        master_secret: Option<&SecretString>,
+        strength: u32,
+        passphrase: &SecretString,
+        scheme: &Slip39Scheme,
+    ) -> ApiResult<Slip39Shares> {
+        let master_secret = match master_secret {
+            Some(hex_secret) => decode_master_secret(hex_secret)?,
+            None => slip39::generate_master_secret(strength)?,
+        };
+        let passphrase = passphrase.clone();
+        let scheme = scheme.clone();
+        
+        run_blocking(move || slip39::split(master_secret.expose_secret(), &passphrase, &scheme)).await
+    }
+
+    /// Master secret of a set of SLIP-39 shares. It is the BIP32 seed of the backed-up
+    /// wallet, ready for `generate_wallet_address_from_seed`. Refused when private key export
+    /// is disabled.
+    pub async fn combine_slip39(&self, shares: &[SecretString], passphrase: &SecretString) -> ApiResult<SecretBytes> {
+        self.private_key_policy.check_secret_export()?;
+        let shares = shares.to_vec();
+        let passphrase = passphrase.clone();
+        
+        run_blocking(move || slip39::combine(&shares, &passphrase)).await
+    }
+
+    /// BIP85 child of a mnemonic for `application` at `index`: a mnemonic, key, hex
+    /// entropy or password that only the mnemonic can recompute. Only passwords are
+    /// returned when private key export is disabled.
+    pub async fn derive_bip85(
+        &self,
+        mnemonic: &SecretString,
+        passphrase: &SecretString,
+        language: Option<Language>,
+        application: Bip85Application,
+        index: u32,
+    ) -> ApiResult<SecretString> {
+        if application.is_key_material() {
+            self.private_key_policy.check_secret_export()?;
+        }
+        let mnemonic = mnemonic.clone();
+        let passphrase = passphrase.clone();
+        
+        run_blocking(move || {
+            let keys = Self::key_tree(&mnemonic, &passphrase, language)?;
+            let master = keys.secp256k1_key(&DerivationPath::hardened(&[]))?;
+            bip85::derive(&master, keys.secp(), &application, index)
+        })
+        .await
+    }
+
+    /// Wordlist of a mnemonic: the named `language`, or the detected one when `None`.
+    /// Fails if the words fit several wordlists and no language is named.
+    pub async fn mnemonic_language(&self, mnemonic: &SecretString, language: Option<&str>) -> ApiResult<Language> {
+        let language = language.map(parse_language).transpose()?;
+        bip39_mnemonic::detect_language(mnemonic, language)
+    }
+
+    /// Parse the mnemonic and run the 2048-round PBKDF2 seed derivation, once per request
+    fn key_tree(mnemonic: &SecretString, passphrase: &SecretString, language: Option<Language>) -> ApiResult<KeyTree> {
+        let (mnemonic, _) = parse_mnemonic(mnemonic, language)?;
+        let seed = bip39_mnemonic::to_seed(&mnemonic, passphrase);
+        
+        Ok(KeyTree::new(&seed[..]))
+    }
+
     pub async fn generate_wallet_address(
-        &mut self,
-        mnemonic_str: &str,
-        passphrase: &str,
+        &self,
+        mnemonic: &SecretString,
+        passphrase: &SecretString,
         chain_type: &ChainType,
         index: u32,
+        options: &DerivationOptions,
     ) -> ApiResult<WalletAddress> {
-        // Parse mnemonic
-        let mnemonic = Mnemonic::parse(mnemonic_str)
-            .map_err(|_| ApiError::InvalidMnemonic)?;
+        let mnemonic = mnemonic.clone();
+        let seed_passphrase = passphrase.clone();
+        let language = options.language;
+        
+        self.derive_address(
+            move || Self::key_tree(&mnemonic, &seed_passphrase, language),
+            passphrase,
+            chain_type,
+            index,
+            options,
+        )
+        .await
+    }
+
+    /// BIP32 seed of a request that gives a hex `master_secret`, such as a recovered SLIP-39
+    /// master secret, in place of a mnemonic; `None` when it gives a mnemonic
+    pub fn master_secret_seed(
+        &self,
+        mnemonic: &SecretString,
+        passphrase: &SecretString,
+        master_secret: Option<&SecretString>,
+    ) -> ApiResult<Option<SecretBytes>> {
+        let Some(master_secret) = master_secret else {
+            return Ok(None);
+        };
+        if !mnemonic.expose_secret().trim().is_empty() {
+            return Err(ApiError::BadRequest("Pass either a mnemonic or a master_secret, not both".to_string()));
+        }
+        // A SLIP-39 passphrase is applied when the shares are combined
+        if !passphrase.expose_secret().is_empty() {
+            return Err(ApiError::BadRequest("A master_secret takes no passphrase".to_string()));
+        }
+
+        let seed = decode_master_secret(master_secret)?;
+        if !(16..=64).contains(&seed.expose_secret().len()) {
+            return Err(ApiError::BadRequest("A master secret must be 16 to 64 bytes".to_string()));
+        }
+        Ok(Some(seed))
+    }
 
-        // Generate seed from mnemonic and passphrase
-        let seed = mnemonic.to_seed(passphrase);
+    /// `generate_wallet_address` for a BIP32 seed that does not come from a BIP39
+    /// mnemonic, such as a recovered SLIP-39 master secret
+    pub async fn generate_wallet_address_from_seed(
+        &self,
+        seed: &SecretBytes,
+        chain_type: &ChainType,
+        index: u32,
+        options: &DerivationOptions,
+    ) -> ApiResult<WalletAddress> {
+        let seed = seed.clone();
+        
+        self.derive_address(
+            move || Ok(KeyTree::new(seed.expose_secret())),
+            &SecretString::default(),
+            chain_type,
+            index,
+            options,
+        )
+        .await
+    }
+
+    async fn derive_address<K>(
+        &self,
+        key_tree: K,
+        passphrase: &SecretString,
+        chain_type: &ChainType,
+        index: u32,
+        options: &DerivationOptions,
+    ) -> ApiResult<WalletAddress>
+    where
+        K: FnOnce() -> ApiResult<KeyTree> + Send + 'static,
+    {
+        let chain = self.resolve_chain(chain_type, options)?;
+        let include_private_key = self.private_key_policy.include_private_key(options.include_private_key)?;
         
-        // Get or create chain instance
-        let chain = self.get_or_create_chain(chain_type);
+        let passphrase = passphrase.clone();
+        let chain_type = chain_type.clone();
+        let options = options.clone();
+        
+        run_blocking(move || {
+            let keys = key_tree()?;
+            Self::derive_wallet_address(chain.as_ref(), &keys, passphrase.expose_secret(), &chain_type, index, &options, include_private_key)
+        })
+        .await
+    }
+
+    fn derive_wallet_address(
+        chain: &dyn Chain,
+        keys: &KeyTree,
+        passphrase: &str,
+        chain_type: &ChainType,
+        index: u32,
+        options: &DerivationOptions,
+        include_private_key: bool,
+    ) -> ApiResult<WalletAddress> {
+        let path = options
+            .derivation_path
+            .as_deref()
+            .map(|template| DerivationPath::from_template(template, options.account, index))
+            .transpose()?;
+
+        // Account-model chains have no level below the account, so the index counts
+        // accounts onwards from `account` and a batch walks through accounts
+        let account = match chain.address_level() {
+            AddressLevel::Account if path.is_none() => options.account.checked_add(index).ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "{} derives one address per account, and account {} + index {} overflows",
+                    chain_type, options.account, index
+                ))
+            })?,
+            _ => options.account,
+        };
+
+        let key_format = include_private_key.then(|| options.key_format.unwrap_or_else(|| chain.default_key_format()));
         
         // Generate address
-        chain.generate_address(&seed, passphrase, index).await
+        chain.generate_address(keys, passphrase, account, index, path.as_ref(), key_format)
     }
 
     pub async fn batch_generate_wallet_addresses(
-        &mut self,
-        mnemonic_str: &str,
-        passphrase: &str,
+        &self,
+        mnemonic: &SecretString,
+        passphrase: &SecretString,
+        chain_types: &[ChainType],
+        start_index: u32,
+        count: u32,
+        options: &DerivationOptions,
+    ) -> ApiResult<Vec<WalletAddress>> {
+        let mnemonic = mnemonic.clone();
+        let seed_passphrase = passphrase.clone();
+        let language = options.language;
+        
+        self.derive_batch(
+            move || Self::key_tree(&mnemonic, &seed_passphrase, language),
+            passphrase,
+            chain_types,
+            start_index,
+            count,
+            options,
+        )
+        .await
+    }
+
+    /// `batch_generate_wallet_addresses` for a BIP32 seed such as a recovered SLIP-39
+    /// master secret
+    pub async fn batch_generate_wallet_addresses_from_seed(
+        &self,
+        seed: &SecretBytes,
         chain_types: &[ChainType],
         start_index: u32,
         count: u32,
+        options: &DerivationOptions,
     ) -> ApiResult<Vec<WalletAddress>> {
-        let mut addresses = Vec::new();
+        let seed = seed.clone();
+        
+        self.derive_batch(
+            move || Ok(KeyTree::new(seed.expose_secret())),
+            &SecretString::default(),
+            chain_types,
+            start_index,
+            count,
+            options,
+        )
+        .await
+    }
+
+    async fn derive_batch<K>(
+        &self,
+        key_tree: K,
+        passphrase: &SecretString,
+        chain_types: &[ChainType],
+        start_index: u32,
+        count: u32,
+        options: &DerivationOptions,
+    ) -> ApiResult<Vec<WalletAddress>>
+    where
+        K: FnOnce() -> ApiResult<KeyTree> + Send + 'static,
+    {
+        // A fixed custom path would derive the same key for every index
+        if let Some(template) = &options.derivation_path {
+            if count > 1 && !template.contains("{index}") {
+                return Err(ApiError::InvalidDerivationPath(format!(
+                    "{}: batch paths must contain an {{index}} placeholder",
+                    template
+                )));
+            }
+        }
+
+        let include_private_key = self.private_key_policy.include_private_key(options.include_private_key)?;
+        let end_index = start_index.checked_add(count).ok_or_else(|| {
+            ApiError::BadRequest(format!("start_index {} + count {} overflows the index range", start_index, count))
+        })?;
         
+        // One job per (chain, index), in the order the addresses are returned
+        let mut jobs = Vec::new();
         for chain_type in chain_types {
-            for i in start_index..(start_index + count) {
-                let wallet = self.generate_wallet_address(
-                    mnemonic_str,
-                    passphrase,
-                    chain_type,
-                    i,
-                ).await?;
-                addresses.push(wallet);
+            let chain = self.resolve_chain(chain_type, options)?;
+            for i in start_index..end_index {
+                jobs.push((chain_type.clone(), chain.clone(), i));
             }
         }
         
-        Ok(addresses)
+        let passphrase = passphrase.clone();
+        let options = options.clone();
+        
+        run_blocking(move || {
+            // One seed for the whole batch; the key tree then caches each chain's
+            // account node so every further index only derives its last level
+            let keys = key_tree()?;
+            
+            jobs.par_iter()
+                .map(|(chain_type, chain, i)| {
+                    Self::derive_wallet_address(chain.as_ref(), &keys, passphrase.expose_secret(), chain_type, *i, &options, include_private_key)
+                })
+                .collect()
+        })
+        .await
+    }
+
+    pub async fn export_account_xpub(
+        &self,
+        mnemonic: &SecretString,
+        passphrase: &SecretString,
+        chain_type: &ChainType,
+        account: u32,
+        language: Option<Language>,
+        network: NetworkType,
+    ) -> ApiResult<ExtendedPublicKey> {
+        let chain = self.chain(chain_type, network)?;
+        
+        let mnemonic = mnemonic.clone();
+        let passphrase = passphrase.clone();
+        
+        run_blocking(move || {
+            let keys = Self::key_tree(&mnemonic, &passphrase, language)?;
+            chain.account_xpub(&keys, account)
+        })
+        .await
+    }
+
+    /// Derive `change/index` addresses from an account-level extended public key,
+    /// without access to the mnemonic
+    pub async fn derive_public_addresses(
+        &self,
+        extended_public_key: &str,
+        chain_type: &ChainType,
+        network: NetworkType,
+        change: u32,
+        start_index: u32,
+        count: u32,
+    ) -> ApiResult<Vec<WatchOnlyAddress>> {
+        let (format, xpub) = XpubFormat::decode(extended_public_key)?;
+        let chain = self.chain(chain_type, network)?;
+        
+        // A ypub or zpub names the script type its addresses use; deriving another
+        // type from it gives addresses outside the wallet. xpub and tpub say nothing.
+        if !format.fits(chain_type) {
+            return Err(ApiError::BadRequest(format!(
+                "A {} extended public key is not for {} addresses; use {} or {}",
+                format.prefix(xpub.network),
+                chain_type,
+                XpubFormat::for_chain(chain_type).prefix(xpub.network),
+                XpubFormat::Xpub.prefix(xpub.network)
+            )));
+        }
+        // A tpub's addresses belong on a test network, an xpub's on mainnet
+        let mainnet_key = xpub.network == bitcoin::Network::Bitcoin;
+        if chains::is_network_specific(chain_type) && mainnet_key != (network == NetworkType::Mainnet) {
+            return Err(ApiError::BadRequest(format!(
+                "A {} extended public key cannot derive {} addresses",
+                format.prefix(xpub.network),
+                network
+            )));
+        }
+        let chain_type = chain_type.clone();
+        
+        run_blocking(move || {
+            (start_index..start_index.saturating_add(count))
+                .into_par_iter()
+                .map(|index| {
+                    let public_key = derive_public_child(&xpub, change, index)?.serialize();
+                    let address = chain.address_from_public_key(&public_key)?;
+                    
+                    Ok(WatchOnlyAddress {
+                        address,
+                        chain_type: chain_type.clone(),
+                        chain_info: chain.info(),
+                        relative_path: format!("{}/{}", change, index),
+                        change,
+                        index,
+                        public_key: hex::encode(public_key),
+                        public_key_formats: chain.public_key_formats(&public_key)?,
+                    })
+                })
+                .collect()
+        })
+        .await
+    }
+
+    /// This wallet's BIP48 account key, to join a multisig wallet as a cosigner
+    pub async fn multisig_cosigner(
+        &self,
+        mnemonic: &SecretString,
+        passphrase: &SecretString,
+        language: Option<Language>,
+        network: NetworkType,
+        account: u32,
+        script_type: MultisigScriptType,
+    ) -> ApiResult<Cosigner> {
+        let mnemonic = mnemonic.clone();
+        let passphrase = passphrase.clone();
+        
+        run_blocking(move || {
+            let keys = Self::key_tree(&mnemonic, &passphrase, language)?;
+            Cosigner::from_keys(&keys, network.bitcoin_network(), account, script_type)
+        })
+        .await
+    }
+
+    /// Derive `change/index` addresses of a multisig wallet from its cosigners' keys
+    pub async fn derive_multisig_addresses(
+        &self,
+        wallet: MultisigWallet,
+        change: u32,
+        start_index: u32,
+        count: u32,
+    ) -> ApiResult<Vec<MultisigAddress>> {
+        run_blocking(move || {
+            (start_index..start_index.saturating_add(count))
+                .into_par_iter()
+                .map(|index| wallet.address(change, index))
+                .collect()
+        })
+        .await
+    }
+
+    /// Compile a spending policy to a P2WSH or Taproot address; key names in it are
+    /// looked up in `keys`
+    pub async fn compile_policy(
+        &self,
+        policy: &str,
+        keys: &BTreeMap<String, String>,
+        script_type: PolicyScriptType,
+        network: NetworkType,
+    ) -> ApiResult<CompiledPolicy> {
+        let policy = policy.to_string();
+        let keys = keys.clone();
+        
+        run_blocking(move || policy::compile(&policy, &keys, script_type, network.bitcoin_network())).await
+    }
+
+    /// Sign a PSBT, or a spend of UTXOs built into one, with the mnemonic's keys
+    pub async fn sign_psbt(
+        &self,
+        mnemonic: &SecretString,
+        passphrase: &SecretString,
+        language: Option<Language>,
+        request: SigningRequest,
+    ) -> ApiResult<SignedPsbt> {
+        let mnemonic = mnemonic.clone();
+        let passphrase = passphrase.clone();
+        
+        run_blocking(move || {
+            let keys = Self::key_tree(&mnemonic, &passphrase, language)?;
+            let mut keychain = Keychain::new(&keys, request.chain_types, request.network, request.account)?
+                .allow_missing_previous_transactions(request.allow_missing_previous_transactions)
+                .allow_any_sighash(request.allow_any_sighash);
+            let psbt = match request.transaction {
+                Unsigned::Psbt(psbt) => psbt,
+                Unsigned::Spend { utxos, payouts } => keychain.create(&utxos, &payouts)?,
+            };
+            
+            keychain.sign(psbt, request.sign_only)
+        })
+        .await
+    }
+
+    /// Select UTXOs for payouts at a fee rate into an unsigned PSBT, with change on the
+    /// mnemonic's change chain
+    pub async fn build_psbt(
+        &self,
+        mnemonic: &SecretString,
+        passphrase: &SecretString,
+        language: Option<Language>,
+        request: BuildRequest,
+    ) -> ApiResult<BuiltPsbt> {
+        let mnemonic = mnemonic.clone();
+        let passphrase = passphrase.clone();
+        
+        run_blocking(move || {
+            let keys = Self::key_tree(&mnemonic, &passphrase, language)?;
+            let keychain = Keychain::new(&keys, request.chain_types, request.network, request.account)?
+                .allow_missing_previous_transactions(request.allow_missing_previous_transactions);
+            keychain.build(&request.utxos, &request.payouts, &request.options, &mut rand::thread_rng())
+        })
+        .await
+    }
+
+    /// Prove ownership of the mnemonic's address at an index by signing a message with
+    /// its key
+    pub async fn sign_message(
+        &self,
+        mnemonic: &SecretString,
+        passphrase: &SecretString,
+        language: Option<Language>,
+        request: MessageRequest,
+    ) -> ApiResult<SignedMessage> {
+        let chain = self.chain(&request.chain_type, request.network)?;
+        let mnemonic = mnemonic.clone();
+        let passphrase = passphrase.clone();
+        
+        run_blocking(move || {
+            let keys = Self::key_tree(&mnemonic, &passphrase, language)?;
+            let path = chain.derivation_path(request.account, request.index);
+            let wallet = chain.generate_address(&keys, "", request.account, request.index, Some(&path), None)?;
+            
+            message::sign(&keys, &wallet, request.network.bitcoin_network(), &request.message, request.format)
+        })
+        .await
+    }
+
+    /// Check a message signature by any address of the coin `chain_types` belong to
+    pub async fn verify_message(
+        &self,
+        chain_types: &[ChainType],
+        network: NetworkType,
+        address: &str,
+        text: &str,
+        signature: &str,
+    ) -> ApiResult<Verification> {
+        let dogecoin = chain_types.contains(&ChainType::Dogecoin);
+        message::verify(address, text, signature, network.bitcoin_network(), dogecoin)
+    }
+
+    /// Address and public key controlled by a raw private key, without any mnemonic
+    pub async fn import_private_key(
+        &self,
+        private_key: &SecretString,
+        chain_type: &ChainType,
+        network: NetworkType,
+    ) -> ApiResult<ImportedKey> {
+        let chain = self.chain(chain_type, network)?;
+        let private_key = private_key.clone();
+        
+        run_blocking(move || chain.address_from_private_key(&private_key)).await
     }
 
     pub async fn get_chain_info(&self, chain_type: &ChainType) -> ChainInfo {
@@ -126,24 +746,535 @@ impl WalletService {
             .collect()
     }
 
-    pub async fn validate_address(&mut self, chain_type: &ChainType, address: &str) -> bool {
-        let chain = self.get_or_create_chain(chain_type);
-        chain.validate_address(address).await
+    pub async fn validate_address(&self, chain_type: &ChainType, network: NetworkType, address: &str) -> bool {
+        match self.chain(chain_type, network) {
+            Ok(chain) => chain.validate_address(address).await,
+            Err(_) => false,
+        }
+    }
+
+    /// Whether `address` is a P2SH address wrapping a witness program: `redeem_script`, or
+    /// the P2WPKH program of `public_key` (both hex). Exactly one of the two must be given.
+    pub async fn is_nested_witness_address(
+        &self,
+        network: NetworkType,
+        address: &str,
+        redeem_script: Option<&str>,
+        public_key: Option<&str>,
+    ) -> ApiResult<bool> {
+        let redeem_script = match (redeem_script, public_key) {
+            (Some(redeem_script), None) => hex::decode(redeem_script)
+                .map_err(|_| ApiError::BadRequest("redeem_script must be hex".to_string()))?,
+            (None, Some(public_key)) => {
+                let public_key = hex::decode(public_key)
+                    .map_err(|_| ApiError::BadRequest("public_key must be hex".to_string()))?;
+                chains::bitcoin::p2wpkh_redeem_script(&public_key)?.into_bytes()
+            }
+            _ => return Err(ApiError::BadRequest("Pass either a redeem_script or a public_key".to_string())),
+        };
+        
+        Ok(chains::bitcoin::is_nested_witness_address(address, &redeem_script, network.bitcoin_network()))
     }
+}
 
-    fn parse_language(&self, language: &str) -> ApiResult<Language> {
-        match language.to_lowercase().as_str() {
-            "english" => Ok(Language::English),
-            "japanese" => Ok(Language::Japanese),
-            "korean" => Ok(Language::Korean),
-            "spanish" => Ok(Language::Spanish),
-            "chinese_simplified" => Ok(Language::SimplifiedChinese),
-            "chinese_traditional" => Ok(Language::TraditionalChinese),
-            "french" => Ok(Language::French),
-            "italian" => Ok(Language::Italian),
-            "czech" => Ok(Language::Czech),
-            "portuguese" => Ok(Language::Portuguese),
-            _ => Err(ApiError::InvalidLanguage(language.to_string())),
+/// Hex master secret, with or without a `0x` prefix
+fn decode_master_secret(hex_secret: &SecretString) -> ApiResult<SecretBytes> {
+    let hex_secret = hex_secret.expose_secret().trim();
+    let bytes = Zeroizing::new(
+        hex::decode(hex_secret.strip_prefix("0x").unwrap_or(hex_secret))
+            .map_err(|e| ApiError::BadRequest(format!("Invalid master secret: {}", e)))?,
+    );
+    Ok(SecretBytes::from(&bytes[..]))
+}
+
+/// Run CPU-bound key derivation on the blocking pool so it never stalls the async workers
+async fn run_blocking<T, F>(f: F) -> ApiResult<T>
+where
+    F: FnOnce() -> ApiResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|_| ApiError::InternalError)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Curve;
+    
+    const TEST_MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    
+    #[tokio::test]
+    async fn test_parallel_batch_matches_single_derivation() {
+        let service = WalletService::new();
+        let mnemonic = SecretString::from(TEST_MNEMONIC);
+        let passphrase = SecretString::default();
+        let options = DerivationOptions::default();
+        let chain_types = [ChainType::BitcoinSegwit, ChainType::Ethereum, ChainType::Solana];
+        
+        let batch = service
+            .batch_generate_wallet_addresses(&mnemonic, &passphrase, &chain_types, 2, 5, &options)
+            .await
+            .unwrap();
+        assert_eq!(batch.len(), 15);
+        
+        // Same order as requested: every index of the first chain, then the next chain
+        for (position, wallet) in batch.iter().enumerate() {
+            let chain_type = &chain_types[position / 5];
+            let index = 2 + (position % 5) as u32;
+            let single = service
+                .generate_wallet_address(&mnemonic, &passphrase, chain_type, index, &options)
+                .await
+                .unwrap();
+            
+            assert_eq!(&wallet.chain_type, chain_type);
+            assert_eq!(wallet.address, single.address);
+            assert_eq!(wallet.derivation_path, single.derivation_path);
         }
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_account_level_batch_walks_accounts() {
+        let service = WalletService::new();
+        let mnemonic = SecretString::from(TEST_MNEMONIC);
+        let passphrase = SecretString::default();
+        let options = |account| DerivationOptions { account, ..Default::default() };
+        
+        let batch = service
+            .batch_generate_wallet_addresses(&mnemonic, &passphrase, &[ChainType::Solana, ChainType::Near], 0, 3, &options(2))
+            .await
+            .unwrap();
+        for (position, wallet) in batch.iter().enumerate() {
+            let account = 2 + (position % 3) as u32;
+            assert_eq!(wallet.account, account);
+            
+            let single = service
+                .generate_wallet_address(&mnemonic, &passphrase, &wallet.chain_type, 0, &options(account))
+                .await
+                .unwrap();
+            assert_eq!(wallet.address, single.address);
+            assert_eq!(wallet.derivation_path, single.derivation_path);
+        }
+        assert_eq!(batch[0].derivation_path, "m/44'/501'/2'/0'");
+        
+        assert!(matches!(
+            service
+                .batch_generate_wallet_addresses(&mnemonic, &passphrase, &[ChainType::Ethereum], u32::MAX - 10, 100, &options(0))
+                .await,
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            service
+                .generate_wallet_address(&mnemonic, &passphrase, &ChainType::Stellar, 1, &options(u32::MAX))
+                .await,
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_private_key_policy() {
+        let mnemonic = SecretString::from(TEST_MNEMONIC);
+        let passphrase = SecretString::default();
+        let options = |include_private_key| DerivationOptions { include_private_key, ..Default::default() };
+        
+        let service = WalletService::new();
+        let wallet = service
+            .generate_wallet_address(&mnemonic, &passphrase, &ChainType::Ethereum, 0, &options(None))
+            .await
+            .unwrap();
+        assert!(wallet.private_key.is_some());
+        let wallet = service
+            .generate_wallet_address(&mnemonic, &passphrase, &ChainType::Ethereum, 0, &options(Some(false)))
+            .await
+            .unwrap();
+        assert!(wallet.private_key.is_none());
+        
+        let service = WalletService::new().with_private_key_policy(PrivateKeyPolicy::Forbid);
+        let batch = service
+            .batch_generate_wallet_addresses(&mnemonic, &passphrase, &[ChainType::Solana, ChainType::Stellar], 0, 2, &options(None))
+            .await
+            .unwrap();
+        assert!(batch.iter().all(|wallet| wallet.private_key.is_none()));
+        assert!(matches!(
+            service
+                .generate_wallet_address(&mnemonic, &passphrase, &ChainType::Ethereum, 0, &options(Some(true)))
+                .await,
+            Err(ApiError::PrivateKeyExportDisabled)
+        ));
+        assert!(matches!(
+            service.mnemonic_to_seed(&mnemonic, &passphrase, "english").await,
+            Err(ApiError::PrivateKeyExportDisabled)
+        ));
+        assert!(matches!(
+            service.combine_slip39(&[SecretString::from("academic")], &passphrase).await,
+            Err(ApiError::PrivateKeyExportDisabled)
+        ));
+        for application in [
+            Bip85Application::new(39, None, Some(12), None).unwrap(),
+            Bip85Application::new(2, None, None, None).unwrap(),
+            Bip85Application::new(32, None, None, None).unwrap(),
+            Bip85Application::new(128169, None, None, Some(32)).unwrap(),
+        ] {
+            assert!(matches!(
+                service.derive_bip85(&mnemonic, &passphrase, None, application, 0).await,
+                Err(ApiError::PrivateKeyExportDisabled)
+            ));
+        }
+        let password = Bip85Application::new(707764, None, None, Some(21)).unwrap();
+        assert!(service.derive_bip85(&mnemonic, &passphrase, None, password, 0).await.is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_native_key_formats_decode_to_hex_key() {
+        let service = WalletService::new();
+        let mnemonic = SecretString::from(TEST_MNEMONIC);
+        let passphrase = SecretString::default();
+        let options = |key_format| DerivationOptions { key_format: Some(key_format), ..Default::default() };
+        
+        for chain_type in get_all_chain_types() {
+            let hex_wallet = service
+                .generate_wallet_address(&mnemonic, &passphrase, &chain_type, 0, &options(KeyFormat::Hex))
+                .await
+                .unwrap();
+            let native_wallet = service
+                .generate_wallet_address(&mnemonic, &passphrase, &chain_type, 0, &options(KeyFormat::Native))
+                .await
+                .unwrap();
+            let key = hex::decode(hex_wallet.private_key.unwrap().expose_secret()).unwrap();
+            let native = native_wallet.private_key.unwrap().expose_secret().to_string();
+            let public_key = hex::decode(&hex_wallet.public_key).unwrap();
+            
+            let decoded = match chain_type {
+                ChainType::BitcoinLegacy
+                | ChainType::BitcoinNestedSegwit
+                | ChainType::BitcoinSegwit
+                | ChainType::BitcoinTaproot => {
+                    let wif = bitcoin::PrivateKey::from_wif(&native).unwrap();
+                    assert!(wif.compressed && native.starts_with(['K', 'L']));
+                    wif.inner.secret_bytes().to_vec()
+                }
+                ChainType::Dogecoin => {
+                    let payload = bitcoin::base58::decode_check(&native).unwrap();
+                    assert!(native.starts_with('Q'));
+                    assert_eq!((payload[0], payload[33]), (0x9e, 0x01));
+                    payload[1..33].to_vec()
+                }
+                ChainType::Solana | ChainType::Near => {
+                    let keypair = bs58::decode(native.trim_start_matches("ed25519:")).into_vec().unwrap();
+                    assert_eq!(chain_type == ChainType::Near, native.starts_with("ed25519:"));
+                    assert_eq!(keypair[32..], public_key[..]);
+                    keypair[..32].to_vec()
+                }
+                ChainType::Tezos => {
+                    let payload = bitcoin::base58::decode_check(&native).unwrap();
+                    assert!(native.starts_with("edsk") && native.len() == 54);
+                    assert_eq!(payload[..4], [13, 15, 58, 7]);
+                    payload[4..].to_vec()
+                }
+                ChainType::Sui => {
+                    use bech32::FromBase32;
+                    let (hrp, data, _) = bech32::decode(&native).unwrap();
+                    let payload = Vec::<u8>::from_base32(&data).unwrap();
+                    assert_eq!((hrp.as_str(), payload[0]), ("suiprivkey", 0x00));
+                    payload[1..].to_vec()
+                }
+                ChainType::Stellar => chains::stellar::decode_strkey(chains::stellar::VERSION_SEED, &native).unwrap(),
+                ChainType::Ripple => {
+                    assert!(native.starts_with("00") && native == native.to_uppercase());
+                    hex::decode(&native[2..]).unwrap()
+                }
+                _ => hex::decode(&native).unwrap(),
+            };
+            assert_eq!(decoded, key, "{:?}", chain_type);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_entropy_mnemonic_seed_vectors() {
+        let service = WalletService::new();
+        
+        // BIP39 test vectors (passphrase "TREZOR")
+        let vectors = [
+            (
+                "00000000000000000000000000000000",
+                TEST_MNEMONIC,
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+            ),
+            (
+                "8080808080808080808080808080808080808080808080808080808080808080",
+                "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic bless",
+                "c0c519bd0e91a2ed54357d9d1ebef6f5af218a153624cf4f2da911a0ed8f7a09e2ef61af0aca007096df430022f7a2b6fb91661a9589097069720d015e4e982f",
+            ),
+        ];
+        
+        for (entropy, mnemonic, seed) in vectors {
+            let derived = service
+                .entropy_to_mnemonic(&entropy.into(), EntropySource::Hex, None, "english")
+                .await
+                .unwrap();
+            assert_eq!(derived.expose_secret(), mnemonic);
+            
+            let mnemonic = SecretString::from(mnemonic);
+            assert_eq!(service.mnemonic_to_entropy(&mnemonic, "english").await.unwrap().expose_secret(), entropy);
+            let derived_seed = service.mnemonic_to_seed(&mnemonic, &"TREZOR".into(), "english").await.unwrap();
+            assert_eq!(derived_seed.expose_secret(), seed);
+        }
+        
+        // Every word of the zero entropy is the first wordlist entry
+        let japanese = service
+            .entropy_to_mnemonic(&"00000000000000000000000000000000".into(), EntropySource::Hex, Some(12), "japanese")
+            .await
+            .unwrap();
+        assert!(japanese.expose_secret().starts_with("あいこくしん あいこくしん"));
+        assert!(service.mnemonic_to_entropy(&japanese, "japanese").await.is_ok());
+        assert!(matches!(service.mnemonic_to_entropy(&japanese, "english").await, Err(ApiError::InvalidMnemonic)));
+    }
+    
+    #[tokio::test]
+    async fn test_imported_keys_match_derived_addresses() {
+        let service = WalletService::new();
+        let mnemonic = SecretString::from(TEST_MNEMONIC);
+        let passphrase = SecretString::default();
+        let options = |key_format| DerivationOptions { key_format: Some(key_format), ..Default::default() };
+        
+        for chain_type in get_all_chain_types() {
+            for key_format in [KeyFormat::Hex, KeyFormat::Native] {
+                let wallet = service
+                    .generate_wallet_address(&mnemonic, &passphrase, &chain_type, 0, &options(key_format))
+                    .await
+                    .unwrap();
+                let imported = service
+                    .import_private_key(wallet.private_key.as_ref().unwrap(), &chain_type, NetworkType::Mainnet)
+                    .await
+                    .unwrap();
+                
+                assert_eq!(imported.address, wallet.address, "{:?} {:?}", chain_type, key_format);
+                assert_eq!(imported.public_key, wallet.public_key);
+                assert_eq!(imported.public_key_formats, wallet.public_key_formats);
+                assert_eq!(imported.compressed.is_some(), imported.curve == Curve::Secp256k1);
+            }
+        }
+        
+        let garbage = SecretString::from("not a private key");
+        assert!(matches!(
+            service.import_private_key(&garbage, &ChainType::Solana, NetworkType::Mainnet).await,
+            Err(ApiError::InvalidPrivateKey(_))
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_public_key_formats_decode_to_public_key() {
+        use base64::Engine;
+        use bech32::FromBase32;
+        
+        let service = WalletService::new();
+        let mnemonic = SecretString::from(TEST_MNEMONIC);
+        let passphrase = SecretString::default();
+        let options = DerivationOptions::default();
+        
+        for chain_type in [ChainType::Cosmos, ChainType::Osmosis, ChainType::Tezos, ChainType::Near, ChainType::Sui, ChainType::Ripple] {
+            let wallet = service
+                .generate_wallet_address(&mnemonic, &passphrase, &chain_type, 0, &options)
+                .await
+                .unwrap();
+            let public_key = hex::decode(&wallet.public_key).unwrap();
+            let formats = &wallet.public_key_formats;
+            
+            match chain_type {
+                ChainType::Cosmos | ChainType::Osmosis => {
+                    let (hrp, data, _) = bech32::decode(&formats["bech32"]).unwrap();
+                    let amino = Vec::<u8>::from_base32(&data).unwrap();
+                    assert_eq!(hrp, if chain_type == ChainType::Cosmos { "cosmospub" } else { "osmopub" });
+                    assert!(formats["bech32"].contains("1addwnpep"));
+                    assert_eq!(amino[5..], public_key[..]);
+                    
+                    let any: serde_json::Value = serde_json::from_str(&formats["any"]).unwrap();
+                    assert_eq!(any["@type"], "/cosmos.crypto.secp256k1.PubKey");
+                    let key = base64::engine::general_purpose::STANDARD.decode(any["key"].as_str().unwrap()).unwrap();
+                    assert_eq!(key, public_key);
+                }
+                ChainType::Tezos => {
+                    let payload = bitcoin::base58::decode_check(&formats["edpk"]).unwrap();
+                    assert!(formats["edpk"].starts_with("edpk"));
+                    assert_eq!(payload[4..], public_key[..]);
+                }
+                ChainType::Near => {
+                    let key = bs58::decode(formats["ed25519"].strip_prefix("ed25519:").unwrap()).into_vec().unwrap();
+                    assert_eq!(key, public_key);
+                }
+                ChainType::Sui => {
+                    let flagged = base64::engine::general_purpose::STANDARD.decode(&formats["base64"]).unwrap();
+                    assert_eq!((flagged[0], &flagged[1..]), (0x00, &public_key[..]));
+                }
+                ChainType::Ripple => {
+                    let payload = bs58::decode(&formats["xrpl"]).with_alphabet(bs58::Alphabet::RIPPLE).into_vec().unwrap();
+                    assert!(formats["xrpl"].starts_with("aB"));
+                    assert_eq!((payload[0], &payload[1..34]), (0x23, &public_key[..]));
+                }
+                _ => unreachable!(),
+            }
+        }
+        
+        // Chains whose tooling takes hex keys add nothing
+        let wallet = service
+            .generate_wallet_address(&mnemonic, &passphrase, &ChainType::Ethereum, 0, &options)
+            .await
+            .unwrap();
+        assert!(wallet.public_key_formats.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_slip39_master_secret_derives_wallets() {
+        let service = WalletService::new();
+        let passphrase = SecretString::from("TREZOR");
+        let options = DerivationOptions::default();
+        let scheme = Slip39Scheme {
+            groups: vec![crate::core::slip39::GroupSpec { member_threshold: 2, member_count: 3 }],
+            iteration_exponent: 0,
+            ..Default::default()
+        };
+        
+        let backup = service.split_slip39(None, 256, &passphrase, &scheme).await.unwrap();
+        let shares = &backup.groups[0].shares;
+        assert_eq!(shares.len(), 3);
+        assert!(shares.iter().all(|share| share.expose_secret().split(' ').count() == 33));
+        
+        let master_secret = service.combine_slip39(&shares[1..], &passphrase).await.unwrap();
+        assert_eq!(master_secret.expose_secret().len(), 32);
+        
+        // The master secret is the BIP32 seed, exactly as a BIP39 seed would be
+        let wallet = service
+            .generate_wallet_address_from_seed(&master_secret, &ChainType::Ethereum, 0, &options)
+            .await
+            .unwrap();
+        let keys = KeyTree::new(master_secret.expose_secret());
+        let chain = service.chain(&ChainType::Ethereum, NetworkType::Mainnet).unwrap();
+        let expected = chain.generate_address(&keys, "", 0, 0, None, None).unwrap();
+        assert_eq!(wallet.address, expected.address);
+        
+        let batch = service
+            .batch_generate_wallet_addresses_from_seed(&master_secret, &[ChainType::Ethereum], 0, 2, &options)
+            .await
+            .unwrap();
+        assert_eq!(batch[0].address, wallet.address);
+        
+        // A BIP39 seed given directly matches its mnemonic
+        let mnemonic = SecretString::from(TEST_MNEMONIC);
+        let seed = service.mnemonic_to_seed(&mnemonic, &SecretString::default(), "english").await.unwrap();
+        let seed = SecretBytes::new(hex::decode(seed.expose_secret()).unwrap());
+        let from_seed = service
+            .generate_wallet_address_from_seed(&seed, &ChainType::Solana, 0, &options)
+            .await
+            .unwrap();
+        let from_mnemonic = service
+            .generate_wallet_address(&mnemonic, &SecretString::default(), &ChainType::Solana, 0, &options)
+            .await
+            .unwrap();
+        assert_eq!(from_seed.address, from_mnemonic.address);
+    }
+    
+    #[tokio::test]
+    async fn test_bip85_children_are_independent_wallets() {
+        let service = WalletService::new();
+        let mnemonic = SecretString::from(TEST_MNEMONIC);
+        let passphrase = SecretString::default();
+        let application = Bip85Application::new(39, Some(Language::Spanish), Some(12), None).unwrap();
+        
+        let first = service.derive_bip85(&mnemonic, &passphrase, None, application, 0).await.unwrap();
+        let second = service.derive_bip85(&mnemonic, &passphrase, None, application, 1).await.unwrap();
+        assert_ne!(first.expose_secret(), second.expose_secret());
+        
+        // Each child is a mnemonic in its own right, deriving its own wallets
+        let language = service.mnemonic_language(&first, None).await.unwrap();
+        assert_eq!(language, Language::Spanish);
+        let child = service
+            .generate_wallet_address(&first, &passphrase, &ChainType::BitcoinSegwit, 0, &DerivationOptions::default())
+            .await
+            .unwrap();
+        let parent = service
+            .generate_wallet_address(&mnemonic, &passphrase, &ChainType::BitcoinSegwit, 0, &DerivationOptions::default())
+            .await
+            .unwrap();
+        assert_ne!(child.address, parent.address);
+        
+        // The passphrase is part of the master key
+        let protected = service
+            .derive_bip85(&mnemonic, &SecretString::from("TREZOR"), None, application, 0)
+            .await
+            .unwrap();
+        assert_ne!(first.expose_secret(), protected.expose_secret());
+    }
+    
+    #[tokio::test]
+    async fn test_network_selection() {
+        let service = WalletService::new();
+        let mnemonic = SecretString::from(TEST_MNEMONIC);
+        let passphrase = SecretString::default();
+        let testnet = DerivationOptions { network: NetworkType::Testnet, ..Default::default() };
+        
+        let wallet = service
+            .generate_wallet_address(&mnemonic, &passphrase, &ChainType::BitcoinSegwit, 0, &testnet)
+            .await
+            .unwrap();
+        assert_eq!(wallet.address, "tb1q6rz28mcfaxtmd6v789l9rrlrusdprr9pqcpvkl");
+        assert!(service.validate_address(&ChainType::BitcoinSegwit, NetworkType::Testnet, &wallet.address).await);
+        assert!(!service.validate_address(&ChainType::BitcoinSegwit, NetworkType::Mainnet, &wallet.address).await);
+        
+        // Dogecoin has no signet
+        let signet = DerivationOptions { network: NetworkType::Signet, ..Default::default() };
+        assert!(service
+            .generate_wallet_address(&mnemonic, &passphrase, &ChainType::Dogecoin, 0, &signet)
+            .await
+            .is_err());
+        
+        // A vpub derives test addresses only
+        let vpub = service
+            .export_account_xpub(&mnemonic, &passphrase, &ChainType::BitcoinSegwit, 0, None, NetworkType::Testnet)
+            .await
+            .unwrap();
+        let watched = service
+            .derive_public_addresses(&vpub.extended_public_key, &ChainType::BitcoinSegwit, NetworkType::Testnet, 0, 0, 1)
+            .await
+            .unwrap();
+        assert_eq!(watched[0].address, wallet.address);
+        assert!(service
+            .derive_public_addresses(&vpub.extended_public_key, &ChainType::BitcoinSegwit, NetworkType::Mainnet, 0, 0, 1)
+            .await
+            .is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_xpub_format_must_match_chain_type() {
+        let service = WalletService::new();
+        let mnemonic = SecretString::from(TEST_MNEMONIC);
+        let passphrase = SecretString::default();
+        let derive = |xpub: String, chain_type: ChainType| {
+            let service = &service;
+            async move {
+                service
+                    .derive_public_addresses(&xpub, &chain_type, NetworkType::Mainnet, 0, 0, 1)
+                    .await
+            }
+        };
+        
+        let zpub = service
+            .export_account_xpub(&mnemonic, &passphrase, &ChainType::BitcoinSegwit, 0, None, NetworkType::Mainnet)
+            .await
+            .unwrap()
+            .extended_public_key;
+        assert!(zpub.starts_with("zpub"));
+        assert!(derive(zpub.clone(), ChainType::BitcoinSegwit).await.is_ok());
+        for chain_type in [ChainType::BitcoinLegacy, ChainType::BitcoinNestedSegwit, ChainType::BitcoinTaproot, ChainType::Ethereum] {
+            assert!(matches!(derive(zpub.clone(), chain_type).await, Err(ApiError::BadRequest(_))));
+        }
+        
+        // A plain xpub of the same key stays usable for any script type
+        let (_, key) = XpubFormat::decode(&zpub).unwrap();
+        let xpub = XpubFormat::Xpub.encode(&key);
+        for chain_type in [ChainType::BitcoinLegacy, ChainType::BitcoinSegwit] {
+            assert!(derive(xpub.clone(), chain_type).await.is_ok());
+        }
+    }
+
+}